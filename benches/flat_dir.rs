@@ -0,0 +1,63 @@
+// Collection and listing of a single flat directory of many entries, half of them matching.
+// RMX_BENCH_ENTRIES changes the number of entries, 1M by default:
+//
+//     cargo bench --bench flat_dir
+//     RMX_BENCH_ENTRIES=100000 cargo bench --bench flat_dir
+
+use std::env;
+use std::fs::File;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use tempfile::TempDir;
+
+fn flat_dir(entries: usize) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..entries {
+        let extension = if i % 2 == 0 { "log" } else { "txt" };
+        File::create(dir.path().join(format!("file-{i}.{extension}"))).unwrap();
+    }
+    dir
+}
+
+// Listed by the binary, whose output goes to /dev/null as it would to a pipe
+fn list(path: &Path) {
+    let status = Command::new(env!("CARGO_BIN_EXE_rmx"))
+        .arg("-n")
+        .arg("-p")
+        .arg(path)
+        .arg("log")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn bench(c: &mut Criterion) {
+    let entries = env::var("RMX_BENCH_ENTRIES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1_000_000);
+    let dir = flat_dir(entries);
+    let path = dir.path().to_path_buf();
+    let extensions = vec!["log".to_string()];
+
+    let mut group = c.benchmark_group(format!("flat_dir_{entries}"));
+    // Each run reads the whole directory
+    group.sample_size(10);
+    group.bench_function("collect", |b| {
+        b.iter(|| {
+            let files =
+                rmx::collect_matching_files(&extensions, &path, &rmx::CollectOptions::default())
+                    .unwrap();
+            assert_eq!(entries.div_ceil(2), files.len());
+        })
+    });
+    group.bench_function("list", |b| b.iter(|| list(&path)));
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
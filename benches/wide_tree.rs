@@ -0,0 +1,63 @@
+// Collection of a directory of many subdirectories, each holding a matching file, measuring both
+// the whole collection and the time until the first match reaches the sink. RMX_BENCH_DIRS
+// changes the number of subdirectories, 100k by default:
+//
+//     cargo bench --bench wide_tree
+//     RMX_BENCH_DIRS=500000 cargo bench --bench wide_tree
+
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use tempfile::TempDir;
+
+fn wide_tree(dirs: usize) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..dirs {
+        let subdir = dir.path().join(format!("dir-{i}"));
+        fs::create_dir(&subdir).unwrap();
+        File::create(subdir.join("file.log")).unwrap();
+    }
+    dir
+}
+
+fn bench(c: &mut Criterion) {
+    let dirs = env::var("RMX_BENCH_DIRS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(100_000);
+    let dir = wide_tree(dirs);
+    let path = dir.path().to_path_buf();
+    let extensions = vec!["log".to_string()];
+    let mut options = rmx::CollectOptions::default();
+    options.recurse = true;
+
+    let mut group = c.benchmark_group(format!("wide_tree_{dirs}"));
+    // Each run reads the whole tree
+    group.sample_size(10);
+    group.bench_function("collect", |b| {
+        b.iter(|| {
+            let files = rmx::collect_matching_files(&extensions, &path, &options).unwrap();
+            assert_eq!(dirs, files.len());
+        })
+    });
+    // The sink failing stops the walk at the first match
+    group.bench_function("first_match", |b| {
+        b.iter(|| {
+            let mut sink = |_: &Path| Err(io::Error::other("first match"));
+            let result = rmx::collect_matching_files_with_sink(
+                &extensions,
+                &path,
+                &options,
+                Some(&mut sink),
+            );
+            assert!(result.is_err());
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|token: &str| {
+    let _ = rmx::duration::parse_duration(token);
+});
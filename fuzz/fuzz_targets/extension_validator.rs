@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|extensions: Vec<String>| {
+    if rmx::arguments::are_extensions_valid(&extensions) {
+        for extension in &extensions {
+            assert!(extension.split('.').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            }));
+        }
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|filename: &str| {
+    if let Some(extension) = rmx::get_fileext(filename) {
+        assert!(filename.ends_with(extension));
+        assert!(extension.len() < filename.len());
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|line: &str| {
+    if let Ok((name, extensions)) = rmx::preset::parse_line(line) {
+        assert!(!name.is_empty() && !name.contains('='));
+        assert!(!extensions.is_empty());
+    }
+});
@@ -0,0 +1,209 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::filter::ExtensionFilter;
+use crate::get_fileext;
+
+// What a matched archive holds (see --peek-archives). Only entry names are read, nothing is
+// ever extracted
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Peek {
+    Entries { matching: usize, total: usize },
+    // Corrupt or unreadable archives do not stop the run
+    Unreadable,
+}
+
+impl fmt::Display for Peek {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Peek::Entries { matching, total } => {
+                let entries = if *total == 1 { "entry" } else { "entries" };
+                write!(f, "archive: {matching} of {total} {entries} match")
+            }
+            Peek::Unreadable => write!(f, "archive: unreadable"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+// Archives are recognized by name, as for any other match
+fn format_of(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(Format::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Format::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(Format::Tar)
+    } else {
+        None
+    }
+}
+
+// Names of the files of the archive, directories left aside
+fn file_names(path: &Path, format: Format) -> io::Result<Vec<String>> {
+    let file = BufReader::new(File::open(path)?);
+    match format {
+        // Only the central directory is read
+        Format::Zip => {
+            let archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+            Ok(archive
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .map(str::to_string)
+                .collect())
+        }
+        Format::Tar => tar_file_names(tar::Archive::new(file)),
+        Format::TarGz => tar_file_names(tar::Archive::new(GzDecoder::new(file))),
+    }
+}
+
+// Entries are streamed one after the other, their content being skipped
+fn tar_file_names(mut archive: tar::Archive<impl io::Read>) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            names.push(entry.path()?.to_string_lossy().into_owned());
+        }
+    }
+    Ok(names)
+}
+
+// None when the path is not a supported archive
+pub(crate) fn peek(path: &Path, extensions: &ExtensionFilter) -> Option<Peek> {
+    let format = format_of(path)?;
+    let Ok(names) = file_names(path, format) else {
+        return Some(Peek::Unreadable);
+    };
+
+    let matching = names
+        .iter()
+        .filter(|name| {
+            let base = name.rsplit('/').next().unwrap_or(name);
+            get_fileext(base).is_some_and(|e| extensions.keeps(e))
+        })
+        .count();
+    Some(Peek::Entries {
+        matching,
+        total: names.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use tempfile::{TempDir, tempdir};
+    use zip::write::SimpleFileOptions;
+
+    const ENTRIES: [&str; 4] = ["a.log", "logs/b.log", "c.tmp", "d.txt"];
+
+    fn log_filter() -> ExtensionFilter {
+        ExtensionFilter::new(&["log".to_string(), "tmp".to_string()], false)
+    }
+
+    fn create_zip(dir: &TempDir) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = dir.path().join("bundle.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path)?);
+        zip.add_directory("logs/", SimpleFileOptions::default())?;
+        for name in ENTRIES {
+            zip.start_file(name, SimpleFileOptions::default())?;
+            zip.write_all(b"content")?;
+        }
+        zip.finish()?;
+        Ok(path)
+    }
+
+    fn create_tar_gz(dir: &TempDir) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = dir.path().join("bundle.tar.gz");
+        let encoder = GzEncoder::new(File::create(&path)?, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for name in ENTRIES {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(7);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &b"content"[..])?;
+        }
+        builder.into_inner()?.finish()?;
+        Ok(path)
+    }
+
+    #[test]
+    fn peek_zip() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = create_zip(&dir)?;
+
+        let peek = peek(&path, &log_filter());
+
+        assert_eq!(
+            Some(Peek::Entries {
+                matching: 3,
+                total: 4
+            }),
+            peek
+        );
+        assert_eq!("archive: 3 of 4 entries match", peek.unwrap().to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek_tar_gz() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = create_tar_gz(&dir)?;
+
+        assert_eq!(
+            Some(Peek::Entries {
+                matching: 3,
+                total: 4
+            }),
+            peek(&path, &log_filter())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek_unreadable_archives() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let corrupt = dir.path().join("corrupt.zip");
+        std::fs::write(&corrupt, b"not a zip")?;
+        let corrupt_tgz = dir.path().join("corrupt.tgz");
+        std::fs::write(&corrupt_tgz, b"not gzip")?;
+
+        assert_eq!(Some(Peek::Unreadable), peek(&corrupt, &log_filter()));
+        assert_eq!(Some(Peek::Unreadable), peek(&corrupt_tgz, &log_filter()));
+        assert_eq!(
+            Some(Peek::Unreadable),
+            peek(&dir.path().join("missing.tar"), &log_filter())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek_other_files() {
+        assert_eq!(None, peek(Path::new("notes.txt"), &log_filter()));
+        assert_eq!(None, format_of(Path::new("zip")));
+        assert_eq!(Some(Format::TarGz), format_of(Path::new("a.TGZ")));
+        assert_eq!(Some(Format::Tar), format_of(Path::new("a.tar")));
+    }
+}
@@ -0,0 +1,129 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::paths::{Separators, resolve, to_portable_string};
+
+// Why a file examined during the collection was selected or spared, see --audit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Decision {
+    Selected,
+    ExtensionMismatch,
+    HiddenSkipped,
+    // Drives the traversal, like --follow-dir-lists files
+    Excluded,
+    // Kept by a safety filter, like --never-older-than
+    Protected,
+    // Targeted by a symlink, see --preserve-link-targets
+    LinkTarget,
+}
+
+impl Decision {
+    fn matched(&self) -> &'static str {
+        match self {
+            Decision::Selected => "yes",
+            _ => "no",
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self {
+            Decision::Selected => "selected",
+            Decision::ExtensionMismatch => "extension mismatch",
+            Decision::HiddenSkipped => "hidden skipped",
+            Decision::Excluded => "excluded",
+            Decision::Protected => "protected",
+            Decision::LinkTarget => "symlink target",
+        }
+    }
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+// One tab separated line per examined file: absolute path, matched (yes/no), reason
+// Lines are written as they come, so that memory does not grow with the number of files
+pub(crate) struct AuditLog {
+    out: Output,
+    // Directory relative paths are resolved against, the current one
+    cwd: PathBuf,
+}
+
+impl AuditLog {
+    // Compressed when the file name ends with .gz
+    pub(crate) fn create(path: &Path) -> io::Result<AuditLog> {
+        let file = BufWriter::new(File::create(path)?);
+        let out = if path.extension().is_some_and(|e| e == "gz") {
+            Output::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Output::Plain(file)
+        };
+        Ok(AuditLog {
+            out,
+            cwd: env::current_dir()?,
+        })
+    }
+
+    pub(crate) fn record(&mut self, path: &Path, decision: Decision) -> io::Result<()> {
+        let out: &mut dyn Write = match &mut self.out {
+            Output::Plain(out) => out,
+            Output::Gzip(out) => out,
+        };
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            to_portable_string(&resolve(&self.cwd, path), Separators::Native),
+            decision.matched(),
+            decision.reason()
+        )
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self.out {
+            Output::Plain(mut out) => out.flush(),
+            Output::Gzip(out) => out.finish()?.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn audit_plain_and_gzip() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+
+        for name in ["audit.tsv", "audit.tsv.gz"] {
+            let path = temp_dir.path().join(name);
+            let mut audit = AuditLog::create(&path)?;
+            audit.record(Path::new("/a.txt"), Decision::Selected)?;
+            audit.record(Path::new("/.b.txt"), Decision::HiddenSkipped)?;
+            audit.finish()?;
+
+            let mut contents = String::new();
+            if name.ends_with(".gz") {
+                GzDecoder::new(File::open(&path)?).read_to_string(&mut contents)?;
+            } else {
+                File::open(&path)?.read_to_string(&mut contents)?;
+            }
+
+            assert_eq!(
+                "/a.txt\tyes\tselected\n/.b.txt\tno\thidden skipped\n",
+                contents
+            );
+        }
+
+        Ok(())
+    }
+}
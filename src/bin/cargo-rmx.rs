@@ -0,0 +1,14 @@
+use std::env;
+use std::process;
+
+use rmx::cargo;
+
+// `cargo rmx`: rmx run on the target directory of the current cargo workspace
+fn main() {
+    let target = || cargo::target_dir(&cargo::workspace_root()?);
+    let argv = cargo::rmx_args(env::args_os().collect(), target).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+    rmx::cli::main(argv);
+}
@@ -1,11 +1,70 @@
 use rmx::arguments::Args;
 
-use clap::CommandFactory;
+use clap::{Command, CommandFactory, Parser};
+use clap_complete::{Shell, generate};
 use clap_mangen::Man;
-use std::io;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
-fn main() -> io::Result<()> {
-    let cmd = Args::command();
-    let man = Man::new(cmd);
-    man.render(&mut io::stdout())
+/// Generate rmx's man page, and its shell completions with --out-dir
+#[derive(Parser, Debug)]
+#[command(name = "man")]
+struct ManArgs {
+    /// Write rmx.1, rmx.bash, _rmx and rmx.fish in this directory (created if needed),
+    /// instead of printing the man page
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+fn write_asset(
+    path: &Path,
+    render: impl FnOnce(&mut File) -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    let mut file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    render(&mut file).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    file.flush()?;
+    Ok(())
+}
+
+fn write_completion(cmd: &mut Command, shell: Shell, path: &Path) -> Result<(), Box<dyn Error>> {
+    write_asset(path, |file| {
+        generate(shell, cmd, "rmx", file);
+        Ok(())
+    })
+}
+
+fn generate_assets(out_dir: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(out_dir)
+        .map_err(|e| format!("Could not create {}: {}", out_dir.display(), e))?;
+
+    let mut cmd = Args::command();
+
+    write_asset(&out_dir.join("rmx.1"), |file| {
+        Man::new(cmd.clone()).render(file)
+    })?;
+    write_completion(&mut cmd, Shell::Bash, &out_dir.join("rmx.bash"))?;
+    write_completion(&mut cmd, Shell::Zsh, &out_dir.join("_rmx"))?;
+    write_completion(&mut cmd, Shell::Fish, &out_dir.join("rmx.fish"))?;
+
+    Ok(())
+}
+
+fn main() {
+    let args = ManArgs::parse();
+
+    let res = match args.out_dir {
+        Some(out_dir) => generate_assets(&out_dir),
+        None => Man::new(Args::command())
+            .render(&mut io::stdout())
+            .map_err(|e| e.into()),
+    };
+
+    if let Err(e) = res {
+        eprintln!("Error: {e}");
+        process::exit(1);
+    }
 }
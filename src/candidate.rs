@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::metadata::{RealFs, StatCache};
+
+/// Type of a candidate, which decides how it is deleted
+///
+/// ```
+/// assert_eq!('-', rmx::CandidateKind::File.as_char());
+/// assert!(rmx::CandidateKind::EmptyDir.is_dir());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CandidateKind {
+    File,
+    // Removed itself, never what it points to (see CollectOptions::symlinks)
+    Symlink,
+    // Directory with nothing left inside once its empty subdirectories are removed
+    EmptyDir,
+    // Directory removed with everything inside, like `__pycache__` or those matched by --dirs
+    Dir,
+}
+
+impl CandidateKind {
+    /// Type column of the long listing, following `ls -l`
+    pub fn as_char(&self) -> char {
+        match self {
+            CandidateKind::File => '-',
+            CandidateKind::Symlink => 'l',
+            CandidateKind::EmptyDir | CandidateKind::Dir => 'd',
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, CandidateKind::EmptyDir | CandidateKind::Dir)
+    }
+}
+
+/// A file or directory selected for deletion, with what was learned about it during the traversal
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join("a.log"))?;
+///
+/// let candidates = rmx::collect_candidates(
+///     &["log".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+/// )?;
+///
+/// assert_eq!(rmx::CandidateKind::File, candidates[0].kind);
+/// assert_eq!(None, candidates[0].link_target());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Candidate {
+    pub path: PathBuf,
+    /// Requested extension the candidate was selected for, the first one matching when several
+    /// do. None when selected otherwise, like with --invert or --apple-cruft
+    pub matched_ext: Option<String>,
+    pub kind: CandidateKind,
+    // Read when a filter or a feature first needs it, like sizes, then kept for the rest of the
+    // run (see metadata::enrich)
+    pub(crate) stat: StatCache,
+    // What a directory deleted whole holds, added up when it is collected
+    pub(crate) contents: Option<Contents>,
+}
+
+// Files inside a directory deleted whole, at any depth, and their total size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Contents {
+    pub(crate) files: usize,
+    pub(crate) bytes: u64,
+}
+
+impl Candidate {
+    pub(crate) fn new(path: PathBuf, kind: CandidateKind) -> Candidate {
+        Candidate {
+            path,
+            matched_ext: None,
+            kind,
+            stat: StatCache::default(),
+            contents: None,
+        }
+    }
+
+    // Files deleted along with the candidate, for the max_files of policies. A directory counts
+    // as one file at least, like an empty one
+    pub(crate) fn file_count(&self) -> usize {
+        self.contents.map_or(1, |contents| contents.files.max(1))
+    }
+
+    /// Size in bytes, the total of the files they hold for directories deleted whole, 0 for
+    /// empty directories and for files that cannot be read. Read once, then kept
+    pub fn size(&self) -> u64 {
+        if self.kind.is_dir() {
+            return self.contents.map_or(0, |contents| contents.bytes);
+        }
+        self.stat.read(&self.path, &RealFs).map_or(0, |s| s.size)
+    }
+
+    /// Target of a symlink, only read when needed as most listings never show it
+    pub fn link_target(&self) -> Option<PathBuf> {
+        match self.kind {
+            CandidateKind::Symlink => fs::read_link(&self.path).ok(),
+            _ => None,
+        }
+    }
+}
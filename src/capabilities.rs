@@ -0,0 +1,131 @@
+use serde_json::json;
+
+// Features wrapper scripts can check for before using them (see --capabilities), each token
+// being the long flag it enables. A feature registers here when it lands, tokens never being
+// renamed nor removed, so that `rmx --capabilities | grep -qx files-from` keeps working
+pub(crate) const CAPABILITIES: &[&str] = &[
+    "accessed-before",
+    "all",
+    "apple-cruft",
+    "audit",
+    "capabilities",
+    "capabilities-json",
+    "checkpoint",
+    "clear-immutable",
+    "color",
+    "confirm-chunks",
+    "dirs",
+    "discover",
+    "empty",
+    "empty-dirs",
+    "exclude",
+    "expand-matched-dirs",
+    "files-from",
+    "follow-dir-lists",
+    "follow-symlinks",
+    "group",
+    "hidden-dirs",
+    "hidden-files",
+    "history",
+    "history-json",
+    "ignore-case",
+    "init-config",
+    "inventory",
+    "invert",
+    "job",
+    "keep-from",
+    "limit",
+    "max-depth",
+    "max-size",
+    "min-depth",
+    "min-size",
+    "name",
+    "never-older-than",
+    "newer-than",
+    "newer-than-file",
+    "nice",
+    "no-ext",
+    "no-ext-check",
+    "notify-cmd",
+    "older-than",
+    "older-than-file",
+    "one-file-system",
+    "oneline",
+    "paths-from-stdin",
+    "peek-archives",
+    "plan-out",
+    "preserve-link-targets",
+    "prefix",
+    "preset",
+    "pycache",
+    "quiet",
+    "regex",
+    "rename-suffix",
+    "respect-locks",
+    "rule",
+    "select",
+    "simulate",
+    "skip-readonly",
+    "sort",
+    "strict",
+    "symlinks",
+    "threads",
+    "touch-parents",
+    "traversal",
+    "user",
+];
+
+// `rmx 0.1.3`, then a token per line
+pub(crate) fn format_lines(version: &str) -> Vec<String> {
+    let mut lines = vec![format!("rmx {version}")];
+    lines.extend(CAPABILITIES.iter().map(|token| token.to_string()));
+    lines
+}
+
+// Like `{"version":"0.1.3","capabilities":["all",...]}`
+pub(crate) fn to_json(version: &str) -> String {
+    json!({ "version": version, "capabilities": CAPABILITIES }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use clap::CommandFactory;
+
+    use crate::arguments::Args;
+
+    // Tokens are flags, so that a token cannot outlive or misname its feature
+    #[test]
+    fn tokens_are_long_flags() {
+        let command = Args::command();
+        let flags: HashSet<&str> = command
+            .get_arguments()
+            .filter_map(|a| a.get_long())
+            .collect();
+
+        for token in CAPABILITIES {
+            assert!(flags.contains(token), "{token} is not a flag");
+        }
+        assert_eq!(
+            CAPABILITIES.len(),
+            CAPABILITIES.iter().collect::<HashSet<_>>().len()
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let lines = format_lines("0.1.3");
+        assert_eq!("rmx 0.1.3", lines[0]);
+        assert!(lines.iter().any(|l| l == "older-than"));
+
+        let json: serde_json::Value = serde_json::from_str(&to_json("0.1.3")).unwrap();
+        assert_eq!("0.1.3", json["version"]);
+        assert_eq!(
+            CAPABILITIES.len(),
+            json["capabilities"].as_array().unwrap().len()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::env;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::Parser;
+use clap::error::ErrorKind;
+use serde::Deserialize;
+
+use crate::arguments::Args;
+
+// Build junk of Rust projects, deleted when no extension nor preset is given
+pub const RUST_EXTENSIONS: [&str; 4] = ["rlib", "rmeta", "d", "o"];
+
+// Output of `cargo locate-project --message-format json`
+#[derive(Deserialize)]
+struct ProjectLocation {
+    // Manifest of the workspace, like /src/app/Cargo.toml
+    root: PathBuf,
+}
+
+// Directory of the workspace, from the output of `cargo locate-project`
+fn parse_location(output: &[u8]) -> Result<PathBuf, Box<dyn Error>> {
+    let location: ProjectLocation = serde_json::from_slice(output)
+        .map_err(|e| format!("Could not read the output of cargo locate-project: {e}"))?;
+    match location.root.parent() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => Err(format!("Invalid manifest path {}", location.root.display()).into()),
+    }
+}
+
+// Root of the workspace holding the current directory, asked to cargo itself so that nested
+// packages lead to their workspace
+pub fn workspace_root() -> Result<PathBuf, Box<dyn Error>> {
+    // Set by cargo when it runs a subcommand
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let output = Command::new(&cargo)
+        .args(["locate-project", "--workspace", "--message-format", "json"])
+        .output()
+        .map_err(|e| format!("Could not run {}: {e}", cargo.to_string_lossy()))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Could not find a cargo workspace:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    parse_location(&output.stdout)
+}
+
+// Whether the arguments hold the given option, as `-x`, within short flags like `-rx`, or as
+// `--long` or `--long=value`. Short flags stop at -p, as what follows is its value
+fn has_option(args: &[OsString], short: char, long: &str) -> bool {
+    args.iter()
+        .map(|a| a.to_string_lossy())
+        .take_while(|a| a != "--")
+        .any(|a| {
+            if let Some(name) = a.strip_prefix("--") {
+                return name == long || name.starts_with(&format!("{long}="));
+            }
+            match a.strip_prefix('-') {
+                Some(flags) => flags
+                    .chars()
+                    .scan(false, |after_value, c| {
+                        let seen = !*after_value;
+                        *after_value |= c == 'p';
+                        seen.then_some(c)
+                    })
+                    .any(|c| c == short),
+                None => false,
+            }
+        })
+}
+
+// Arguments of `rmx` standing for `cargo rmx <args>`: recursive, in `target` unless a path is
+// given, and deleting Rust build junk unless told what to delete
+pub fn rmx_args(
+    args: Vec<OsString>,
+    target: impl FnOnce() -> Result<PathBuf, Box<dyn Error>>,
+) -> Result<Vec<OsString>, Box<dyn Error>> {
+    // Cargo passes the name of the subcommand first
+    let mut args = args.into_iter().skip(1).peekable();
+    args.next_if(|a| a == OsStr::new("rmx"));
+    let args: Vec<OsString> = args.collect();
+
+    let mut argv = vec![OsString::from("cargo-rmx")];
+    if !has_option(&args, 'r', "recurse") {
+        argv.push("-r".into());
+    }
+    if !has_option(&args, 'p', "path") {
+        argv.push("-p".into());
+        argv.push(target()?.into());
+    }
+    argv.extend(args);
+
+    if let Err(e) = Args::try_parse_from(&argv)
+        && e.kind() == ErrorKind::MissingRequiredArgument
+    {
+        argv.extend(RUST_EXTENSIONS.map(OsString::from));
+    }
+    Ok(argv)
+}
+
+// Directory of the build artifacts of the workspace, the only one cleaned by default
+pub fn target_dir(root: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let target = root.join("target");
+    if !target.is_dir() {
+        return Err(format!(
+            "No target directory in {}, nothing to clean.",
+            root.display()
+        )
+        .into());
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn no_target() -> Result<PathBuf, Box<dyn Error>> {
+        Err("not needed".into())
+    }
+
+    #[test]
+    fn location() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            PathBuf::from("/src/app"),
+            parse_location(br#"{"root":"/src/app/Cargo.toml"}"#)?
+        );
+        assert!(parse_location(b"error").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn options() {
+        assert!(has_option(&args(&["-r"]), 'r', "recurse"));
+        assert!(has_option(&args(&["-nr"]), 'r', "recurse"));
+        assert!(has_option(&args(&["--recurse"]), 'r', "recurse"));
+        assert!(has_option(&args(&["--path=src"]), 'p', "path"));
+        assert!(has_option(&args(&["-np", "src"]), 'p', "path"));
+        // Value of -p
+        assert!(!has_option(&args(&["-p/srv/r"]), 'r', "recurse"));
+        assert!(!has_option(&args(&["--", "-r"]), 'r', "recurse"));
+        assert!(!has_option(&args(&["--recursive-ish"]), 'r', "recurse"));
+        assert!(!has_option(&args(&["rlib"]), 'r', "recurse"));
+    }
+
+    #[test]
+    fn defaults() -> Result<(), Box<dyn Error>> {
+        let argv = rmx_args(args(&["cargo-rmx", "rmx", "-n"]), || {
+            Ok(PathBuf::from("/src/app/target"))
+        })?;
+        assert_eq!(
+            args(&[
+                "cargo-rmx",
+                "-r",
+                "-p",
+                "/src/app/target",
+                "-n",
+                "rlib",
+                "rmeta",
+                "d",
+                "o"
+            ]),
+            argv
+        );
+
+        // Whatever is given replaces the defaults
+        let argv = rmx_args(
+            args(&["cargo-rmx", "rmx", "-r", "-p", "src", "tmp"]),
+            no_target,
+        )?;
+        assert_eq!(args(&["cargo-rmx", "-r", "-p", "src", "tmp"]), argv);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::hash;
+use crate::paths::{self, Separators};
+
+// Directories whose matches were all deleted by a run, one `invocation<TAB>directory` line per
+// directory, appended and synced as soon as the directory is done (see --checkpoint)
+// Directories are written absolute, and relative ones read back from the directory of the
+// checkpoint
+pub struct Checkpoint {
+    path: PathBuf,
+    invocation: String,
+    completed: HashSet<PathBuf>,
+    file: File,
+    // Directory relative directories given to the checkpoint are relative to, the current one
+    cwd: PathBuf,
+}
+
+// Identifies a run by its working directory and its arguments, so that a checkpoint is only
+// resumed by the command line that wrote it
+pub fn invocation(cwd: &Path, args: impl IntoIterator<Item = OsString>) -> String {
+    let mut bytes = cwd.as_os_str().as_encoded_bytes().to_vec();
+    for arg in args {
+        bytes.push(0);
+        bytes.extend_from_slice(arg.as_encoded_bytes());
+    }
+    hash::sha256_reader(bytes.as_slice()).expect("reading from memory cannot fail")
+}
+
+impl Checkpoint {
+    // Reads the directories completed by an earlier run, if any. A last line without its newline
+    // was cut by an interruption, and is ignored
+    pub fn open(path: &Path, invocation: &str) -> Result<Checkpoint, Box<dyn Error>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(format!("Could not read checkpoint {}: {}", path.display(), e).into());
+            }
+        };
+
+        let base = paths::list_base(path, None)?;
+        let mut completed = HashSet::new();
+        let mut lines: Vec<&str> = content.split('\n').collect();
+        // Either empty, or the partial line
+        lines.pop();
+        for line in lines {
+            let Some((hash, dir)) = line.split_once('\t') else {
+                return Err(format!("Malformed checkpoint {}: {}", path.display(), line).into());
+            };
+            if hash != invocation {
+                return Err(format!(
+                    "Checkpoint {} was written by another invocation, refusing to resume. \
+                    Run the same command line again, or remove the checkpoint.",
+                    path.display()
+                )
+                .into());
+            }
+            let dir = paths::from_portable_string(dir, Separators::Native);
+            completed.insert(paths::resolve(&base, &dir));
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Could not open checkpoint {}: {}", path.display(), e))?;
+        // Appended lines must not be glued to a partial one
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Checkpoint {
+            path: path.to_path_buf(),
+            invocation: invocation.to_string(),
+            completed,
+            file,
+            cwd: env::current_dir()?,
+        })
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed.len()
+    }
+
+    pub fn is_completed(&self, dir: &Path) -> bool {
+        self.completed.contains(&paths::resolve(&self.cwd, dir))
+    }
+
+    // Only returns once the line is on disk, so that a crash right after it keeps the record
+    pub fn complete(&mut self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let dir = paths::resolve(&self.cwd, dir);
+        let name = paths::to_portable_string(&dir, Separators::Native);
+        writeln!(self.file, "{}\t{}", self.invocation, name)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|e| format!("Could not write checkpoint {}: {}", self.path.display(), e))?;
+        self.completed.insert(dir);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn checkpoint_round_trip() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("checkpoint");
+
+        let mut checkpoint = Checkpoint::open(&path, "abc")?;
+        assert_eq!(0, checkpoint.completed());
+        checkpoint.complete(Path::new("/data/a"))?;
+        checkpoint.complete(Path::new("/data/b"))?;
+        // Used to be left out, as a line could not hold them
+        checkpoint.complete(Path::new("/data/a\nb"))?;
+        checkpoint.complete(Path::new("/data/50%"))?;
+        drop(checkpoint);
+
+        assert!(fs::read_to_string(&path)?.contains("abc\t/data/a%0Ab\n"));
+        let checkpoint = Checkpoint::open(&path, "abc")?;
+        assert_eq!(4, checkpoint.completed());
+        assert!(checkpoint.is_completed(Path::new("/data/a\nb")));
+        assert!(checkpoint.is_completed(Path::new("/data/50%")));
+        assert!(checkpoint.is_completed(Path::new("/data/a")));
+        assert!(!checkpoint.is_completed(Path::new("/data/c")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint_partial_line() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("checkpoint");
+        fs::write(&path, "abc\t/data/a\nabc\t/da")?;
+
+        let mut checkpoint = Checkpoint::open(&path, "abc")?;
+        assert_eq!(1, checkpoint.completed());
+        checkpoint.complete(Path::new("/data/b"))?;
+
+        assert_eq!(
+            "abc\t/data/a\nabc\t/da\nabc\t/data/b\n",
+            fs::read_to_string(&path)?
+        );
+        // The partial line is now complete, and names a directory that does not exist
+        assert_eq!(3, Checkpoint::open(&path, "abc")?.completed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint_other_invocation() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("checkpoint");
+        fs::write(&path, "abc\t/data/a\n")?;
+
+        let error = Checkpoint::open(&path, "def").err().unwrap().to_string();
+        assert!(error.contains("another invocation"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invocation_depends_on_arguments() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        let cwd = Path::new("/srv");
+
+        assert_eq!(
+            invocation(cwd, args(&["rmx", "-r", "log"])),
+            invocation(cwd, args(&["rmx", "-r", "log"]))
+        );
+        assert_ne!(
+            invocation(cwd, args(&["rmx", "-r", "log"])),
+            invocation(cwd, args(&["rmx", "-r", "tmp"]))
+        );
+        // Arguments are separated, not concatenated
+        assert_ne!(
+            invocation(cwd, args(&["rmx", "ab", "c"])),
+            invocation(cwd, args(&["rmx", "a", "bc"]))
+        );
+        assert_ne!(
+            invocation(cwd, args(&["rmx", "log"])),
+            invocation(Path::new("/tmp"), args(&["rmx", "log"]))
+        );
+    }
+}
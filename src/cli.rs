@@ -0,0 +1,475 @@
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
+use crate::arguments::Args;
+use crate::checkpoint::{self, Checkpoint};
+use crate::history;
+use crate::plan::DeletionPlan;
+use crate::report::{self, CollectStats, DeleteReport};
+use crate::warning;
+use crate::wizard;
+use crate::{Action, Candidate, CandidateKind, CollectOptions, DeleteOptions};
+
+// Under --strict, warnings given during the collection stop the run before any deletion
+const STRICT_BEFORE_DELETION: &str = "aborting before deleting anything";
+
+// Roots are collected together, before a single prompt and deletion
+fn run(
+    extensions: &[String],
+    roots: &[PathBuf],
+    options: &(CollectOptions, DeleteOptions),
+    // For the steps around the collection and the deletion, like --plan-out or --select
+    args: &Args,
+    checkpoint: Option<&mut Checkpoint>,
+) -> Result<(CollectStats, DeleteReport, Option<String>), Box<dyn Error>> {
+    // Filesystem of the start path, or of the first one, for the freed space
+    let path = &roots[0];
+
+    if options.0.empty_dirs {
+        let mut to_delete = Vec::new();
+        for root in roots {
+            to_delete.extend(crate::collect_empty_dirs(root, &options.0)?);
+        }
+        let candidates: Vec<Candidate> = to_delete
+            .iter()
+            .map(|dir| Candidate::new(dir.clone(), CandidateKind::EmptyDir))
+            .collect();
+        args.check_policy(&candidates)?;
+        warning::check(STRICT_BEFORE_DELETION)?;
+        let report = crate::delete_empty_dirs(&to_delete, &options.1)?;
+        return Ok((CollectStats::default(), report, None));
+    }
+
+    if args.pycache() {
+        let (to_delete, stats) = crate::collect_pycache_in_roots(roots, &options.0)?;
+        args.check_policy(&to_delete)?;
+        warning::check(STRICT_BEFORE_DELETION)?;
+        let report = crate::delete_pycache(&to_delete, &options.1)?;
+        return Ok((stats, report, None));
+    }
+
+    // Candidates rather than paths, so that their metadata is read once for the whole run
+    let (mut to_delete, stats) = match args.files_from() {
+        // Nothing is walked, the file being what is deleted
+        None if let Some(file) = args.single_file() => {
+            if !args.no_ext_check() && !crate::file_matches(file, extensions, &options.0) {
+                return Err(format!(
+                    "{} does not match the given extensions, --no-ext-check deletes it anyway.",
+                    file.display()
+                )
+                .into());
+            }
+            let candidates = vec![Candidate::new(file.to_path_buf(), CandidateKind::File)];
+            crate::list_candidates(&candidates, &options.0)?;
+            (candidates, CollectStats::default())
+        }
+        Some(list) => {
+            let mut files = crate::listed_files(list, extensions, &options.0)?;
+            let mut stats = CollectStats::default();
+            if let Some(limit) = args.limit()
+                && files.len() > limit
+            {
+                files.truncate(limit);
+                stats.limit_reached = true;
+            }
+            let candidates: Vec<Candidate> = files
+                .into_iter()
+                .map(|f| Candidate::new(f, CandidateKind::File))
+                .collect();
+            crate::list_candidates(&candidates, &options.0)?;
+            (candidates, stats)
+        }
+        None => crate::collect_candidates_in_roots(extensions, roots, &options.0)?,
+    };
+    if let Some(limit) = args.limit()
+        && let Some(note) = report::format_limit_reached(&stats, limit)
+        && !args.oneline()
+        && !args.quiet()
+    {
+        println!("{note}");
+    }
+    if args.select() {
+        match crate::select_files(&to_delete, options.0.long)? {
+            Some(chosen) => to_delete = chosen,
+            None => {
+                println!("Selection aborted, nothing was deleted.");
+                let report = DeleteReport {
+                    matched: to_delete.len(),
+                    cancelled: true,
+                    ..Default::default()
+                };
+                return Ok((stats, report, None));
+            }
+        }
+    }
+    args.check_policy(&to_delete)?;
+    let paths: Vec<PathBuf> = to_delete.iter().map(|c| c.path.clone()).collect();
+    if let Some(plan_out) = args.plan_out() {
+        DeletionPlan::from_candidates(path, &to_delete, plan_out.1).write(plan_out.0)?;
+    }
+    // Written and flushed before anything is deleted, any error aborting the run
+    if let Some((out, jobs)) = args.inventory() {
+        for (file, e) in crate::inventory::write(out, &paths, jobs)? {
+            warning::warn(format!("could not hash {}: {e}", file.display()));
+        }
+    }
+    warning::check(STRICT_BEFORE_DELETION)?;
+
+    // Reading the device of each file is only worth it when something may be deleted
+    let measured =
+        !options.1.dry_run && !paths.is_empty() && crate::on_start_filesystem(path, &to_delete);
+    let available_before = crate::available_space(path);
+
+    let report = crate::delete_collected(&to_delete, &options.1, checkpoint)?;
+
+    let freed = if report.deleted == 0 {
+        None
+    } else if !measured {
+        Some(report::FREED_NOT_MEASURED.to_string())
+    } else {
+        available_before
+            .zip(crate::available_space(path))
+            .map(|(before, after)| report::format_freed(before, after, report.bytes))
+    };
+    Ok((stats, report, freed))
+}
+
+fn simulate(
+    plan_path: &Path,
+    extensions: &[String],
+    options: &CollectOptions,
+) -> Result<(), Box<dyn Error>> {
+    let plan = DeletionPlan::load(plan_path)?;
+    let (files, stats) = crate::simulate(&plan, extensions, options);
+
+    for file in &files {
+        println!("{}", file.path);
+    }
+    let bytes = files.iter().map(|f| f.size).sum();
+    println!(
+        "{}",
+        report::format_simulation(files.len(), plan.files.len(), bytes)
+    );
+    if let Some(note) = report::format_age_protection(&stats) {
+        println!("{note}");
+    }
+    Ok(())
+}
+
+// Best-effort: a history that cannot be written never fails the run
+fn record_history(
+    args: &Args,
+    path: &Path,
+    report: &DeleteReport,
+    status: i32,
+    ad_hoc_extensions: &[String],
+) {
+    let limit = args.history_limit();
+    let Some(file) = history::history_path().filter(|_| limit > 0) else {
+        return;
+    };
+    let invocation = std::env::args_os()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    let mut record = history::Record::new(invocation, path, report, status);
+    record.extensions = ad_hoc_extensions.to_vec();
+    if let Err(e) = history::append(&file, &record, limit) {
+        warning::warn(format!(
+            "could not record the run in {}: {e}",
+            file.display()
+        ));
+    }
+}
+
+// Once the history holds three runs typing the same extensions, the one just recorded included.
+// Later runs say nothing more, so that it is not repeated
+fn preset_tip(args: &Args, ad_hoc_extensions: &[String]) -> Option<String> {
+    if ad_hoc_extensions.is_empty() || !args.suggestions() || args.history_limit() == 0 {
+        return None;
+    }
+    let records = history::read_last(&history::history_path()?, args.history_limit()).ok()?;
+    (history::runs_with_extensions(&records, ad_hoc_extensions) == 3)
+        .then(|| history::format_preset_tip(ad_hoc_extensions, &args.config_path()))
+}
+
+// Start paths in the notation of the user, a path given twice or inside another one being
+// dropped so that no file is listed nor deleted twice
+fn unique_roots(paths: &[PathBuf], args: &Args) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if paths.len() < 2 {
+        return Ok(paths.to_vec());
+    }
+    let roots = crate::roots::dedup_roots(paths)?;
+    if !args.oneline() && !args.quiet() {
+        for (dropped, kept) in &roots.dropped {
+            println!("{}", report::format_covered_root(dropped, kept));
+        }
+    }
+
+    let mut unique = Vec::new();
+    let mut kept = roots.kept.iter().peekable();
+    for path in paths {
+        // Kept roots come in the order they were given, each canonical path once
+        if kept
+            .peek()
+            .is_some_and(|k| fs::canonicalize(path).is_ok_and(|c| c == **k))
+        {
+            kept.next();
+            unique.push(path.clone());
+        }
+    }
+    Ok(unique)
+}
+
+// The start path, or all of them joined like PATH, for summaries, hooks and the history
+fn roots_label(roots: &[PathBuf]) -> PathBuf {
+    match roots {
+        [root] => root.clone(),
+        _ => env::join_paths(roots)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| roots[0].clone()),
+    }
+}
+
+// Asks the user to type the name of the directory, as a plain y/n answer is too easy to give
+fn confirm_invert_recursive(path: &Path) -> Result<(), Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(
+            "Refusing to combine --invert, --recurse and --force without a terminal, \
+            pass --allow-invert-recursive to proceed."
+                .into(),
+        );
+    }
+
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => path.to_string_lossy(),
+    };
+
+    println!(
+        "This will delete every file not matching the given extensions under {}, without any prompt.",
+        path.display()
+    );
+    print!("Type the directory name ({name}) to proceed: ");
+    io::stdout().flush()?;
+
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf)?;
+
+    if buf.trim() != name {
+        return Err("Directory name did not match, nothing was deleted.".into());
+    }
+    Ok(())
+}
+
+// Arguments of the run the wizard leads to, exiting when it leads to none
+fn start_wizard() -> Result<Vec<OsString>, Box<dyn Error>> {
+    let mut prompter = wizard::Prompter::new(io::stdin().lock(), io::stdout());
+    let config = crate::config::resolve_config_path(None);
+    match wizard::run(&mut prompter, &env::current_dir()?, &config)? {
+        Some(argv) => Ok(argv),
+        None => process::exit(0),
+    }
+}
+
+// Whole run of the `rmx` binary, from its arguments, the first one being the name of the binary.
+// Shared with `cargo-rmx`, which gives its own defaults
+// Prints a fatal error, then exits. Runs printing JSON get it as a JSON object on stdout instead
+// (see error::to_json), other ones as `context` and the message on stderr
+fn fail(json: bool, context: &str, e: &(dyn Error + 'static)) -> ! {
+    if json {
+        println!("{}", crate::error::to_json(e));
+    } else {
+        eprintln!("{context}{e}");
+    }
+    process::exit(1);
+}
+
+pub fn main<I, T>(argv: I)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    // New users are guided rather than shown a usage error, which scripts keep getting
+    if argv.len() == 1 && io::stdin().is_terminal() && io::stdout().is_terminal() {
+        argv = start_wizard().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            process::exit(1);
+        });
+    }
+
+    let json = Args::json_requested(&argv);
+    let args = Args::parse_from(argv).unwrap_or_else(|e| fail(json, "", e.as_ref()));
+
+    let Some(args) = args else {
+        // Happens with --help / --version
+        process::exit(0);
+    };
+
+    let (extensions, sources): (Vec<String>, Vec<String>) = args
+        .get_extensions_with_sources()
+        .unwrap_or_else(|e| fail(json, "Error while collecting extensions: ", e.as_ref()))
+        .into_iter()
+        .unzip();
+
+    let paths = args.get_paths();
+
+    if args.discover() {
+        let stats = crate::discover_extensions(&paths[0], &args.get_options().0)
+            .unwrap_or_else(|e| fail(json, "Error: ", e.as_ref()));
+        for line in crate::discover::format_table(&stats) {
+            println!("{line}");
+        }
+        return;
+    }
+
+    if let Some(plan_path) = args.simulate() {
+        simulate(plan_path, &extensions, &args.get_options().0)
+            .unwrap_or_else(|e| fail(json, "Error: ", e.as_ref()));
+        return;
+    }
+
+    let roots = unique_roots(&paths, &args).unwrap_or_else(|e| fail(json, "Error: ", e.as_ref()));
+    let path = roots_label(&roots);
+
+    for root in &roots {
+        // Paths keep the notation of the user, the real one being shown once
+        let resolved = crate::roots::resolve_symlink(root);
+        if let Some(real) = &resolved
+            && !args.oneline()
+            && !args.quiet()
+        {
+            println!("Note: {} resolves to {}", root.display(), real.display());
+        }
+
+        if args.needs_invert_recursive_ack() {
+            // The name to type is the real one, so that an unexpected link target is noticed
+            confirm_invert_recursive(resolved.as_deref().unwrap_or(root))
+                .unwrap_or_else(|e| fail(json, "", e.as_ref()));
+        }
+    }
+
+    let options = args.get_options();
+
+    if args.nice() {
+        crate::lower_priority();
+    }
+
+    // Opened before anything is deleted, so that a mismatched checkpoint stops the run
+    let mut checkpoint = args.checkpoint().map(|file| {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let invocation = checkpoint::invocation(&cwd, std::env::args_os());
+        Checkpoint::open(file, &invocation).unwrap_or_else(|e| fail(json, "Error: ", e.as_ref()))
+    });
+    if let Some(checkpoint) = &checkpoint
+        && checkpoint.completed() > 0
+        && !args.oneline()
+        && !args.quiet()
+    {
+        println!("{}", report::format_resume(checkpoint.completed()));
+    }
+
+    let start = Instant::now();
+    let (stats, report, freed) = run(&extensions, &roots, &options, &args, checkpoint.as_mut())
+        .unwrap_or_else(|e| fail(json, "Error: ", e.as_ref()));
+
+    if args.touch_parents() {
+        for (dir, e) in crate::touch_parents(&report) {
+            warning::warn(format!("could not touch {}: {e}", dir.display()));
+        }
+    }
+
+    if args.oneline() {
+        println!(
+            "{}",
+            report::format_oneline(&report, &path, start.elapsed(), args.human_width())
+        );
+    } else if !args.quiet() {
+        for line in report::format_locked(&report, args.human_width()) {
+            println!("{line}");
+        }
+        for line in report::format_vanished(&report, args.human_width()) {
+            println!("{line}");
+        }
+        let renamed = match &options.1.action {
+            Action::Rename(suffix) => report::format_renamed(&report, suffix),
+            _ => None,
+        };
+        let notes = [
+            report::format_declined(&report),
+            renamed,
+            freed,
+            report::format_locked_count(&report),
+            report::format_attribution(&sources, &stats),
+            report::format_age_protection(&stats),
+            report::format_owner_protection(&stats),
+            report::format_readonly(&stats),
+            report::format_link_targets(&stats),
+            report::format_mount_points(&stats),
+            report::format_keep_list(&stats),
+            report::format_dirs_protected(&stats),
+            report::format_hidden_hint(&stats),
+        ];
+        for note in notes.iter().flatten() {
+            println!("{note}");
+        }
+    }
+
+    if let Some(cmd) = args.notify_cmd()
+        && let Err(e) = crate::hook::notify(cmd, &report, &path, args.notify_timeout())
+    {
+        warning::warn(e.to_string());
+    }
+
+    // Anything that went wrong fails the run, deleting having stopped at the first failure
+    let mut conditions = Vec::new();
+    if warning::is_strict() {
+        conditions = warning::warnings();
+        conditions.extend(
+            report
+                .locked
+                .iter()
+                .map(|p| format!("{} is locked by another process", p.display())),
+        );
+        conditions.extend(
+            report
+                .failures
+                .iter()
+                .map(|(p, e)| format!("could not delete {}: {e}", p.display())),
+        );
+    }
+
+    let status = if conditions.is_empty() && report.failures.is_empty() {
+        0
+    } else {
+        1
+    };
+    if !report.dry_run {
+        let ad_hoc_extensions = args.ad_hoc_extensions(&extensions);
+        record_history(&args, &path, &report, status, &ad_hoc_extensions);
+        if let Some(tip) = preset_tip(&args, &ad_hoc_extensions)
+            && !args.oneline()
+            && !args.quiet()
+        {
+            println!("{tip}");
+        }
+    }
+
+    if !conditions.is_empty() {
+        eprintln!("{}", warning::format_strict("stopped", &conditions));
+        process::exit(1);
+    }
+
+    if !report.failures.is_empty() {
+        for line in report::format_failures(&report, args.human_width()) {
+            eprintln!("{line}");
+        }
+        process::exit(1);
+    }
+}
@@ -0,0 +1,47 @@
+// Files created by macOS on foreign filesystems, matched by name rather than by extension
+enum Pattern {
+    Exact(&'static str),
+    // Only matches names longer than the prefix itself
+    Prefix(&'static str),
+}
+
+// AppleDouble files (`._photo.jpg`) are matched whether or not `photo.jpg` still exists next to
+// them, as they are useless once copied out of a macOS filesystem
+const APPLE_CRUFT: &[Pattern] = &[Pattern::Prefix("._"), Pattern::Exact(".DS_Store")];
+
+pub(crate) fn is_apple_cruft(filename: &str) -> bool {
+    APPLE_CRUFT.iter().any(|pattern| match pattern {
+        Pattern::Exact(name) => filename == *name,
+        Pattern::Prefix(prefix) => filename.len() > prefix.len() && filename.starts_with(prefix),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ds_store_is_cruft() {
+        assert!(is_apple_cruft(".DS_Store"));
+        assert!(!is_apple_cruft("DS_Store"));
+        assert!(!is_apple_cruft(".DS_Store.txt"));
+    }
+
+    #[test]
+    fn apple_double_with_sibling_name_is_cruft() {
+        // Would sit next to photo.jpg
+        assert!(is_apple_cruft("._photo.jpg"));
+    }
+
+    #[test]
+    fn apple_double_without_extension_is_cruft() {
+        assert!(is_apple_cruft("._Icon"));
+    }
+
+    #[test]
+    fn apple_double_prefix_alone_is_not_cruft() {
+        assert!(!is_apple_cruft("._"));
+        assert!(!is_apple_cruft("_.photo.jpg"));
+        assert!(!is_apple_cruft(".photo.jpg"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::paths::resolve;
+use crate::warning;
+
+// One directory per line, relative paths being resolved against the directory holding the list
+pub(crate) fn parse(contents: &str, base: &Path) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| resolve(base, Path::new(line)))
+        .collect()
+}
+
+// Reads the list `name` in `dir` if there is one, warning about and skipping unusable lines
+pub(crate) fn read(dir: &Path, name: &str) -> Vec<PathBuf> {
+    let list = dir.join(name);
+    let contents = match fs::read_to_string(&list) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            warning::warn(format!("could not read {:?}, ignoring it: {e}", list));
+            return Vec::new();
+        }
+    };
+
+    parse(&contents, dir)
+        .into_iter()
+        .filter(|p| {
+            let is_dir = p.is_dir();
+            if !is_dir {
+                warning::warn(format!(
+                    "{:?} listed in {:?} is not a directory, skipping it",
+                    p, list
+                ));
+            }
+            is_dir
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_resolves_relative_paths() {
+        let listed = parse("/srv/cache\n\n  ../logs  \nbuild\n", Path::new("/data"));
+
+        assert_eq!(
+            vec![
+                PathBuf::from("/srv/cache"),
+                PathBuf::from("/logs"),
+                PathBuf::from("/data/build"),
+            ],
+            listed
+        );
+    }
+
+    #[test]
+    fn read_skips_invalid_lines() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let dir = temp_dir.path();
+        fs::create_dir(dir.join("kept"))?;
+        fs::File::create(dir.join("file"))?;
+        fs::write(dir.join("rmx.dirs"), "kept\nfile\nmissing\n")?;
+
+        assert_eq!(vec![dir.join("kept")], read(dir, "rmx.dirs"));
+        assert!(read(dir, "other.dirs").is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::report::{format_count, format_size};
+
+// Files sharing an extension, as found by --discover
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionStats {
+    // None for files without extension
+    pub extension: Option<String>,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+// Groups files by extension, most frequent first, then by name so that the order is stable
+pub fn aggregate<'a>(
+    files: impl IntoIterator<Item = (Option<&'a str>, u64)>,
+) -> Vec<ExtensionStats> {
+    let mut groups: HashMap<Option<&str>, (usize, u64)> = HashMap::new();
+    for (extension, bytes) in files {
+        // `file.` has an empty extension, which is no extension at all
+        let extension = extension.filter(|e| !e.is_empty());
+        let group = groups.entry(extension).or_default();
+        group.0 += 1;
+        group.1 += bytes;
+    }
+
+    let mut stats: Vec<ExtensionStats> = groups
+        .into_iter()
+        .map(|(extension, (count, bytes))| ExtensionStats {
+            extension: extension.map(|e| e.to_string()),
+            count,
+            bytes,
+        })
+        .collect();
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then(a.extension.cmp(&b.extension)));
+    stats
+}
+
+// Header and one aligned row per extension
+pub fn format_table(stats: &[ExtensionStats]) -> Vec<String> {
+    const HEADER: &str = "extension";
+
+    let names: Vec<&str> = stats
+        .iter()
+        .map(|s| s.extension.as_deref().unwrap_or("(none)"))
+        .collect();
+    let width = names
+        .iter()
+        .map(|n| n.chars().count())
+        .fold(HEADER.len(), usize::max);
+
+    let mut lines = vec![format!("{HEADER:<width$} {:>9} {:>10}", "files", "size")];
+    for (name, s) in names.iter().zip(stats) {
+        lines.push(format!(
+            "{name:<width$} {:>9} {:>10}",
+            format_count(s.count),
+            format_size(s.bytes)
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_by_extension() {
+        let files = [
+            (Some("txt"), 10),
+            (None, 1),
+            (Some("log"), 100),
+            (Some("txt"), 5),
+            (Some(""), 2),
+            (Some("dat"), 1),
+        ];
+
+        let stats = aggregate(files);
+
+        assert_eq!(
+            vec![
+                ExtensionStats {
+                    extension: None,
+                    count: 2,
+                    bytes: 3
+                },
+                ExtensionStats {
+                    extension: Some("txt".to_string()),
+                    count: 2,
+                    bytes: 15
+                },
+                ExtensionStats {
+                    extension: Some("dat".to_string()),
+                    count: 1,
+                    bytes: 1
+                },
+                ExtensionStats {
+                    extension: Some("log".to_string()),
+                    count: 1,
+                    bytes: 100
+                },
+            ],
+            stats
+        );
+    }
+
+    #[test]
+    fn aggregate_nothing() {
+        assert!(aggregate([]).is_empty());
+    }
+
+    #[test]
+    fn table_is_aligned() {
+        let stats = aggregate([(Some("tar.gz"), 2048), (Some("tar.gz"), 0), (None, 12)]);
+
+        assert_eq!(
+            vec![
+                "extension     files       size",
+                "tar.gz            2    2.0 KiB",
+                "(none)            1       12 B",
+            ],
+            format_table(&stats)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::env;
+use std::io;
+use std::path::{MAIN_SEPARATOR, Path};
+
+use terminal_size::{Width, terminal_size, terminal_size_of};
+
+const ELLIPSIS: char = '…';
+const DEFAULT_WIDTH: usize = 80;
+// Paths embedded in longer messages are never shortened below this
+const MIN_PATH_WIDTH: usize = 20;
+
+// Width of the terminal, falling back to $COLUMNS, then to 80 columns
+pub(crate) fn output_width() -> usize {
+    terminal_size()
+        .or_else(|| terminal_size_of(io::stderr()))
+        .map(|(Width(w), _)| w as usize)
+        .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+        .filter(|w| *w > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+// Last `n` characters of `s`, cut on a character boundary
+fn tail(s: &str, n: usize) -> &str {
+    let skip = char_len(s).saturating_sub(n);
+    match s.char_indices().nth(skip) {
+        Some((i, _)) => &s[i..],
+        None => "",
+    }
+}
+
+// Shortens a path to at most `max` characters by replacing its middle with an ellipsis, keeping
+// the first component and the last two, as the filename is the most important part:
+// /srv/nfs/projects/2024/build/output/report.log -> /srv/…/output/report.log
+pub(crate) fn shorten_path(path: &str, max: usize) -> String {
+    if char_len(path) <= max {
+        return path.to_string();
+    }
+
+    let separator = MAIN_SEPARATOR.to_string();
+    let absolute = path.starts_with(MAIN_SEPARATOR);
+    let components: Vec<&str> = path
+        .split(MAIN_SEPARATOR)
+        .filter(|c| !c.is_empty())
+        .collect();
+    let root = if absolute { separator.as_str() } else { "" };
+
+    for kept in [2, 1] {
+        if components.len() <= kept + 1 {
+            continue;
+        }
+        let shortened = format!(
+            "{root}{}{MAIN_SEPARATOR}{ELLIPSIS}{MAIN_SEPARATOR}{}",
+            components[0],
+            components[components.len() - kept..].join(&separator)
+        );
+        if char_len(&shortened) <= max {
+            return shortened;
+        }
+    }
+
+    // Even the filename alone is too long: keep its end
+    format!("{ELLIPSIS}{}", tail(path, max.saturating_sub(1)))
+}
+
+// Builds `before path after`, shortening the path so that the whole line fits in `width`
+pub(crate) fn line_with_path(
+    before: &str,
+    path: &Path,
+    after: &str,
+    width: Option<usize>,
+) -> String {
+    let path = path.to_string_lossy();
+    let path = match width {
+        Some(width) => {
+            let budget = width.saturating_sub(char_len(before) + char_len(after));
+            shorten_path(&path, budget.max(MIN_PATH_WIDTH))
+        }
+        None => path.into_owned(),
+    };
+    format!("{before}{path}{after}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_path_is_kept() {
+        assert_eq!("/srv/logs/a.log", shorten_path("/srv/logs/a.log", 80));
+    }
+
+    #[test]
+    fn long_path_keeps_first_and_last_two_components() {
+        let path = "/srv/nfs/projects/2024/build/output/report.log";
+
+        assert_eq!("/srv/…/output/report.log", shorten_path(path, 30));
+    }
+
+    #[test]
+    fn relative_path() {
+        let path = "build/debug/deps/incremental/lib/output.o";
+
+        assert_eq!("build/…/lib/output.o", shorten_path(path, 25));
+    }
+
+    #[test]
+    fn very_narrow_keeps_filename() {
+        let path = "/srv/nfs/projects/2024/build/output/report.log";
+
+        assert_eq!("/srv/…/report.log", shorten_path(path, 18));
+        assert_eq!("…port.log", shorten_path(path, 9));
+    }
+
+    #[test]
+    fn multibyte_characters() {
+        let path = "/données/été/œuvres/très/longues/résumé.txt";
+
+        let shortened = shorten_path(path, 30);
+
+        assert_eq!("/données/…/longues/résumé.txt", shortened);
+        assert!(char_len(&shortened) <= 30);
+
+        let tail = shorten_path(path, 6);
+        assert_eq!("…é.txt", tail);
+    }
+
+    #[test]
+    fn line_with_path_fits_width() {
+        let path = Path::new("/srv/nfs/projects/2024/build/output/report.log");
+
+        let line = line_with_path("rmx: deleted under ", path, " in 1.0s", Some(51));
+        let unbounded = line_with_path("rmx: deleted under ", path, " in 1.0s", None);
+
+        assert_eq!("rmx: deleted under /srv/…/output/report.log in 1.0s", line);
+        assert!(unbounded.contains("/srv/nfs/projects/2024/build/output/report.log"));
+    }
+}
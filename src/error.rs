@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+/// Errors of the library API, for callers that need to tell failures apart
+///
+/// ```
+/// use rmx::RmxError;
+///
+/// let res = rmx::preset::load_preset("latex", std::path::Path::new("-missing-"));
+///
+/// assert!(matches!(res, Err(RmxError::ConfigRead { .. })));
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RmxError {
+    /// The config file exists but could not be read
+    ConfigRead {
+        path: PathBuf,
+        source: io::Error,
+    },
+    PresetNotFound {
+        name: String,
+        path: PathBuf,
+    },
+    /// The preset line exists but is malformed, or lists no extension
+    InvalidPreset {
+        reason: String,
+        path: PathBuf,
+    },
+    /// A requested extension is malformed once normalized, like `*`
+    InvalidExtension {
+        value: String,
+        origin: ExtensionOrigin,
+    },
+    /// A start path that does not exist
+    PathNotFound {
+        path: PathBuf,
+    },
+}
+
+/// Where an invalid extension was requested, to point at the line to fix
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtensionOrigin {
+    /// Position among the extensions of the command line, from 1
+    CommandLine { position: usize },
+    /// Extensions of the job, used as the command line gives none
+    Job { name: String, path: PathBuf },
+    Preset {
+        name: String,
+        path: PathBuf,
+        line: usize,
+    },
+    /// Synonym of a requested extension, from an `alias` line of the config file
+    Alias { extension: String, path: PathBuf },
+}
+
+impl fmt::Display for ExtensionOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtensionOrigin::CommandLine { position } => {
+                write!(f, "given as extension {position} on the command line")
+            }
+            ExtensionOrigin::Job { name, path } => {
+                write!(f, "in job \"{}\" of {}", name, path.display())
+            }
+            ExtensionOrigin::Preset { name, path, line } => {
+                write!(
+                    f,
+                    "in preset \"{}\" of {}, line {}",
+                    name,
+                    path.display(),
+                    line
+                )
+            }
+            ExtensionOrigin::Alias { extension, path } => {
+                write!(f, "in an alias of \"{}\" in {}", extension, path.display())
+            }
+        }
+    }
+}
+
+impl RmxError {
+    /// Name of the variant, which never changes once released, for callers and outputs that
+    /// tell failures apart without matching on messages
+    ///
+    /// ```
+    /// let res = rmx::preset::load_preset("latex", std::path::Path::new("-missing-"));
+    ///
+    /// assert_eq!("config_read", res.unwrap_err().kind());
+    /// ```
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RmxError::ConfigRead { .. } => "config_read",
+            RmxError::PresetNotFound { .. } => "preset_not_found",
+            RmxError::InvalidPreset { .. } => "invalid_preset",
+            RmxError::InvalidExtension { .. } => "invalid_extension",
+            RmxError::PathNotFound { .. } => "path_not_found",
+        }
+    }
+
+    /// File the error is about, if any, like the config file of a preset
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            RmxError::ConfigRead { path, .. }
+            | RmxError::PresetNotFound { path, .. }
+            | RmxError::InvalidPreset { path, .. }
+            | RmxError::PathNotFound { path } => Some(path),
+            RmxError::InvalidExtension { origin, .. } => origin.path(),
+        }
+    }
+}
+
+impl ExtensionOrigin {
+    /// Config file the extension was read from, None for the command line
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ExtensionOrigin::CommandLine { .. } => None,
+            ExtensionOrigin::Job { path, .. }
+            | ExtensionOrigin::Preset { path, .. }
+            | ExtensionOrigin::Alias { path, .. } => Some(path),
+        }
+    }
+}
+
+impl fmt::Display for RmxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RmxError::ConfigRead { path, source } => {
+                write!(f, "Could not read {}: {}", path.display(), source)
+            }
+            RmxError::PresetNotFound { name, path } => {
+                write!(
+                    f,
+                    "Could not find preset \"{}\" in {}",
+                    name,
+                    path.display()
+                )
+            }
+            RmxError::InvalidPreset { reason, path } => {
+                write!(f, "{} in {}", reason, path.display())
+            }
+            RmxError::InvalidExtension { value, origin } => {
+                write!(f, "Invalid extension \"{value}\" {origin}")
+            }
+            RmxError::PathNotFound { path } => {
+                write!(f, "Could not find {}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for RmxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RmxError::ConfigRead { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// The `{"error": {...}}` object printed on stdout instead of the message when a run fails in a
+// mode whose output is JSON. Errors other than RmxError are of kind `io` or `other`, with no path
+pub(crate) fn to_json(error: &(dyn Error + 'static)) -> String {
+    let (kind, path) = match error.downcast_ref::<RmxError>() {
+        Some(e) => (e.kind(), e.path()),
+        None if error.is::<io::Error>() => ("io", None),
+        None => ("other", None),
+    };
+    json!({
+        "error": {
+            "kind": kind,
+            "message": error.to_string(),
+            "path": path.map(|p| p.to_string_lossy()),
+        }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+
+    #[test]
+    fn errors_as_json() {
+        let e = RmxError::PathNotFound {
+            path: PathBuf::from("/srv/missing"),
+        };
+        let json: Value = serde_json::from_str(&to_json(&e)).unwrap();
+        assert_eq!("path_not_found", json["error"]["kind"]);
+        assert_eq!("Could not find /srv/missing", json["error"]["message"]);
+        assert_eq!("/srv/missing", json["error"]["path"]);
+
+        let e: Box<dyn Error> = "--limit must be positive.".into();
+        let json: Value = serde_json::from_str(&to_json(e.as_ref())).unwrap();
+        assert_eq!("other", json["error"]["kind"]);
+        assert_eq!(Value::Null, json["error"]["path"]);
+
+        let e = io::Error::other("disk on fire");
+        let json: Value = serde_json::from_str(&to_json(&e)).unwrap();
+        assert_eq!("io", json["error"]["kind"]);
+    }
+}
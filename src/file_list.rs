@@ -0,0 +1,135 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use crate::paths::{self, resolve};
+use crate::warning;
+
+// Files to delete as they are, one per line (see --files-from), relative paths being resolved
+// against `base`. Trailing whitespace and blank lines are left out, like those of a list edited
+// by hand
+pub(crate) fn parse(contents: &str, base: &Path) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| resolve(base, Path::new(line)))
+        .collect()
+}
+
+// Reads the list `source`, `-` being stdin, warning about and skipping lines that are not an
+// existing regular file. Relative lines of stdin are relative to the current directory
+pub(crate) fn read(source: &Path, base: Option<&Path>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let (contents, base, label) = if source == Path::new("-") {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        let base = match base {
+            Some(base) => paths::absolute(base)?,
+            None => env::current_dir()?,
+        };
+        (contents, base, "stdin".to_string())
+    } else {
+        let contents = fs::read_to_string(source)
+            .map_err(|e| format!("Could not read file list {}: {e}", source.display()))?;
+        (
+            contents,
+            paths::list_base(source, base)?,
+            format!("{:?}", source),
+        )
+    };
+
+    Ok(parse(&contents, &base)
+        .into_iter()
+        .filter(|p| match fs::symlink_metadata(p) {
+            Ok(metadata) if metadata.is_file() => true,
+            Ok(metadata) if metadata.is_dir() => {
+                warning::warn(format!(
+                    "{:?} listed in {label} is a directory, skipping it",
+                    p
+                ));
+                false
+            }
+            Ok(_) => {
+                warning::warn(format!(
+                    "{:?} listed in {label} is not a regular file, skipping it",
+                    p
+                ));
+                false
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                warning::warn(format!(
+                    "{:?} listed in {label} does not exist, skipping it",
+                    p
+                ));
+                false
+            }
+            Err(e) => {
+                warning::warn(format!("could not read {:?}, skipping it: {e}", p));
+                false
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_skips_blank_lines_and_trailing_whitespace() {
+        let listed = parse(
+            "/srv/a.log  \n\n   \nlogs/b.log\t\r\n../c.log\n",
+            Path::new("/data"),
+        );
+
+        assert_eq!(
+            vec![
+                PathBuf::from("/srv/a.log"),
+                PathBuf::from("/data/logs/b.log"),
+                PathBuf::from("/c.log"),
+            ],
+            listed
+        );
+    }
+
+    #[test]
+    fn parse_keeps_leading_whitespace() {
+        assert_eq!(
+            vec![PathBuf::from("/data/ a.log")],
+            parse(" a.log\n", Path::new("/data"))
+        );
+    }
+
+    #[test]
+    fn read_skips_missing_files_and_directories() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        File::create(dir.path().join("a.log"))?;
+        fs::create_dir(dir.path().join("logs"))?;
+        let list = dir.path().join("list.txt");
+        fs::write(&list, "a.log\nlogs\nmissing.log\n")?;
+
+        assert_eq!(vec![dir.path().join("a.log")], read(&list, None)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_against_base() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let data = dir.path().join("data");
+        fs::create_dir(&data)?;
+        File::create(data.join("a.log"))?;
+        let list = dir.path().join("list.txt");
+        fs::write(&list, "a.log\n")?;
+
+        assert_eq!(vec![data.join("a.log")], read(&list, Some(&data))?);
+        assert!(read(&dir.path().join("missing.txt"), None).is_err());
+
+        Ok(())
+    }
+}
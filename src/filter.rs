@@ -0,0 +1,487 @@
+use std::time::{Duration, SystemTime};
+
+use regex::{Regex, RegexBuilder};
+
+// Filters are predicates over what is known of a file, so that they apply the same way to files
+// met during the traversal and to files stored in a plan (see --simulate)
+
+// Extensions given on the command line, matched as suffixes of the file extension, so that `gz`
+// matches `tar.gz`
+pub(crate) struct ExtensionFilter {
+    dotted: Vec<String>,
+    invert: bool,
+    // Files without any extension, like `Makefile`, stand for one more given extension
+    no_ext: bool,
+    // ASCII case only, like the extensions of files written by Windows tools (see --ignore-case)
+    ignore_case: bool,
+}
+
+impl ExtensionFilter {
+    pub(crate) fn new(extensions: &[String], invert: bool) -> ExtensionFilter {
+        ExtensionFilter {
+            dotted: extensions.iter().map(|s| format!(".{s}")).collect(),
+            invert,
+            no_ext: false,
+            ignore_case: false,
+        }
+    }
+
+    // `JPG` and `Jpg` match `jpg` as well (see --ignore-case)
+    pub(crate) fn with_ignore_case(mut self, ignore_case: bool) -> ExtensionFilter {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    // Also matches files without extension (see --no-ext)
+    pub(crate) fn with_no_ext(mut self, no_ext: bool) -> ExtensionFilter {
+        self.no_ext = no_ext;
+        self
+    }
+
+    // Index of the first given extension matching, whatever the inversion
+    pub(crate) fn matching(&self, file_ext: &str) -> Option<usize> {
+        let dotted_fil_ext = format!(".{file_ext}");
+        if !self.ignore_case {
+            return self.dotted.iter().position(|e| dotted_fil_ext.ends_with(e));
+        }
+        // Compared as bytes, as the suffix may not start on a char boundary
+        let file_bytes = dotted_fil_ext.as_bytes();
+        self.dotted.iter().position(|e| {
+            file_bytes.len() >= e.len()
+                && file_bytes[file_bytes.len() - e.len()..].eq_ignore_ascii_case(e.as_bytes())
+        })
+    }
+
+    pub(crate) fn keeps(&self, file_ext: &str) -> bool {
+        self.invert != self.matching(file_ext).is_some()
+    }
+
+    // Whether a file with this extension, or without any, is one of the given ones
+    pub(crate) fn matches_file(&self, file_ext: Option<&str>) -> bool {
+        match file_ext {
+            Some(file_ext) => self.matching(file_ext).is_some(),
+            None => self.no_ext,
+        }
+    }
+
+    // Files without extension are only ever selected through --no-ext, --invert alone leaving
+    // them alone
+    pub(crate) fn keeps_file(&self, file_ext: Option<&str>) -> bool {
+        match file_ext {
+            Some(file_ext) => self.keeps(file_ext),
+            None => self.no_ext && !self.invert,
+        }
+    }
+
+    // Given extension at this index, as returned by matching
+    pub(crate) fn extension(&self, i: usize) -> &str {
+        &self.dotted[i][1..]
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.dotted.len()
+    }
+
+    // Nothing given, not even --no-ext
+    pub(crate) fn is_empty(&self) -> bool {
+        self.dotted.is_empty() && !self.no_ext
+    }
+}
+
+// Shell patterns over file names, like `data.*` (see --exclude)
+pub(crate) struct ExcludeFilter {
+    patterns: Vec<Regex>,
+}
+
+impl ExcludeFilter {
+    // `fold` matches names whatever their case (see --case-insensitive-fs)
+    pub(crate) fn new(patterns: &[String], fold: bool) -> ExcludeFilter {
+        ExcludeFilter {
+            patterns: patterns.iter().map(|p| glob(p, fold)).collect(),
+        }
+    }
+
+    pub(crate) fn excludes(&self, filename: &str) -> bool {
+        self.patterns.iter().any(|p| p.is_match(filename))
+    }
+}
+
+// Shell pattern matching whole names, whatever their case when `fold` is set
+pub(crate) fn glob(pattern: &str, fold: bool) -> Regex {
+    RegexBuilder::new(&glob_regex(pattern))
+        .case_insensitive(fold)
+        .build()
+        .expect("escaped globs are valid regexes")
+}
+
+// Names compared as a filesystem does, whatever their case on case insensitive ones
+pub(crate) fn same_name(a: &str, b: &str, fold: bool) -> bool {
+    if fold {
+        a.chars()
+            .flat_map(char::to_lowercase)
+            .eq(b.chars().flat_map(char::to_lowercase))
+    } else {
+        a == b
+    }
+}
+
+// `*` matches any run of characters, `?` any single one and `[abc]` or `[!abc]` one of a set,
+// everything else being literal, including a `[` that is never closed
+fn glob_regex(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut re = String::from("^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' if let Some(len) = chars[i + 1..].iter().skip(1).position(|c| *c == ']') => {
+                // A `]` right after the opening bracket belongs to the set
+                let set = &chars[i + 1..i + 2 + len];
+                let (negated, set) = match set.first() {
+                    Some('!') if set.len() > 1 => (true, &set[1..]),
+                    _ => (false, set),
+                };
+                re.push_str(if negated { "[^" } else { "[" });
+                for c in set {
+                    match c {
+                        '\\' | '[' | ']' | '^' | '&' | '~' => {
+                            re.push_str(&regex::escape(&c.to_string()))
+                        }
+                        '-' => re.push('-'),
+                        c => re.push(*c),
+                    }
+                }
+                re.push(']');
+                i += len + 2;
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re.push('$');
+    re
+}
+
+// Extensions paired with the age their files must have reached to be collected (see --rule)
+pub(crate) struct AgeRules {
+    extensions: ExtensionFilter,
+    rules: Vec<(String, Duration)>,
+    // Last modification time allowed by each rule, from when the run started
+    cutoffs: Vec<Option<SystemTime>>,
+}
+
+impl AgeRules {
+    pub(crate) fn new(rules: &[(String, Duration)], now: SystemTime) -> AgeRules {
+        let extensions: Vec<String> = rules.iter().map(|(e, _)| e.clone()).collect();
+        AgeRules {
+            extensions: ExtensionFilter::new(&extensions, false),
+            rules: rules.to_vec(),
+            cutoffs: rules.iter().map(|(_, age)| now.checked_sub(*age)).collect(),
+        }
+    }
+
+    // Rule of the first extension matching, as for the extensions themselves
+    pub(crate) fn rule_for(&self, file_ext: &str) -> Option<&(String, Duration)> {
+        self.extensions.matching(file_ext).map(|i| &self.rules[i])
+    }
+
+    // Files whose age is unknown are kept, as are files no rule applies to
+    pub(crate) fn is_old_enough(&self, file_ext: &str, modified: Option<SystemTime>) -> bool {
+        let Some(i) = self.extensions.matching(file_ext) else {
+            return false;
+        };
+        match (self.cutoffs[i], modified) {
+            (Some(cutoff), Some(modified)) => modified <= cutoff,
+            _ => false,
+        }
+    }
+}
+
+// Bounds of the modification times collected, both included (see --older-than and --newer-than)
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct AgeWindow {
+    pub(crate) oldest: Option<SystemTime>,
+    pub(crate) newest: Option<SystemTime>,
+    // Modification times of reference files, left out themselves (see --newer-than-file and
+    // --older-than-file)
+    pub(crate) after: Option<SystemTime>,
+    pub(crate) before: Option<SystemTime>,
+}
+
+impl AgeWindow {
+    // Files whose age is unknown are not collected, as nothing tells they are in the window
+    pub(crate) fn contains(&self, modified: Option<SystemTime>) -> bool {
+        let Some(modified) = modified else {
+            return false;
+        };
+        self.oldest.is_none_or(|oldest| modified >= oldest)
+            && self.newest.is_none_or(|newest| modified <= newest)
+            && self.after.is_none_or(|after| modified > after)
+            && self.before.is_none_or(|before| modified < before)
+    }
+}
+
+// Filesystems mounted with noatime never update access times, leaving them equal to the
+// modification times, which then tell nothing about reads. Only told once every file examined
+// looks like this
+pub(crate) fn unreliable_atime_warning(examined: usize, same_as_mtime: usize) -> Option<String> {
+    (examined > 0 && examined == same_as_mtime).then(|| {
+        format!(
+            "the access time of every file examined was its modification time, the filesystem \
+            may not record reads (noatime), so --accessed-before may select files read recently \
+            ({examined} examined)"
+        )
+    })
+}
+
+// Files whose age is unknown are protected as well, as this filter is a safety net
+pub(crate) fn is_protected_by_age(
+    modified: Option<SystemTime>,
+    oldest_allowed: Option<SystemTime>,
+) -> bool {
+    match (oldest_allowed, modified) {
+        (None, _) => false,
+        (Some(oldest_allowed), Some(modified)) => modified < oldest_allowed,
+        (Some(_), None) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn extension_filter_suffixes() {
+        let filter = ExtensionFilter::new(&["gz".to_string()], false);
+        let inverted = ExtensionFilter::new(&["gz".to_string()], true);
+
+        assert!(filter.keeps("tar.gz"));
+        assert!(!filter.keeps("tgz"));
+        assert!(!inverted.keeps("gz"));
+        assert!(inverted.keeps("txt"));
+        assert!(!filter.keeps_file(None));
+        assert!(!inverted.keeps_file(None));
+    }
+
+    #[test]
+    fn ignore_case() {
+        let filter = ExtensionFilter::new(&["jpg".to_string()], false);
+        let ignoring = ExtensionFilter::new(&["jpg".to_string(), "gz".to_string()], false)
+            .with_ignore_case(true);
+
+        assert!(filter.keeps("jpg"));
+        assert!(!filter.keeps("JPG"));
+        assert_eq!(Some(0), ignoring.matching("JPG"));
+        assert_eq!(Some(0), ignoring.matching("Jpg"));
+        assert_eq!(Some(1), ignoring.matching("tar.GZ"));
+        assert_eq!(None, ignoring.matching("jpeg"));
+        assert_eq!(None, ignoring.matching("é"));
+    }
+
+    #[test]
+    fn extensionless() {
+        let filter = ExtensionFilter::new(&["o".to_string()], false).with_no_ext(true);
+        let inverted = ExtensionFilter::new(&["o".to_string()], true).with_no_ext(true);
+
+        assert!(filter.keeps_file(None));
+        assert!(filter.keeps_file(Some("o")));
+        assert!(!filter.keeps_file(Some("tar.gz")));
+        assert!(!filter.keeps_file(Some("")));
+        assert!(!inverted.keeps_file(None));
+        assert!(inverted.keeps_file(Some("tar.gz")));
+        assert!(
+            !ExtensionFilter::new(&[], false)
+                .with_no_ext(true)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn exclude_filter_globs() {
+        let filter = ExcludeFilter::new(&["data.*".to_string(), "?.log".to_string()], false);
+
+        assert!(filter.excludes("data.dat"));
+        assert!(filter.excludes("data."));
+        assert!(!filter.excludes("mydata.dat"));
+        assert!(!filter.excludes("data"));
+        assert!(filter.excludes("a.log"));
+        assert!(!filter.excludes("ab.log"));
+        assert!(!ExcludeFilter::new(&[], false).excludes("data.dat"));
+    }
+
+    #[test]
+    fn exclude_filter_sets() {
+        let filter = ExcludeFilter::new(
+            &["file[0-9].txt".to_string(), "[!a]*.bak".to_string()],
+            false,
+        );
+
+        assert!(filter.excludes("file1.txt"));
+        assert!(!filter.excludes("fileA.txt"));
+        assert!(filter.excludes("b.bak"));
+        assert!(!filter.excludes("a.bak"));
+
+        // Literal when never closed, or made of regex syntax
+        let filter = ExcludeFilter::new(
+            &["[ab".to_string(), "(a|b)+".to_string(), "[]]".to_string()],
+            false,
+        );
+        assert!(filter.excludes("[ab"));
+        assert!(!filter.excludes("a"));
+        assert!(filter.excludes("(a|b)+"));
+        assert!(filter.excludes("]"));
+    }
+
+    #[test]
+    fn exclude_filter_folding() {
+        let patterns = ["Important.log".to_string(), "[a-c]*.TMP".to_string()];
+
+        let filter = ExcludeFilter::new(&patterns, false);
+        assert!(filter.excludes("Important.log"));
+        assert!(!filter.excludes("important.LOG"));
+        assert!(!filter.excludes("B.tmp"));
+
+        let filter = ExcludeFilter::new(&patterns, true);
+        assert!(filter.excludes("important.LOG"));
+        assert!(filter.excludes("B.tmp"));
+        assert!(!filter.excludes("d.tmp"));
+    }
+
+    #[test]
+    fn names_compared_like_the_filesystem() {
+        assert!(same_name("Thumbs.db", "Thumbs.db", false));
+        assert!(!same_name("Thumbs.db", "thumbs.DB", false));
+        assert!(same_name("Thumbs.db", "thumbs.DB", true));
+        assert!(same_name("Été.txt", "été.TXT", true));
+        assert!(!same_name("Thumbs.db", "Thumbs.db.bak", true));
+    }
+
+    #[test]
+    fn age_rules_per_extension() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 86400);
+        let days_ago = |days: u64| Some(now - Duration::from_secs(days * 86400));
+        let rules = AgeRules::new(
+            &[
+                ("log".to_string(), Duration::from_secs(30 * 86400)),
+                ("tmp".to_string(), Duration::from_secs(86400)),
+            ],
+            now,
+        );
+
+        assert!(rules.is_old_enough("log", days_ago(31)));
+        assert!(!rules.is_old_enough("log", days_ago(2)));
+        assert!(rules.is_old_enough("tmp", days_ago(2)));
+        assert!(!rules.is_old_enough("tmp", None));
+        assert!(!rules.is_old_enough("txt", days_ago(365)));
+        assert_eq!("log", rules.rule_for("app.log").unwrap().0);
+        assert!(rules.rule_for("txt").is_none());
+    }
+
+    #[test]
+    fn extension_filter_first_match() {
+        let filter = ExtensionFilter::new(
+            &["log".to_string(), "gz".to_string(), "tar.gz".to_string()],
+            false,
+        );
+
+        assert_eq!(Some(1), filter.matching("tar.gz"));
+        assert_eq!(Some(0), filter.matching("log"));
+        assert_eq!(None, filter.matching("txt"));
+    }
+
+    #[test]
+    fn age_protection() {
+        let now = SystemTime::now();
+        let day_ago = now - Duration::from_secs(86400);
+
+        assert!(!is_protected_by_age(Some(day_ago), None));
+        assert!(!is_protected_by_age(None, None));
+        assert!(is_protected_by_age(Some(day_ago), Some(now)));
+        assert!(!is_protected_by_age(Some(now), Some(day_ago)));
+        assert!(is_protected_by_age(None, Some(day_ago)));
+    }
+
+    #[test]
+    fn age_window() {
+        let now = SystemTime::now();
+        let hour_ago = now - Duration::from_secs(3600);
+        let day_ago = now - Duration::from_secs(86400);
+
+        let older = AgeWindow {
+            newest: Some(hour_ago),
+            ..Default::default()
+        };
+        assert!(older.contains(Some(day_ago)));
+        assert!(!older.contains(Some(now)));
+        assert!(!older.contains(None));
+
+        let newer = AgeWindow {
+            oldest: Some(hour_ago),
+            ..Default::default()
+        };
+        assert!(newer.contains(Some(now)));
+        assert!(!newer.contains(Some(day_ago)));
+        assert!(!newer.contains(None));
+
+        let window = AgeWindow {
+            oldest: Some(day_ago),
+            newest: Some(hour_ago),
+            ..Default::default()
+        };
+        assert!(window.contains(Some(now - Duration::from_secs(7200))));
+        assert!(!window.contains(Some(now)));
+        assert!(!window.contains(Some(day_ago - Duration::from_secs(1))));
+    }
+
+    // A modification time equal to a cutoff is in the window, whichever the bound
+    #[test]
+    fn age_window_boundaries() {
+        let now = SystemTime::now();
+        let hour_ago = now - Duration::from_secs(3600);
+        let day_ago = now - Duration::from_secs(86400);
+        let window = AgeWindow {
+            oldest: Some(day_ago),
+            newest: Some(hour_ago),
+            ..Default::default()
+        };
+
+        assert!(window.contains(Some(day_ago)));
+        assert!(window.contains(Some(hour_ago)));
+        assert!(!window.contains(Some(hour_ago + Duration::from_nanos(1))));
+        assert!(!window.contains(Some(day_ago - Duration::from_nanos(1))));
+    }
+
+    // Unlike the bounds of ages, the times of reference files are left out
+    #[test]
+    fn age_window_reference_files() {
+        let stamp = SystemTime::now() - Duration::from_secs(3600);
+        let before = AgeWindow {
+            before: Some(stamp),
+            ..Default::default()
+        };
+        assert!(before.contains(Some(stamp - Duration::from_nanos(1))));
+        assert!(!before.contains(Some(stamp)));
+        assert!(!before.contains(None));
+
+        let after = AgeWindow {
+            after: Some(stamp),
+            ..Default::default()
+        };
+        assert!(after.contains(Some(stamp + Duration::from_nanos(1))));
+        assert!(!after.contains(Some(stamp)));
+    }
+
+    #[test]
+    fn unreliable_atimes() {
+        assert_eq!(None, unreliable_atime_warning(0, 0));
+        assert_eq!(None, unreliable_atime_warning(3, 2));
+        assert!(
+            unreliable_atime_warning(3, 3)
+                .unwrap()
+                .ends_with("(3 examined)")
+        );
+    }
+}
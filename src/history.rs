@@ -0,0 +1,301 @@
+use std::env;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{Separators, to_portable_string};
+use crate::report::{DeleteReport, format_size};
+
+// Records kept when the config file does not say (see history_limit)
+pub const DEFAULT_LIMIT: usize = 1000;
+
+// One run, as appended to the history file, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    // Seconds since the Unix epoch, when the run ended
+    pub timestamp: u64,
+    pub invocation: Vec<String>,
+    pub path: String,
+    pub matched: usize,
+    pub deleted: usize,
+    pub failed: usize,
+    pub bytes: u64,
+    pub status: i32,
+    // Extensions given on the command line, sorted, when no preset nor job gave any, so that a
+    // set typed again and again can be suggested as a preset
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+}
+
+impl Record {
+    pub fn new(invocation: Vec<String>, path: &Path, report: &DeleteReport, status: i32) -> Record {
+        Record {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            invocation,
+            // The notation of the user, like `.`, would mean nothing later
+            path: to_portable_string(
+                &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+                Separators::Native,
+            ),
+            matched: report.matched,
+            deleted: report.deleted,
+            failed: report.failures.len(),
+            bytes: report.bytes,
+            status,
+            extensions: Vec::new(),
+        }
+    }
+}
+
+// Runs of the history given exactly this set of extensions on the command line
+pub fn runs_with_extensions(records: &[Record], extensions: &[String]) -> usize {
+    records
+        .iter()
+        .filter(|r| r.extensions == extensions)
+        .count()
+}
+
+// Shown once, after the third run typing the same extensions
+pub fn format_preset_tip(extensions: &[String], config: &Path) -> String {
+    let extensions = extensions.join(" ");
+    format!(
+        "Tip: save this as a preset with a `preset NAME={extensions}` line in {}, then run \
+        `rmx --preset NAME`.",
+        config.display()
+    )
+}
+
+// History file of the current user, following the XDG base directory specification
+pub fn history_path() -> Option<PathBuf> {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        // Relative paths are to be ignored, as per the specification
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_home.join("rmx").join("history.jsonl"))
+}
+
+// Appends a record, then drops the oldest ones beyond `limit`
+pub fn append(path: &Path, record: &Record, limit: usize) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    drop(file);
+    rotate(path, limit)
+}
+
+// Rewrites the file with its last `limit` lines, if it holds more. The new file replaces the old
+// one at once, so that an interrupted rotation loses nothing
+fn rotate(path: &Path, limit: usize) -> io::Result<()> {
+    let lines: Vec<String> = BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<io::Result<_>>()?;
+    if lines.len() <= limit {
+        return Ok(());
+    }
+
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut file = File::create(&tmp)?;
+    for line in &lines[lines.len() - limit..] {
+        writeln!(file, "{line}")?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+// Last `n` records, oldest first. Lines that cannot be read, like ones cut by a full disk, are
+// skipped, and a missing file is an empty history
+pub fn read_last(path: &Path, n: usize) -> Result<Vec<Record>, Box<dyn Error>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Could not read {}: {e}", path.display()).into()),
+    };
+    let records: Vec<Record> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    let skip = records.len().saturating_sub(n);
+    Ok(records.into_iter().skip(skip).collect())
+}
+
+// Like `2024-06-01 13:05:09`, in UTC
+fn format_timestamp(timestamp: u64) -> String {
+    let (days, secs) = (timestamp / 86400, timestamp % 86400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+// One line per record, like
+// `2024-06-01 13:05:09  exit 0  42/42          0       1.3 GiB     /srv/logs  rmx -rf log`
+pub fn format_table(records: &[Record]) -> Vec<String> {
+    let header = "Date (UTC)           Status  Deleted        Failed  Size        Path  Command";
+    let mut lines = vec![header.to_string()];
+    lines.extend(records.iter().map(|r| {
+        let deleted = format!("{}/{}", r.deleted, r.matched);
+        format!(
+            "{}  exit {:<2} {deleted:<14} {:<7} {:<11} {}  {}",
+            format_timestamp(r.timestamp),
+            r.status,
+            r.failed,
+            format_size(r.bytes),
+            r.path,
+            r.invocation.join(" ")
+        )
+    }));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    fn record(deleted: usize) -> Record {
+        Record {
+            timestamp: 1717247109,
+            invocation: vec!["rmx".into(), "-rf".into(), "log".into()],
+            path: "/srv/logs".into(),
+            matched: deleted,
+            deleted,
+            failed: 0,
+            bytes: 1024,
+            status: 0,
+            extensions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn append_and_read() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("rmx").join("history.jsonl");
+        assert!(read_last(&path, 10)?.is_empty());
+
+        append(&path, &record(1), 10)?;
+        append(&path, &record(2), 10)?;
+
+        assert_eq!(vec![record(1), record(2)], read_last(&path, 10)?);
+        assert_eq!(vec![record(2)], read_last(&path, 1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn runs_with_the_same_extensions() {
+        let with = |extensions: &[&str]| Record {
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            ..record(1)
+        };
+        let records = [
+            with(&["log", "tmp"]),
+            with(&[]),
+            with(&["log"]),
+            with(&["log", "tmp"]),
+        ];
+
+        assert_eq!(
+            2,
+            runs_with_extensions(&records, &["log".to_string(), "tmp".to_string()])
+        );
+        assert_eq!(1, runs_with_extensions(&records, &["log".to_string()]));
+    }
+
+    // Records written before the extensions were recorded still read
+    #[test]
+    fn reads_records_without_extensions() -> Result<(), Box<dyn Error>> {
+        let line = r#"{"timestamp":1,"invocation":["rmx"],"path":"/srv","matched":0,"deleted":0,"failed":0,"bytes":0,"status":0}"#;
+        let record: Record = serde_json::from_str(line)?;
+
+        assert!(record.extensions.is_empty());
+        assert!(!serde_json::to_string(&record)?.contains("extensions"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn preset_tip() {
+        assert_eq!(
+            "Tip: save this as a preset with a `preset NAME=bak log tmp` line in /etc/rmx/rmx.conf, \
+            then run `rmx --preset NAME`.",
+            format_preset_tip(
+                &["bak".to_string(), "log".to_string(), "tmp".to_string()],
+                Path::new("/etc/rmx/rmx.conf")
+            )
+        );
+    }
+
+    #[test]
+    fn append_rotates() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("history.jsonl");
+
+        for deleted in 0..5 {
+            append(&path, &record(deleted), 3)?;
+        }
+
+        assert_eq!(vec![record(2), record(3), record(4)], read_last(&path, 10)?);
+        assert_eq!(3, fs::read_to_string(&path)?.lines().count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_skips_broken_lines() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("history.jsonl");
+        let line = serde_json::to_string(&record(1))?;
+        fs::write(&path, format!("{line}\n{{\"timestamp\": 17\n{line}\n"))?;
+
+        assert_eq!(vec![record(1), record(1)], read_last(&path, 10)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!("1970-01-01 00:00:00", format_timestamp(0));
+        assert_eq!("2024-06-01 13:05:09", format_timestamp(1717247109));
+        assert_eq!("2000-02-29 23:59:59", format_timestamp(951868799));
+    }
+
+    #[test]
+    fn table() {
+        let lines = format_table(&[record(42)]);
+
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("Date (UTC)"));
+        assert_eq!(
+            "2024-06-01 13:05:09  exit 0  42/42          0       1.0 KiB     /srv/logs  rmx -rf log",
+            lines[1]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::report::DeleteReport;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Outcome of the run, as given to hooks in RMX_STATUS
+pub fn status(report: &DeleteReport) -> &'static str {
+    if report.dry_run {
+        "dry-run"
+    } else if report.cancelled {
+        "cancelled"
+    } else if report.failures.is_empty() {
+        "success"
+    } else if report.deleted > 0 {
+        "partial"
+    } else {
+        "failed"
+    }
+}
+
+fn environment(report: &DeleteReport, path: &Path) -> Vec<(&'static str, String)> {
+    vec![
+        ("RMX_DELETED", report.deleted.to_string()),
+        ("RMX_FAILED", report.failures.len().to_string()),
+        ("RMX_BYTES", report.bytes.to_string()),
+        ("RMX_PATH", path.to_string_lossy().into_owned()),
+        ("RMX_STATUS", status(report).to_string()),
+    ]
+}
+
+// Runs `cmd` with `sh -c` once the run is over, with its summary in RMX_* variables
+// The command is killed after `timeout`. Errors are meant to be reported as warnings, as the
+// deletion itself already happened
+pub fn notify(
+    cmd: &str,
+    report: &DeleteReport,
+    path: &Path,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .envs(environment(report, path))
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run notify command: {e}"))?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("notify command failed ({status})")),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "notify command timed out after {}s",
+                    timeout.as_secs_f64()
+                ));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("could not wait for notify command: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn status_of_each_outcome() {
+        let failure = (PathBuf::from("/a"), "Permission denied".to_string());
+
+        let success = DeleteReport {
+            deleted: 2,
+            ..Default::default()
+        };
+        let partial = DeleteReport {
+            deleted: 1,
+            failures: vec![failure.clone()],
+            ..Default::default()
+        };
+        let failed = DeleteReport {
+            failures: vec![failure],
+            ..Default::default()
+        };
+        let cancelled = DeleteReport {
+            cancelled: true,
+            ..Default::default()
+        };
+        let dry_run = DeleteReport {
+            dry_run: true,
+            ..Default::default()
+        };
+
+        assert_eq!("success", status(&success));
+        assert_eq!("partial", status(&partial));
+        assert_eq!("failed", status(&failed));
+        assert_eq!("cancelled", status(&cancelled));
+        assert_eq!("dry-run", status(&dry_run));
+        assert_eq!("success", status(&DeleteReport::default()));
+    }
+
+    #[test]
+    fn notify_failing_command() {
+        let res = notify(
+            "exit 3",
+            &DeleteReport::default(),
+            Path::new("/"),
+            DEFAULT_TIMEOUT,
+        );
+
+        assert!(res.unwrap_err().contains("failed"));
+    }
+
+    #[test]
+    fn notify_timeout() {
+        let start = Instant::now();
+
+        let res = notify(
+            "exec sleep 5",
+            &DeleteReport::default(),
+            Path::new("/"),
+            Duration::from_millis(100),
+        );
+
+        assert!(res.unwrap_err().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
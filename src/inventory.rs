@@ -0,0 +1,197 @@
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::hash;
+use crate::paths::{Separators, resolve, to_portable_string};
+
+// One line of the inventory. Unreadable files keep their size and mtime when known, with an
+// empty hash
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub hash: Option<String>,
+    pub size: u64,
+    // Seconds since the Unix epoch
+    pub modified: Option<u64>,
+    pub path: PathBuf,
+    pub error: Option<String>,
+}
+
+impl Entry {
+    fn of(path: &Path) -> Entry {
+        let metadata = fs::metadata(path).ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let (hash, error) = match hash::sha256_file(path) {
+            Ok(hash) => (Some(hash), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Entry {
+            hash,
+            size: metadata.map(|m| m.len()).unwrap_or(0),
+            modified,
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    // With the path made absolute against `cwd`
+    fn to_csv(&self, cwd: &Path) -> String {
+        format!(
+            "{},{},{},{}",
+            self.hash.as_deref().unwrap_or(""),
+            self.size,
+            self.modified.map(|m| m.to_string()).unwrap_or_default(),
+            csv_field(&to_portable_string(
+                &resolve(cwd, &self.path),
+                Separators::Native
+            ))
+        )
+    }
+}
+
+// Quoted only when needed, doubling quotes as in RFC 4180
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Hashes files on `jobs` threads, keeping their order. Progress is shown on stderr when it is a
+// terminal
+pub fn hash_files(files: &[PathBuf], jobs: usize) -> Vec<Entry> {
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let progress = io::stderr().is_terminal();
+    let done = AtomicUsize::new(0);
+    let per_thread = files.len().div_ceil(jobs.max(1));
+
+    let entries: Vec<Entry> = thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(per_thread)
+            .map(|chunk| {
+                let done = &done;
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|path| {
+                            let entry = Entry::of(path);
+                            let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                            if progress {
+                                eprint!("\rHashing files: {n}/{}", files.len());
+                            }
+                            entry
+                        })
+                        .collect::<Vec<Entry>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("hashing thread panicked"))
+            .collect()
+    });
+
+    if progress {
+        eprintln!();
+    }
+    entries
+}
+
+// Writes `hash,size,mtime,path` rows for every file, with absolute paths, and only returns once
+// they are flushed to disk, so that nothing is deleted before the inventory is complete (see
+// --inventory)
+// Returns the files that could not be hashed, with why
+pub fn write(
+    out: &Path,
+    files: &[PathBuf],
+    jobs: usize,
+) -> Result<Vec<(PathBuf, String)>, Box<dyn Error>> {
+    let entries = hash_files(files, jobs);
+
+    let write_all = || -> io::Result<()> {
+        let cwd = env::current_dir()?;
+        let file = File::create(out)?;
+        let mut writer = BufWriter::new(&file);
+        writeln!(writer, "hash,size,mtime,path")?;
+        for entry in &entries {
+            writeln!(writer, "{}", entry.to_csv(&cwd))?;
+        }
+        writer.flush()?;
+        drop(writer);
+        file.sync_all()
+    };
+    write_all().map_err(|e| format!("Could not write inventory {}: {}", out.display(), e))?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|e| e.error.map(|error| (e.path, error)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn inventory_rows() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let abc = dir.path().join("abc.log");
+        let quoted = dir.path().join("a, \"b\".log");
+        let missing = dir.path().join("missing.log");
+        fs::write(&abc, "abc")?;
+        fs::write(&quoted, "")?;
+        let out = dir.path().join("inv.csv");
+
+        let unreadable = write(&out, &[abc.clone(), quoted.clone(), missing.clone()], 2)?;
+
+        let content = fs::read_to_string(&out)?;
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!("hash,size,mtime,path", lines[0]);
+        assert!(
+            lines[1]
+                .starts_with("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad,3,")
+        );
+        assert!(lines[1].ends_with(&format!(",{}", abc.display())));
+        assert!(lines[2].ends_with(&format!(
+            ",\"{}\"",
+            quoted.display().to_string().replace('"', "\"\"")
+        )));
+        // Unreadable files keep their row, with an empty hash
+        assert_eq!(format!(",0,,{}", missing.display()), lines[3]);
+        assert_eq!(1, unreadable.len());
+        assert_eq!(missing, unreadable[0].0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hash_files_keeps_order() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let files: Vec<PathBuf> = (0..10).map(|i| dir.path().join(format!("{i}"))).collect();
+        for (i, file) in files.iter().enumerate() {
+            fs::write(file, "x".repeat(i))?;
+        }
+
+        let entries = hash_files(&files, 3);
+
+        let sizes: Vec<u64> = entries.iter().map(|e| e.size).collect();
+        assert_eq!((0..10).collect::<Vec<u64>>(), sizes);
+        assert!(hash_files(&[], 4).is_empty());
+
+        Ok(())
+    }
+}
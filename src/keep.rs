@@ -0,0 +1,225 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::filter::{glob, same_name};
+use crate::paths::resolve;
+
+// Files that must survive the run, whatever else selects them (see --keep-from). Each line is a
+// path, absolute or relative to the directory of the list (see --base), or a shell pattern like
+// `*.key`. Blank lines and lines starting with `#` are ignored
+pub(crate) struct KeepList {
+    entries: Vec<Entry>,
+    // Files each entry matched so far, so that entries matching nothing can be reported
+    hits: Vec<usize>,
+    // Directory the paths of relative files are relative to, the current one
+    cwd: PathBuf,
+    // Paths and patterns match whatever the case (see --case-insensitive-fs)
+    fold: bool,
+}
+
+struct Entry {
+    // As written in the file
+    line: String,
+    pattern: Pattern,
+}
+
+enum Pattern {
+    Path(PathBuf),
+    // Matched against the name of the file, then against its whole path
+    Glob { name: Regex, path: Regex },
+}
+
+impl KeepList {
+    pub(crate) fn load(path: &Path, base: &Path, fold: bool) -> Result<KeepList, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read keep list {}: {e}", path.display()))?;
+        Ok(KeepList::parse(&content, base, &env::current_dir()?, fold))
+    }
+
+    // Entries are relative to `base`, and files to `cwd`
+    pub(crate) fn parse(content: &str, base: &Path, cwd: &Path, fold: bool) -> KeepList {
+        let entries: Vec<Entry> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| Entry {
+                line: line.to_string(),
+                pattern: Pattern::new(line, base, fold),
+            })
+            .collect();
+        KeepList {
+            hits: vec![0; entries.len()],
+            entries,
+            cwd: cwd.to_path_buf(),
+            fold,
+        }
+    }
+
+    // Whether the file is to be kept. Every matching entry is counted, so that an entry is not
+    // reported as unmatched because another one also matched its files
+    pub(crate) fn keeps(&mut self, path: &Path) -> bool {
+        let path = resolve(&self.cwd, path);
+        let mut kept = false;
+        for (entry, hits) in self.entries.iter().zip(&mut self.hits) {
+            if entry.pattern.matches(&path, self.fold) {
+                *hits += 1;
+                kept = true;
+            }
+        }
+        kept
+    }
+
+    // Entries that matched no file, likely typos, as written in the file
+    pub(crate) fn unmatched(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .zip(&self.hits)
+            .filter(|(_, hits)| **hits == 0)
+            .map(|(entry, _)| entry.line.as_str())
+            .collect()
+    }
+}
+
+impl Pattern {
+    fn new(line: &str, base: &Path, fold: bool) -> Pattern {
+        if !line.contains(['*', '?', '[']) {
+            return Pattern::Path(resolve(base, Path::new(line)));
+        }
+        let whole = if Path::new(line).is_absolute() {
+            line.to_string()
+        } else {
+            format!("{}/{line}", escape_glob(&base.to_string_lossy()))
+        };
+        Pattern::Glob {
+            name: glob(line, fold),
+            path: glob(&whole, fold),
+        }
+    }
+
+    fn matches(&self, path: &Path, fold: bool) -> bool {
+        match self {
+            Pattern::Path(kept) if fold => {
+                same_name(&kept.to_string_lossy(), &path.to_string_lossy(), true)
+            }
+            Pattern::Path(kept) => kept == path,
+            Pattern::Glob { name, path: whole } => {
+                path.file_name()
+                    .is_some_and(|n| name.is_match(&n.to_string_lossy()))
+                    || whole.is_match(&path.to_string_lossy())
+            }
+        }
+    }
+}
+
+// Makes the characters of a directory name literal within a pattern
+fn escape_glob(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '*' | '?' | '[' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn load() -> Result<(), Box<dyn Error>> {
+        let file = NamedTempFile::new()?;
+        fs::write(
+            file.path(),
+            "# Must survive the cleanup\n\n  /srv/app/current.log  \nkeys/*.pem\n",
+        )?;
+
+        let list = KeepList::load(file.path(), Path::new("/srv"), false)?;
+
+        assert_eq!(vec!["/srv/app/current.log", "keys/*.pem"], list.unmatched());
+        assert!(KeepList::load(&file.path().join("missing"), Path::new("/srv"), false).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn matches_paths_and_globs() {
+        let base = Path::new("/srv/app");
+        let mut list = KeepList::parse(
+            "./current.log\n/etc/app.conf\n*.pem\nlogs/*.gz",
+            base,
+            base,
+            false,
+        );
+
+        assert!(list.keeps(Path::new("current.log")));
+        assert!(list.keeps(Path::new("/srv/app/./current.log")));
+        assert!(list.keeps(Path::new("/etc/app.conf")));
+        assert!(!list.keeps(Path::new("/etc/other.conf")));
+        // By name, anywhere
+        assert!(list.keeps(Path::new("/var/keys/server.pem")));
+        // By path, relative to the base
+        assert!(list.keeps(Path::new("logs/2024.gz")));
+        assert!(!list.keeps(Path::new("/var/logs/2024.gz")));
+    }
+
+    #[test]
+    fn entries_relative_to_the_list() {
+        let mut list = KeepList::parse(
+            "current.log\nlogs/*.gz",
+            Path::new("/srv/app"),
+            Path::new("/home/me"),
+            false,
+        );
+
+        assert!(list.keeps(Path::new("/srv/app/current.log")));
+        assert!(!list.keeps(Path::new("current.log")));
+        assert!(list.keeps(Path::new("/srv/app/logs/2024.gz")));
+        assert!(!list.keeps(Path::new("logs/2024.gz")));
+    }
+
+    #[test]
+    fn reports_unmatched_entries() {
+        let base = Path::new("/srv/app");
+        let mut list = KeepList::parse("a.log\n*.log\ntypo.lgo", base, base, false);
+
+        assert!(list.keeps(Path::new("a.log")));
+
+        // `*.log` matched the file too, even though `a.log` already kept it
+        assert_eq!(vec!["typo.lgo"], list.unmatched());
+    }
+
+    #[test]
+    fn escapes_directories() {
+        let base = Path::new("/srv/[v1]");
+        let mut list = KeepList::parse("*/current.log", base, base, false);
+
+        assert!(list.keeps(Path::new("/srv/[v1]/logs/current.log")));
+        assert!(!list.keeps(Path::new("/srv/v/logs/current.log")));
+    }
+
+    #[test]
+    fn folds_case() {
+        let base = Path::new("/srv/App");
+        let mut list = KeepList::parse("Current.log\n*.PEM", base, base, true);
+
+        assert!(list.keeps(Path::new("/srv/app/current.LOG")));
+        assert!(list.keeps(Path::new("/var/keys/server.pem")));
+        assert!(!list.keeps(Path::new("/srv/app/other.log")));
+
+        let mut list = KeepList::parse("Current.log\n*.PEM", base, base, false);
+        assert!(!list.keeps(Path::new("/srv/App/current.log")));
+        assert!(!list.keeps(Path::new("/var/keys/server.pem")));
+    }
+}
@@ -1,580 +1,4661 @@
+//! Deletes files based on their extension, like the `rmx` command line tool.
+//!
+//! ```
+//! use rmx::{CollectOptions, DeleteOptions};
+//!
+//! let dir = tempfile::tempdir()?;
+//! std::fs::File::create(dir.path().join("build.log"))?;
+//!
+//! let mut options = CollectOptions::default();
+//! options.recurse = true;
+//! let files = rmx::collect_matching_files(&["log".to_string()], &dir.path().to_path_buf(), &options)?;
+//!
+//! let mut delete_options = DeleteOptions::default();
+//! delete_options.force = true;
+//! delete_options.quiet = true;
+//! let report = rmx::delete_files(&files, &delete_options)?;
+//!
+//! assert_eq!(1, report.deleted);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Stability
+//!
+//! Documented items follow semantic versioning, and `tests/public_api.rs` pins their
+//! signatures so that changing them is always deliberate. Option and report types are
+//! `#[non_exhaustive]`: build them from `Default` and set their fields, so that new options do
+//! not break callers.
+//!
+//! Items hidden from the documentation, like the `arguments`, `duration`, `hook`, `plan`,
+//! `report` or `roots` modules, only serve the `rmx` binaries and fuzz targets, and may change in
+//! any release.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::error::Error;
+use std::ffi::OsStr;
+
+use std::fs::{self, DirEntry, File, FileType, ReadDir};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::time::SystemTime;
+
+use crate::audit::{AuditLog, Decision};
+use crate::candidate::Contents;
+pub use crate::candidate::{Candidate, CandidateKind};
+use crate::checkpoint::Checkpoint;
+pub use crate::error::{ExtensionOrigin, RmxError};
+use crate::filter::{AgeRules, AgeWindow, ExcludeFilter, ExtensionFilter, same_name};
+use crate::keep::KeepList;
+use crate::metadata::{RealFs, StatCache};
+#[doc(hidden)]
+pub use crate::parsing::arguments;
+pub use crate::parsing::arguments::{
+    Action, CollectOptions, DeleteOptions, Traversal, Veto, VetoFn, are_extensions_valid,
+};
+pub use crate::parsing::config;
+#[doc(hidden)]
+pub use crate::parsing::duration;
+pub use crate::parsing::preset;
+use crate::plan::{DeletionPlan, PlannedFile};
+pub use crate::report::{CollectStats, DeleteReport};
+mod archive;
+mod audit;
+mod candidate;
+mod capabilities;
+#[doc(hidden)]
+pub mod cargo;
+#[doc(hidden)]
+pub mod checkpoint;
+#[doc(hidden)]
+pub mod cli;
+mod cruft;
+mod dir_list;
+#[doc(hidden)]
+pub mod discover;
+mod display;
+mod error;
+mod file_list;
+mod filter;
+mod hash;
+#[doc(hidden)]
+pub mod history;
+#[doc(hidden)]
+pub mod hook;
+#[doc(hidden)]
+pub mod inventory;
+mod keep;
+mod listing;
+mod metadata;
+mod parsing;
+mod paths;
+#[doc(hidden)]
+pub mod plan;
+mod platform;
+mod pycache;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod roots;
+mod select;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+#[doc(hidden)]
+pub mod warning;
+#[doc(hidden)]
+pub mod wizard;
+
+/// Returns the largest extension (i.e. using file.tar.gz will return tar.gz)
+///
+/// ```
+/// assert_eq!(Some("tar.gz"), rmx::get_fileext("file.tar.gz"));
+/// assert_eq!(Some("lock"), rmx::get_fileext(".file.lock"));
+/// assert_eq!(None, rmx::get_fileext("Makefile"));
+/// ```
+pub fn get_fileext(filename: &str) -> Option<&str> {
+    let parts = filename.split_once(".")?;
+    if parts.0.is_empty() {
+        // In case file is hidden, like ".file.lock", split again to get ".lock"
+        return Some(parts.1.split_once(".")?.1);
+    }
+    Some(parts.1)
+}
+
+fn get_filetype(entry: &DirEntry) -> Result<FileType, Box<dyn Error>> {
+    entry
+        .file_type()
+        .map_err(|e| format!("Couldn't extract filetype from {:?}: {}", entry.path(), e).into())
+}
+
+// Identity of a directory, whichever path leads to it (see --follow-symlinks)
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    fs::canonicalize(path)
+}
+
+// Device other filesystems are told from (see --one-file-system)
+#[cfg(unix)]
+fn root_device(path: &Path) -> Result<u64, Box<dyn Error>> {
+    platform::mounts::device(path)
+        .map_err(|e| format!("Could not read the filesystem of {}: {e}", path.display()).into())
+}
+
+#[cfg(not(unix))]
+fn root_device(_path: &Path) -> Result<u64, Box<dyn Error>> {
+    Err("--one-file-system is only supported on Unix.".into())
+}
+
+// Borrows the name returned by DirEntry::file_name, sparing a copy per entry
+fn get_filename<'a>(entry: &DirEntry, name: &'a OsStr) -> Result<&'a str, Box<dyn Error>> {
+    match name.to_str() {
+        Some(s) => Ok(s),
+        None => Err(format!("Couldn't extract filename from {:?}", entry).into()),
+    }
+}
+
+/// Called on each match as soon as it is found, before the traversal completes
+///
+/// ```
+/// use std::path::Path;
+///
+/// let mut count = 0;
+/// let mut sink = |_: &Path| {
+///     count += 1;
+///     Ok(())
+/// };
+/// let sink: rmx::Sink = &mut sink;
+/// # let _ = sink;
+/// ```
+pub type Sink<'a> = &'a mut dyn FnMut(&Path) -> io::Result<()>;
+
+// State shared by the whole traversal
+struct Walk<'a, 's> {
+    options: &'a CollectOptions,
+    extensions: &'a ExtensionFilter,
+    excludes: ExcludeFilter,
+    // Ages files must have reached, per extension (see --rule)
+    rules: Option<AgeRules>,
+    sink: Option<Sink<'s>>,
+    stats: CollectStats,
+    // Files last modified before this are never collected (see --never-older-than)
+    oldest_allowed: Option<SystemTime>,
+    // Modification times collected, if bounded (see --older-than, --newer-than and their -file
+    // variants)
+    age_window: Option<AgeWindow>,
+    // Files last read after this are not collected (see --accessed-before)
+    accessed_before: Option<SystemTime>,
+    // Files examined by --accessed-before, and those whose access time is their modification time
+    atimes: (usize, usize),
+    // Canonical directories already collected, when directories can be reached several times
+    // (see --follow-dir-lists)
+    visited: HashSet<PathBuf>,
+    // Directories already walked, when symlinks can lead to them again (see --follow-symlinks)
+    walked: HashSet<DirId>,
+    // Records why each examined file was selected or not (see --audit)
+    audit: Option<AuditLog>,
+    // Canonical targets of the symlinks met so far (see --preserve-link-targets)
+    link_targets: HashSet<PathBuf>,
+    // Files never collected, whatever selects them (see --keep-from)
+    keep: Option<KeepList>,
+    // Files collected so far from every root, for --limit
+    collected: usize,
+    // Device of the root being walked, when other filesystems are not walked (see
+    // --one-file-system)
+    root_device: Option<u64>,
+}
+
+impl Walk<'_, '_> {
+    fn select(&mut self, candidate: &Candidate) -> Result<(), Box<dyn Error>> {
+        self.record(&candidate.path, Decision::Selected)?;
+        if let Some(sink) = &mut self.sink {
+            sink(&candidate.path)?;
+        }
+        Ok(())
+    }
+
+    // Drops candidates targeted by a symlink met during the traversal, so that links like
+    // `current.log -> 2024-06-01.log` keep working, and directories holding such a target. The
+    // limit applies to the candidates left
+    fn spare_link_targets(
+        &mut self,
+        candidates: Vec<Candidate>,
+    ) -> Result<Vec<Candidate>, Box<dyn Error>> {
+        let mut kept = Vec::with_capacity(candidates.len());
+        let mut candidates = candidates.into_iter();
+        for candidate in candidates.by_ref() {
+            let targeted =
+                fs::canonicalize(&candidate.path).is_ok_and(|canonical| match candidate.kind {
+                    CandidateKind::Dir => self
+                        .link_targets
+                        .iter()
+                        .any(|target| target.starts_with(&canonical)),
+                    _ => self.link_targets.contains(&canonical),
+                });
+            if !targeted {
+                if self.reaches_limit() {
+                    self.unmatch(&candidate);
+                    break;
+                }
+                self.select(&candidate)?;
+                kept.push(candidate);
+                continue;
+            }
+
+            self.stats.link_targets_preserved += 1;
+            self.unmatch(&candidate);
+            self.record(&candidate.path, Decision::LinkTarget)?;
+        }
+        // Past the limit, as if the walk had stopped there
+        for candidate in candidates {
+            self.unmatch(&candidate);
+        }
+        Ok(kept)
+    }
+
+    // Takes back the count of a candidate for its extension (see keep_match)
+    fn unmatch(&mut self, candidate: &Candidate) {
+        if let Some(ext) = &candidate.matched_ext
+            && let Some(i) = self.extensions.matching(ext)
+        {
+            self.stats.matched_by_extension[i] -= 1;
+        }
+    }
+
+    fn record(&mut self, path: &Path, decision: Decision) -> io::Result<()> {
+        match &mut self.audit {
+            Some(audit) => audit.record(path, decision),
+            None => Ok(()),
+        }
+    }
+
+    // Files must be older than the age of their rule, if rules are given
+    fn is_old_enough(&self, path: &Path, stat: &StatCache, filename: &str) -> bool {
+        let Some(rules) = &self.rules else {
+            return true;
+        };
+        let modified = modified_time(path, stat).ok();
+        get_fileext(filename).is_some_and(|e| rules.is_old_enough(e, modified))
+    }
+
+    // Reads the modification time once, whichever bounds are given
+    fn is_in_age_window(&self, path: &Path, stat: &StatCache) -> bool {
+        let Some(window) = &self.age_window else {
+            return true;
+        };
+        let modified = match modified_time(path, stat) {
+            Ok(modified) => Some(modified),
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the modification time of {:?}, skipping it: {e}",
+                    path
+                ));
+                None
+            }
+        };
+        window.contains(modified)
+    }
+
+    // Access times in the future, as left by clock skew, are recent ones
+    fn is_accessed_long_ago(&mut self, path: &Path, stat: &StatCache) -> bool {
+        let Some(cutoff) = self.accessed_before else {
+            return true;
+        };
+        let times = stat.read(path, &RealFs).and_then(|stat| {
+            let accessed = stat
+                .accessed
+                .ok_or_else(|| "not available on this platform".to_string())?;
+            Ok((accessed, stat.modified))
+        });
+        match times {
+            Ok((accessed, modified)) => {
+                self.atimes.0 += 1;
+                if modified == Some(accessed) {
+                    self.atimes.1 += 1;
+                }
+                accessed <= cutoff
+            }
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the access time of {:?}, skipping it: {e}",
+                    path
+                ));
+                false
+            }
+        }
+    }
+
+    // Reads the size once, whichever bounds are given
+    fn is_in_size_range(&self, path: &Path, stat: &StatCache) -> bool {
+        if self.options.min_size.is_none() && self.options.max_size.is_none() {
+            return true;
+        }
+        match stat.read(path, &RealFs) {
+            Ok(stat) => self.size_in_range(stat.size),
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the size of {:?}, skipping it: {e}",
+                    path
+                ));
+                false
+            }
+        }
+    }
+
+    fn size_in_range(&self, len: u64) -> bool {
+        let (min, max) = (self.options.min_size, self.options.max_size);
+        min.is_none_or(|min| len >= min) && max.is_none_or(|max| len <= max)
+    }
+
+    fn is_too_old(&self, path: &Path, stat: &StatCache) -> bool {
+        if self.oldest_allowed.is_none() {
+            // Spares reading the metadata
+            return false;
+        }
+        let modified = match modified_time(path, stat) {
+            Ok(modified) => Some(modified),
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the modification time of {:?}, keeping it: {e}",
+                    path
+                ));
+                None
+            }
+        };
+        filter::is_protected_by_age(modified, self.oldest_allowed)
+    }
+
+    // Files have no owner off Unix, and are never protected there
+    fn is_owner_protected(&self, path: &Path, stat: &StatCache) -> bool {
+        if self.options.protected_owners.is_empty() {
+            return false;
+        }
+        match stat.read(path, &RealFs) {
+            Ok(stat) => stat
+                .uid
+                .is_some_and(|uid| self.options.protected_owners.contains(&uid)),
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the owner of {:?}, keeping it: {e}",
+                    path
+                ));
+                true
+            }
+        }
+    }
+
+    // Files whose permissions cannot be read are kept, like those of an unknown owner
+    fn is_readonly_skipped(&self, path: &Path, stat: &StatCache) -> bool {
+        if !self.options.skip_readonly {
+            return false;
+        }
+        match stat.read(path, &RealFs) {
+            // Without write permission for the owner, as `rm` tells write-protected files
+            Ok(stat) => stat.mode & 0o200 == 0,
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the permissions of {:?}, keeping it: {e}",
+                    path
+                ));
+                true
+            }
+        }
+    }
+
+    // Safety filters, each counting the files it keeps
+    fn is_protected(&mut self, path: &Path, stat: &StatCache) -> bool {
+        if self.is_too_old(path, stat) {
+            self.stats.age_protected += 1;
+            return true;
+        }
+        if self.is_owner_protected(path, stat) {
+            self.stats.owner_protected += 1;
+            return true;
+        }
+        if self.is_readonly_skipped(path, stat) {
+            self.stats.readonly_skipped += 1;
+            return true;
+        }
+        if self.keep.as_mut().is_some_and(|keep| keep.keeps(path)) {
+            self.stats.keep_listed += 1;
+            return true;
+        }
+        false
+    }
+
+    // Goes through what a directory deleted whole holds, without following symlinks, adding up
+    // its files. Returns None when anything inside is excluded, protected or cannot be read, the
+    // directory then being kept whole
+    fn scan_whole_dir(&mut self, dir: &Path) -> Option<Contents> {
+        let mut contents = Contents::default();
+        let mut kept = false;
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = match fs::read_dir(&current) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warning::warn(format!(
+                        "could not read {:?}, keeping {:?}: {e}",
+                        current, dir
+                    ));
+                    return None;
+                }
+            };
+            for entry in entries {
+                let Ok(entry) = entry else {
+                    return None;
+                };
+                let path = entry.path();
+                if self.excludes.excludes(&entry.file_name().to_string_lossy()) {
+                    kept = true;
+                    continue;
+                }
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    if self.keep.as_mut().is_some_and(|keep| keep.keeps(&path)) {
+                        self.stats.keep_listed += 1;
+                        kept = true;
+                    } else {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+                // Every file is checked rather than stopping at the first one kept, so that the
+                // stats count all of them
+                let stat = StatCache::default();
+                kept |= self.is_protected(&path, &stat);
+                contents.files += 1;
+                contents.bytes += stat.read(&path, &RealFs).map_or(0, |s| s.size);
+            }
+        }
+        (!kept).then_some(contents)
+    }
+
+    // Owned by the user and group asked for, if any (see --user and --group)
+    fn is_owned_as_asked(&self, path: &Path, stat: &StatCache) -> bool {
+        let (user, group) = (self.options.user, self.options.group);
+        if user.is_none() && group.is_none() {
+            return true;
+        }
+        match stat.read(path, &RealFs) {
+            Ok(stat) => {
+                user.is_none_or(|uid| stat.uid == Some(uid))
+                    && group.is_none_or(|gid| stat.gid == Some(gid))
+            }
+            Err(e) => {
+                warning::warn(format!(
+                    "could not read the owner of {:?}, skipping it: {e}",
+                    path
+                ));
+                false
+            }
+        }
+    }
+
+    // Files match by extension, by regex, by prefix, or by all of those given, before --invert
+    // flips the outcome
+    fn name_matches(&self, path: &Path, filename: &str) -> bool {
+        let options = self.options;
+        if options.regex.is_none() && options.prefixes.is_empty() {
+            return self.extensions.keeps_file(get_fileext(filename));
+        }
+
+        let by_extension =
+            self.extensions.is_empty() || self.extensions.matches_file(get_fileext(filename));
+        let by_regex = options.regex.as_ref().is_none_or(|regex| {
+            if options.regex_path {
+                regex.is_match(&path.to_string_lossy())
+            } else {
+                regex.is_match(filename)
+            }
+        });
+        let by_prefix = options.prefixes.is_empty()
+            || options
+                .prefixes
+                .iter()
+                .any(|prefix| filename.starts_with(prefix.as_str()));
+        (by_extension && by_regex && by_prefix) != options.invert
+    }
+
+    // Only an extension selects a directory, as --no-ext alone would match most of them.
+    // Exclusions and depth limits apply like to files, safety filters to what it holds (see
+    // scan_whole_dir)
+    fn dir_matches(&self, path: &Path, filename: &str, depth: usize) -> bool {
+        get_fileext(filename).is_some_and(|e| self.extensions.matching(e).is_some())
+            && self.name_matches(path, filename)
+            && !self.excludes.excludes(filename)
+            && self.options.min_depth.is_none_or(|min| depth + 1 >= min)
+    }
+
+    // The walk stops at the first match past the limit, which tells there are more. Link targets
+    // are only known once the walk is over, the limit then waiting for them to be spared
+    fn past_limit(&mut self) -> bool {
+        !self.options.preserve_link_targets && self.reaches_limit()
+    }
+
+    fn reaches_limit(&mut self) -> bool {
+        if self
+            .options
+            .limit
+            .is_some_and(|limit| self.collected >= limit)
+        {
+            self.stats.limit_reached = true;
+            return true;
+        }
+        self.collected += 1;
+        false
+    }
+
+    // Adds a match to `acc`, counted for the extension it matched
+    fn keep_match(
+        &mut self,
+        mut candidate: Candidate,
+        filename: &str,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.options.invert
+            && let Some(i) = get_fileext(filename).and_then(|e| self.extensions.matching(e))
+        {
+            self.stats.matched_by_extension[i] += 1;
+            candidate.matched_ext = Some(self.extensions.extension(i).to_string());
+        }
+
+        // A symlink found later in the traversal can still spare the file, so it is only
+        // reported as selected once every symlink is known
+        if !self.options.preserve_link_targets {
+            self.select(&candidate)?;
+        }
+
+        acc.push(candidate);
+        Ok(())
+    }
+
+    // Directories listed by dir lists can point anywhere, including to an ancestor
+    fn first_visit(&mut self, path: &Path) -> bool {
+        if self.options.follow_dir_lists.is_none() {
+            return true;
+        }
+        match fs::canonicalize(path) {
+            Ok(canonical) => self.visited.insert(canonical),
+            Err(_) => true,
+        }
+    }
+
+    #[cfg(unix)]
+    fn crosses_filesystem(&self, dir: &Path) -> bool {
+        self.root_device.is_some_and(|root| {
+            platform::mounts::crosses_filesystem(root, dir, platform::mounts::device)
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn crosses_filesystem(&self, _dir: &Path) -> bool {
+        false
+    }
+
+    // Followed symlinks can lead back to a directory being walked, which would never end
+    fn first_walk(&mut self, path: &Path) -> bool {
+        if !self.options.follow_symlinks {
+            return true;
+        }
+        match dir_id(path) {
+            Ok(id) if !self.walked.insert(id) => {
+                warning::warn(format!(
+                    "skipping {:?}, already walked through another path, like a symlink loop",
+                    path
+                ));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    // Walks the directories from a work list rather than by recursion, so that the order is
+    // chosen by how the list is consumed (see Traversal). The start path was asked for by name,
+    // so it is walked even when hidden, whatever the options: only its entries are filtered
+    fn collect(&mut self, path: PathBuf) -> Result<Vec<Candidate>, Box<dyn Error>> {
+        // Given by the user, so named in the error, unlike entries vanishing during the walk
+        if let Err(e) = fs::metadata(&path)
+            && e.kind() == io::ErrorKind::NotFound
+        {
+            return Err(RmxError::PathNotFound { path }.into());
+        }
+        self.root_device = if self.options.one_file_system {
+            Some(root_device(&path)?)
+        } else {
+            None
+        };
+        let mut acc: Vec<Candidate> = Vec::new();
+        match self.options.traversal {
+            Traversal::Dfs => self.collect_depth_first(path, &mut acc)?,
+            Traversal::Bfs => self.collect_breadth_first(path, &mut acc)?,
+        }
+        Ok(acc)
+    }
+
+    // Reads the entries of the directories on the stack as it goes, so that files of the first
+    // subdirectories are found before their siblings are all read, and only a batch of pending
+    // subdirectories per open directory is kept in memory, even in very wide trees
+    fn collect_depth_first(
+        &mut self,
+        path: PathBuf,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut stack: Vec<OpenDir> = Vec::new();
+        stack.extend(self.open(path, 0, SUBDIR_BATCH)?);
+
+        while let Some(dir) = stack.last_mut() {
+            if self.stats.limit_reached {
+                break;
+            }
+            if dir.entries.is_some() && dir.subdirs.len() < dir.batch {
+                self.read_entry(dir, acc)?;
+                continue;
+            }
+            let Some((subdir, depth)) = dir.subdirs.pop_front() else {
+                stack.pop();
+                continue;
+            };
+            // Past as many open directories, the next ones are read entirely before being
+            // walked, which closes them, so that deep trees do not run out of file descriptors
+            let open = stack.iter().filter(|d| d.entries.is_some()).count();
+            let batch = if open < MAX_OPEN_DIRS {
+                SUBDIR_BATCH
+            } else {
+                usize::MAX
+            };
+            stack.extend(self.open(subdir, depth, batch)?);
+        }
+
+        Ok(())
+    }
+
+    // Every directory of a level is read before the next level, which has to be kept whole
+    fn collect_breadth_first(
+        &mut self,
+        path: PathBuf,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut pending: VecDeque<(PathBuf, usize)> = VecDeque::from([(path, 0)]);
+
+        while let Some((path, depth)) = pending.pop_front() {
+            let Some(mut dir) = self.open(path, depth, usize::MAX)? else {
+                continue;
+            };
+            while dir.entries.is_some() && !self.stats.limit_reached {
+                self.read_entry(&mut dir, acc)?;
+            }
+            if self.stats.limit_reached {
+                break;
+            }
+            pending.extend(dir.subdirs);
+        }
+
+        Ok(())
+    }
+
+    // Opens a directory to walk, None meaning it is not walked
+    fn open(
+        &mut self,
+        path: PathBuf,
+        depth: usize,
+        batch: usize,
+    ) -> Result<Option<OpenDir>, Box<dyn Error>> {
+        // Entries of a directory are one level deeper than it
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            return Ok(None);
+        }
+        if !self.first_visit(&path) || !self.first_walk(&path) {
+            return Ok(None);
+        }
+
+        let listed = match &self.options.follow_dir_lists {
+            Some(name) => dir_list::read(&path, name),
+            None => Vec::new(),
+        };
+        Ok(Some(OpenDir {
+            entries: Some(fs::read_dir(&path)?),
+            depth,
+            listed,
+            subdirs: VecDeque::new(),
+            batch,
+        }))
+    }
+
+    // Collects the next entry of `dir` into `acc`, or queues it when it is a directory to walk.
+    // Once every entry is read, the directory is closed and its listed directories are queued
+    fn read_entry(
+        &mut self,
+        dir: &mut OpenDir,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(entry) = dir.entries.as_mut().and_then(Iterator::next) else {
+            dir.entries = None;
+            // Listed directories are collected like the start path, even without --recurse
+            let listed = mem::take(&mut dir.listed);
+            dir.subdirs.extend(listed.into_iter().map(|path| (path, 0)));
+            return Ok(());
+        };
+        let entry = entry.map_err(|e| format!("Error while retrieving file data: {:?}", e))?;
+        if let Some(subdir) = self.collect_entry(&entry, dir.depth, acc)? {
+            dir.subdirs.push_back((subdir, dir.depth + 1));
+        }
+        Ok(())
+    }
+
+    // Collects a single entry of a directory at `depth` into `acc`, returning it when it is a
+    // directory to walk
+    fn collect_entry(
+        &mut self,
+        entry: &DirEntry,
+        depth: usize,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let options = self.options;
+
+        let filepath = entry.path();
+        let os_filename = entry.file_name();
+        let filename = get_filename(entry, &os_filename)?;
+        let filetype = get_filetype(entry)?;
+
+        // Hidden symlinks count too, as they break just the same. Broken ones protect nothing
+        if options.preserve_link_targets
+            && filetype.is_symlink()
+            && let Ok(target) = fs::canonicalize(&filepath)
+        {
+            self.link_targets.insert(target);
+        }
+
+        if options.follow_dir_lists.as_deref() == Some(filename) {
+            // Drives the traversal, so it is never a candidate itself
+            self.record(&filepath, Decision::Excluded)?;
+            return Ok(None);
+        }
+
+        // Symlinks to directories are walked like them when followed
+        let is_dir = filetype.is_dir()
+            || (options.follow_symlinks
+                && filetype.is_symlink()
+                && fs::metadata(&filepath).is_ok_and(|m| m.is_dir()));
+
+        // Explicitly requested names are matched even without --all, though they are hidden
+        let is_cruft = options.apple_cruft && cruft::is_apple_cruft(filename);
+        let is_named = !is_dir
+            && options
+                .names
+                .iter()
+                .any(|name| same_name(name, filename, options.case_insensitive_fs));
+        let is_match =
+            || options.discover || is_cruft || is_named || self.name_matches(&filepath, filename);
+
+        // Hidden directories and hidden files are included independently (see --all)
+        if filename.starts_with('.') && !is_cruft && !is_named {
+            if is_dir && !options.include_hidden_dirs() {
+                return Ok(None);
+            }
+            if !is_dir && !options.include_hidden_files() {
+                // Both the name and the type are already known, so counting costs no extra IO
+                if filetype.is_file() && is_match() {
+                    self.stats.hidden_skipped += 1;
+                }
+                if filetype.is_file() {
+                    self.record(&filepath, Decision::HiddenSkipped)?;
+                }
+                return Ok(None);
+            }
+        }
+
+        // Directories named like an extension are matches themselves, deleted whole rather than
+        // walked, unless asked to expand them. Links to directories are walked when followed,
+        // never deleted whole. Anything inside kept by the safety filters keeps the directory
+        if options.dirs
+            && !options.expand_matched_dirs
+            && filetype.is_dir()
+            && self.dir_matches(&filepath, filename, depth)
+        {
+            if self.keep.as_mut().is_some_and(|keep| keep.keeps(&filepath)) {
+                self.stats.keep_listed += 1;
+                self.record(&filepath, Decision::Protected)?;
+                return Ok(None);
+            }
+            let Some(contents) = self.scan_whole_dir(&filepath) else {
+                self.stats.dirs_protected += 1;
+                self.record(&filepath, Decision::Protected)?;
+                return Ok(None);
+            };
+            // Sizes bound the total of what it holds, as deleting it frees that much
+            if !self.size_in_range(contents.bytes) {
+                self.record(&filepath, Decision::Excluded)?;
+                return Ok(None);
+            }
+            if self.past_limit() {
+                return Ok(None);
+            }
+            let mut candidate = Candidate::new(filepath, CandidateKind::Dir);
+            candidate.contents = Some(contents);
+            self.keep_match(candidate, filename, acc)?;
+            return Ok(None);
+        }
+
+        if options.recurse && is_dir {
+            if self.crosses_filesystem(&filepath) {
+                self.stats.mount_points_skipped.push(filepath);
+                return Ok(None);
+            }
+            return Ok(Some(filepath));
+        }
+
+        // Symlinks are removed themselves, whatever they point to, and only when asked
+        let is_link = options.symlinks && filetype.is_symlink() && !is_dir;
+        if !filetype.is_file() && !is_link {
+            return Ok(None);
+        }
+
+        // Files too close to the start path are left alone, whatever their name
+        if options.min_depth.is_some_and(|min| depth + 1 < min) {
+            self.record(&filepath, Decision::Excluded)?;
+            return Ok(None);
+        }
+
+        // Already renamed by an earlier run, which --invert would otherwise rename again
+        if options
+            .renamed_suffix
+            .as_ref()
+            .is_some_and(|suffix| filename.ends_with(suffix.as_str()))
+        {
+            self.record(&filepath, Decision::Excluded)?;
+            return Ok(None);
+        }
+
+        if !is_match() {
+            self.record(&filepath, Decision::ExtensionMismatch)?;
+            return Ok(None);
+        };
+        if self.excludes.excludes(filename) {
+            self.record(&filepath, Decision::Excluded)?;
+            return Ok(None);
+        }
+        // Read by the first filter needing it, then kept by the candidate
+        let stat = StatCache::default();
+        if !self.is_old_enough(&filepath, &stat, filename)
+            || !self.is_in_age_window(&filepath, &stat)
+            || !self.is_accessed_long_ago(&filepath, &stat)
+            || !self.is_in_size_range(&filepath, &stat)
+            || !self.is_owned_as_asked(&filepath, &stat)
+        {
+            self.record(&filepath, Decision::Excluded)?;
+            return Ok(None);
+        }
+
+        // Safety filters come last, once every other filter selected the file
+        if self.is_protected(&filepath, &stat) {
+            self.record(&filepath, Decision::Protected)?;
+            return Ok(None);
+        }
+
+        if self.past_limit() {
+            return Ok(None);
+        }
+
+        let kind = if is_link {
+            CandidateKind::Symlink
+        } else {
+            CandidateKind::File
+        };
+        let mut candidate = Candidate::new(filepath, kind);
+        candidate.stat = stat;
+        self.keep_match(candidate, filename, acc)?;
+        Ok(None)
+    }
+}
+
+// Subdirectories gathered per directory before walking them (see Walk::collect_depth_first).
+// Small trees keep their order, the files of a directory coming before those of its
+// subdirectories
+const SUBDIR_BATCH: usize = 256;
+
+// Directories kept open at once while walking depth-first
+const MAX_OPEN_DIRS: usize = 64;
+
+// A directory being walked (see Walk::collect_depth_first). Storing PathBuf for subdirectories
+// instead of DirEntry avoids keeping their file descriptors opened, thus avoiding a "Too many
+// open files" error
+struct OpenDir {
+    // None once every entry is read, which closes the directory
+    entries: Option<ReadDir>,
+    depth: usize,
+    // Directories listed by its dir list (see --follow-dir-lists)
+    listed: Vec<PathBuf>,
+    // Directories found and not walked yet, along with their depth
+    subdirs: VecDeque<(PathBuf, usize)>,
+    // Subdirectories gathered before walking them, while entries are left to read
+    batch: usize,
+}
+
+// Modification time of a file being collected, read along with its size
+fn modified_time(path: &Path, stat: &StatCache) -> Result<SystemTime, String> {
+    stat.read(path, &RealFs)?
+        .modified
+        .ok_or_else(|| "not available on this platform".to_string())
+}
+
+// Files last modified before this are never collected (see --never-older-than)
+fn oldest_allowed(options: &CollectOptions) -> Option<SystemTime> {
+    options
+        .never_older_than
+        .and_then(|age| SystemTime::now().checked_sub(age))
+}
+
+// Modification times collected (see --older-than, --newer-than and their -file variants), ages
+// going back before the epoch stopping there
+fn age_window(options: &CollectOptions) -> Option<AgeWindow> {
+    if options.older_than.is_none()
+        && options.newer_than.is_none()
+        && options.modified_before.is_none()
+        && options.modified_after.is_none()
+    {
+        return None;
+    }
+    let now = SystemTime::now();
+    let cutoff = |age| now.checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH);
+    Some(AgeWindow {
+        oldest: options.newer_than.map(cutoff),
+        newest: options.older_than.map(cutoff),
+        after: options.modified_after,
+        before: options.modified_before,
+    })
+}
+
+// Extensions given to collect, along with how they match
+fn extension_filter(extensions: &[String], options: &CollectOptions) -> ExtensionFilter {
+    ExtensionFilter::new(extensions, options.invert)
+        .with_no_ext(options.no_ext)
+        .with_ignore_case(options.ignore_case)
+}
+
+// Roots share the audit log, the keep list and the directories already walked, so that several
+// roots make a single collection
+fn walk(
+    extensions: &[String],
+    roots: &[PathBuf],
+    options: &CollectOptions,
+    sink: Option<Sink>,
+) -> Result<(Vec<Candidate>, CollectStats), Box<dyn Error>> {
+    let extension_filter = extension_filter(extensions, options);
+
+    let mut walk = Walk::new(&extension_filter, options, sink)?;
+    let mut candidates = Vec::new();
+    for root in roots {
+        if walk.stats.limit_reached {
+            break;
+        }
+        candidates.extend(walk.collect(root.clone())?);
+    }
+    if options.preserve_link_targets {
+        candidates = walk.spare_link_targets(candidates)?;
+    }
+    walk.finish()?;
+
+    if options.sort {
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    Ok((candidates, walk.stats))
+}
+
+impl<'a, 's> Walk<'a, 's> {
+    fn new(
+        extensions: &'a ExtensionFilter,
+        options: &'a CollectOptions,
+        sink: Option<Sink<'s>>,
+    ) -> Result<Walk<'a, 's>, Box<dyn Error>> {
+        Ok(Walk {
+            options,
+            extensions,
+            excludes: ExcludeFilter::new(&options.exclude, options.case_insensitive_fs),
+            rules: (!options.age_rules.is_empty())
+                .then(|| AgeRules::new(&options.age_rules, SystemTime::now())),
+            sink,
+            stats: CollectStats {
+                matched_by_extension: vec![0; extensions.len()],
+                ..Default::default()
+            },
+            oldest_allowed: oldest_allowed(options),
+            age_window: age_window(options),
+            accessed_before: options.accessed_before.map(|age| {
+                SystemTime::now()
+                    .checked_sub(age)
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+            }),
+            atimes: (0, 0),
+            visited: HashSet::new(),
+            walked: HashSet::new(),
+            collected: 0,
+            root_device: None,
+            link_targets: HashSet::new(),
+            audit: match &options.audit {
+                Some(audit_path) => Some(AuditLog::create(audit_path).map_err(|e| {
+                    format!(
+                        "Could not create audit file {}: {}",
+                        audit_path.display(),
+                        e
+                    )
+                })?),
+                None => None,
+            },
+            keep: match &options.keep_from {
+                // Relative entries are relative to where the list was likely written
+                Some(keep_from) => Some(KeepList::load(
+                    keep_from,
+                    &paths::list_base(keep_from, options.base.as_deref())?,
+                    options.case_insensitive_fs,
+                )?),
+                None => None,
+            },
+        })
+    }
+
+    // Once every root is collected
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(audit) = self.audit.take() {
+            audit.finish()?;
+        }
+        if let Some(warning) = filter::unreliable_atime_warning(self.atimes.0, self.atimes.1) {
+            warning::warn(warning);
+        }
+        // Entries keeping nothing are likely typos, which would leave the files they meant unkept
+        if let Some(keep) = &self.keep {
+            for entry in keep.unmatched() {
+                warning::warn(format!("{entry:?} of the keep list matched no file"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn into_paths(candidates: Vec<Candidate>) -> Vec<PathBuf> {
+    candidates.into_iter().map(|c| c.path).collect()
+}
+
+/// Same as [collect_matching_files], without any listing: matches are only given to the sink.
+/// Extensions are assumed to be valid (see [are_extensions_valid])
+///
+/// ```
+/// use std::path::Path;
+///
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join("a.log"))?;
+///
+/// let mut seen = Vec::new();
+/// let mut sink = |p: &Path| {
+///     seen.push(p.to_path_buf());
+///     Ok(())
+/// };
+/// let files = rmx::collect_matching_files_with_sink(
+///     &["log".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+///     Some(&mut sink),
+/// )?;
+///
+/// assert_eq!(files, seen);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_matching_files_with_sink(
+    extensions: &[String],
+    path: &PathBuf,
+    options: &CollectOptions,
+    sink: Option<Sink>,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let (candidates, _) = walk(extensions, slice::from_ref(path), options, sink)?;
+    Ok(into_paths(candidates))
+}
+
+/// Same as [collect_candidates], along with counters gathered during the collection
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join(".hidden.log"))?;
+///
+/// let (candidates, stats) = rmx::collect_candidates_with_stats(
+///     &["log".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+/// )?;
+///
+/// assert!(candidates.is_empty());
+/// assert_eq!(1, stats.hidden_skipped);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_candidates_with_stats(
+    extensions: &[String],
+    path: &PathBuf,
+    options: &CollectOptions,
+) -> Result<(Vec<Candidate>, CollectStats), Box<dyn Error>> {
+    collect_in_roots(extensions, slice::from_ref(path), options)
+}
+
+// Same as collect_candidates_with_stats, from several roots listed together
+fn collect_in_roots(
+    extensions: &[String],
+    roots: &[PathBuf],
+    options: &CollectOptions,
+) -> Result<(Vec<Candidate>, CollectStats), Box<dyn Error>> {
+    if !options.list {
+        return walk(extensions, roots, options, None);
+    }
+
+    let (peek, rules) = listing_filters(options);
+    let notes = listing::Notes {
+        peek: peek.as_ref(),
+        rules: rules.as_ref(),
+        symlinks: options.symlinks,
+        dirs: options.dirs,
+    };
+
+    // Sorting alone only needs the listed paths, which are then buffered up to sort_limit: past
+    // it, paths buffered so far are printed as they are, and the rest is streamed
+    let bounded_sort = options.sort
+        && options.sort_limit.is_some()
+        && !options.long
+        && !options.preserve_link_targets;
+
+    if options.buffered_listing() && !bounded_sort {
+        let (candidates, stats) = walk(extensions, roots, options, None)?;
+        print_listing(&candidates, options, notes)?;
+        return Ok((candidates, stats));
+    }
+
+    let limit = options.sort_limit.unwrap_or(usize::MAX);
+    let mut sorted: Option<Vec<PathBuf>> = bounded_sort.then(Vec::new);
+    let mut out = listing::MatchWriter::new(io::stdout());
+    let mut stream = |p: &Path| {
+        if let Some(mut buffer) = sorted.take() {
+            if buffer.len() < limit {
+                buffer.push(p.to_path_buf());
+                sorted = Some(buffer);
+                return Ok(());
+            }
+            warning::warn(listing::format_sort_fallback(limit));
+            for buffered in &buffer {
+                out.write(buffered, options.truncate, notes)?;
+            }
+        }
+        out.write(p, options.truncate, notes)
+    };
+    let (candidates, stats) = walk(extensions, roots, options, Some(&mut stream))?;
+    if let Some(mut buffer) = sorted {
+        buffer.sort();
+        for p in &buffer {
+            out.write(p, options.truncate, notes)?;
+        }
+    }
+    out.finish()?;
+    // Sizes are read once every match is printed, rather than one by one as they are found
+    metadata::enrich(&candidates, &RealFs, options.threads);
+    let bytes = candidates.iter().map(Candidate::size).sum();
+    listing::print_footer(candidates.len(), bytes)?;
+    Ok((candidates, stats))
+}
+
+// Archives peeked into and age rules, which listings show along with the files
+fn listing_filters(options: &CollectOptions) -> (Option<ExtensionFilter>, Option<AgeRules>) {
+    let peek = (!options.peek_extensions.is_empty())
+        .then(|| ExtensionFilter::new(&options.peek_extensions, false));
+    let rules = (!options.age_rules.is_empty())
+        .then(|| AgeRules::new(&options.age_rules, SystemTime::now()));
+    (peek, rules)
+}
+
+fn print_listing(
+    candidates: &[Candidate],
+    options: &CollectOptions,
+    notes: listing::Notes,
+) -> Result<(), Box<dyn Error>> {
+    metadata::enrich(candidates, &RealFs, options.threads);
+    listing::print_candidates(
+        candidates,
+        options.long,
+        options.truncate,
+        notes,
+        options.size_heat.as_ref(),
+    )?;
+    let bytes = candidates.iter().map(Candidate::size).sum();
+    listing::print_footer(candidates.len(), bytes)?;
+    Ok(())
+}
+
+// Lists files known without walking anything, like those of --files-from, the way collecting
+// lists its matches
+#[doc(hidden)]
+pub fn list_candidates(
+    candidates: &[Candidate],
+    options: &CollectOptions,
+) -> Result<(), Box<dyn Error>> {
+    if !options.list {
+        return Ok(());
+    }
+    let (peek, rules) = listing_filters(options);
+    let notes = listing::Notes {
+        peek: peek.as_ref(),
+        rules: rules.as_ref(),
+        symlinks: options.symlinks,
+        dirs: options.dirs,
+    };
+    print_listing(candidates, options, notes)
+}
+
+/// Like [collect_matching_files], along with what is known of each match
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join("a.tar.gz"))?;
+///
+/// let candidates = rmx::collect_candidates(
+///     &["gz".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+/// )?;
+///
+/// assert_eq!(Some("gz"), candidates[0].matched_ext.as_deref());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_candidates(
+    extensions: &[String],
+    path: &PathBuf,
+    options: &CollectOptions,
+) -> Result<Vec<Candidate>, Box<dyn Error>> {
+    Ok(collect_candidates_with_stats(extensions, path, options)?.0)
+}
+
+/// Same as [collect_candidates_with_stats], from several directories collected and listed
+/// together, each directory being walked once
+#[doc(hidden)]
+pub fn collect_candidates_in_roots(
+    extensions: &[String],
+    roots: &[PathBuf],
+    options: &CollectOptions,
+) -> Result<(Vec<Candidate>, CollectStats), Box<dyn Error>> {
+    collect_in_roots(extensions, roots, options)
+}
+
+/// Same as [collect_matching_files], along with counters gathered during the collection
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join("a.log"))?;
+///
+/// let (files, stats) = rmx::collect_matching_files_with_stats(
+///     &["log".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+/// )?;
+///
+/// assert_eq!(1, files.len());
+/// assert_eq!(0, stats.hidden_skipped);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_matching_files_with_stats(
+    extensions: &[String],
+    path: &PathBuf,
+    options: &CollectOptions,
+) -> Result<(Vec<PathBuf>, CollectStats), Box<dyn Error>> {
+    let (candidates, stats) = collect_candidates_with_stats(extensions, path, options)?;
+    Ok((into_paths(candidates), stats))
+}
+
+/// Files under `path` matching the given extensions, according to the options. Extensions are
+/// assumed to be valid (see [are_extensions_valid])
+///
+/// With `options.list`, matches are either printed as they are found, or once everything is
+/// collected when the listing needs the complete set (see `sort` and `long`)
+///
+/// A hidden `path` is walked all the same, hidden entries under it following the options
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::File::create(dir.path().join("a.log"))?;
+/// std::fs::File::create(dir.path().join("b.txt"))?;
+///
+/// let files = rmx::collect_matching_files(
+///     &["log".to_string()],
+///     &dir.path().to_path_buf(),
+///     &rmx::CollectOptions::default(),
+/// )?;
+///
+/// assert_eq!(vec![dir.path().join("a.log")], files);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_matching_files(
+    extensions: &[String],
+    path: &PathBuf,
+    options: &CollectOptions,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    Ok(into_paths(collect_candidates(extensions, path, options)?))
+}
+
+// Narrows a saved plan with the given extensions, if any, and safety filters, using the
+// metadata stored in the plan only: the filesystem is never read (see --simulate)
+#[doc(hidden)]
+pub fn simulate(
+    plan: &DeletionPlan,
+    extensions: &[String],
+    options: &CollectOptions,
+) -> (Vec<PlannedFile>, CollectStats) {
+    let extension_filter = extension_filter(extensions, options);
+    let oldest_allowed = oldest_allowed(options);
+    let mut stats = CollectStats::default();
+
+    let files = plan
+        .files
+        .iter()
+        .filter(|f| {
+            let name = f.path.rsplit('/').next().unwrap_or(&f.path);
+            extension_filter.is_empty() || extension_filter.keeps_file(get_fileext(name))
+        })
+        .filter(|f| {
+            let protected = filter::is_protected_by_age(f.modified_time(), oldest_allowed);
+            if protected {
+                stats.age_protected += 1;
+            }
+            !protected
+        })
+        .cloned()
+        .collect();
+
+    (files, stats)
+}
+
+// Files of the list `source` (see --files-from), narrowed by the given extensions, if any. Other
+// filters do not apply, the list being what is deleted
+#[doc(hidden)]
+pub fn listed_files(
+    source: &Path,
+    extensions: &[String],
+    options: &CollectOptions,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let extension_filter = extension_filter(extensions, options);
+    Ok(file_list::read(source, options.base.as_deref())?
+        .into_iter()
+        .filter(|f| has_extension(f, &extension_filter))
+        .collect())
+}
+
+// Whether a file given by name, rather than found by a walk, matches the given extensions, if any
+#[doc(hidden)]
+pub fn file_matches(file: &Path, extensions: &[String], options: &CollectOptions) -> bool {
+    has_extension(file, &extension_filter(extensions, options))
+}
+
+fn has_extension(file: &Path, extension_filter: &ExtensionFilter) -> bool {
+    extension_filter.is_empty()
+        || extension_filter.keeps_file(
+            file.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(get_fileext),
+        )
+}
+
+// Files per extension under `path`, as shown by --discover. Nothing is listed nor deleted
+#[doc(hidden)]
+pub fn discover_extensions(
+    path: &PathBuf,
+    options: &CollectOptions,
+) -> Result<Vec<discover::ExtensionStats>, Box<dyn Error>> {
+    let (candidates, _) = walk(&[], slice::from_ref(path), options, None)?;
+    metadata::enrich(&candidates, &RealFs, options.threads);
+
+    Ok(discover::aggregate(candidates.iter().map(|c| {
+        let extension = c
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(get_fileext);
+        (extension, c.size())
+    })))
+}
+
+// Lowers CPU and IO priority of rmx for the rest of the run, see --nice
+// Failing to do so is not worth stopping the run, so it only warns
+#[cfg(unix)]
+#[doc(hidden)]
+pub fn lower_priority() {
+    if let Err(e) = platform::priority::lower_cpu_priority() {
+        warning::warn(format!("could not lower CPU priority: {e}"));
+    }
+    if let Err(e) = platform::priority::lower_io_priority() {
+        warning::warn(format!("could not lower IO priority: {e}"));
+    }
+}
+
+#[cfg(not(unix))]
+#[doc(hidden)]
+pub fn lower_priority() {
+    warning::warn("--nice is not supported on this platform, ignoring it");
+}
+
+// Bytes available on the filesystem holding `path`, to measure what a deletion gives back
+#[cfg(unix)]
+#[doc(hidden)]
+pub fn available_space(path: &Path) -> Option<u64> {
+    platform::space::available_bytes(path).ok()
+}
+
+#[cfg(not(unix))]
+#[doc(hidden)]
+pub fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+// Whether every candidate lives on the filesystem of `path`, in which case measuring the free
+// space of that filesystem alone tells what deleting them gives back. Devices come from the
+// metadata read during the run
+#[cfg(unix)]
+#[doc(hidden)]
+pub fn on_start_filesystem(path: &Path, candidates: &[Candidate]) -> bool {
+    let Ok(start) = platform::space::device(path) else {
+        return false;
+    };
+    candidates.iter().all(|c| {
+        c.stat
+            .read(&c.path, &RealFs)
+            .is_ok_and(|s| s.dev == Some(start))
+    })
+}
+
+#[cfg(not(unix))]
+#[doc(hidden)]
+pub fn on_start_filesystem(_path: &Path, _candidates: &[Candidate]) -> bool {
+    false
+}
+
+// Bytecode goes through the safety filters like any other file, a `__pycache__` directory
+// holding anything they keep being kept whole
+impl Walk<'_, '_> {
+    fn collect_pycache(
+        &mut self,
+        path: &Path,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        let options = self.options;
+        for entry in fs::read_dir(path)? {
+            let entry = entry.map_err(|e| format!("Error while retrieving file data: {:?}", e))?;
+
+            let filepath = entry.path();
+            let os_filename = entry.file_name();
+            let filename = get_filename(&entry, &os_filename)?;
+            let filetype = get_filetype(&entry)?;
+
+            if filetype.is_dir() && filename == pycache::CACHE_DIR {
+                if self.excludes.excludes(filename) {
+                    self.record(&filepath, Decision::Excluded)?;
+                    continue;
+                }
+                if self.keep.as_mut().is_some_and(|keep| keep.keeps(&filepath)) {
+                    self.stats.keep_listed += 1;
+                    self.record(&filepath, Decision::Protected)?;
+                    continue;
+                }
+                if !options.orphans_only {
+                    let Some(contents) = self.scan_whole_dir(&filepath) else {
+                        self.stats.dirs_protected += 1;
+                        self.record(&filepath, Decision::Protected)?;
+                        continue;
+                    };
+                    let mut candidate = Candidate::new(filepath, CandidateKind::Dir);
+                    candidate.contents = Some(contents);
+                    self.select(&candidate)?;
+                    acc.push(candidate);
+                    continue;
+                }
+                for cached in fs::read_dir(&filepath)? {
+                    let cached = cached?;
+                    let cached_name = cached.file_name();
+                    let cached_name = get_filename(&cached, &cached_name)?;
+                    if get_filetype(&cached)?.is_file()
+                        && pycache::is_bytecode(cached_name)
+                        && pycache::is_orphan(&cached.path())
+                    {
+                        self.collect_bytecode(cached.path(), cached_name, acc)?;
+                    }
+                }
+                continue;
+            }
+
+            if filename.starts_with('.') {
+                if filetype.is_dir() && !options.include_hidden_dirs() {
+                    continue;
+                }
+                if !filetype.is_dir() && !options.include_hidden_files() {
+                    continue;
+                }
+            }
+
+            if filetype.is_dir() {
+                if options.recurse {
+                    self.collect_pycache(&filepath, acc)?;
+                }
+                continue;
+            }
+
+            // Bytecode left outside of __pycache__, by Python 2 or by copying files around
+            if filetype.is_file()
+                && pycache::is_bytecode(filename)
+                && (!options.orphans_only || pycache::is_orphan(&filepath))
+            {
+                self.collect_bytecode(filepath, filename, acc)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_bytecode(
+        &mut self,
+        path: PathBuf,
+        filename: &str,
+        acc: &mut Vec<Candidate>,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.excludes.excludes(filename) {
+            self.record(&path, Decision::Excluded)?;
+            return Ok(());
+        }
+        let stat = StatCache::default();
+        if self.is_protected(&path, &stat) {
+            self.record(&path, Decision::Protected)?;
+            return Ok(());
+        }
+        let mut candidate = Candidate::new(path, CandidateKind::File);
+        candidate.stat = stat;
+        self.select(&candidate)?;
+        acc.push(candidate);
+        Ok(())
+    }
+}
+
+/// Python bytecode under `path`: whole `__pycache__` directories, and `.pyc` / `.pyo` files
+/// outside of them. With `options.orphans_only`, only bytecode files whose source no longer
+/// exists are collected, `__pycache__` directories being kept. The safety filters, like
+/// `options.protected_owners`, apply to every bytecode file, a `__pycache__` directory holding
+/// one they keep being kept whole
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::create_dir(dir.path().join("__pycache__"))?;
+///
+/// let candidates = rmx::collect_pycache(dir.path(), &rmx::CollectOptions::default())?;
+///
+/// assert_eq!(vec![dir.path().join("__pycache__")], candidates.into_iter().map(|c| c.path).collect::<Vec<_>>());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_pycache(
+    path: &Path,
+    options: &CollectOptions,
+) -> Result<Vec<Candidate>, Box<dyn Error>> {
+    Ok(collect_pycache_in_roots(slice::from_ref(&path.to_path_buf()), options)?.0)
+}
+
+/// Same as [collect_pycache], from several directories collected and listed together, along
+/// with counters gathered during the collection
+#[doc(hidden)]
+pub fn collect_pycache_in_roots(
+    roots: &[PathBuf],
+    options: &CollectOptions,
+) -> Result<(Vec<Candidate>, CollectStats), Box<dyn Error>> {
+    let extension_filter = extension_filter(&[], options);
+    let mut walk = Walk::new(&extension_filter, options, None)?;
+    let mut candidates = Vec::new();
+    for root in roots {
+        walk.collect_pycache(root, &mut candidates)?;
+    }
+    walk.finish()?;
+
+    if options.sort {
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    if options.list {
+        listing::print_candidates(
+            &candidates,
+            options.long,
+            options.truncate,
+            listing::Notes::default(),
+            options.size_heat.as_ref(),
+        )?;
+    }
+
+    Ok((candidates, walk.stats))
+}
+
+// Empty directories under `path`, children first so that they can be removed in order
+// Returns whether `path` itself holds nothing but such directories
+fn collect_empty_dirs_rec(
+    path: &Path,
+    options: &CollectOptions,
+    acc: &mut Vec<Candidate>,
+) -> Result<bool, Box<dyn Error>> {
+    let mut empty = true;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry.map_err(|e| format!("Error while retrieving file data: {:?}", e))?;
+
+        let filepath = entry.path();
+        let os_filename = entry.file_name();
+        let filename = get_filename(&entry, &os_filename)?;
+        let filetype = get_filetype(&entry)?;
+
+        // Whatever is not removed, including skipped hidden directories, keeps its parent
+        if !filetype.is_dir() || (!options.include_hidden_dirs() && filename.starts_with('.')) {
+            empty = false;
+            continue;
+        }
+
+        // Without --recurse, only directories with nothing at all inside are removed
+        let is_empty = if options.recurse {
+            collect_empty_dirs_rec(&filepath, options, acc)?
+        } else {
+            fs::read_dir(&filepath)?.next().is_none()
+        };
+
+        if is_empty {
+            acc.push(Candidate::new(filepath, CandidateKind::EmptyDir));
+        } else {
+            empty = false;
+        }
+    }
+
+    Ok(empty)
+}
+
+/// Directories containing no file, evaluated bottom-up: a directory holding only empty
+/// directories is empty as well. The start path itself is never part of them
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::create_dir_all(dir.path().join("a/b"))?;
+///
+/// let mut options = rmx::CollectOptions::default();
+/// options.recurse = true;
+/// let dirs = rmx::collect_empty_dirs(dir.path(), &options)?;
+///
+/// // Children first, so that they can be deleted in order
+/// assert_eq!(vec![dir.path().join("a/b"), dir.path().join("a")], dirs);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn collect_empty_dirs(
+    path: &Path,
+    options: &CollectOptions,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut candidates = Vec::new();
+    collect_empty_dirs_rec(path, options, &mut candidates)?;
+
+    if options.list {
+        listing::print_candidates(
+            &candidates,
+            options.long,
+            options.truncate,
+            listing::Notes::default(),
+            options.size_heat.as_ref(),
+        )?;
+    }
+
+    Ok(into_paths(candidates))
+}
+
+#[cfg(unix)]
+fn print_filesystem_span(files: &[PathBuf]) {
+    let Some(mounts) = platform::mounts::read_mounts() else {
+        return;
+    };
+    let Some(counts) = platform::mounts::count_per_mount_point(&mounts, files) else {
+        return;
+    };
+    if let Some(span) = report::format_filesystem_span(&counts) {
+        println!("{span}");
+    }
+}
+
+#[cfg(not(unix))]
+fn print_filesystem_span(_files: &[PathBuf]) {}
+
+// `what` counts the candidates, like `3 file(s)`
+fn prompt_for_confirmation(
+    files: &[PathBuf],
+    what: &str,
+    verb: &str,
+) -> Result<bool, Box<dyn Error>> {
+    print_filesystem_span(files);
+
+    print!("Do you really want to {verb} {what}? [Y/n] ");
+    io::stdout().flush()?;
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf)?;
+    end_prompt_line();
+
+    Ok(matches!(buf.trim(), "" | "y" | "Y"))
+}
+
+// Progress and outcome messages, unlike listings and prompts, which quiet never hides
+fn status(delete_options: &DeleteOptions, message: &str) {
+    if !delete_options.quiet {
+        println!("{message}");
+    }
+}
+
+// Piped answers are not echoed, so the prompt line is ended for them, keeping the messages that
+// follow on their own lines
+fn end_prompt_line() {
+    if !io::stdin().is_terminal() {
+        println!();
+    }
+}
+
+fn remove_candidate(candidate: &Candidate) -> io::Result<()> {
+    match candidate.kind {
+        CandidateKind::File | CandidateKind::Symlink => fs::remove_file(&candidate.path),
+        CandidateKind::EmptyDir => fs::remove_dir(&candidate.path),
+        CandidateKind::Dir => fs::remove_dir_all(&candidate.path),
+    }
+}
+
+// Failures are described by their message, explaining the ones caused by inode flags
+#[cfg(target_os = "linux")]
+fn remove_explained(candidate: &Candidate, clear_immutable: bool) -> Result<(), String> {
+    use platform::immutable;
+    immutable::remove(&candidate.path, clear_immutable, &immutable::Ioctl, || {
+        remove_candidate(candidate)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn remove_explained(candidate: &Candidate, _clear_immutable: bool) -> Result<(), String> {
+    remove_candidate(candidate).map_err(|e| e.to_string())
+}
+
+// First free name among `foo.log<suffix>`, `foo.log.1<suffix>`, `foo.log.2<suffix>`... so that
+// renamed files still end with the suffix
+fn rename_target(path: &Path, suffix: &str) -> PathBuf {
+    let mut n = 0;
+    loop {
+        let mut name = path.as_os_str().to_os_string();
+        if n > 0 {
+            name.push(format!(".{n}"));
+        }
+        name.push(suffix);
+        let target = PathBuf::from(name);
+        if fs::symlink_metadata(&target).is_err() {
+            return target;
+        }
+        n += 1;
+    }
+}
+
+fn rename_candidate(candidate: &Candidate, suffix: &str) -> Result<PathBuf, String> {
+    let target = rename_target(&candidate.path, suffix);
+    fs::rename(&candidate.path, &target).map_err(|e| e.to_string())?;
+    Ok(target)
+}
+
+// Verb of the prompts and progress messages, like ("delete", "Delete", "Deleting")
+fn verbs(action: &Action) -> (&'static str, &'static str, &'static str) {
+    match action {
+        Action::Delete => ("delete", "Delete", "Deleting"),
+        Action::Rename(_) => ("rename", "Rename", "Renaming"),
+    }
+}
+
+// Only files are locked by the processes writing them, a directory deleted whole being locked
+// by any of those it holds (see --respect-locks)
+#[cfg(unix)]
+fn is_locked(candidate: &Candidate) -> bool {
+    match candidate.kind {
+        CandidateKind::File => platform::locks::is_locked(&candidate.path),
+        CandidateKind::Dir => holds_locked_file(&candidate.path),
+        _ => false,
+    }
+}
+
+// Symlinks are not followed, as removing them leaves their target alone
+#[cfg(unix)]
+fn holds_locked_file(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| match entry.file_type() {
+        Ok(t) if t.is_dir() => holds_locked_file(&entry.path()),
+        Ok(t) if t.is_file() => platform::locks::is_locked(&entry.path()),
+        _ => false,
+    })
+}
+
+#[cfg(not(unix))]
+fn is_locked(_candidate: &Candidate) -> bool {
+    false
+}
+
+// Deepest paths first, so that anything inside a directory is removed before it, and files
+// before directories of the same depth. Ties are broken by path to keep the order stable
+fn deletion_order(candidates: &[Candidate]) -> Vec<Candidate> {
+    let depth = |c: &Candidate| c.path.components().count();
+
+    let mut ordered = candidates.to_vec();
+    ordered.sort_by(|a, b| {
+        depth(b)
+            .cmp(&depth(a))
+            .then(a.kind.is_dir().cmp(&b.kind.is_dir()))
+            .then(a.path.cmp(&b.path))
+    });
+    ordered
+}
+
+// How candidates are named in the prompt and in the progress message, directories deleted with
+// their content being counted apart from the rest
+struct Nouns {
+    // Like `file(s)`
+    files: &'static str,
+    // Like `directory(ies)`
+    dirs: &'static str,
+    // Like `files`, in `Deleting files...`
+    progress: &'static str,
+}
+
+// Collected files, along with directories matched by --dirs
+const FILES: Nouns = Nouns {
+    files: "file(s)",
+    dirs: "directory(ies)",
+    progress: "files",
+};
+
+impl Nouns {
+    // Like `3 file(s) and 1 directory(ies)`, either being left out when there is none
+    fn count(&self, candidates: &[Candidate]) -> String {
+        let dirs = candidates
+            .iter()
+            .filter(|c| c.kind == CandidateKind::Dir)
+            .count();
+        let files = candidates.len() - dirs;
+        match (files, dirs) {
+            (files, 0) => format!("{files} {}", self.files),
+            (0, dirs) => format!("{dirs} {}", self.dirs),
+            (files, dirs) => format!("{files} {} and {dirs} {}", self.files, self.dirs),
+        }
+    }
+}
+
+// Candidates that cannot be deleted do not stop the deletion, they are recorded in the report
+// instead
+fn delete_candidates(
+    candidates: &[Candidate],
+    delete_options: &DeleteOptions,
+    what: Nouns,
+    checkpoint: Option<&mut Checkpoint>,
+) -> Result<DeleteReport, Box<dyn Error>> {
+    let mut report = DeleteReport {
+        matched: candidates.len(),
+        dry_run: delete_options.dry_run,
+        ..Default::default()
+    };
+
+    let chunk_size = delete_options
+        .confirm_chunks
+        .filter(|_| !delete_options.force && !delete_options.dry_run);
+
+    // Only directories make the order matter, files are otherwise removed in collection order.
+    // Chunks are made of files sorted by path, so that each one covers few directories
+    let ordered;
+    let candidates = if candidates.iter().any(|c| c.kind.is_dir()) {
+        ordered = deletion_order(candidates);
+        &ordered
+    } else if chunk_size.is_some() {
+        ordered = {
+            let mut sorted = candidates.to_vec();
+            sorted.sort_by(|a, b| a.path.cmp(&b.path));
+            sorted
+        };
+        &ordered
+    } else {
+        candidates
+    };
+
+    if delete_options.dry_run {
+        metadata::enrich(candidates, &RealFs, delete_options.threads);
+        for candidate in candidates {
+            if !vetoed(candidate, delete_options, &mut report) {
+                report.bytes += candidate.size();
+            }
+        }
+        return Ok(report);
+    }
+
+    if let Some(chunk_size) = chunk_size {
+        metadata::enrich(candidates, &RealFs, delete_options.threads);
+        delete_in_chunks(
+            candidates,
+            chunk_size,
+            delete_options,
+            what.files,
+            &mut report,
+        )?;
+        return Ok(report);
+    }
+
+    let (verb, _, progress) = verbs(&delete_options.action);
+    let paths: Vec<PathBuf> = candidates.iter().map(|c| c.path.clone()).collect();
+    if !delete_options.force && !prompt_for_confirmation(&paths, &what.count(candidates), verb)? {
+        status(delete_options, "Cancelled file deletion.");
+        report.cancelled = true;
+        return Ok(report);
+    }
+
+    status(delete_options, &format!("{progress} {}...", what.progress));
+    // Sizes are needed before the files are gone
+    metadata::enrich(candidates, &RealFs, delete_options.threads);
+    match checkpoint {
+        Some(checkpoint) => delete_in_groups(candidates, delete_options, checkpoint, &mut report)?,
+        None => remove_all(candidates, delete_options, &mut report),
+    }
+    status(delete_options, "Done!");
+
+    Ok(report)
+}
+
+// Under --strict, the first file that could not be deleted or was locked stops the deletion
+fn strict_stop(report: &DeleteReport) -> bool {
+    warning::is_strict() && !(report.failures.is_empty() && report.locked.is_empty())
+}
+
+// Records the outcome of each deletion in the report
+fn remove_all(candidates: &[Candidate], delete_options: &DeleteOptions, report: &mut DeleteReport) {
+    for candidate in candidates {
+        if strict_stop(report) {
+            return;
+        }
+        // Probed right before the deletion, as close as possible to it
+        if delete_options.respect_locks && is_locked(candidate) {
+            report.locked.push(candidate.path.clone());
+            continue;
+        }
+        if vetoed(candidate, delete_options, report) {
+            continue;
+        }
+        if let Action::Rename(suffix) = &delete_options.action {
+            match rename_candidate(candidate, suffix) {
+                Ok(target) => report.renamed.push((candidate.path.clone(), target)),
+                Err(_) if vanished(&candidate.path) => report.vanished.push(candidate.path.clone()),
+                Err(e) => report.failures.push((candidate.path.clone(), e)),
+            }
+            continue;
+        }
+        let size = candidate.size();
+        match remove_explained(candidate, delete_options.clear_immutable) {
+            Ok(()) => {
+                report.deleted += 1;
+                report.bytes += size;
+                report.deleted_paths.push(candidate.path.clone());
+            }
+            // Removed by someone else since it was collected, which is not worth a failure
+            Err(_) if vanished(&candidate.path) => report.vanished.push(candidate.path.clone()),
+            Err(e) => report.failures.push((candidate.path.clone(), e)),
+        }
+    }
+}
+
+// Whether the veto of the caller keeps the file, which is then recorded in the report
+fn vetoed(
+    candidate: &Candidate,
+    delete_options: &DeleteOptions,
+    report: &mut DeleteReport,
+) -> bool {
+    let Some(veto) = &delete_options.veto else {
+        return false;
+    };
+    match veto(&candidate.path) {
+        Veto::Allow => false,
+        Veto::Skip(reason) => {
+            report.vetoed.push((candidate.path.clone(), reason));
+            true
+        }
+    }
+}
+
+fn vanished(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_err_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+// Deletes files directory by directory, in path order, recording each directory in the
+// checkpoint once all its files are gone (see --checkpoint)
+// Directories completed by an earlier run are only done again for the files still there
+fn delete_in_groups(
+    candidates: &[Candidate],
+    delete_options: &DeleteOptions,
+    checkpoint: &mut Checkpoint,
+    report: &mut DeleteReport,
+) -> Result<(), Box<dyn Error>> {
+    let mut groups: BTreeMap<&Path, Vec<Candidate>> = BTreeMap::new();
+    for candidate in candidates {
+        let dir = candidate.path.parent().unwrap_or(Path::new(""));
+        groups.entry(dir).or_default().push(candidate.clone());
+    }
+
+    for (dir, mut group) in groups {
+        if checkpoint.is_completed(dir) {
+            group.retain(|c| !vanished(&c.path));
+            if group.is_empty() {
+                continue;
+            }
+            warning::warn(format!(
+                "{} was completed by an earlier run, but still holds {} matching file(s)",
+                dir.display(),
+                group.len()
+            ));
+        }
+
+        let (failures, locked) = (report.failures.len(), report.locked.len());
+        remove_all(&group, delete_options, report);
+        if strict_stop(report) {
+            break;
+        }
+        if report.failures.len() == failures
+            && report.locked.len() == locked
+            && !checkpoint.is_completed(dir)
+        {
+            checkpoint.complete(dir)?;
+        }
+    }
+    Ok(())
+}
+
+// Answer to the prompt of a chunk, see --confirm-chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkAnswer {
+    Yes,
+    No,
+    // Accepts every remaining chunk
+    All,
+    // Keeps every remaining chunk
+    Quit,
+}
+
+// Asks again until the answer is understood. The end of the input stands for q, so that
+// nothing is deleted without an explicit answer
+// `verb` starts the prompt, like "Delete"
+fn ask_chunk(input: &mut impl BufRead, verb: &str) -> io::Result<ChunkAnswer> {
+    loop {
+        print!("{verb} this chunk? [Y/n/a/q] ");
+        io::stdout().flush()?;
+        let mut buf = String::new();
+        if input.read_line(&mut buf)? == 0 {
+            println!();
+            return Ok(ChunkAnswer::Quit);
+        }
+        end_prompt_line();
+        match buf.trim() {
+            "" | "y" | "Y" => return Ok(ChunkAnswer::Yes),
+            "n" | "N" => return Ok(ChunkAnswer::No),
+            "a" | "A" => return Ok(ChunkAnswer::All),
+            "q" | "Q" => return Ok(ChunkAnswer::Quit),
+            _ => println!("Please answer y (yes), n (no), a (all remaining) or q (quit)."),
+        }
+    }
+}
+
+// Declined chunks are kept while the deletion goes on with the next ones
+fn delete_in_chunks(
+    candidates: &[Candidate],
+    chunk_size: usize,
+    delete_options: &DeleteOptions,
+    what: &str,
+    report: &mut DeleteReport,
+) -> Result<(), Box<dyn Error>> {
+    let chunks: Vec<&[Candidate]> = candidates.chunks(chunk_size.max(1)).collect();
+    let (_, verb, _) = verbs(&delete_options.action);
+    let mut input = io::stdin().lock();
+    let mut accept_all = false;
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        if !accept_all {
+            let paths: Vec<PathBuf> = chunk.iter().map(|c| c.path.clone()).collect();
+            let bytes = chunk.iter().map(Candidate::size).sum();
+            println!(
+                "{}",
+                report::format_chunk(i + 1, chunks.len(), &paths, bytes, what)
+            );
+            match ask_chunk(&mut input, verb)? {
+                ChunkAnswer::Yes => (),
+                ChunkAnswer::All => accept_all = true,
+                ChunkAnswer::No => {
+                    report.declined_chunks += 1;
+                    report.declined += chunk.len();
+                    continue;
+                }
+                ChunkAnswer::Quit => {
+                    report.declined_chunks += chunks.len() - i;
+                    report.declined += chunks[i..].iter().map(|c| c.len()).sum::<usize>();
+                    break;
+                }
+            }
+        }
+        remove_all(chunk, delete_options, report);
+        if strict_stop(report) {
+            break;
+        }
+    }
+
+    report.cancelled = report.declined == report.matched;
+    status(delete_options, "Done!");
+    Ok(())
+}
+
+/// Deletes the given files, asking for confirmation unless `delete_options.force` is set.
+/// Files that cannot be deleted do not stop the deletion, they are recorded in the report
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// let file = dir.path().join("a.log");
+/// std::fs::File::create(&file)?;
+///
+/// let mut delete_options = rmx::DeleteOptions::default();
+/// delete_options.dry_run = true;
+/// let report = rmx::delete_files(&[file.clone()], &delete_options)?;
+///
+/// assert_eq!(1, report.matched);
+/// assert!(file.exists());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn delete_files(
+    files: &[PathBuf],
+    delete_options: &DeleteOptions,
+) -> Result<DeleteReport, Box<dyn Error>> {
+    let candidates: Vec<Candidate> = files
+        .iter()
+        .map(|f| Candidate::new(f.clone(), CandidateKind::File))
+        .collect();
+    delete_collected(&candidates, delete_options, None)
+}
+
+// Same as delete_files, for collected files, whose metadata read during the collection is not
+// read again. With a checkpoint, progress is recorded in it, and the directories it already
+// completed are skipped (see --checkpoint)
+#[doc(hidden)]
+pub fn delete_collected(
+    candidates: &[Candidate],
+    delete_options: &DeleteOptions,
+    checkpoint: Option<&mut Checkpoint>,
+) -> Result<DeleteReport, Box<dyn Error>> {
+    if candidates.is_empty() {
+        status(delete_options, "No matching file.");
+        return Ok(DeleteReport {
+            dry_run: delete_options.dry_run,
+            ..Default::default()
+        });
+    }
+
+    delete_candidates(candidates, delete_options, FILES, checkpoint)
+}
+
+/// Same as [delete_files] for bytecode as returned by [collect_pycache], `__pycache__`
+/// directories being deleted with their content
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::create_dir(dir.path().join("__pycache__"))?;
+/// std::fs::File::create(dir.path().join("__pycache__/mod.cpython-311.pyc"))?;
+///
+/// let candidates = rmx::collect_pycache(dir.path(), &rmx::CollectOptions::default())?;
+/// let mut delete_options = rmx::DeleteOptions::default();
+/// delete_options.force = true;
+/// delete_options.quiet = true;
+/// let report = rmx::delete_pycache(&candidates, &delete_options)?;
+///
+/// assert_eq!(1, report.deleted);
+/// assert!(!dir.path().join("__pycache__").exists());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn delete_pycache(
+    candidates: &[Candidate],
+    delete_options: &DeleteOptions,
+) -> Result<DeleteReport, Box<dyn Error>> {
+    if candidates.is_empty() {
+        status(delete_options, "No Python bytecode.");
+        return Ok(DeleteReport {
+            dry_run: delete_options.dry_run,
+            ..Default::default()
+        });
+    }
+
+    delete_candidates(
+        candidates,
+        delete_options,
+        Nouns {
+            files: "bytecode file(s)",
+            dirs: "__pycache__ directory(ies)",
+            progress: "bytecode",
+        },
+        None,
+    )
+}
+
+/// Same as [delete_files] for directories as returned by [collect_empty_dirs], children first
+///
+/// ```
+/// let dir = tempfile::tempdir()?;
+/// std::fs::create_dir(dir.path().join("empty"))?;
+///
+/// let dirs = rmx::collect_empty_dirs(dir.path(), &rmx::CollectOptions::default())?;
+/// let mut delete_options = rmx::DeleteOptions::default();
+/// delete_options.force = true;
+/// delete_options.quiet = true;
+/// let report = rmx::delete_empty_dirs(&dirs, &delete_options)?;
+///
+/// assert_eq!(1, report.deleted);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn delete_empty_dirs(
+    dirs: &[PathBuf],
+    delete_options: &DeleteOptions,
+) -> Result<DeleteReport, Box<dyn Error>> {
+    if dirs.is_empty() {
+        status(delete_options, "No empty directory.");
+        return Ok(DeleteReport {
+            dry_run: delete_options.dry_run,
+            ..Default::default()
+        });
+    }
+
+    let candidates: Vec<Candidate> = dirs
+        .iter()
+        .map(|d| Candidate::new(d.clone(), CandidateKind::EmptyDir))
+        .collect();
+    delete_candidates(
+        &candidates,
+        delete_options,
+        Nouns {
+            files: "empty directory(ies)",
+            dirs: "empty directory(ies)",
+            progress: "directories",
+        },
+        None,
+    )
+}
+
+// Lets the user uncheck candidates in a terminal screen before the deletion (see --select), None
+// standing for an aborted selection. The chosen ones keep their kind and metadata
+#[doc(hidden)]
+pub fn select_files(
+    candidates: &[Candidate],
+    long: bool,
+) -> Result<Option<Vec<Candidate>>, Box<dyn Error>> {
+    select_with(candidates, long, select::run)
+}
+
+// The screen is given so that tests can stand for the user
+fn select_with(
+    candidates: &[Candidate],
+    long: bool,
+    screen: impl FnOnce(&[String]) -> Result<select::Outcome, Box<dyn Error>>,
+) -> Result<Option<Vec<Candidate>>, Box<dyn Error>> {
+    if candidates.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+
+    let lines: Vec<String> = candidates
+        .iter()
+        .map(|c| {
+            if long {
+                listing::format_long(c, None, listing::Notes::default(), None)
+            } else {
+                c.path.to_string_lossy().into_owned()
+            }
+        })
+        .collect();
+    match screen(&lines)? {
+        select::Outcome::Confirmed(chosen) => Ok(Some(
+            chosen.into_iter().map(|i| candidates[i].clone()).collect(),
+        )),
+        select::Outcome::Aborted => Ok(None),
+    }
+}
+
+// Directories that lost at least one entry, leaving aside the ones deleted themselves
+fn affected_parents(deleted: &[PathBuf]) -> BTreeSet<PathBuf> {
+    let deleted_set: HashSet<&Path> = deleted.iter().map(PathBuf::as_path).collect();
+    deleted
+        .iter()
+        .filter_map(|p| p.parent())
+        .filter(|dir| !deleted_set.contains(dir))
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+// Sets the modification time of each directory where something was deleted to now (see
+// --touch-parents), returning the directories that could not be touched
+#[doc(hidden)]
+pub fn touch_parents(report: &DeleteReport) -> Vec<(PathBuf, String)> {
+    let now = SystemTime::now();
+    affected_parents(&report.deleted_paths)
+        .into_iter()
+        .filter_map(|dir| {
+            File::open(&dir)
+                .and_then(|f| f.set_modified(now))
+                .err()
+                .map(|e| (dir, e.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+    use tempfile::{TempDir, tempdir};
+
+    use crate::testing::FixtureTree;
+
+    // The tree of the tests of rmx (see FixtureTree::standard), with files of multi-dot
+    // extensions
+    fn create_temp_folder() -> TempDir {
+        FixtureTree::standard()
+            .file("file.tar.gz")
+            .file("other.md.gz")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn get_fileext_normal() -> Result<(), Box<dyn Error>> {
+        let filename = "file.txt".to_string();
+
+        let extension = get_fileext(&filename).unwrap();
+
+        assert_eq!("txt", extension);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_fileext_hidden_file() -> Result<(), Box<dyn Error>> {
+        let filename = ".file.tar.gz".to_string();
+
+        let extension = get_fileext(&filename).unwrap();
+
+        assert_eq!("tar.gz", extension);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_fileext_edge_cases() {
+        assert_eq!(None, get_fileext(""));
+        assert_eq!(None, get_fileext("."));
+        assert_eq!(None, get_fileext(".hidden"));
+        assert_eq!(Some(""), get_fileext("file."));
+        assert_eq!(Some("b"), get_fileext("..b"));
+        assert_eq!(Some("tär.gz"), get_fileext("fïle.tär.gz"));
+    }
+
+    #[test]
+    fn collect_without_match() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["json".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_with_extension_suffix_should_fail() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["g".to_string()]; // For root.log
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_one_extension() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(path_buf.join("root.txt"), files[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_reverse() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: false,
+            invert: true,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 4);
+        assert!(files.contains(&path_buf.join("root.log")));
+        assert!(files.contains(&path_buf.join("data.dat")));
+        assert!(files.contains(&path_buf.join("file.tar.gz")));
+        assert!(files.contains(&path_buf.join("other.md.gz")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_with_hidden_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&path_buf.join("root.txt")));
+        assert!(files.contains(&path_buf.join(".hidden.txt")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_reverse_with_hidden_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["dat".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: true,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 5);
+        assert!(files.contains(&path_buf.join("root.txt")));
+        assert!(files.contains(&path_buf.join("root.log")));
+        assert!(files.contains(&path_buf.join(".hidden.txt")));
+        assert!(files.contains(&path_buf.join("file.tar.gz")));
+        assert!(files.contains(&path_buf.join("other.md.gz")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_multiple_extension() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["dat".to_string(), "txt".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: true,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 3);
+        assert!(files.contains(&path_buf.join("root.log")));
+        assert!(files.contains(&path_buf.join("file.tar.gz")));
+        assert!(files.contains(&path_buf.join("other.md.gz")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_recursive() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: true,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 3);
+        assert!(files.contains(&path_buf.join("root.txt")));
+        assert!(files.contains(&path_buf.join("subfolder1").join("sub1.txt")));
+        assert!(
+            files.contains(
+                &path_buf
+                    .join("subfolder1")
+                    .join("subfolder2")
+                    .join("sub2.txt")
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_with_max_depth() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let sub2 = path_buf.join("subfolder1/subfolder2/sub2.txt");
+
+        let extensions = vec!["txt".to_string()];
+        let collect = |max_depth| {
+            let options = CollectOptions {
+                recurse: true,
+                max_depth: Some(max_depth),
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &path_buf, &options)
+        };
+
+        assert_eq!(Vec::<PathBuf>::new(), collect(0)?);
+        assert_eq!(vec![path_buf.join("root.txt")], collect(1)?);
+        let files = collect(2)?;
+        assert_eq!(2, files.len());
+        assert!(files.contains(&path_buf.join("subfolder1/sub1.txt")));
+        assert!(!files.contains(&sub2));
+        assert!(collect(3)?.contains(&sub2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_with_min_depth() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let sub1 = path_buf.join("subfolder1/sub1.txt");
+        let sub2 = path_buf.join("subfolder1/subfolder2/sub2.txt");
+
+        let extensions = vec!["txt".to_string()];
+        let collect = |min_depth, max_depth| {
+            let options = CollectOptions {
+                recurse: true,
+                min_depth: Some(min_depth),
+                max_depth,
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &path_buf, &options)
+        };
+
+        let files = collect(2, None)?;
+        assert_eq!(2, files.len());
+        assert!(!files.contains(&path_buf.join("root.txt")));
+        assert!(files.contains(&sub1));
+        assert!(files.contains(&sub2));
+
+        assert_eq!(vec![sub1], collect(2, Some(2))?);
+        assert_eq!(vec![sub2], collect(3, Some(3))?);
+        assert_eq!(3, collect(1, None)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_from_hidden_start_path() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let start = temp_dir.path().join(".hidden_folder");
+        fs::create_dir(start.join(".git"))?;
+        File::create(start.join(".git/config.txt"))?;
+        File::create(start.join(".secret.txt"))?;
+        fs::create_dir(start.join("foo"))?;
+        File::create(start.join("foo/bar.txt"))?;
+
+        let extensions = vec!["txt".to_string()];
+        let collect = |all| {
+            let options = CollectOptions {
+                all,
+                recurse: true,
+                sort: true,
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &start, &options)
+        };
+
+        // Never filtered itself, its hidden entries following --all
+        assert_eq!(
+            vec![start.join("foo/bar.txt"), start.join("hidden.txt")],
+            collect(false)?
+        );
+        assert_eq!(
+            vec![
+                start.join(".git/config.txt"),
+                start.join(".secret.txt"),
+                start.join("foo/bar.txt"),
+                start.join("hidden.txt"),
+            ],
+            collect(true)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_with_sink_streams_every_match() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: true,
+            invert: false,
+            ..Default::default()
+        };
+
+        let mut streamed: Vec<PathBuf> = Vec::new();
+        let mut sink = |p: &Path| {
+            streamed.push(p.to_path_buf());
+            Ok(())
+        };
+
+        let files =
+            collect_matching_files_with_sink(&extensions, &path_buf, &options, Some(&mut sink))?;
+
+        assert_eq!(files.len(), 5);
+        assert_eq!(files, streamed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_sorted() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let unsorted_options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: true,
+            invert: false,
+            ..Default::default()
+        };
+        let sorted_options = CollectOptions {
+            sort: true,
+            all: true,
+            recurse: true,
+            ..Default::default()
+        };
+
+        let mut unsorted = collect_matching_files(&extensions, &path_buf, &unsorted_options)?;
+        let sorted = collect_matching_files(&extensions, &path_buf, &sorted_options)?;
+
+        assert!(sorted.is_sorted());
+        unsorted.sort();
+        assert_eq!(unsorted, sorted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_should_not_traverse_symlinks() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+
+        let other_temp_dir = tempdir().unwrap();
+        let path_buf = other_temp_dir.path().to_path_buf();
+
+        let link = path_buf.join("link");
+
+        // Creating a symbolic link to the real directory
+        symlink(&temp_dir, &link)?;
+
+        // Verify that the symlink works by checking that we can see files through it
+        let to_temp_dir = fs::read_link(&link).unwrap();
+        let file_count = fs::read_dir(&to_temp_dir)?.count();
+        assert_eq!(file_count, 8);
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: true,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_symlinks() -> Result<(), Box<dyn Error>> {
+        let temp_dir = FixtureTree::new()
+            .file("outside/target.txt")
+            .file("outside/dir/inner.log")
+            .symlink("tree/build.log", "../outside/target.txt")
+            .symlink("tree/dangling.log", "../outside/missing.txt")
+            .symlink("tree/cache.d", "../outside/dir")
+            .build()?;
+        let path_buf = temp_dir.path().join("tree");
+        let extensions = vec!["log".to_string()];
+
+        let options = CollectOptions {
+            recurse: true,
+            ..Default::default()
+        };
+        assert!(collect_matching_files(&extensions, &path_buf, &options)?.is_empty());
+
+        let options = CollectOptions {
+            recurse: true,
+            symlinks: true,
+            ..Default::default()
+        };
+        let mut candidates = collect_candidates(&extensions, &path_buf, &options)?;
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // The directory behind cache.d is never walked
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|c| (c.path.clone(), c.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (path_buf.join("build.log"), CandidateKind::Symlink),
+                (path_buf.join("dangling.log"), CandidateKind::Symlink)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_follow_symlinks() -> Result<(), Box<dyn Error>> {
+        let temp_dir = FixtureTree::new()
+            .file("real/data.txt")
+            .symlink("tree/linked", "../real")
+            .file("tree/own.txt")
+            .build()?;
+        let path_buf = temp_dir.path().join("tree");
+        let extensions = vec!["txt".to_string()];
+
+        let options = CollectOptions {
+            recurse: true,
+            ..Default::default()
+        };
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        assert_eq!(files, vec![path_buf.join("own.txt")]);
+
+        let options = CollectOptions {
+            recurse: true,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&extensions, &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![path_buf.join("linked/data.txt"), path_buf.join("own.txt")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_follow_symlink_loops() -> Result<(), Box<dyn Error>> {
+        // a -> b -> a, along with a link to the start path itself
+        let temp_dir = FixtureTree::new()
+            .file("a/a.txt")
+            .file("b/b.txt")
+            .symlink("a/to_b", "../b")
+            .symlink("b/to_a", "../a")
+            .symlink("a/to_start", "..")
+            .build()?;
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let options = CollectOptions {
+            recurse: true,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let files = collect_matching_files(&["txt".to_string()], &path_buf, &options)?;
+
+        // Each file is found once, through the first path leading to it
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|f| f.ends_with("a.txt")));
+        assert!(files.iter().any(|f| f.ends_with("b.txt")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_multi_dot_extension_with_extension_start() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["tar".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_multi_dot_extension_with_complete_extension() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["tar.gz".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 1);
+        assert!(files.contains(&path_buf.join("file.tar.gz")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_multi_dot_extension_with_last_part() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["gz".to_string()];
+        let options = CollectOptions {
+            all: true,
+            list: false,
+            recurse: false,
+            invert: false,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&path_buf.join("file.tar.gz")));
+        assert!(files.contains(&path_buf.join("other.md.gz")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_one_file() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let options = DeleteOptions {
+            dry_run: false,
+            force: true,
+            ..Default::default()
+        };
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        let file = path_buf.join("root.txt");
+        files.push(file.clone());
+
+        assert!(file.exists());
+
+        let report = delete_files(&files, &options)?;
+
+        assert!(!file.exists());
+        assert_eq!(report.deleted, 1);
+        assert!(report.failures.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_multiple_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let options = DeleteOptions {
+            dry_run: false,
+            force: true,
+            ..Default::default()
+        };
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        let first_file = path_buf.join("root.txt");
+        let second_file = path_buf
+            .join("subfolder1")
+            .join("subfolder2")
+            .join("sub2.txt");
+        files.push(first_file.clone());
+        files.push(second_file.clone());
+
+        assert!(first_file.exists());
+        assert!(second_file.exists());
+
+        delete_files(&files, &options)?;
+
+        assert!(!first_file.exists());
+        assert!(!second_file.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_dry_run() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let options = DeleteOptions {
+            dry_run: true,
+            force: true,
+            ..Default::default()
+        };
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        let first_file = path_buf.join("root.txt");
+        let second_file = path_buf
+            .join("subfolder1")
+            .join("subfolder2")
+            .join("sub2.txt");
+        files.push(first_file.clone());
+        files.push(second_file.clone());
+
+        assert!(first_file.exists());
+        assert!(second_file.exists());
+
+        delete_files(&files, &options)?;
+
+        assert!(first_file.exists());
+        assert!(second_file.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_missing_file_is_reported() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let options = DeleteOptions {
+            dry_run: false,
+            force: true,
+            ..Default::default()
+        };
+
+        let existing = path_buf.join("root.txt");
+        let missing = path_buf.join("missing.txt");
+        let files = vec![missing.clone(), existing.clone()];
+
+        let report = delete_files(&files, &options)?;
+
+        assert!(!existing.exists());
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.deleted, 1);
+        // Not a failure, as there is nothing left to delete
+        assert!(report.failures.is_empty());
+        assert_eq!(report.vanished, vec![missing]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rename_instead_of_delete() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("a.log");
+        File::create(&file)?;
+        // Both names are taken, the disambiguator going before the suffix
+        File::create(temp_dir.path().join("a.log.trashed"))?;
+        File::create(temp_dir.path().join("a.log.1.trashed"))?;
+
+        let options = DeleteOptions {
+            force: true,
+            quiet: true,
+            action: Action::Rename(".trashed".to_string()),
+            ..Default::default()
+        };
+        let report = delete_files(std::slice::from_ref(&file), &options)?;
+
+        let target = temp_dir.path().join("a.log.2.trashed");
+        assert!(!file.exists());
+        assert!(target.exists());
+        assert_eq!(vec![(file, target)], report.renamed);
+        assert_eq!(0, report.deleted);
+
+        Ok(())
+    }
+
+    // Skips every other file it is asked about, starting with the second one
+    fn every_other_file() -> Option<VetoFn> {
+        let asked = std::cell::Cell::new(0);
+        Some(Box::new(move |_| {
+            asked.set(asked.get() + 1);
+            if asked.get() % 2 == 0 {
+                Veto::Skip("odd one out".to_string())
+            } else {
+                Veto::Allow
+            }
+        }))
+    }
+
+    #[test]
+    fn veto_keeps_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let files: Vec<PathBuf> = (0..4)
+            .map(|i| temp_dir.path().join(format!("{i}.log")))
+            .collect();
+        for file in &files {
+            fs::write(file, "data")?;
+        }
+
+        let options = DeleteOptions {
+            force: true,
+            quiet: true,
+            veto: every_other_file(),
+            ..Default::default()
+        };
+        let report = delete_files(&files, &options)?;
+
+        assert_eq!(4, report.matched);
+        assert_eq!(2, report.deleted);
+        assert_eq!(8, report.bytes);
+        assert_eq!(
+            vec![files[0].clone(), files[2].clone()],
+            report.deleted_paths
+        );
+        assert_eq!(
+            vec![
+                (files[1].clone(), "odd one out".to_string()),
+                (files[3].clone(), "odd one out".to_string()),
+            ],
+            report.vetoed
+        );
+        assert!(report.failures.is_empty());
+        assert!(!files[0].exists());
+        assert!(files[1].exists());
+        assert!(!files[2].exists());
+        assert!(files[3].exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn veto_during_dry_run() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let files: Vec<PathBuf> = (0..4)
+            .map(|i| temp_dir.path().join(format!("{i}.log")))
+            .collect();
+        for file in &files {
+            fs::write(file, "data")?;
+        }
+
+        let options = DeleteOptions {
+            dry_run: true,
+            quiet: true,
+            veto: every_other_file(),
+            ..Default::default()
+        };
+        let report = delete_files(&files, &options)?;
+
+        // Only the files the veto allowed count towards the size
+        assert_eq!(8, report.bytes);
+        assert_eq!(2, report.vetoed.len());
+        assert!(files.iter().all(|f| f.exists()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_skips_renamed_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        File::create(temp_dir.path().join("a.log"))?;
+        File::create(temp_dir.path().join("b.log.trashed"))?;
+
+        let options = CollectOptions {
+            invert: true,
+            renamed_suffix: Some(".trashed".to_string()),
+            ..Default::default()
+        };
+        let files = collect_matching_files(
+            &["txt".to_string()],
+            &temp_dir.path().to_path_buf(),
+            &options,
+        )?;
+
+        assert_eq!(vec![temp_dir.path().join("a.log")], files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_counts_skipped_hidden_matches() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let extensions = vec!["txt".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: true,
+            invert: false,
+            ..Default::default()
+        };
+
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        // .hidden.txt is counted, but not .hidden_folder/hidden.txt as the folder is never read
+        assert_eq!(files.len(), 3);
+        assert_eq!(stats.hidden_skipped, 1);
+
+        let options = CollectOptions {
+            all: true,
+            ..options
+        };
+        let (_, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        assert_eq!(stats.hidden_skipped, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_never_older_than() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let old_file = path_buf.join("root.log");
+        let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 86400);
+        File::options()
+            .write(true)
+            .open(&old_file)?
+            .set_modified(two_days_ago)?;
+
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let options = CollectOptions {
+            all: false,
+            list: false,
+            recurse: false,
+            invert: false,
+            never_older_than: Some(Duration::from_secs(86400)),
+            ..Default::default()
+        };
+
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files, vec![path_buf.join("root.txt")]);
+        assert_eq!(stats.age_protected, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_older_than() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+
+        let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 86400);
+        File::options()
+            .write(true)
+            .open(path_buf.join("root.log"))?
+            .set_modified(two_days_ago)?;
+
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let options = CollectOptions {
+            older_than: Some(Duration::from_secs(86400)),
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files, vec![path_buf.join("root.log")]);
+
+        Ok(())
+    }
+
+    // Files exactly at the threshold are collected
+    #[test]
+    fn collect_min_size() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let path_buf = temp_dir.path().to_path_buf();
+        for (name, len) in [("small.log", 1023), ("exact.log", 1024), ("big.log", 4096)] {
+            File::create(path_buf.join(name))?.set_len(len)?;
+        }
+
+        let extensions = vec!["log".to_string()];
+        let options = CollectOptions {
+            min_size: Some(1024),
+            sort: true,
+            ..Default::default()
+        };
+
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(
+            files,
+            vec![path_buf.join("big.log"), path_buf.join("exact.log")]
+        );
+
+        Ok(())
+    }
 
-use std::fs::{self, DirEntry, FileType};
-use std::io::{self, Write};
-use std::path::PathBuf;
+    // Both bounds are included
+    #[test]
+    fn collect_size_range() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let path_buf = temp_dir.path().to_path_buf();
+        for (name, len) in [
+            ("below.log", 99),
+            ("min.log", 100),
+            ("max.log", 200),
+            ("above.log", 201),
+        ] {
+            File::create(path_buf.join(name))?.set_len(len)?;
+        }
 
-pub use crate::parsing::arguments;
-pub use crate::parsing::arguments::{CollectOptions, DeleteOptions};
-mod parsing;
+        let extensions = vec!["log".to_string()];
+        let options = CollectOptions {
+            min_size: Some(100),
+            max_size: Some(200),
+            sort: true,
+            ..Default::default()
+        };
 
-// Returns the largest extension (i.e. using file.tar.gz will return tar.gz)
-fn get_fileext(filename: &String) -> Option<&str> {
-    let parts = filename.split_once(".")?;
-    if parts.0.is_empty() {
-        // In case file is hidden, like ".file.lock", split again to get ".lock"
-        return Some(parts.1.split_once(".")?.1);
+        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+
+        assert_eq!(
+            files,
+            vec![path_buf.join("max.log"), path_buf.join("min.log")]
+        );
+
+        Ok(())
     }
-    Some(parts.1)
-}
 
-fn get_filetype(entry: &DirEntry) -> Result<FileType, Box<dyn Error>> {
-    entry
-        .file_type()
-        .map_err(|e| format!("Couldn't extract filetype from {:?}: {}", entry.path(), e).into())
-}
+    // The first files in traversal order, the walk telling whether more matched
+    #[test]
+    fn collect_limit() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("a.log")
+            .file("b.log")
+            .file("sub/c.log")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        let extensions = vec!["log".to_string()];
+
+        for (limit, count, reached) in [(1, 1, true), (2, 2, true), (3, 3, false), (4, 3, false)] {
+            let options = CollectOptions {
+                recurse: true,
+                traversal: Traversal::Bfs,
+                limit: Some(limit),
+                ..Default::default()
+            };
+            let (files, stats) =
+                collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+            assert_eq!(count, files.len(), "limit {limit}");
+            assert_eq!(reached, stats.limit_reached, "limit {limit}");
+            // Files of the start path come first breadth first
+            if limit == 2 {
+                assert!(files.iter().all(|f| f.parent() == Some(tree.path())));
+            }
+        }
 
-fn get_filename(entry: &DirEntry) -> Result<String, Box<dyn Error>> {
-    match entry.file_name().to_str() {
-        Some(s) => Ok(String::from(s)),
-        None => Err(format!("Couldn't extract filename from {:?}", entry).into()),
+        Ok(())
     }
-}
 
-fn collect_matching_files_rec(
-    options: &CollectOptions,
-    keep: &dyn Fn(&str) -> bool,
-    path: &PathBuf,
-) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    // Storing PathBuf instead of DirEntry avoids keeping the directories' file descriptors opened
-    // thus avoiding a "Too many open files" error
-    let mut acc: Vec<PathBuf> = Vec::new();
-    let mut directories: Vec<PathBuf> = Vec::new();
+    #[test]
+    fn collect_dirs() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("foo.egg-info/PKG-INFO")
+            .file("foo.egg-info/nested.egg-info/a.txt")
+            .file("build/App.dSYM/Contents/Info.plist")
+            .file("build/lib.egg-info")
+            .file("setup.py")
+            .file("egg-info/b.txt")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        let extensions = vec!["egg-info".to_string(), "dSYM".to_string()];
+        let options = CollectOptions {
+            recurse: true,
+            dirs: true,
+            sort: true,
+            ..Default::default()
+        };
 
-    for entry in fs::read_dir(path)? {
-        let entry = entry.map_err(|e| format!("Error while retrieving file data: {:?}", e))?;
+        let candidates = collect_candidates(&extensions, &path_buf, &options)?;
+
+        // Matched directories are not walked, and a name has to have an extension
+        let found: Vec<(PathBuf, CandidateKind)> =
+            candidates.into_iter().map(|c| (c.path, c.kind)).collect();
+        assert_eq!(
+            vec![
+                (path_buf.join("build/App.dSYM"), CandidateKind::Dir),
+                (path_buf.join("build/lib.egg-info"), CandidateKind::File),
+                (path_buf.join("foo.egg-info"), CandidateKind::Dir),
+            ],
+            found
+        );
 
-        let filepath = entry.path();
-        let filename = get_filename(&entry)?;
-        let filetype = get_filetype(&entry)?;
+        // Without it, directories are walked as before
+        let options = CollectOptions {
+            dirs: false,
+            ..options
+        };
+        assert_eq!(
+            vec![path_buf.join("build/lib.egg-info")],
+            collect_matching_files(&extensions, &path_buf, &options)?
+        );
 
-        if !options.all && filename.starts_with('.') {
-            continue;
-        }
+        Ok(())
+    }
 
-        if options.recurse && filetype.is_dir() {
-            directories.push(filepath);
-            continue;
-        }
+    // Files of the temporary folder belong to the current user, standing for a service account
+    #[test]
+    fn collect_dirs_protected_inside() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("foo.egg-info/PKG-INFO")
+            .file("foo.egg-info/sub/SOURCES.txt")
+            .file("bar.egg-info/PKG-INFO")
+            .file("keep.txt")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        let extensions = vec!["egg-info".to_string()];
+        fs::write(
+            path_buf.join("keep.txt"),
+            path_buf
+                .join("foo.egg-info/sub/SOURCES.txt")
+                .to_string_lossy()
+                .as_ref(),
+        )?;
+        let options = || CollectOptions {
+            recurse: true,
+            dirs: true,
+            sort: true,
+            ..Default::default()
+        };
 
-        if !filetype.is_file() || !get_fileext(&filename).is_some_and(|f| keep(&f)) {
-            continue;
+        // Its contents added up, for the policy
+        let candidates = collect_candidates(&extensions, &path_buf, &options())?;
+        assert_eq!(2, candidates.len());
+        assert_eq!(2, candidates[1].file_count());
+
+        // A kept file keeps the whole directory
+        let keep_options = CollectOptions {
+            keep_from: Some(path_buf.join("keep.txt")),
+            ..options()
+        };
+        let (candidates, stats) =
+            collect_candidates_with_stats(&extensions, &path_buf, &keep_options)?;
+        let found: Vec<PathBuf> = candidates.into_iter().map(|c| c.path).collect();
+        assert_eq!(vec![path_buf.join("bar.egg-info")], found);
+        assert_eq!(1, stats.keep_listed);
+        assert_eq!(1, stats.dirs_protected);
+
+        // So does a file of a protected owner
+        let owner_options = CollectOptions {
+            protected_owners: vec![unsafe { libc::getuid() }],
+            ..options()
         };
+        let (candidates, stats) =
+            collect_candidates_with_stats(&extensions, &path_buf, &owner_options)?;
+        assert!(candidates.is_empty());
+        assert_eq!(3, stats.owner_protected);
+        assert_eq!(2, stats.dirs_protected);
 
-        if options.list {
-            println!("{}", filepath.to_string_lossy());
-        }
+        Ok(())
+    }
+
+    #[test]
+    fn collect_dirs_sizes() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("foo.egg-info/PKG-INFO")
+            .file("foo.egg-info/sub/SOURCES.txt")
+            .file("bar.egg-info/PKG-INFO")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        fs::write(path_buf.join("foo.egg-info/PKG-INFO"), [0; 1024])?;
+        fs::write(path_buf.join("foo.egg-info/sub/SOURCES.txt"), [0; 1024])?;
+        let extensions = vec!["egg-info".to_string()];
+        let options = CollectOptions {
+            recurse: true,
+            dirs: true,
+            min_size: Some(1),
+            ..Default::default()
+        };
 
-        acc.push(filepath);
+        // The total of what it holds, the empty one being left out
+        let candidates = collect_candidates(&extensions, &path_buf, &options)?;
+        assert_eq!(1, candidates.len());
+        assert_eq!(path_buf.join("foo.egg-info"), candidates[0].path);
+        assert_eq!(2048, candidates[0].size());
+
+        Ok(())
     }
 
-    for p in directories.iter() {
-        acc.extend(collect_matching_files_rec(options, keep, p)?);
+    #[test]
+    fn collect_pycache_protected_owners() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("pkg/__pycache__/mod.cpython-311.pyc")
+            .file("pkg/legacy.pyc")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        let options = CollectOptions {
+            recurse: true,
+            protected_owners: vec![unsafe { libc::getuid() }],
+            ..Default::default()
+        };
+
+        let (candidates, stats) = collect_pycache_in_roots(slice::from_ref(&path_buf), &options)?;
+
+        assert!(candidates.is_empty());
+        assert_eq!(2, stats.owner_protected);
+        assert_eq!(1, stats.dirs_protected);
+
+        Ok(())
     }
 
-    Ok(acc)
-}
+    #[test]
+    fn collect_dirs_expanded() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("foo.egg-info/PKG-INFO")
+            .file("foo.egg-info/top_level.egg-info")
+            .file("foo.egg-info/sub/deps.egg-info")
+            .file("setup.py")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        let extensions = vec!["egg-info".to_string()];
+        let options = CollectOptions {
+            recurse: true,
+            dirs: true,
+            sort: true,
+            ..Default::default()
+        };
 
-// Assume extensions are valid
-pub fn collect_matching_files(
-    extensions: &Vec<String>,
-    path: &PathBuf,
-    options: &CollectOptions,
-) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    let dotted: Vec<String> = extensions.iter().map(|s| format!(".{s}")).collect();
-    let keep = |file_ext: &str| {
-        let dotted_fil_ext = format!(".{file_ext}");
-        options.invert != dotted.iter().any(|e| dotted_fil_ext.ends_with(e))
-    };
+        // The matched directory once, whatever matches inside it
+        let (candidates, stats) = collect_candidates_with_stats(&extensions, &path_buf, &options)?;
+        let found: Vec<(PathBuf, CandidateKind)> =
+            candidates.into_iter().map(|c| (c.path, c.kind)).collect();
+        assert_eq!(
+            vec![(path_buf.join("foo.egg-info"), CandidateKind::Dir)],
+            found
+        );
+        assert_eq!(vec![1], stats.matched_by_extension);
 
-    collect_matching_files_rec(options, &keep, path)
-}
+        // Expanded, the matching files it holds instead, and nothing else
+        let options = CollectOptions {
+            expand_matched_dirs: true,
+            ..options
+        };
+        let (candidates, stats) = collect_candidates_with_stats(&extensions, &path_buf, &options)?;
+        let found: Vec<(PathBuf, CandidateKind)> =
+            candidates.into_iter().map(|c| (c.path, c.kind)).collect();
+        assert_eq!(
+            vec![
+                (
+                    path_buf.join("foo.egg-info/sub/deps.egg-info"),
+                    CandidateKind::File
+                ),
+                (
+                    path_buf.join("foo.egg-info/top_level.egg-info"),
+                    CandidateKind::File
+                ),
+            ],
+            found
+        );
+        assert_eq!(vec![2], stats.matched_by_extension);
 
-fn prompt_for_confirmation(files: &[PathBuf]) -> Result<bool, Box<dyn Error>> {
-    print!(
-        "Do you really want to delete {} file(s)? [Y/n] ",
-        files.len()
-    );
-    io::stdout().flush()?;
-    let mut buf = String::new();
-    io::stdin().read_line(&mut buf)?;
+        Ok(())
+    }
 
-    Ok(matches!(buf.trim(), "" | "y" | "Y"))
-}
+    #[test]
+    fn selection_keeps_candidates() -> Result<(), Box<dyn Error>> {
+        let candidates = vec![
+            Candidate::new(PathBuf::from("/a.log"), CandidateKind::File),
+            Candidate::new(PathBuf::from("/b.egg-info"), CandidateKind::Dir),
+            Candidate::new(PathBuf::from("/c.log"), CandidateKind::Symlink),
+        ];
+
+        let chosen = select_with(&candidates, false, |lines| {
+            assert_eq!(vec!["/a.log", "/b.egg-info", "/c.log"], lines);
+            Ok(select::Outcome::Confirmed(vec![1, 2]))
+        })?;
+        assert_eq!(Some(candidates[1..].to_vec()), chosen);
+
+        let aborted = select_with(&candidates, false, |_| Ok(select::Outcome::Aborted))?;
+        assert_eq!(None, aborted);
 
-pub fn delete_files(
-    files: &[PathBuf],
-    delete_options: &DeleteOptions,
-) -> Result<(), Box<dyn Error>> {
-    if files.is_empty() {
-        println!("No matching file.");
-        return Ok(());
+        Ok(())
     }
 
-    if delete_options.dry_run {
-        return Ok(());
+    #[test]
+    fn nouns_count_directories_apart() {
+        let what = FILES;
+        let file = Candidate::new(PathBuf::from("/a.log"), CandidateKind::File);
+        let dir = Candidate::new(PathBuf::from("/b.egg-info"), CandidateKind::Dir);
+
+        assert_eq!("1 file(s)", what.count(slice::from_ref(&file)));
+        assert_eq!("1 directory(ies)", what.count(slice::from_ref(&dir)));
+        assert_eq!(
+            "2 file(s) and 1 directory(ies)",
+            what.count(&[file.clone(), dir, file])
+        );
     }
 
-    if !delete_options.force && !prompt_for_confirmation(&files)? {
-        println!("Cancelled file deletion.");
-        return Ok(());
+    // Matches found by reading each directory whole before its subdirectories, like the walk
+    // did before reading them as it goes
+    fn read_whole_dirs(path: &Path, extension: &str, acc: &mut Vec<PathBuf>) -> io::Result<()> {
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                subdirs.push(path);
+            } else if path.extension().is_some_and(|e| e == extension) {
+                acc.push(path);
+            }
+        }
+        for subdir in subdirs {
+            read_whole_dirs(&subdir, extension, acc)?;
+        }
+        Ok(())
     }
 
-    println!("Deleting files...");
-    for file in files {
-        fs::remove_file(file)?;
+    // Files found in both orders, sorted, along with those found by reading whole directories
+    fn matches_in_both_orders(path: &Path) -> Result<Vec<Vec<PathBuf>>, Box<dyn Error>> {
+        let mut expected = Vec::new();
+        read_whole_dirs(path, "log", &mut expected)?;
+        expected.sort();
+
+        let mut found = vec![expected];
+        for traversal in [Traversal::Dfs, Traversal::Bfs] {
+            let options = CollectOptions {
+                recurse: true,
+                all: true,
+                traversal,
+                sort: true,
+                ..Default::default()
+            };
+            found.push(collect_matching_files(
+                &["log".to_string()],
+                &path.to_path_buf(),
+                &options,
+            )?);
+        }
+        Ok(found)
     }
-    println!("Done!");
 
-    Ok(())
-}
+    #[test]
+    fn fixture_tree_matches_in_both_orders() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::standard()
+            .hidden_file(".hidden.log")
+            .file(".hidden_folder/hidden.log")
+            .build()?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let found = matches_in_both_orders(tree.path())?;
 
-    use std::fs::File;
-    use std::os::unix::fs::symlink;
-    use tempfile::{TempDir, tempdir};
+        assert_eq!(4, found[0].len());
+        assert!(found.iter().all(|files| *files == found[0]));
 
-    // Creates a directory as follow:
-    //
-    // <temp_dir>/
-    // ├── .hidden_folder/
-    // │   └── hidden.txt
-    // ├── subfolder1/
-    // │   ├── subfolder2/
-    // │   │   ├── backup.bak
-    // │   │   ├── data.dat
-    // │   │   └── sub2.txt
-    // │   ├── sub1.log
-    // │   └── sub1.txt
-    // ├── .hidden.txt
-    // ├── data.dat
-    // ├── file.tar.gz
-    // ├── other.md.gz
-    // ├── root.log
-    // └── root.txt
-    //
-    fn create_temp_folder() -> TempDir {
-        let temp_dir = tempdir().unwrap();
+        Ok(())
+    }
 
-        // Create files in the root directory
-        File::create(temp_dir.path().join("root.txt")).unwrap();
-        File::create(temp_dir.path().join("root.log")).unwrap();
-        File::create(temp_dir.path().join("data.dat")).unwrap();
-        File::create(temp_dir.path().join(".hidden.txt")).unwrap();
-        File::create(temp_dir.path().join("file.tar.gz")).unwrap();
-        File::create(temp_dir.path().join("other.md.gz")).unwrap();
+    // More subdirectories than gathered at once, walked before the rest of the entries are read
+    #[test]
+    fn wide_tree_matches_in_both_orders() -> Result<(), Box<dyn Error>> {
+        let mut tree = FixtureTree::new();
+        for i in 0..SUBDIR_BATCH * 2 + 1 {
+            tree = tree.file(format!("{i}/a.log")).file(format!("{i}/a.txt"));
+        }
+        let tree = tree.file("root.log").build()?;
 
-        // Create subfolder1
-        let subfolder1 = temp_dir.path().join("subfolder1");
-        fs::create_dir(&subfolder1).unwrap();
+        let found = matches_in_both_orders(tree.path())?;
 
-        // Create files in subfolder1
-        File::create(subfolder1.join("sub1.txt")).unwrap();
-        File::create(subfolder1.join("sub1.log")).unwrap();
+        assert_eq!(SUBDIR_BATCH * 2 + 2, found[0].len());
+        assert!(found.iter().all(|files| *files == found[0]));
 
-        // Create subfolder2 inside subfolder1
-        let subfolder2 = subfolder1.join("subfolder2");
-        fs::create_dir(&subfolder2).unwrap();
+        Ok(())
+    }
 
-        // Create files in subfolder2
-        File::create(subfolder2.join("sub2.txt")).unwrap();
-        File::create(subfolder2.join("backup.bak")).unwrap();
-        File::create(subfolder2.join("data.dat")).unwrap();
+    // Deeper than the directories kept open, past which they are read whole
+    #[test]
+    fn deep_tree_matches_in_both_orders() -> Result<(), Box<dyn Error>> {
+        let mut tree = FixtureTree::new();
+        let mut dir = PathBuf::new();
+        for _ in 0..MAX_OPEN_DIRS + 8 {
+            tree = tree.file(dir.join("a.log")).file(dir.join("b/b.log"));
+            dir.push("d");
+        }
+        let tree = tree.build()?;
 
-        // Create a hidden subfolder
-        let hidden_folder = temp_dir.path().join(".hidden_folder");
-        fs::create_dir(&hidden_folder).unwrap();
+        let found = matches_in_both_orders(tree.path())?;
 
-        // Create a file in the hidden folder
-        File::create(hidden_folder.join("hidden.txt")).unwrap();
+        assert_eq!((MAX_OPEN_DIRS + 8) * 2, found[0].len());
+        assert!(found.iter().all(|files| *files == found[0]));
 
-        temp_dir
+        Ok(())
     }
 
+    // Sizes read by the size filters are the ones counted later, without reading them again
     #[test]
-    fn get_fileext_normal() -> Result<(), Box<dyn Error>> {
-        let filename = "file.txt".to_string();
+    fn collect_keeps_sizes_read_by_filters() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let path_buf = temp_dir.path().to_path_buf();
+        File::create(path_buf.join("a.log"))?.set_len(2048)?;
 
-        let extension = get_fileext(&filename).unwrap();
+        let extensions = vec!["log".to_string()];
+        let options = CollectOptions {
+            min_size: Some(1024),
+            ..Default::default()
+        };
 
-        assert_eq!("txt", extension);
+        let candidates = collect_candidates(&extensions, &path_buf, &options)?;
+        File::create(path_buf.join("a.log"))?.set_len(4096)?;
+
+        assert_eq!(2048, candidates[0].size());
 
         Ok(())
     }
 
     #[test]
-    fn get_fileext_hidden_file() -> Result<(), Box<dyn Error>> {
-        let filename = ".file.tar.gz".to_string();
+    fn collect_ignore_case() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let path_buf = temp_dir.path().to_path_buf();
+        for name in ["photo.JPG", "photo.jpg", "photo.Jpg", "photo.jpeg"] {
+            File::create(path_buf.join(name))?;
+        }
+        let extensions = vec!["jpg".to_string()];
 
-        let extension = get_fileext(&filename).unwrap();
+        let files = collect_matching_files(&extensions, &path_buf, &CollectOptions::default())?;
+        assert_eq!(files, vec![path_buf.join("photo.jpg")]);
 
-        assert_eq!("tar.gz", extension);
+        let options = CollectOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&extensions, &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                path_buf.join("photo.JPG"),
+                path_buf.join("photo.Jpg"),
+                path_buf.join("photo.jpg")
+            ]
+        );
 
         Ok(())
     }
 
     #[test]
-    fn collect_without_match() -> Result<(), Box<dyn Error>> {
+    fn collect_names() -> Result<(), Box<dyn Error>> {
+        let temp_dir = FixtureTree::new()
+            .hidden_file(".DS_Store")
+            .hidden_file("sub/.DS_Store")
+            .hidden_file(".hidden/.DS_Store")
+            .file("Thumbs.db")
+            .file("sub/main.o")
+            .file("sub/readme.txt")
+            .dir("named/.DS_Store")
+            .build()?;
+        let path_buf = temp_dir.path().to_path_buf();
+
+        // Hidden names match without --all, though hidden directories are still skipped
+        let options = CollectOptions {
+            recurse: true,
+            names: vec![".DS_Store".to_string()],
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&[], &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![path_buf.join(".DS_Store"), path_buf.join("sub/.DS_Store")]
+        );
+
+        // Along with extensions, either one selecting the file
+        let options = CollectOptions {
+            recurse: true,
+            names: vec!["Thumbs.db".to_string()],
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&["o".to_string()], &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![path_buf.join("Thumbs.db"), path_buf.join("sub/main.o")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_case_insensitive_fs() -> Result<(), Box<dyn Error>> {
+        let temp_dir = FixtureTree::new()
+            .file("Important.log")
+            .file("other.LOG")
+            .file("debug.log")
+            .file("Thumbs.DB")
+            .build()?;
+        let path_buf = temp_dir.path().to_path_buf();
+
+        // Extensions follow ignore_case, and names case_insensitive_fs
+        let collect = |ignore_case, case_insensitive_fs| {
+            let options = CollectOptions {
+                ignore_case,
+                case_insensitive_fs,
+                exclude: vec!["important.log".to_string()],
+                names: vec!["thumbs.db".to_string()],
+                ..Default::default()
+            };
+            let mut files = collect_matching_files(&["log".to_string()], &path_buf, &options)?;
+            files.sort();
+            let names: Vec<String> = files
+                .iter()
+                .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            Ok::<_, Box<dyn Error>>(names)
+        };
+
+        assert_eq!(vec!["Important.log", "debug.log"], collect(false, false)?);
+        assert_eq!(
+            vec!["Important.log", "debug.log", "other.LOG"],
+            collect(true, false)?
+        );
+        assert_eq!(vec!["Thumbs.DB", "debug.log"], collect(false, true)?);
+        assert_eq!(
+            vec!["Thumbs.DB", "debug.log", "other.LOG"],
+            collect(true, true)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_extensionless_files() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let path_buf = temp_dir.path().to_path_buf();
+        for name in ["Makefile", "core", ".bashrc", "file.tar.gz", "main.o"] {
+            File::create(path_buf.join(name))?;
+        }
+
+        let options = CollectOptions {
+            no_ext: true,
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&[], &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![path_buf.join("Makefile"), path_buf.join("core")]
+        );
+
+        // Dotfiles are hidden files, and have no extension either
+        let options = CollectOptions {
+            no_ext: true,
+            all: true,
+            ..Default::default()
+        };
+        let mut files = collect_matching_files(&["o".to_string()], &path_buf, &options)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                path_buf.join(".bashrc"),
+                path_buf.join("Makefile"),
+                path_buf.join("core"),
+                path_buf.join("main.o")
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_empty_files() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
+        fs::write(path_buf.join("root.txt"), "content")?;
 
-        let extensions = vec!["json".to_string()];
+        let extensions = vec!["txt".to_string(), "log".to_string()];
         let options = CollectOptions {
-            all: false,
-            list: false,
-            recurse: false,
-            invert: false,
+            max_size: Some(0),
+            ..Default::default()
         };
 
         let files = collect_matching_files(&extensions, &path_buf, &options)?;
 
-        assert_eq!(files.len(), 0);
+        assert_eq!(files, vec![path_buf.join("root.log")]);
 
         Ok(())
     }
 
+    // Files of the temporary folder belong to the current user, standing for a service account
     #[test]
-    fn collect_with_extension_suffix_should_fail() -> Result<(), Box<dyn Error>> {
+    fn collect_protected_owners() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
+        let uid = unsafe { libc::getuid() };
 
-        let extensions = vec!["g".to_string()]; // For root.log
+        let extensions = vec!["txt".to_string(), "log".to_string()];
         let options = CollectOptions {
-            all: false,
-            list: false,
-            recurse: false,
-            invert: false,
+            protected_owners: vec![uid],
+            ..Default::default()
+        };
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        assert!(files.is_empty());
+        assert_eq!(stats.owner_protected, 2);
+
+        let options = CollectOptions {
+            protected_owners: vec![uid.wrapping_add(1)],
+            ..Default::default()
+        };
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(stats.owner_protected, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_accessed_before() -> Result<(), Box<dyn Error>> {
+        use std::fs::FileTimes;
+        use std::time::Duration;
+
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86400);
+        let set_accessed = |name: &str, accessed| {
+            File::options()
+                .write(true)
+                .open(path_buf.join(name))?
+                .set_times(FileTimes::new().set_accessed(accessed))
         };
+        set_accessed("root.txt", now - 10 * day)?;
+        set_accessed("root.log", now - Duration::from_secs(3600))?;
+        // Clock skew, as from another machine writing to a share
+        set_accessed("data.dat", now + day)?;
 
+        let extensions = vec!["txt".to_string(), "log".to_string(), "dat".to_string()];
+        let options = CollectOptions {
+            accessed_before: Some(day),
+            ..Default::default()
+        };
         let files = collect_matching_files(&extensions, &path_buf, &options)?;
 
-        assert_eq!(files.len(), 0);
+        assert_eq!(vec![path_buf.join("root.txt")], files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_against_reference_files() -> Result<(), Box<dyn Error>> {
+        use std::fs::FileTimes;
+        use std::time::Duration;
+
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path();
+        let hour = Duration::from_secs(3600);
+        let stamp = SystemTime::now() - hour;
+        // A batch of artifacts before the stamp, another after it, and one as old as it
+        for (name, modified) in [
+            ("old.o", stamp - hour),
+            ("new.o", stamp + hour / 2),
+            ("same.o", stamp),
+        ] {
+            File::create(path.join(name))?.set_times(FileTimes::new().set_modified(modified))?;
+        }
+
+        let extensions = vec!["o".to_string()];
+        let collect = |before, after| {
+            let options = CollectOptions {
+                modified_before: before,
+                modified_after: after,
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &path.to_path_buf(), &options)
+        };
+
+        assert_eq!(vec![path.join("old.o")], collect(Some(stamp), None)?);
+        assert_eq!(vec![path.join("new.o")], collect(None, Some(stamp))?);
+        assert!(collect(Some(stamp), Some(stamp))?.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_skip_readonly() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        fs::set_permissions(path_buf.join("root.txt"), fs::Permissions::from_mode(0o444))?;
+        // Writable by its owner only, which is enough
+        fs::set_permissions(path_buf.join("root.log"), fs::Permissions::from_mode(0o600))?;
+
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let options = CollectOptions {
+            skip_readonly: true,
+            ..Default::default()
+        };
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+        assert_eq!(vec![path_buf.join("root.log")], files);
+        assert_eq!(1, stats.readonly_skipped);
+
+        let files = collect_matching_files(&extensions, &path_buf, &CollectOptions::default())?;
+        assert_eq!(2, files.len());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_by_owner() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let collect = |user, group| {
+            let options = CollectOptions {
+                user,
+                group,
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &path_buf, &options).map(|files| files.len())
+        };
+
+        assert_eq!(2, collect(Some(uid), None)?);
+        assert_eq!(2, collect(Some(uid), Some(gid))?);
+        // Files of others are skipped without a note
+        assert_eq!(0, collect(Some(uid.wrapping_add(1)), None)?);
+        assert_eq!(0, collect(None, Some(gid.wrapping_add(1)))?);
 
         Ok(())
     }
 
     #[test]
-    fn collect_one_extension() -> Result<(), Box<dyn Error>> {
+    fn collect_following_cyclic_dir_lists() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
+        let other_dir = tempdir()?;
+        let other = other_dir.path().to_path_buf();
+        File::create(other.join("other.txt"))?;
+
+        // Each directory lists the other, and the root also lists one of its own subfolders
+        fs::write(
+            path_buf.join("rmx.dirs"),
+            format!("{}\nsubfolder1\n", other.display()),
+        )?;
+        fs::write(other.join("rmx.dirs"), format!("{}\n", path_buf.display()))?;
 
         let extensions = vec!["txt".to_string()];
         let options = CollectOptions {
-            all: false,
-            list: false,
-            recurse: false,
-            invert: false,
+            recurse: true,
+            follow_dir_lists: Some("rmx.dirs".to_string()),
+            ..Default::default()
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
-
-        assert_eq!(files.len(), 1);
-        assert_eq!(path_buf.join("root.txt"), files[0]);
+        let mut files = collect_matching_files(&extensions, &path_buf, &options)?;
+        files.sort();
+
+        let mut expected = vec![
+            path_buf.join("root.txt"),
+            path_buf.join("subfolder1").join("sub1.txt"),
+            path_buf
+                .join("subfolder1")
+                .join("subfolder2")
+                .join("sub2.txt"),
+            other.join("other.txt"),
+        ];
+        expected.sort();
+        assert_eq!(expected, files);
 
         Ok(())
     }
 
+    // <temp_dir>/
+    // ├── .hidden_empty/
+    // ├── chain/
+    // │   └── a/
+    // │       └── b/
+    // └── mixed/
+    //     ├── empty/
+    //     ├── not_empty/
+    //     │   └── .keep
+    //     └── file.txt
+    fn create_empty_dirs_folder() -> TempDir {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+
+        fs::create_dir(path.join(".hidden_empty")).unwrap();
+        fs::create_dir_all(path.join("chain").join("a").join("b")).unwrap();
+        fs::create_dir_all(path.join("mixed").join("empty")).unwrap();
+        fs::create_dir_all(path.join("mixed").join("not_empty")).unwrap();
+        File::create(path.join("mixed").join("not_empty").join(".keep")).unwrap();
+        File::create(path.join("mixed").join("file.txt")).unwrap();
+
+        temp_dir
+    }
+
     #[test]
-    fn collect_reverse() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
+    fn collect_empty_dirs_bottom_up() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_empty_dirs_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
-        let extensions = vec!["txt".to_string()];
         let options = CollectOptions {
-            all: false,
-            list: false,
-            recurse: false,
-            invert: true,
+            recurse: true,
+            empty_dirs: true,
+            ..Default::default()
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        let dirs = collect_empty_dirs(&path_buf, &options)?;
+
+        let chain = path_buf.join("chain");
+        let mut sorted = dirs.clone();
+        sorted.sort();
+        assert_eq!(
+            vec![
+                chain.clone(),
+                chain.join("a"),
+                chain.join("a").join("b"),
+                path_buf.join("mixed").join("empty"),
+            ],
+            sorted
+        );
 
-        assert_eq!(files.len(), 4);
-        assert!(files.contains(&path_buf.join("root.log")));
-        assert!(files.contains(&path_buf.join("data.dat")));
-        assert!(files.contains(&path_buf.join("file.tar.gz")));
-        assert!(files.contains(&path_buf.join("other.md.gz")));
+        // Children always come before their parent
+        let position = |d: &Path| dirs.iter().position(|p| p == d).unwrap();
+        assert!(position(&chain.join("a").join("b")) < position(&chain.join("a")));
+        assert!(position(&chain.join("a")) < position(&chain));
 
         Ok(())
     }
 
     #[test]
-    fn collect_with_hidden_files() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
+    fn collect_empty_dirs_keeps_start_path() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
         let path_buf = temp_dir.path().to_path_buf();
+        fs::create_dir_all(path_buf.join("a").join("b"))?;
 
-        let extensions = vec!["txt".to_string()];
         let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: false,
+            recurse: true,
+            empty_dirs: true,
+            ..Default::default()
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        let dirs = collect_empty_dirs(&path_buf, &options)?;
 
-        assert_eq!(files.len(), 2);
-        assert!(files.contains(&path_buf.join("root.txt")));
-        assert!(files.contains(&path_buf.join(".hidden.txt")));
+        assert_eq!(vec![path_buf.join("a").join("b"), path_buf.join("a")], dirs);
 
         Ok(())
     }
 
     #[test]
-    fn collect_reverse_with_hidden_files() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
+    fn collect_empty_dirs_hidden_and_recurse() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_empty_dirs_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
-        let extensions = vec!["dat".to_string()];
+        // Without --recurse, chain/ holds a/ and is not empty
         let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: true,
+            empty_dirs: true,
+            ..Default::default()
         };
+        assert!(collect_empty_dirs(&path_buf, &options)?.is_empty());
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
-
-        assert_eq!(files.len(), 5);
-        assert!(files.contains(&path_buf.join("root.txt")));
-        assert!(files.contains(&path_buf.join("root.log")));
-        assert!(files.contains(&path_buf.join(".hidden.txt")));
-        assert!(files.contains(&path_buf.join("file.tar.gz")));
-        assert!(files.contains(&path_buf.join("other.md.gz")));
+        let options = CollectOptions {
+            all: true,
+            empty_dirs: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![path_buf.join(".hidden_empty")],
+            collect_empty_dirs(&path_buf, &options)?
+        );
 
         Ok(())
     }
 
     #[test]
-    fn collect_multiple_extension() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
+    fn delete_empty_dirs_in_order() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_empty_dirs_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
-        let extensions = vec!["dat".to_string(), "txt".to_string()];
-        let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: true,
+        let collect_options = CollectOptions {
+            recurse: true,
+            empty_dirs: true,
+            ..Default::default()
+        };
+        let delete_options = DeleteOptions {
+            force: true,
+            ..Default::default()
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        let dirs = collect_empty_dirs(&path_buf, &collect_options)?;
+        let report = delete_empty_dirs(&dirs, &delete_options)?;
 
-        assert_eq!(files.len(), 3);
-        assert!(files.contains(&path_buf.join("root.log")));
-        assert!(files.contains(&path_buf.join("file.tar.gz")));
-        assert!(files.contains(&path_buf.join("other.md.gz")));
+        assert_eq!(report.deleted, 4);
+        assert!(report.failures.is_empty());
+        assert!(!path_buf.join("chain").exists());
+        assert!(!path_buf.join("mixed").join("empty").exists());
+        assert!(path_buf.join("mixed").join("not_empty").exists());
+        assert!(path_buf.join(".hidden_empty").exists());
 
         Ok(())
     }
 
     #[test]
-    fn collect_recursive() -> Result<(), Box<dyn Error>> {
+    fn collect_hidden_files_and_dirs_matrix() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
         let extensions = vec!["txt".to_string()];
-        let options = CollectOptions {
-            all: false,
-            list: false,
-            recurse: true,
-            invert: false,
+        let visible = vec![
+            path_buf.join("root.txt"),
+            path_buf.join("subfolder1").join("sub1.txt"),
+            path_buf
+                .join("subfolder1")
+                .join("subfolder2")
+                .join("sub2.txt"),
+        ];
+        let hidden_file = path_buf.join(".hidden.txt");
+        let in_hidden_dir = path_buf.join(".hidden_folder").join("hidden.txt");
+
+        let collect = |hidden_files: bool, hidden_dirs: bool| {
+            let options = CollectOptions {
+                recurse: true,
+                hidden_files,
+                hidden_dirs,
+                sort: true,
+                ..Default::default()
+            };
+            collect_matching_files(&extensions, &path_buf, &options)
+        };
+        let sorted = |mut files: Vec<PathBuf>| {
+            files.sort();
+            files
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        assert_eq!(sorted(visible.clone()), collect(false, false)?);
+        assert_eq!(
+            sorted([visible.clone(), vec![hidden_file.clone()]].concat()),
+            collect(true, false)?
+        );
+        assert_eq!(
+            sorted([visible.clone(), vec![in_hidden_dir.clone()]].concat()),
+            collect(false, true)?
+        );
+        assert_eq!(
+            sorted([visible, vec![hidden_file, in_hidden_dir]].concat()),
+            collect(true, true)?
+        );
 
-        assert_eq!(files.len(), 3);
-        assert!(files.contains(&path_buf.join("root.txt")));
-        assert!(files.contains(&path_buf.join("subfolder1").join("sub1.txt")));
-        assert!(
-            files.contains(
-                &path_buf
-                    .join("subfolder1")
-                    .join("subfolder2")
-                    .join("sub2.txt")
-            )
+        let all = CollectOptions {
+            recurse: true,
+            all: true,
+            sort: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            collect(true, true)?,
+            collect_matching_files(&extensions, &path_buf, &all)?
         );
 
         Ok(())
     }
 
     #[test]
-    fn collect_should_not_traverse_symlinks() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
-
-        let other_temp_dir = tempdir().unwrap();
-        let path_buf = other_temp_dir.path().to_path_buf();
-
-        let link = path_buf.join("link");
-
-        // Creating a symbolic link to the real directory
-        symlink(&temp_dir, &link)?;
-
-        // Verify that the symlink works by checking that we can see files through it
-        let to_temp_dir = fs::read_link(&link).unwrap();
-        let file_count = fs::read_dir(&to_temp_dir)?.count();
-        assert_eq!(file_count, 8);
+    fn deletion_order_puts_content_before_directories() {
+        let candidate = |path: &str, kind| Candidate::new(PathBuf::from(path), kind);
+        let candidates = vec![
+            candidate("/a", CandidateKind::Dir),
+            candidate("/a/b", CandidateKind::EmptyDir),
+            candidate("/a/f.txt", CandidateKind::File),
+            candidate("/a/b/c", CandidateKind::EmptyDir),
+            candidate("/z.txt", CandidateKind::File),
+        ];
+
+        let ordered: Vec<PathBuf> = deletion_order(&candidates)
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+
+        assert_eq!(
+            vec![
+                PathBuf::from("/a/b/c"),
+                PathBuf::from("/a/f.txt"),
+                PathBuf::from("/a/b"),
+                PathBuf::from("/z.txt"),
+                PathBuf::from("/a"),
+            ],
+            ordered
+        );
+    }
 
-        let extensions = vec!["txt".to_string()];
-        let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: true,
-            invert: false,
+    #[test]
+    fn delete_overlapping_candidates() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let subfolder1 = path_buf.join("subfolder1");
+        let subfolder2 = subfolder1.join("subfolder2");
+        let empty = subfolder2.join("empty");
+        fs::create_dir(&empty)?;
+
+        // Parents first, the order that would fail without reordering
+        let candidates = vec![
+            Candidate::new(subfolder1.clone(), CandidateKind::Dir),
+            Candidate::new(subfolder2.clone(), CandidateKind::Dir),
+            Candidate::new(empty.clone(), CandidateKind::EmptyDir),
+            Candidate::new(subfolder2.join("sub2.txt"), CandidateKind::File),
+            Candidate::new(subfolder1.join("sub1.txt"), CandidateKind::File),
+            Candidate::new(path_buf.join("root.txt"), CandidateKind::File),
+        ];
+        let options = DeleteOptions {
+            force: true,
+            ..Default::default()
         };
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        let report = delete_candidates(&candidates, &options, FILES, None)?;
 
-        assert_eq!(files.len(), 0);
+        assert!(report.failures.is_empty());
+        assert_eq!(report.deleted, 6);
+        assert!(!subfolder1.exists());
+        assert!(!path_buf.join("root.txt").exists());
+        assert!(path_buf.join("root.log").exists());
 
         Ok(())
     }
 
+    fn matched_exts(candidates: &[Candidate]) -> Vec<(String, Option<&str>)> {
+        let mut matched: Vec<(String, Option<&str>)> = candidates
+            .iter()
+            .map(|c| {
+                let name = c.path.file_name().unwrap().to_string_lossy().to_string();
+                (name, c.matched_ext.as_deref())
+            })
+            .collect();
+        matched.sort();
+        matched
+    }
+
     #[test]
-    fn collect_multi_dot_extension_with_extension_start() -> Result<(), Box<dyn Error>> {
+    fn candidates_matched_extension() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
-        let extensions = vec!["tar".to_string()];
-        let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: false,
-        };
+        // The first requested extension matching wins, whatever its length
+        let extensions = vec!["log".to_string(), "gz".to_string(), "tar.gz".to_string()];
+        let candidates = collect_candidates(&extensions, &path_buf, &CollectOptions::default())?;
+
+        assert_eq!(
+            vec![
+                ("file.tar.gz".to_string(), Some("gz")),
+                ("other.md.gz".to_string(), Some("gz")),
+                ("root.log".to_string(), Some("log")),
+            ],
+            matched_exts(&candidates)
+        );
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        let extensions = vec!["tar.gz".to_string(), "gz".to_string()];
+        let candidates = collect_candidates(&extensions, &path_buf, &CollectOptions::default())?;
 
-        assert_eq!(files.len(), 0);
+        assert_eq!(
+            vec![
+                ("file.tar.gz".to_string(), Some("tar.gz")),
+                ("other.md.gz".to_string(), Some("gz")),
+            ],
+            matched_exts(&candidates)
+        );
 
         Ok(())
     }
 
+    // <temp_dir>/
+    // ├── outside/
+    // │   ├── external.log
+    // │   └── to_inside.log -> ../tree/logs/linked_from_outside.log
+    // └── tree/
+    //     ├── current.log -> logs/2024-06-01.log
+    //     ├── external.log -> ../outside/external.log
+    //     └── logs/
+    //         ├── 2024-05-01.log
+    //         ├── 2024-06-01.log
+    //         └── linked_from_outside.log
     #[test]
-    fn collect_multi_dot_extension_with_complete_extension() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
-        let path_buf = temp_dir.path().to_path_buf();
+    fn preserve_link_targets() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir()?;
+        let outside = temp_dir.path().join("outside");
+        let tree = temp_dir.path().join("tree");
+        fs::create_dir_all(tree.join("logs"))?;
+        fs::create_dir(&outside)?;
+        for file in [
+            "2024-05-01.log",
+            "2024-06-01.log",
+            "linked_from_outside.log",
+        ] {
+            File::create(tree.join("logs").join(file))?;
+        }
+        File::create(outside.join("external.log"))?;
+        symlink("logs/2024-06-01.log", tree.join("current.log"))?;
+        symlink("../outside/external.log", tree.join("external.log"))?;
+        symlink(
+            "../tree/logs/linked_from_outside.log",
+            outside.join("to_inside.log"),
+        )?;
+
+        let extensions = vec!["log".to_string()];
+        let options = CollectOptions {
+            recurse: true,
+            sort: true,
+            preserve_link_targets: true,
+            ..Default::default()
+        };
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &tree, &options)?;
+
+        // Only symlinks met during the collection count, wherever their target lives
+        assert_eq!(
+            vec![
+                tree.join("logs/2024-05-01.log"),
+                tree.join("logs/linked_from_outside.log"),
+            ],
+            files
+        );
+        assert_eq!(1, stats.link_targets_preserved);
+        assert_eq!(vec![2], stats.matched_by_extension);
 
-        let extensions = vec!["tar.gz".to_string()];
         let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: false,
+            preserve_link_targets: false,
+            ..options
         };
+        let files = collect_matching_files(&extensions, &tree, &options)?;
+        assert_eq!(3, files.len());
 
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
+        Ok(())
+    }
 
-        assert_eq!(files.len(), 1);
-        assert!(files.contains(&path_buf.join("file.tar.gz")));
+    // Spared link targets do not take up the limit, which counts the files left only
+    #[test]
+    fn limit_after_link_targets() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::new()
+            .file("a.log")
+            .file("b.log")
+            .file("c.log")
+            .build()?;
+        let path_buf = tree.path().to_path_buf();
+        symlink("a.log", path_buf.join("current"))?;
+        symlink("b.log", path_buf.join("previous"))?;
+        let extensions = vec!["log".to_string()];
+
+        for limit in [1, 2] {
+            let options = CollectOptions {
+                preserve_link_targets: true,
+                limit: Some(limit),
+                ..Default::default()
+            };
+            let (files, stats) =
+                collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+
+            assert_eq!(vec![path_buf.join("c.log")], files, "limit {limit}");
+            assert!(!stats.limit_reached, "limit {limit}");
+            assert_eq!(2, stats.link_targets_preserved, "limit {limit}");
+            assert_eq!(vec![1], stats.matched_by_extension, "limit {limit}");
+        }
+
+        // Once spared, more files are left than the limit takes
+        File::create(path_buf.join("d.log"))?;
+        let options = CollectOptions {
+            preserve_link_targets: true,
+            limit: Some(1),
+            ..Default::default()
+        };
+        let (files, stats) = collect_matching_files_with_stats(&extensions, &path_buf, &options)?;
+        assert_eq!(1, files.len());
+        assert!(stats.limit_reached);
+        assert_eq!(vec![1], stats.matched_by_extension);
 
         Ok(())
     }
 
     #[test]
-    fn collect_multi_dot_extension_with_last_part() -> Result<(), Box<dyn Error>> {
+    fn inverted_candidates_have_no_matched_extension() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
 
-        let extensions = vec!["gz".to_string()];
+        let extensions = vec!["txt".to_string(), "gz".to_string()];
         let options = CollectOptions {
-            all: true,
-            list: false,
-            recurse: false,
-            invert: false,
+            invert: true,
+            ..Default::default()
         };
-
-        let files = collect_matching_files(&extensions, &path_buf, &options)?;
-
-        assert_eq!(files.len(), 2);
-        assert!(files.contains(&path_buf.join("file.tar.gz")));
-        assert!(files.contains(&path_buf.join("other.md.gz")));
+        let candidates = collect_candidates(&extensions, &path_buf, &options)?;
+
+        assert_eq!(
+            vec![
+                ("data.dat".to_string(), None),
+                ("root.log".to_string(), None),
+            ],
+            matched_exts(&candidates)
+        );
+        assert_eq!(
+            into_paths(candidates),
+            collect_matching_files(&extensions, &path_buf, &options)?
+        );
 
         Ok(())
     }
 
     #[test]
-    fn delete_one_file() -> Result<(), Box<dyn Error>> {
+    fn collect_with_audit() -> Result<(), Box<dyn Error>> {
         let temp_dir = create_temp_folder();
         let path_buf = temp_dir.path().to_path_buf();
+        let audit_dir = tempdir()?;
+        let audit_path = audit_dir.path().join("audit.tsv");
 
-        let options = DeleteOptions {
-            dry_run: false,
-            force: true,
-        };
-
-        let mut files: Vec<PathBuf> = Vec::new();
-        let file = path_buf.join("root.txt");
-        files.push(file.clone());
+        let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 86400);
+        File::options()
+            .write(true)
+            .open(path_buf.join("root.log"))?
+            .set_modified(two_days_ago)?;
 
-        assert!(file.exists());
+        let extensions = vec!["txt".to_string(), "log".to_string()];
+        let options = CollectOptions {
+            never_older_than: Some(Duration::from_secs(86400)),
+            audit: Some(audit_path.clone()),
+            ..Default::default()
+        };
 
-        delete_files(&files, &options)?;
+        collect_matching_files(&extensions, &path_buf, &options)?;
 
-        assert!(!file.exists());
+        let audit = fs::read_to_string(&audit_path)?;
+        let line = |name: &str, decision: &str| {
+            format!("{}\t{}\n", path_buf.join(name).display(), decision)
+        };
+        assert!(audit.contains(&line("root.txt", "yes\tselected")));
+        assert!(audit.contains(&line("root.log", "no\tprotected")));
+        assert!(audit.contains(&line("data.dat", "no\textension mismatch")));
+        assert!(audit.contains(&line(".hidden.txt", "no\thidden skipped")));
+        // Folders are not files, and are not audited
+        assert_eq!(6, audit.lines().count());
 
         Ok(())
     }
 
     #[test]
-    fn delete_multiple_files() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
-        let path_buf = temp_dir.path().to_path_buf();
-
-        let options = DeleteOptions {
-            dry_run: false,
-            force: true,
+    fn simulate_narrows_plan() {
+        let now = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let planned = |path: &str, size, age_days: Option<u64>| PlannedFile {
+            path: path.to_string(),
+            size,
+            modified: age_days.map(|days| now - days * 86400),
+        };
+        let plan = DeletionPlan {
+            schema_version: plan::SCHEMA_VERSION,
+            files: vec![
+                planned("a.log", 10, Some(1)),
+                planned("old/b.log", 20, Some(90)),
+                planned("c.txt", 30, Some(1)),
+                planned("unknown.log", 40, None),
+            ],
         };
 
-        let mut files: Vec<PathBuf> = Vec::new();
-        let first_file = path_buf.join("root.txt");
-        let second_file = path_buf
-            .join("subfolder1")
-            .join("subfolder2")
-            .join("sub2.txt");
-        files.push(first_file.clone());
-        files.push(second_file.clone());
+        let (all, _) = simulate(&plan, &[], &CollectOptions::default());
+        assert_eq!(plan.files, all);
 
-        assert!(first_file.exists());
-        assert!(second_file.exists());
+        let options = CollectOptions {
+            never_older_than: Some(Duration::from_secs(30 * 86400)),
+            ..Default::default()
+        };
+        let (files, stats) = simulate(&plan, &["log".to_string()], &options);
 
-        delete_files(&files, &options)?;
+        assert_eq!(vec![plan.files[0].clone()], files);
+        // Files with no stored age are protected, like unreadable ones during a real run
+        assert_eq!(stats.age_protected, 2);
+    }
 
-        assert!(!first_file.exists());
-        assert!(!second_file.exists());
+    #[test]
+    fn chunk_answers() -> Result<(), Box<dyn Error>> {
+        let mut input = io::Cursor::new("y\n\nN\nwhat\na\nq\n");
+
+        let answers: Vec<ChunkAnswer> = (0..6)
+            .map(|_| ask_chunk(&mut input, "Delete"))
+            .collect::<io::Result<_>>()?;
+
+        // Unknown answers are asked again, and the end of the input quits
+        assert_eq!(
+            vec![
+                ChunkAnswer::Yes,
+                ChunkAnswer::Yes,
+                ChunkAnswer::No,
+                ChunkAnswer::All,
+                ChunkAnswer::Quit,
+                ChunkAnswer::Quit,
+            ],
+            answers
+        );
 
         Ok(())
     }
 
     #[test]
-    fn delete_dry_run() -> Result<(), Box<dyn Error>> {
-        let temp_dir = create_temp_folder();
-        let path_buf = temp_dir.path().to_path_buf();
+    fn affected_parents_of_deleted_paths() {
+        let deleted: Vec<PathBuf> = [
+            "/srv/a/x.log",
+            "/srv/a/y.log",
+            "/srv/b/empty/inner",
+            "/srv/b/empty",
+            "/srv/c.log",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let parents: Vec<PathBuf> = affected_parents(&deleted).into_iter().collect();
+
+        // /srv/b/empty was deleted itself, so only /srv/b is touched for it
+        assert_eq!(
+            vec![
+                PathBuf::from("/srv"),
+                PathBuf::from("/srv/a"),
+                PathBuf::from("/srv/b"),
+            ],
+            parents
+        );
+    }
 
-        let options = DeleteOptions {
-            dry_run: true,
-            force: true,
+    #[test]
+    fn touch_affected_parents() -> Result<(), Box<dyn Error>> {
+        let temp_dir = create_temp_folder();
+        let dir = temp_dir.path().join("subfolder1");
+        let old = SystemTime::now() - Duration::from_secs(10 * 86400);
+        File::open(&dir)?.set_modified(old)?;
+        File::open(temp_dir.path())?.set_modified(old)?;
+
+        let report = DeleteReport {
+            deleted_paths: vec![dir.join("sub1.log"), temp_dir.path().join("gone/x.log")],
+            ..Default::default()
         };
+        let failures = touch_parents(&report);
 
-        let mut files: Vec<PathBuf> = Vec::new();
-        let first_file = path_buf.join("root.txt");
-        let second_file = path_buf
-            .join("subfolder1")
-            .join("subfolder2")
-            .join("sub2.txt");
-        files.push(first_file.clone());
-        files.push(second_file.clone());
-
-        assert!(first_file.exists());
-        assert!(second_file.exists());
-
-        delete_files(&files, &options)?;
-
-        assert!(first_file.exists());
-        assert!(second_file.exists());
+        assert!(fs::metadata(&dir)?.modified()? > old);
+        assert_eq!(fs::metadata(temp_dir.path())?.modified()?, old);
+        assert_eq!(
+            vec![temp_dir.path().join("gone")],
+            failures.into_iter().map(|f| f.0).collect::<Vec<_>>()
+        );
 
         Ok(())
     }
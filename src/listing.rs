@@ -0,0 +1,352 @@
+use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::archive;
+use crate::candidate::Candidate;
+use crate::display::shorten_path;
+use crate::filter::{AgeRules, ExtensionFilter};
+use crate::get_fileext;
+use crate::parsing::duration::format_duration;
+use crate::report::{format_count, format_listing_footer, format_size};
+
+// Sizes in bytes from which --long listing shows sizes in yellow, then in red, green being used
+// below (see size_heat in the config file)
+pub(crate) const DEFAULT_SIZE_HEAT: [u64; 2] = [1024 * 1024, 100 * 1024 * 1024];
+
+// Matches a sorted listing buffers at most when the config file does not say (see sort_limit)
+pub(crate) const DEFAULT_SORT_LIMIT: usize = 1_000_000;
+
+// Given once, when a sorted listing holds more than `limit` matches
+pub(crate) fn format_sort_fallback(limit: usize) -> String {
+    format!(
+        "more than {} matches to sort, listing them unsorted as they are found instead (see \
+        sort_limit in the config file)",
+        format_count(limit)
+    )
+}
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+// Color of a size, given the thresholds between green and yellow, then yellow and red
+fn heat_color(bytes: u64, thresholds: &[u64; 2]) -> &'static str {
+    if bytes < thresholds[0] {
+        GREEN
+    } else if bytes < thresholds[1] {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+fn display_path(path: &Path, width: Option<usize>) -> String {
+    match width {
+        Some(width) => shorten_path(&path.to_string_lossy(), width),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+// What is shown after listed paths, when asked for
+#[derive(Default, Clone, Copy)]
+pub(crate) struct Notes<'a> {
+    // Extensions counted inside matched archives (see --peek-archives)
+    pub(crate) peek: Option<&'a ExtensionFilter>,
+    // Rules, the one selecting each match being named (see --rule)
+    pub(crate) rules: Option<&'a AgeRules>,
+    // Symlinks get a trailing `@`, like with `ls -F`, when they can be matched (see --symlinks)
+    pub(crate) symlinks: bool,
+    // Directories get a trailing `/` when they can be matched (see --dirs)
+    pub(crate) dirs: bool,
+}
+
+// Like `  [rule log:30d]  [archive: 2 of 5 entries match]`
+fn format_notes(path: &Path, notes: Notes) -> String {
+    let mut line = String::new();
+    if let Some(rules) = notes.rules
+        && let Some(name) = path.file_name().and_then(|n| n.to_str())
+        && let Some((extension, age)) = get_fileext(name).and_then(|e| rules.rule_for(e))
+    {
+        line.push_str(&format!("  [rule {extension}:{}]", format_duration(*age)));
+    }
+    if let Some(peek) = notes
+        .peek
+        .and_then(|extensions| archive::peek(path, extensions))
+    {
+        line.push_str(&format!("  [{peek}]"));
+    }
+    line
+}
+
+// Prints one match per line, as used by -l/--list
+// With a width, paths are shortened to fit in it (see --no-truncate)
+pub(crate) fn print_match(
+    out: &mut impl Write,
+    path: &Path,
+    width: Option<usize>,
+    notes: Notes,
+) -> io::Result<()> {
+    let note = format_notes(path, notes);
+    // Only read when it can make a difference
+    let metadata = (notes.symlinks || notes.dirs).then(|| fs::symlink_metadata(path));
+    let marker = match metadata {
+        Some(Ok(m)) if notes.symlinks && m.is_symlink() => "@",
+        Some(Ok(m)) if notes.dirs && m.is_dir() => "/",
+        _ => "",
+    };
+    let width = width.map(|w| w.saturating_sub(marker.len() + note.chars().count()));
+    writeln!(out, "{}{marker}{note}", display_path(path, width))
+}
+
+// Buffers streamed matches, writing them once per directory rather than once per line, which
+// matters in directories of millions of entries. The matches of a directory are still shown as
+// soon as another directory yields one, letting the user interrupt a long run early
+pub(crate) struct MatchWriter<W: Write> {
+    out: BufWriter<W>,
+    // Directory of the matches buffered so far
+    dir: Option<PathBuf>,
+}
+
+impl<W: Write> MatchWriter<W> {
+    pub(crate) fn new(out: W) -> MatchWriter<W> {
+        MatchWriter {
+            out: BufWriter::new(out),
+            dir: None,
+        }
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        path: &Path,
+        width: Option<usize>,
+        notes: Notes,
+    ) -> io::Result<()> {
+        let dir = path.parent();
+        if self.dir.as_deref() != dir {
+            self.out.flush()?;
+            self.dir = dir.map(Path::to_path_buf);
+        }
+        print_match(&mut self.out, path, width, notes)
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// Width of the size column, enough for `1023.9 KiB`
+const SIZE_WIDTH: usize = 10;
+
+// Line of --long listing: type and size columns, path, and symlink target if any. Sizes are
+// colored by magnitude given heat thresholds, directories having none
+pub(crate) fn format_long(
+    candidate: &Candidate,
+    width: Option<usize>,
+    notes: Notes,
+    heat: Option<&[u64; 2]>,
+) -> String {
+    let note = format_notes(&candidate.path, notes);
+    // Leaves room for the type and size columns
+    let width = width.map(|w| w.saturating_sub(3 + SIZE_WIDTH + note.chars().count()));
+
+    // Directories deleted whole show the total of what they hold
+    let size = if candidate.kind.is_dir() && candidate.contents.is_none() {
+        format!("{:>SIZE_WIDTH$}", "-")
+    } else {
+        let bytes = candidate.size();
+        let size = format!("{:>SIZE_WIDTH$}", format_size(bytes));
+        match heat {
+            Some(thresholds) => format!("{}{size}{RESET}", heat_color(bytes, thresholds)),
+            None => size,
+        }
+    };
+    let mut line = format!(
+        "{} {size} {}",
+        candidate.kind.as_char(),
+        display_path(&candidate.path, width)
+    );
+    if let Some(target) = candidate.link_target() {
+        line.push_str(&format!(" -> {}", target.to_string_lossy()));
+    }
+    line.push_str(&note);
+    line
+}
+
+// Goes to stderr when stdout is read as data, like `rmx -ln log | xargs ...`, so that it only
+// ever holds paths. An empty listing has none
+pub(crate) fn print_footer(count: usize, bytes: u64) -> io::Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    let footer = format_listing_footer(count, bytes);
+    if io::stdout().is_terminal() {
+        writeln!(io::stdout().lock(), "{footer}")
+    } else {
+        writeln!(io::stderr().lock(), "{footer}")
+    }
+}
+
+pub(crate) fn print_candidates(
+    candidates: &[Candidate],
+    long: bool,
+    width: Option<usize>,
+    notes: Notes,
+    heat: Option<&[u64; 2]>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for candidate in candidates {
+        if long {
+            writeln!(out, "{}", format_long(candidate, width, notes, heat))?;
+        } else {
+            print_match(&mut out, &candidate.path, width, notes)?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::candidate::CandidateKind;
+    use std::fs::File;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn format_long_file() {
+        let candidate =
+            Candidate::new(Path::new("dir/file.txt").to_path_buf(), CandidateKind::File);
+
+        assert_eq!(
+            "-        0 B dir/file.txt",
+            format_long(&candidate, None, Notes::default(), None)
+        );
+    }
+
+    #[test]
+    fn heat_buckets() {
+        let thresholds = [1024, 4096];
+
+        assert_eq!(GREEN, heat_color(0, &thresholds));
+        assert_eq!(GREEN, heat_color(1023, &thresholds));
+        assert_eq!(YELLOW, heat_color(1024, &thresholds));
+        assert_eq!(YELLOW, heat_color(4095, &thresholds));
+        assert_eq!(RED, heat_color(4096, &thresholds));
+        assert_eq!(RED, heat_color(u64::MAX, &thresholds));
+    }
+
+    // Colors only wrap the size, the rest of the line being the same as without them
+    #[test]
+    fn format_long_heat() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("big.log");
+        fs::write(&path, vec![0; 2048])?;
+        let candidate = Candidate::new(path.clone(), CandidateKind::File);
+
+        let plain = format_long(&candidate, Some(200), Notes::default(), None);
+        assert_eq!(format!("-    2.0 KiB {}", path.display()), plain);
+        assert!(!plain.contains('\x1b'));
+
+        let colored = format_long(&candidate, Some(200), Notes::default(), Some(&[1024, 4096]));
+        assert_eq!(
+            format!("- {YELLOW}   2.0 KiB{RESET} {}", path.display()),
+            colored
+        );
+        assert_eq!(plain, colored.replace(YELLOW, "").replace(RESET, ""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn match_writer_flushes_per_directory() -> io::Result<()> {
+        let mut writer = MatchWriter::new(Vec::new());
+
+        writer.write(Path::new("a/1.log"), None, Notes::default())?;
+        writer.write(Path::new("a/2.log"), None, Notes::default())?;
+        assert!(writer.out.get_ref().is_empty());
+
+        writer.write(Path::new("b/1.log"), None, Notes::default())?;
+        assert_eq!(b"a/1.log\na/2.log\n", writer.out.get_ref().as_slice());
+
+        let out = writer.out.into_inner().map_err(|e| e.into_error())?;
+        assert_eq!(b"a/1.log\na/2.log\nb/1.log\n", out.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn marks_dirs() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let dir = temp_dir.path().join("foo.egg-info");
+        std::fs::create_dir(&dir)?;
+        let notes = Notes {
+            dirs: true,
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        print_match(&mut out, &dir, None, notes)?;
+        print_match(&mut out, &dir, None, Notes::default())?;
+
+        assert_eq!(
+            format!("{}/\n{}\n", dir.display(), dir.display()),
+            String::from_utf8(out)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn marks_symlinks() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let target = temp_dir.path().join("target.log");
+        let link = temp_dir.path().join("link.log");
+        File::create(&target)?;
+        symlink(&target, &link)?;
+        let notes = Notes {
+            symlinks: true,
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        print_match(&mut out, &link, None, notes)?;
+        print_match(&mut out, &target, None, notes)?;
+        print_match(&mut out, &link, None, Notes::default())?;
+
+        assert_eq!(
+            format!(
+                "{}@\n{}\n{}\n",
+                link.display(),
+                target.display(),
+                link.display()
+            ),
+            String::from_utf8(out)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_long_symlink() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let target = temp_dir.path().join("target.log");
+        let link = temp_dir.path().join("link.log");
+        File::create(&target)?;
+        symlink(&target, &link)?;
+
+        let candidate = Candidate::new(link.clone(), CandidateKind::Symlink);
+
+        // The link itself, never its target
+        let size = format_size(fs::symlink_metadata(&link)?.len());
+        let expected = format!("l {size:>10} {} -> {}", link.display(), target.display());
+        assert_eq!(
+            expected,
+            format_long(&candidate, None, Notes::default(), None)
+        );
+
+        Ok(())
+    }
+}
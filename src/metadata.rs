@@ -0,0 +1,281 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::SystemTime;
+
+use crate::candidate::Candidate;
+
+// What the features reading metadata need: size filters and totals, age filters and listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Stat {
+    pub(crate) size: u64,
+    // None where the platform does not record it
+    pub(crate) modified: Option<SystemTime>,
+    // Only as reliable as the mount options of the filesystem (see --accessed-before)
+    pub(crate) accessed: Option<SystemTime>,
+    // Owner and group, None where the platform has none (see --user, --group and
+    // protected_owners)
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    // Permission bits, off Unix only the write bits being known, from the read-only attribute
+    // (see --skip-readonly)
+    pub(crate) mode: u32,
+    // Filesystem holding the file, as in st_dev, None off Unix (see the freed space)
+    pub(crate) dev: Option<u64>,
+}
+
+// Where metadata is read from, so that tests can count reads
+pub(crate) trait Fs: Sync {
+    fn stat(&self, path: &Path) -> io::Result<Stat>;
+}
+
+// Links are read themselves, never what they point to, as they are what gets deleted
+pub(crate) struct RealFs;
+
+impl Fs for RealFs {
+    fn stat(&self, path: &Path) -> io::Result<Stat> {
+        let metadata = fs::symlink_metadata(path)?;
+        let (uid, gid) = ownership(&metadata);
+        Ok(Stat {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            uid,
+            gid,
+            mode: mode(&metadata),
+            dev: device(&metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn ownership(metadata: &fs::Metadata) -> (Option<u32>, Option<u32>) {
+    use std::os::unix::fs::MetadataExt;
+
+    (Some(metadata.uid()), Some(metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn ownership(_metadata: &fs::Metadata) -> (Option<u32>, Option<u32>) {
+    (None, None)
+}
+
+#[cfg(unix)]
+fn device(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.mode()
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+// Metadata of a path, read at most once per run, the error being kept for the warnings of the
+// filters that needed it. It does not make candidates differ
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatCache(OnceLock<Result<Stat, String>>);
+
+impl StatCache {
+    pub(crate) fn read(&self, path: &Path, fs: &impl Fs) -> Result<Stat, String> {
+        self.0
+            .get_or_init(|| fs.stat(path).map_err(|e| e.to_string()))
+            .clone()
+    }
+
+    fn is_read(&self) -> bool {
+        self.0.get().is_some()
+    }
+}
+
+impl PartialEq for StatCache {
+    fn eq(&self, _: &StatCache) -> bool {
+        true
+    }
+}
+
+impl Eq for StatCache {}
+
+// Reads the metadata of the candidates not read yet on `threads` threads, before the features
+// needing it go through them one by one, which would otherwise wait for each read in turn on
+// network filesystems. Unreadable candidates keep their error, and directories are left out
+pub(crate) fn enrich(candidates: &[Candidate], fs: &impl Fs, threads: usize) {
+    let pending: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| !c.kind.is_dir() && !c.stat.is_read())
+        .collect();
+    let per_thread = pending.len().div_ceil(threads.max(1)).max(1);
+    if per_thread >= pending.len() {
+        for candidate in pending {
+            let _ = candidate.stat.read(&candidate.path, fs);
+        }
+        return;
+    }
+
+    thread::scope(|scope| {
+        for chunk in pending.chunks(per_thread) {
+            scope.spawn(move || {
+                for candidate in chunk {
+                    let _ = candidate.stat.read(&candidate.path, fs);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    use crate::candidate::CandidateKind;
+
+    // Counts the reads of each path, failing for paths ending in `.err`
+    #[derive(Default)]
+    struct CountingFs {
+        reads: Mutex<HashMap<PathBuf, usize>>,
+    }
+
+    impl Fs for CountingFs {
+        fn stat(&self, path: &Path) -> io::Result<Stat> {
+            *self
+                .reads
+                .lock()
+                .unwrap()
+                .entry(path.to_path_buf())
+                .or_default() += 1;
+            if path.extension().is_some_and(|e| e == "err") {
+                return Err(io::Error::other("unreachable share"));
+            }
+            Ok(Stat {
+                size: path.as_os_str().len() as u64,
+                modified: None,
+                accessed: None,
+                uid: None,
+                gid: None,
+                mode: 0o644,
+                dev: None,
+            })
+        }
+    }
+
+    fn candidates(n: usize) -> Vec<Candidate> {
+        (0..n)
+            .map(|i| Candidate::new(PathBuf::from(format!("/srv/{i}.log")), CandidateKind::File))
+            .collect()
+    }
+
+    #[test]
+    fn reads_each_candidate_once() {
+        let fs = CountingFs::default();
+        let candidates = candidates(100);
+
+        enrich(&candidates, &fs, 4);
+        // Already read, by the stage or by the features using them
+        enrich(&candidates, &fs, 4);
+        for candidate in &candidates {
+            candidate.stat.read(&candidate.path, &fs).unwrap();
+        }
+
+        let reads = fs.reads.lock().unwrap();
+        assert_eq!(100, reads.len());
+        assert!(reads.values().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn errors_are_kept_once() {
+        let fs = CountingFs::default();
+        let mut candidates = candidates(3);
+        candidates.push(Candidate::new(
+            PathBuf::from("/srv/gone.err"),
+            CandidateKind::File,
+        ));
+        candidates.push(Candidate::new(
+            PathBuf::from("/srv/cache"),
+            CandidateKind::Dir,
+        ));
+
+        enrich(&candidates, &fs, 8);
+
+        assert_eq!(
+            Err("unreachable share".to_string()),
+            candidates[3].stat.read(&candidates[3].path, &fs)
+        );
+        let reads = fs.reads.lock().unwrap();
+        assert_eq!(Some(&1), reads.get(Path::new("/srv/gone.err")));
+        // Directories are not read
+        assert_eq!(None, reads.get(Path::new("/srv/cache")));
+    }
+
+    #[test]
+    fn cache_does_not_make_candidates_differ() {
+        let fs = CountingFs::default();
+        let read = candidates(1);
+        enrich(&read, &fs, 1);
+
+        assert_eq!(candidates(1), read);
+    }
+
+    #[test]
+    fn sizes_of_real_files() -> io::Result<()> {
+        let dir = tempdir()?;
+        let candidates: Vec<Candidate> = (0..50)
+            .map(|i| {
+                let path = dir.path().join(format!("{i}.log"));
+                fs::write(&path, vec![0; i])?;
+                Ok(Candidate::new(path, CandidateKind::File))
+            })
+            .collect::<io::Result<_>>()?;
+
+        enrich(&candidates, &RealFs, 4);
+
+        for (i, candidate) in candidates.iter().enumerate() {
+            assert_eq!(i as u64, candidate.size());
+        }
+
+        Ok(())
+    }
+
+    // Read along with the size, so that owner and permission filters need no other read
+    #[cfg(unix)]
+    #[test]
+    fn owners_of_real_files() -> io::Result<()> {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir()?;
+        let path = dir.path().join("a.log");
+        fs::write(&path, "")?;
+        let metadata = fs::metadata(&path)?;
+
+        let stat = RealFs.stat(&path)?;
+        assert_eq!(Some(metadata.uid()), stat.uid);
+        assert_eq!(Some(metadata.gid()), stat.gid);
+        assert_eq!(metadata.mode(), stat.mode);
+        assert_eq!(Some(metadata.dev()), stat.dev);
+
+        Ok(())
+    }
+}
@@ -1,17 +1,32 @@
-use clap::Parser;
+use clap::builder::Resettable;
 use clap::error::ErrorKind as ClapErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
 
 use std::error::Error;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use regex::Regex;
 
+use crate::Candidate;
+use crate::capabilities;
+use crate::display;
+use crate::error::{ExtensionOrigin, RmxError};
+use crate::history;
+use crate::hook;
+use crate::listing::{DEFAULT_SIZE_HEAT, DEFAULT_SORT_LIMIT};
+use crate::parsing::config::{self, Config, Job};
+use crate::parsing::duration::{self, parse_duration};
+use crate::parsing::policy::{self, Policy, PolicySource};
 use crate::parsing::preset;
-
-// (Linux only)
-fn default_config_path() -> PathBuf {
-    PathBuf::from("/etc/rmx/rmx.conf")
-}
+use crate::parsing::size::parse_size;
+use crate::platform::case;
+use crate::report;
+use crate::warning;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -21,149 +36,1798 @@ fn default_config_path() -> PathBuf {
     long_about = "Rust CLI to delete files based on their extension"
 )]
 pub struct Args {
-    /// File extension list, like `md5 tar.gz R`, without extension's dot
-    #[arg(num_args(1..), required_unless_present="preset", required_unless_present="presets", conflicts_with="preset", conflicts_with="presets")]
+    /// File extension list, like `md5 tar.gz R`. A leading dot is dropped and commas separate
+    /// extensions too, so `.log,tmp` is `log tmp`. Case matters
+    #[arg(num_args(1..), required_unless_present="preset", required_unless_present="presets", required_unless_present="apple_cruft", required_unless_present="empty_dirs", required_unless_present="discover", required_unless_present="simulate", required_unless_present="job", required_unless_present="jobs", required_unless_present="init_config", required_unless_present="pycache", required_unless_present="regex", required_unless_present="rule", required_unless_present="history", required_unless_present="no_ext", required_unless_present="name", required_unless_present="files_from", required_unless_present="prefix", required_unless_present="capabilities", conflicts_with="presets")]
     extensions: Vec<String>,
 
-    /// Directory in which to delete files
+    /// Directory in which to delete files. Can be repeated, like -p a -p b, to clean several
+    /// directories with a single prompt. A file given alone is deleted itself, extensions being
+    /// optional then
     #[arg(short, long)]
-    path: Option<PathBuf>,
+    path: Vec<PathBuf>,
 
-    /// Include hidden files, and files in hidden folders
-    #[arg(short, long, default_value_t = false)]
+    /// Read directories in which to delete files from stdin, one per line, along with -p/--path.
+    /// Requires --force or --dry-run, stdin not being left for the prompt
+    #[arg(long, default_value_t = false)]
+    paths_from_stdin: bool,
+
+    /// Include hidden files, and files in hidden folders (both --hidden-files and --hidden-dirs)
+    #[arg(short, long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "all")]
     all: bool,
 
+    /// Include hidden files, without going through hidden folders
+    #[arg(long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "hidden_files")]
+    hidden_files: bool,
+
+    /// Go through hidden folders, like `.cache/`, while still skipping hidden files, like `.env`
+    #[arg(long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "hidden_dirs")]
+    hidden_dirs: bool,
+
     /// Remove confirmation prompt
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "force")]
     force: bool,
 
-    /// Print matches (slower), do not block deletion, overriden by -n/--dry-run
+    /// Print matches (slower) as they are found, do not block deletion, overriden by -n/--dry-run
     #[arg(short, long, default_value_t = false)]
     list: bool,
 
+    /// Walk each subdirectory entirely before the next one (dfs), or a level of the tree before
+    /// the next one (bfs) to find shallow matches first, at the cost of memory in wide trees
+    #[arg(long, value_name = "ORDER", default_value = "dfs", value_parser = ["dfs", "bfs"])]
+    traversal: String,
+
+    /// Only match files whose whole name matches this regular expression, like `app-\d+\.log`.
+    /// Given extensions, files must match both
+    #[arg(long, value_name = "RE", conflicts_with_all = ["empty_dirs", "pycache", "discover"])]
+    regex: Option<String>,
+
+    /// Match --regex against the whole path, as listed, instead of the file name
+    #[arg(long, default_value_t = false, requires = "regex")]
+    regex_path: bool,
+
+    /// Delete the file given as -p/--path even when it does not match the given extensions
+    #[arg(long, default_value_t = false)]
+    no_ext_check: bool,
+
+    /// Only match files whose name starts with STR, like `tmp_` or `~$`. Given extensions, files
+    /// must match both. Names starting with a dot are hidden files, matched with --all. Can be
+    /// repeated, files matching any of them
+    #[arg(long, value_name = "STR", value_parser = prefix_arg, conflicts_with_all = ["empty_dirs", "pycache", "discover"])]
+    prefix: Vec<String>,
+
+    /// Delete files of an extension once older than an age, like log:30d, instead of the given
+    /// extensions. Can be repeated with other extensions, like --rule log:30d --rule tmp:1d
+    #[arg(long, value_name = "EXT:AGE", value_parser = rule_arg, conflicts_with_all = ["extensions", "preset", "invert", "empty_dirs", "pycache", "discover"])]
+    rule: Vec<(String, Duration)>,
+
+    /// Never collect files whose name matches this pattern, like `data.*`, whatever their
+    /// extension: `*` matches anything, `?` any character and `[abc]` one of them. Can be repeated
+    #[arg(long, value_name = "PATTERN", conflicts_with_all = ["empty_dirs"])]
+    exclude: Vec<String>,
+
+    /// Turn warnings into errors: any of them aborts the run before anything is deleted, and
+    /// deleting stops at the first file that cannot be deleted or is locked
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// Sort matches by path, listing them only once every file is collected
+    #[arg(long, default_value_t = false)]
+    sort: bool,
+
+    /// List matches with their type (`-` file, `l` symlink), size and symlink targets, enables
+    /// --list
+    #[arg(long, default_value_t = false)]
+    long: bool,
+
+    /// Color sizes of --long listing by magnitude: auto only colors when stdout is a terminal
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = ["auto", "always", "never"])]
+    color: String,
+
     /// Do not perform deletion, enables --list
-    #[arg(short = 'n', long, default_value_t = false)]
+    #[arg(short = 'n', long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "dry_run")]
     dry_run: bool,
 
     /// Whether the files should be deleted recursively or not
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "recurse")]
     recurse: bool,
 
+    /// Only descend N levels below the start path, 1 meaning its direct entries only, like find.
+    /// Implies -r/--recurse
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["empty_dirs", "pycache"])]
+    max_depth: Option<u64>,
+
+    /// Only collect files at least N levels below the start path, 2 skipping its direct entries,
+    /// like find. Implies -r/--recurse
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["empty_dirs", "pycache"])]
+    min_depth: Option<u64>,
+
+    /// Also match files without any extension, like `Makefile` or `core`, along with the given
+    /// extensions if any. Dotfiles like `.bashrc` have none either, and are hidden files
+    #[arg(long, default_value_t = false, conflicts_with_all = ["rule", "empty_dirs", "pycache", "discover"])]
+    no_ext: bool,
+
+    /// Also match files with exactly this name, like `Thumbs.db`, along with the given
+    /// extensions if any. Hidden names like `.DS_Store` match even without --all. Can be repeated
+    #[arg(long, value_name = "FILENAME", conflicts_with_all = ["rule", "invert", "empty_dirs", "pycache", "discover"])]
+    name: Vec<String>,
+
+    /// Also match directories whose name ends with one of the extensions, like `foo.egg-info` or
+    /// `App.dSYM`, deleting them with everything inside instead of walking them. Anything inside
+    /// kept by a safety filter keeps them, and sizes apply to the total of what they hold, other
+    /// filters, like ages, only applying to files. Listed directories end with `/`
+    #[arg(long, default_value_t = false, conflicts_with_all = ["invert", "empty_dirs", "pycache", "discover", "files_from"])]
+    dirs: bool,
+
+    /// With --dirs, walk matched directories instead of deleting them whole, deleting only the
+    /// matching files they hold and leaving the directories in place. Handy with -n/--dry-run to
+    /// see what a matched directory holds
+    #[arg(long, default_value_t = false)]
+    expand_matched_dirs: bool,
+
+    /// Also delete symlinks whose name matches, like `build.log -> /var/log/build.log`. Only the
+    /// link is removed, never what it points to. Listed symlinks end with `@`
+    #[arg(long, default_value_t = false, conflicts_with_all = ["empty_dirs", "pycache"])]
+    symlinks: bool,
+
+    /// Walk symlinks to directories like directories with -r/--recurse. A directory reached
+    /// again, like through a symlink loop, is skipped with a warning
+    #[arg(long, default_value_t = false, conflicts_with_all = ["empty_dirs", "pycache"])]
+    follow_symlinks: bool,
+
+    /// Do not walk into directories on another filesystem than the start path, like NFS shares
+    /// or other disks mounted below it, with -r/--recurse. Skipped mount points are listed after
+    /// the run. Unix only
+    #[arg(long, default_value_t = false, conflicts_with_all = ["empty_dirs", "pycache"])]
+    one_file_system: bool,
+
+    /// Only delete files owned by this user, given by name or uid, like on shared build
+    /// servers. Unix only
+    #[arg(long, value_name = "NAME|UID", value_parser = user_arg, conflicts_with_all = ["empty_dirs", "pycache", "files_from"])]
+    user: Option<u32>,
+
+    /// Only delete files owned by this group, given by name or gid. Unix only
+    #[arg(long, value_name = "NAME|GID", value_parser = group_arg, conflicts_with_all = ["empty_dirs", "pycache", "files_from"])]
+    group: Option<u32>,
+
+    /// Match extensions whatever their case, so that `jpg` also matches `photo.JPG` and
+    /// `photo.Jpg` (ASCII letters only)
+    #[arg(long, default_value_t = false)]
+    ignore_case: bool,
+
+    /// Match --exclude, --keep-from and --name whatever the case, like the filesystem finds
+    /// files. Detected from the start path unless given; extensions follow --ignore-case
+    #[arg(long, default_value_t = false)]
+    case_insensitive_fs: bool,
+
     /// Invert selection: keep given extensions, delete other files
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "invert")]
     invert: bool,
 
-    /// (Linux) Load a preset of extension, saved in the config file (see --config), cannot be used with other extensions
+    /// Allow -i/--invert, -r/--recurse and -f/--force together without typing the directory name
+    #[arg(long, default_value_t = false)]
+    allow_invert_recursive: bool,
+
+    /// Also clean the directories listed (one per line) in files with this name found during the
+    /// traversal, the lists themselves being kept
+    #[arg(long, value_name = "FILENAME")]
+    follow_dir_lists: Option<String>,
+
+    /// Write the files that would be deleted to FILE as JSON, with sorted paths relative to the
+    /// start path, so that plans can be compared across runs and machines
+    #[arg(long, value_name = "FILE")]
+    plan_out: Option<PathBuf>,
+
+    /// Store modification times in the plan (see --plan-out), so that it can be narrowed by age
+    /// with --simulate. Plans then differ across machines
+    #[arg(long, default_value_t = false, requires = "plan_out")]
+    plan_mtimes: bool,
+
+    /// Write the sha256, size, modification time and path of each file to delete to FILE as CSV
+    /// (`hash,size,mtime,path`), before anything is deleted. Unreadable files get an empty hash
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["empty_dirs", "pycache"])]
+    inventory: Option<PathBuf>,
+
+    /// Never collect more than N files, the first ones found in traversal order (see
+    /// --traversal). The walk stops there, and the files left are mentioned before the prompt
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["empty_dirs", "pycache", "discover", "simulate"])]
+    limit: Option<u64>,
+
+    /// Number of threads reading file metadata, like sizes for listings and totals, and hashing
+    /// files for --inventory. Defaults to the number of CPUs
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Number of files hashed in parallel for --inventory, defaults to --threads
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), requires = "inventory")]
+    inventory_jobs: Option<u64>,
+
+    /// Show what a plan written by --plan-out would delete once narrowed by the given extensions
+    /// and filters, using the plan only: the filesystem is not read nor modified
+    #[arg(long, value_name = "PLAN", conflicts_with_all = ["plan_out", "empty_dirs", "discover"])]
+    simulate: Option<PathBuf>,
+
+    /// Never delete files matching a line of FILE: a path, absolute or relative to the directory
+    /// of FILE, or a shell pattern like `*.pem` matched against names and whole paths. Blank
+    /// lines and lines starting with `#` are ignored, and lines matching no file are reported
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["empty_dirs"])]
+    keep_from: Option<PathBuf>,
+
+    /// Resolve relative lines of --keep-from and --files-from against DIR instead of the directory
+    /// of the list
+    #[arg(long, value_name = "DIR")]
+    base: Option<PathBuf>,
+
+    /// Delete the files listed in FILE, one per line, instead of collecting them: paths are
+    /// absolute or relative to the directory of FILE, and `-` reads stdin. Lines that are not an
+    /// existing file are skipped. Extensions, if any, narrow the list
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["empty_dirs", "pycache", "discover", "simulate", "rule", "regex", "prefix", "name", "paths_from_stdin"])]
+    files_from: Option<PathBuf>,
+
+    /// Write why each examined file was selected or spared to FILE, one `path<TAB>yes|no<TAB>reason`
+    /// line per file, compressed if FILE ends with .gz
+    #[arg(long, value_name = "FILE")]
+    audit: Option<PathBuf>,
+
+    /// Only delete files last modified longer ago than this (like 90m, 12h, 30d, 2w, 1y)
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    older_than: Option<Duration>,
+
+    /// Lower the age of --older-than by this duration, usually the interval between scheduled
+    /// runs, so that files that would reach it before the next run are deleted now: with 7d,
+    /// `--older-than 30d` deletes files last modified longer ago than 23 days
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg, requires = "older_than")]
+    age_slack: Option<Duration>,
+
+    /// Only delete files last modified more recently than this (like 90m, 12h, 30d, 2w, 1y), along
+    /// with --older-than for a window
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    newer_than: Option<Duration>,
+
+    /// Only delete files last modified before FILE was, like the artifacts older than a build
+    /// stamp. The time of FILE is read once, before anything is walked
+    #[arg(long, value_name = "FILE", value_parser = reference_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    older_than_file: Option<SystemTime>,
+
+    /// Only delete files last modified after FILE was, along with --older-than-file for a window
+    #[arg(long, value_name = "FILE", value_parser = reference_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    newer_than_file: Option<SystemTime>,
+
+    /// Only delete files last read longer ago than this (like 90m, 12h, 30d, 2w, 1y), for caches.
+    /// Filesystems mounted with noatime or relatime record reads late or never
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    accessed_before: Option<Duration>,
+
+    /// Only delete files of at least this size (like 500K, 10M, 1.5G), in bytes without unit
+    #[arg(long, value_name = "SIZE", value_parser = size_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    min_size: Option<u64>,
+
+    /// Only delete files of at most this size (like 500K, 10M, 1.5G), in bytes without unit
+    #[arg(long, value_name = "SIZE", value_parser = size_arg, conflicts_with_all = ["empty_dirs", "pycache"])]
+    max_size: Option<u64>,
+
+    /// Only delete empty files, like marker or lock files left by build systems
+    #[arg(long, default_value_t = false, conflicts_with_all = ["min_size", "max_size", "empty_dirs", "pycache"])]
+    empty: bool,
+
+    /// Never delete files last modified longer ago than this (like 90m, 12h, 30d, 2w, 1y),
+    /// whatever the other options
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg)]
+    never_older_than: Option<Duration>,
+
+    /// Never delete files that a symlink met during the collection points to, like the target of
+    /// `current.log -> 2024-06-01.log`. Matches are only listed once the collection is over
+    #[arg(long, default_value_t = false)]
+    preserve_link_targets: bool,
+
+    /// Shell command run once the deletion is over, with its summary in RMX_DELETED, RMX_FAILED,
+    /// RMX_BYTES, RMX_PATH and RMX_STATUS, overrides notify_cmd of the config file
+    #[arg(long, value_name = "CMD")]
+    notify_cmd: Option<String>,
+
+    /// (Linux) Clear the immutable and append-only flags (see chattr) of files that cannot be
+    /// deleted because of them, and retry once. Requires the CAP_LINUX_IMMUTABLE capability
+    #[arg(long, default_value_t = false)]
+    clear_immutable: bool,
+
+    /// Ask for confirmation for each chunk of N files, sorted by path, instead of once for all:
+    /// y deletes the chunk, n keeps it, a deletes every remaining chunk and q keeps them
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["force", "dry_run"])]
+    confirm_chunks: Option<u64>,
+
+    /// Rename each matched file by appending SUFFIX (like .trashed) instead of deleting it, so that
+    /// a later `rmx trashed` deletes them for good. Files already ending with SUFFIX are left alone
+    #[arg(long, value_name = "SUFFIX", conflicts_with_all = ["empty_dirs", "pycache"])]
+    rename_suffix: Option<String>,
+
+    /// Pick the files to delete among the matches in a terminal screen: arrows move, space
+    /// toggles a file, a toggles them all, enter goes on with the checked files and q aborts
+    #[arg(long, default_value_t = false, conflicts_with_all = ["oneline", "empty_dirs", "pycache"])]
+    select: bool,
+
+    /// Delete files directory by directory, appending each completed directory to FILE. Running
+    /// the same command line again with the same FILE resumes an interrupted run
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["dry_run", "confirm_chunks", "empty_dirs", "pycache"])]
+    checkpoint: Option<PathBuf>,
+
+    /// (Unix) Skip files another process holds a flock or fcntl lock on, like files still being
+    /// written, checking each file right before deleting it
+    #[arg(long, default_value_t = false)]
+    respect_locks: bool,
+
+    /// Once files are deleted, set the modification time of each directory where at least one
+    /// file was deleted to now, to wake up tools watching them
+    #[arg(long, default_value_t = false)]
+    touch_parents: bool,
+
+    /// Run with the lowest CPU priority and, on Linux, the idle IO priority, to keep the system
+    /// responsive during large cleanups
+    #[arg(long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "nice")]
+    nice: bool,
+
+    /// Never shorten long paths to fit in the terminal
+    #[arg(long, default_value_t = false)]
+    no_truncate: bool,
+
+    /// Only print a single summary line (plus one line per failure), for cron emails
+    #[arg(long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "oneline", conflicts_with_all = ["list", "long"])]
+    oneline: bool,
+
+    /// Only print listings, prompts, warnings and failures, without progress messages nor notes
+    #[arg(short, long, default_value_t = false, conflicts_with = "oneline")]
+    quiet: bool,
+
+    /// Also delete macOS clutter (`._*` AppleDouble files and `.DS_Store`), even without --all
+    #[arg(long, default_value_t = false, action = ArgAction::Set, num_args = 0..=1, require_equals = true, default_missing_value = "true", value_name = "BOOL", overrides_with = "apple_cruft")]
+    apple_cruft: bool,
+
+    /// For each listed .zip, .tar, .tar.gz or .tgz archive, show how many entries inside it match
+    /// --peek-ext, only reading entry names, enables --list
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "peek_ext",
+        conflicts_with = "oneline"
+    )]
+    peek_archives: bool,
+
+    /// Extensions counted inside archives by --peek-archives, like `log tmp`
+    #[arg(long, num_args(1..), value_name = "EXT")]
+    peek_ext: Vec<String>,
+
+    /// Delete empty directories instead of files, including directories holding only empty
+    /// directories with -r/--recurse, the start path being kept
+    #[arg(long, default_value_t = false, conflicts_with_all = ["extensions", "preset", "invert", "apple_cruft"])]
+    empty_dirs: bool,
+
+    /// Only count files and their size per extension, to help choosing what to delete. Never
+    /// deletes anything
+    #[arg(long, default_value_t = false, conflicts_with_all = ["extensions", "preset", "invert", "empty_dirs", "apple_cruft"])]
+    discover: bool,
+
+    /// Delete Python bytecode: `__pycache__` directories, and `.pyc` / `.pyo` files outside of
+    /// them, instead of files matching extensions
+    #[arg(long, default_value_t = false, conflicts_with_all = ["extensions", "preset", "invert", "empty_dirs", "discover", "apple_cruft"])]
+    pycache: bool,
+
+    /// With --pycache, only delete bytecode whose source file (`mod.py` for
+    /// `__pycache__/mod.cpython-311.pyc`) no longer exists, keeping `__pycache__` directories
+    #[arg(long, default_value_t = false)]
+    orphans_only: bool,
+
+    /// (Linux) Load a preset of extension, saved in the config file (see --config), can be repeated
+    /// and combined with other extensions
     #[arg(long)]
-    preset: Option<String>,
+    preset: Vec<String>,
 
     /// (Linux) Show available presets, cannot be used with other extensions
     #[arg(long)]
     presets: bool,
 
-    /// (Linux) File location for presets (see --preset/--presets)
-    #[arg(long, default_value_os_t = default_config_path())]
-    config: PathBuf,
+    /// (Linux) Run the job described in the `[job.NAME]` section of the config file, options given
+    /// alongside taking precedence
+    #[arg(long, value_name = "NAME")]
+    job: Option<String>,
+
+    /// (Linux) Show jobs defined in the config file
+    #[arg(long, conflicts_with = "job")]
+    jobs: bool,
+
+    /// Show the last N runs (10 by default) recorded in $XDG_STATE_HOME/rmx/history.jsonl, dry
+    /// runs excluded
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["job", "jobs"])]
+    history: Option<u64>,
+
+    /// Show the runs of --history as JSON, one object per line
+    #[arg(long, default_value_t = false, requires = "history")]
+    history_json: bool,
+
+    /// Print the version, then the features this rmx supports, one per line and named after the
+    /// flag they enable, for scripts to check before using them
+    #[arg(long, default_value_t = false)]
+    capabilities: bool,
+
+    /// Print --capabilities as a JSON object
+    #[arg(long, default_value_t = false, requires = "capabilities")]
+    capabilities_json: bool,
+
+    /// (Linux) Create the config file (see --config) with a few example presets, an existing file
+    /// being left untouched
+    #[arg(long)]
+    init_config: bool,
+
+    /// (Linux) File location for presets (see --preset/--presets) and settings, defaults to
+    /// $XDG_CONFIG_HOME/rmx/rmx.conf (~/.config/rmx/rmx.conf) if it exists, /etc/rmx/rmx.conf
+    /// otherwise
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Also delete files owned by users listed in protected_owners of the config file
+    #[arg(long, default_value_t = false)]
+    ignore_protected_owners: bool,
+
+    /// Keep files their owner cannot write to, which `rm` asks about before removing them. Kept
+    /// files are counted after the run
+    #[arg(long, default_value_t = false, conflicts_with_all = ["empty_dirs", "files_from"])]
+    skip_readonly: bool,
+
+    // Loaded from the config file once arguments are parsed
+    #[arg(skip)]
+    settings: Config,
+
+    // Uids resolved from protected_owners of the config file, unless ignored
+    #[arg(skip)]
+    protected_uids: Vec<u32>,
+
+    // Whether the extensions come from the job rather than from the command line
+    #[arg(skip)]
+    job_extensions: bool,
+
+    // The file given as -p/--path, deleted itself without walking anything
+    #[arg(skip)]
+    single_file: Option<PathBuf>,
+
+    // Compiled from --regex once arguments are parsed
+    #[arg(skip)]
+    compiled_regex: Option<Regex>,
+
+    // Policy files of the machine, applied once everything else is
+    #[arg(skip)]
+    policy: Policy,
+}
+
+// An empty prefix would match every file
+fn prefix_arg(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("the prefix cannot be empty".to_string());
+    }
+    Ok(s.to_string())
+}
+
+// Users and groups are resolved once, before the walk
+fn user_arg(s: &str) -> Result<u32, String> {
+    config::resolve_owner(s).ok_or_else(|| format!("unknown user \"{s}\""))
+}
+
+fn group_arg(s: &str) -> Result<u32, String> {
+    config::resolve_group(s).ok_or_else(|| format!("unknown group \"{s}\""))
 }
 
+// The whole name has to match, as with shell patterns. The pattern is first compiled as given,
+// so that errors point at the right position in it
+fn compile_regex(pattern: &str) -> Result<Regex, Box<dyn Error>> {
+    Regex::new(pattern)
+        .and_then(|_| Regex::new(&format!("^(?:{pattern})$")))
+        .map_err(|e| format!("Invalid --regex {pattern:?}:\n{e}").into())
+}
+
+fn duration_arg(token: &str) -> Result<Duration, String> {
+    parse_duration(token).map_err(|e| e.to_string())
+}
+
+// Read when arguments are parsed, so that a missing reference stops the run before the walk
+fn reference_arg(s: &str) -> Result<SystemTime, String> {
+    fs::metadata(s)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("could not read the modification time of {s}: {e}"))
+}
+
+fn size_arg(token: &str) -> Result<u64, String> {
+    parse_size(token).map_err(|e| e.to_string())
+}
+
+// Extension and age of a rule, like `log:30d`, the extension being read as on the command line
+fn rule_arg(token: &str) -> Result<(String, Duration), String> {
+    let (extension, age) = token
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid rule \"{token}\", expected EXT:AGE like log:30d"))?;
+    let extension = extension.strip_prefix('.').unwrap_or(extension);
+    if !are_extensions_valid(&[extension.to_string()]) {
+        return Err(format!(
+            "Invalid extension \"{extension}\" in rule \"{token}\""
+        ));
+    }
+    Ok((extension.to_string(), duration_arg(age)?))
+}
+
+/// What to collect, each field standing for the command line option of the same name
+///
+/// Hidden files and directories are filtered below the start path only: a hidden start path,
+/// like `./.cache`, is always walked
+///
+/// ```
+/// let mut options = rmx::CollectOptions::default();
+/// options.recurse = true;
+/// options.hidden_files = true;
+///
+/// assert!(options.include_hidden_files());
+/// assert!(!options.include_hidden_dirs());
+/// ```
+#[derive(Default)]
+#[non_exhaustive]
 pub struct CollectOptions {
+    /// Same as both hidden_files and hidden_dirs
     pub all: bool,
+    pub hidden_files: bool,
+    pub hidden_dirs: bool,
     pub list: bool,
     pub recurse: bool,
     pub invert: bool,
+    pub sort: bool,
+    pub long: bool,
+    pub apple_cruft: bool,
+    /// Width listed paths are shortened to, if any
+    pub truncate: Option<usize>,
+    pub never_older_than: Option<Duration>,
+    /// Only collect files last modified at least this long ago
+    pub older_than: Option<Duration>,
+    /// Only collect files last modified at most this long ago
+    pub newer_than: Option<Duration>,
+    /// Only collect files last read at least this long ago
+    pub accessed_before: Option<Duration>,
+    /// Only collect files last modified before this time, excluded (see --older-than-file)
+    pub modified_before: Option<SystemTime>,
+    /// Only collect files last modified after this time, excluded (see --newer-than-file)
+    pub modified_after: Option<SystemTime>,
+    /// Uids whose files are never collected (Unix only)
+    pub protected_owners: Vec<u32>,
+    /// Only collect files owned by this uid (Unix only)
+    pub user: Option<u32>,
+    /// Only collect files owned by this gid (Unix only)
+    pub group: Option<u32>,
+    /// Spare files targeted by symlinks met during the collection
+    pub preserve_link_targets: bool,
+    /// Keep files without write permission for their owner
+    pub skip_readonly: bool,
+    /// Name of the files listing extra directories to collect
+    pub follow_dir_lists: Option<String>,
+    /// Collect empty directories instead of files
+    pub empty_dirs: bool,
+    /// Collect every file, whatever its extension (see --discover)
+    pub discover: bool,
+    /// Only collect bytecode whose source no longer exists (see collect_pycache)
+    pub orphans_only: bool,
+    /// File recording the decision taken for each examined file
+    pub audit: Option<PathBuf>,
+    /// Extensions counted inside listed archives, nothing being peeked at when empty (see
+    /// --peek-archives)
+    pub peek_extensions: Vec<String>,
+    /// Files ending with this suffix are never collected, as they were renamed by an earlier run
+    /// (see Action::Rename)
+    pub renamed_suffix: Option<String>,
+    pub traversal: Traversal,
+    /// Levels walked below the start path with recurse, 1 meaning its direct entries only, no
+    /// entry being collected at 0
+    pub max_depth: Option<usize>,
+    /// Levels below the start path files have to be at least, 1 meaning every file
+    pub min_depth: Option<usize>,
+    /// Extensions paired with the age their files must have reached to be collected, a file
+    /// following the rule of the first extension it matches. Extensions must include them
+    pub age_rules: Vec<(String, Duration)>,
+    /// Shell patterns, like `data.*`, of file names never collected
+    pub exclude: Vec<String>,
+    /// Names have to match it, along with the extensions if any. It is not anchored for you:
+    /// `--regex` wraps its pattern in `^(?:...)$`
+    pub regex: Option<Regex>,
+    /// Match regex against the whole path instead of the file name
+    pub regex_path: bool,
+    /// Names have to start with one of them, along with the extensions if any
+    pub prefixes: Vec<String>,
+    /// Also match files without any extension, like `Makefile`
+    pub no_ext: bool,
+    /// Also collect directories whose name matches one of the extensions, deleted with their
+    /// content rather than walked
+    pub dirs: bool,
+    /// Walk directories matched by dirs like any other, collecting the matching files they hold
+    /// instead of the directories themselves
+    pub expand_matched_dirs: bool,
+    /// Exact names of files to collect too, hidden or not, along with the extensions if any
+    pub names: Vec<String>,
+    /// Match extensions whatever their ASCII case, `jpg` matching `JPG`
+    pub ignore_case: bool,
+    /// Match exclusions, the keep list and names whatever their case, as case insensitive
+    /// filesystems like APFS or NTFS do. Extensions follow ignore_case instead
+    pub case_insensitive_fs: bool,
+    /// File listing paths and shell patterns of files never collected, one per line, relative
+    /// paths being relative to the directory of the file
+    pub keep_from: Option<PathBuf>,
+    /// Directory relative paths of keep_from are relative to instead, if any
+    pub base: Option<PathBuf>,
+    /// Walk symlinks to directories, each directory being walked once whichever path leads to
+    /// it
+    pub follow_symlinks: bool,
+    /// Skip directories on another filesystem than the start path they are found from. Not
+    /// supported outside of Unix, where collecting fails
+    pub one_file_system: bool,
+    /// Collect symlinks whose name matches, to be removed themselves
+    pub symlinks: bool,
+    /// Sizes in bytes from which --long listing colors sizes in yellow, then in red, green being
+    /// used below. Sizes are not colored when None
+    pub size_heat: Option<[u64; 2]>,
+    /// Size in bytes files must have at least to be collected
+    pub min_size: Option<u64>,
+    /// Matches a sorted listing buffers at most, matches past it being listed unsorted as they
+    /// are found, along with a warning. Unbounded when None
+    pub sort_limit: Option<usize>,
+    /// Size in bytes files must have at most to be collected, 0 keeping empty files only (see
+    /// --empty)
+    pub max_size: Option<u64>,
+    /// Threads reading the sizes of listed matches, 0 being taken as 1
+    pub threads: usize,
+    /// Number of files collected at most, in traversal order, the walk stopping past it
+    pub limit: Option<usize>,
 }
 
+impl CollectOptions {
+    pub fn include_hidden_files(&self) -> bool {
+        self.all || self.hidden_files
+    }
+
+    pub fn include_hidden_dirs(&self) -> bool {
+        self.all || self.hidden_dirs
+    }
+
+    /// Whether the listing has to wait for the whole collection instead of streaming matches
+    pub fn buffered_listing(&self) -> bool {
+        self.sort || self.long || self.preserve_link_targets
+    }
+}
+
+/// Order in which directories are walked. Depth-first only keeps the pending siblings of the
+/// current directory and of its parents, while breadth-first keeps a whole level of the tree,
+/// which grows large in wide trees, but finds shallow files first
+///
+/// ```
+/// let mut options = rmx::CollectOptions::default();
+/// options.recurse = true;
+/// options.traversal = rmx::Traversal::Bfs;
+/// # let _ = options;
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Traversal {
+    /// Each subdirectory is walked entirely before the next one
+    #[default]
+    Dfs,
+    /// Every directory of a level is walked before the next level
+    Bfs,
+}
+
+/// What happens to collected files
+///
+/// ```
+/// let mut options = rmx::DeleteOptions::default();
+/// options.action = rmx::Action::Rename(".trashed".to_string());
+/// # let _ = options;
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Action {
+    #[default]
+    Delete,
+    /// Rename `foo.log` to `foo.log<suffix>` in place, or to `foo.log.1<suffix>` and so on when
+    /// the name is taken
+    Rename(String),
+}
+
+/// Answer of `DeleteOptions::veto` for a file about to be deleted
+///
+/// ```
+/// use rmx::Veto;
+///
+/// let mut options = rmx::DeleteOptions::default();
+/// options.veto = Some(Box::new(|path| match path.extension() {
+///     Some(ext) if ext == "db" => Veto::Skip("still in use".to_string()),
+///     _ => Veto::Allow,
+/// }));
+/// # let _ = options;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Veto {
+    Allow,
+    /// Keep the file, the reason being recorded in `DeleteReport::vetoed`
+    Skip(String),
+}
+
+/// Callback deciding whether a file may be deleted, see `DeleteOptions::veto`
+pub type VetoFn = Box<dyn Fn(&Path) -> Veto>;
+
+/// How to delete collected files, each field standing for the command line option of the same
+/// name
+///
+/// ```
+/// let mut options = rmx::DeleteOptions::default();
+/// options.force = true;
+/// # let _ = options;
+/// ```
+#[derive(Default)]
+#[non_exhaustive]
 pub struct DeleteOptions {
     pub force: bool,
     pub dry_run: bool,
+    /// Do not print progress messages, like "Deleting files..." or "No matching file."
+    pub quiet: bool,
+    /// Clear immutable and append-only flags preventing a deletion, and retry it once
+    pub clear_immutable: bool,
+    /// Skip files another process holds an advisory lock on (Unix only)
+    pub respect_locks: bool,
+    /// Ask for each chunk of this many files instead of once for all, unless force or dry_run
+    /// is set
+    pub confirm_chunks: Option<usize>,
+    /// Threads reading the sizes of the files to delete before counting them, 0 being taken as 1
+    pub threads: usize,
+    pub action: Action,
+    /// Asked for each file right before it is deleted or renamed, and during a dry run, to
+    /// keep it anyway. Not available from the command line
+    pub veto: Option<VetoFn>,
 }
 
 impl Args {
     // After parse is called, .path and .extensions can be safely called
     pub fn parse() -> Result<Option<Args>, Box<dyn Error>> {
-        let args = match Args::try_parse() {
-            Ok(args) => Some(args),
+        Args::parse_from(std::env::args_os())
+    }
+
+    // Same as parse, from the given arguments, the first one being the name of the binary
+    pub fn parse_from<I, T>(argv: I) -> Result<Option<Args>, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        Args::parse_with_policy(argv, &policy::sources())
+    }
+
+    // Same as parse_from, the policy being read from `policy` rather than from the machine
+    pub(crate) fn parse_with_policy<I, T>(
+        argv: I,
+        policy: &[PolicySource],
+    ) -> Result<Option<Args>, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+        // Kept to tell flags given on the command line from defaults
+        let matches = match Args::command().try_get_matches_from(&argv) {
+            Ok(matches) => Some(matches),
             Err(err) => {
                 if err.kind() == ClapErrorKind::DisplayHelp
                     || err.kind() == ClapErrorKind::DisplayVersion
                 {
                     err.print()?;
                     return Ok(None);
+                } else if err.kind() == ClapErrorKind::MissingRequiredArgument
+                    && let Some(matches) = parse_single_file(&argv)
+                {
+                    Some(matches?)
+                } else if err.kind() == ClapErrorKind::MissingRequiredArgument
+                    && let Some(hint) = swallowed_extension_hint(&given_paths(&argv))
+                {
+                    return Err(format!("{}\n{hint}", err.render().to_string().trim_end()).into());
                 } else {
                     return Err(err.into());
                 }
             }
         };
 
-        let Some(mut args) = args else {
+        let Some(matches) = matches else {
             return Ok(None);
         };
+        let mut args = Args::from_arg_matches(&matches)?;
+        // Before reading the config file, which may warn already
+        warning::set_strict(args.strict);
 
-        args.path = match &args.path {
-            Some(p) => Some(p.clone()),
-            None => Some(std::env::current_dir()?),
-        };
+        // The start path is only taken for granted when not given at all
+        if args.path.is_empty()
+            && args.job.is_none()
+            && !args.paths_from_stdin
+            && args.files_from.is_none()
+            && let Some(hint) = directory_as_extension_hint(&args.extensions)
+        {
+            warning::warn(hint);
+        }
+
+        if args.capabilities {
+            let version = env!("CARGO_PKG_VERSION");
+            if args.capabilities_json {
+                println!("{}", capabilities::to_json(version));
+            } else {
+                for line in capabilities::format_lines(version) {
+                    println!("{line}");
+                }
+            }
+            return Ok(None);
+        }
+
+        if args.init_config {
+            let path = args.config_to_create()?;
+            config::init_config(&path)?;
+            println!("Created {}", path.display());
+            return Ok(None);
+        }
+
+        if args.presets || !args.preset.is_empty() {
+            args.offer_config_creation()?;
+        }
 
         if args.presets {
-            preset::show(&args.config)?;
+            preset::show(&args.config_path())?;
+            return Ok(None);
+        }
+
+        args.settings = Config::load(&args.config_path())?;
+        if !args.preset.is_empty() {
+            let path = args.config_path();
+            for reason in preset::invalid_lines(&path) {
+                warning::warn(format!("{reason} in {}, ignoring it", path.display()));
+            }
+        }
+        if !args.ignore_protected_owners {
+            args.protected_uids = args.settings.protected_uids();
+        }
+
+        if args.jobs {
+            args.show_jobs();
             return Ok(None);
         }
 
-        if args.dry_run {
+        if let Some(n) = args.history {
+            args.show_history(n as usize)?;
+            return Ok(None);
+        }
+
+        if let Some(name) = &args.job {
+            let Some(job) = args.settings.find_job(name).cloned() else {
+                return Err(format!(
+                    "Could not find job \"{}\" in {}",
+                    name,
+                    args.config_path().display()
+                )
+                .into());
+            };
+            args.apply_job(&job, &matches)?;
+        }
+
+        if args.paths_from_stdin {
+            if !args.force && !args.dry_run {
+                return Err(
+                    "--paths-from-stdin requires -f/--force or -n/--dry-run, as the prompt cannot read stdin."
+                        .into(),
+                );
+            }
+            let read = read_paths(io::stdin().lock())?;
+            if read.is_empty() && args.path.is_empty() {
+                return Err("No directory was read from stdin.".into());
+            }
+            args.path.extend(read);
+        }
+        if args.path.is_empty() {
+            args.path.push(std::env::current_dir()?);
+        }
+        // Once flags, jobs and settings are, so that none of them can get around it
+        args.policy = Policy::load_from(policy)?;
+        args.apply_policy()?;
+        args.single_file = single_file(&args)?;
+        if args.no_ext_check && args.single_file.is_none() {
+            return Err("--no-ext-check requires -p/--path to be a file.".into());
+        }
+        // Plans and discovered extensions are about a single tree
+        if args.path.len() > 1 {
+            if args.plan_out.is_some() {
+                return Err(
+                    "--plan-out takes a single -p/--path, plan paths being relative to it.".into(),
+                );
+            }
+            if args.discover {
+                return Err("--discover takes a single -p/--path.".into());
+            }
+        }
+
+        // Only probed when names are compared
+        if !args.case_insensitive_fs
+            && (!args.exclude.is_empty() || args.keep_from.is_some() || !args.name.is_empty())
+        {
+            args.case_insensitive_fs = args.path.iter().any(|p| case::is_case_insensitive(p));
+        }
+
+        if args.invert
+            && args.extensions.is_empty()
+            && args.preset.is_empty()
+            && args.regex.is_none()
+            && args.prefix.is_empty()
+            && !args.no_ext
+        {
+            // Would otherwise select every file
+            return Err(
+                "--invert requires extensions, --no-ext, --regex or --prefix to keep.".into(),
+            );
+        }
+
+        if args.dirs && args.extensions.is_empty() && args.preset.is_empty() {
+            // Directories are only ever matched by extension
+            return Err("--dirs requires extensions to match directories with.".into());
+        }
+
+        // Boolean flags always have a value for clap, so that `requires` cannot point to them
+        if args.expand_matched_dirs && !args.dirs {
+            return Err("--expand-matched-dirs requires --dirs.".into());
+        }
+
+        // Boolean flags always have a value for clap, so that `requires` cannot point to them
+        if !args.peek_ext.is_empty() && !args.peek_archives {
+            return Err("--peek-ext requires --peek-archives.".into());
+        }
+        if args.base.is_some() && args.keep_from.is_none() && args.files_from.is_none() {
+            return Err("--base requires --keep-from or --files-from.".into());
+        }
+        if args.files_from.as_deref() == Some(Path::new("-")) && !args.force && !args.dry_run {
+            return Err(
+                "--files-from - requires -f/--force or -n/--dry-run, as the prompt cannot read stdin."
+                    .into(),
+            );
+        }
+        if cfg!(not(unix)) && args.one_file_system {
+            return Err("--one-file-system is only supported on Unix.".into());
+        }
+        if cfg!(not(unix)) && (args.user.is_some() || args.group.is_some()) {
+            return Err("--user and --group are only supported on Unix.".into());
+        }
+        if args.orphans_only && !args.pycache {
+            return Err("--orphans-only requires --pycache.".into());
+        }
+
+        args.recurse |= args.max_depth.is_some() || args.min_depth.is_some();
+        if let (Some(min), Some(max)) = (args.min_depth, args.max_depth)
+            && min > max
+        {
+            return Err(format!(
+                "--min-depth {min} is greater than --max-depth {max}, nothing would be collected."
+            )
+            .into());
+        }
+
+        if let (Some(older), Some(slack)) = (args.older_than, args.age_slack)
+            && slack >= older
+        {
+            return Err(format!(
+                "--age-slack {} is not shorter than --older-than {}, every file would be collected.",
+                duration::format_duration(slack),
+                duration::format_duration(older)
+            )
+            .into());
+        }
+
+        if let (Some(older), Some(newer)) = (args.older_than, args.newer_than)
+            && newer <= args.cutoff_age(older)
+        {
+            let older = match args.age_slack {
+                Some(slack) => format!(
+                    "{} minus --age-slack {}",
+                    duration::format_duration(older),
+                    duration::format_duration(slack)
+                ),
+                None => duration::format_duration(older),
+            };
+            return Err(format!(
+                "--newer-than {} is not longer than --older-than {older}, nothing would be \
+                collected.",
+                duration::format_duration(newer)
+            )
+            .into());
+        }
+
+        if let (Some(before), Some(after)) = (args.older_than_file, args.newer_than_file)
+            && after >= before
+        {
+            return Err(
+                "The file of --newer-than-file was not modified before the one of \
+                --older-than-file, nothing would be collected."
+                    .into(),
+            );
+        }
+
+        if let (Some(min), Some(max)) = (args.min_size, args.max_size)
+            && min > max
+        {
+            return Err(format!(
+                "--min-size {} is greater than --max-size {}, nothing would be collected.",
+                report::format_size(min),
+                report::format_size(max)
+            )
+            .into());
+        }
+
+        if let Some(pattern) = &args.regex {
+            args.compiled_regex = Some(compile_regex(pattern)?);
+        }
+
+        if !are_extensions_valid(&args.peek_ext) {
+            return Err("Invalid extensions for --peek-ext.".into());
+        }
+        // A valid extension once renamed, so that renamed files can be matched by a later run
+        if let Some(suffix) = &args.rename_suffix
+            && !suffix
+                .strip_prefix('.')
+                .is_some_and(|e| are_extensions_valid(&[e.to_string()]))
+        {
+            return Err(
+                "Invalid suffix for --rename-suffix, expected a dot followed by an extension, like .trashed."
+                    .into(),
+            );
+        }
+
+        if (args.dry_run || args.long || args.peek_archives) && !args.oneline {
             args.list = true;
         }
 
         Ok(Some(args))
     }
 
-    pub fn get_path(&self) -> Result<PathBuf, Box<dyn Error>> {
-        Ok(match &self.path {
-            Some(p) => p.clone(),
-            None => std::env::current_dir()?,
-        })
+    // Options of the job fill in what the command line leaves unset: given extensions or presets
+    // replace those of the job, and flags of the job cannot be turned off
+    fn apply_job(&mut self, job: &Job, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+        if self.path.is_empty() {
+            self.path.extend(job.path.clone());
+        }
+        if self.extensions.is_empty() && self.preset.is_empty() && self.rule.is_empty() {
+            self.extensions = job.extensions.clone();
+            self.preset = job.preset.clone();
+            self.job_extensions = true;
+        }
+
+        // Flags of the job are defaults, which flags given on the command line override, like
+        // --force=false
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        for (id, flag, value) in [
+            ("recurse", &mut self.recurse, job.recurse),
+            ("force", &mut self.force, job.force),
+            ("dry_run", &mut self.dry_run, job.dry_run),
+            ("invert", &mut self.invert, job.invert),
+            ("all", &mut self.all, job.all),
+            ("hidden_files", &mut self.hidden_files, job.hidden_files),
+            ("hidden_dirs", &mut self.hidden_dirs, job.hidden_dirs),
+            ("apple_cruft", &mut self.apple_cruft, job.apple_cruft),
+            ("nice", &mut self.nice, job.nice),
+            ("oneline", &mut self.oneline, job.oneline),
+        ] {
+            if !given(id) {
+                *flag = value;
+            }
+        }
+
+        self.never_older_than = self.never_older_than.or(job.never_older_than);
+        self.older_than = self.older_than.or(job.older_than);
+        self.newer_than = self.newer_than.or(job.newer_than);
+        self.min_size = self.min_size.or(job.min_size);
+        self.max_size = self.max_size.or(job.max_size);
+        if self.older_than_file.is_none()
+            && let Some(file) = &job.older_than_file
+        {
+            self.older_than_file = Some(reference_arg(&file.to_string_lossy())?);
+        }
+        if self.newer_than_file.is_none()
+            && let Some(file) = &job.newer_than_file
+        {
+            self.newer_than_file = Some(reference_arg(&file.to_string_lossy())?);
+        }
+        if self.follow_dir_lists.is_none() {
+            self.follow_dir_lists = job.follow_dir_lists.clone();
+        }
+        if self.audit.is_none() {
+            self.audit = job.audit.clone();
+        }
+        Ok(())
     }
 
-    fn raw_get_extensions(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        if let Some(p) = &self.preset {
-            return preset::parse(p, &self.config);
+    fn apply_policy(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.dry_run
+            && let Some((root, (file, dir))) = self
+                .path
+                .iter()
+                .find_map(|root| Some((root, self.policy.dry_run_rule(root)?)))
+        {
+            eprintln!(
+                "Note: dry run only, as {} is under {}, a dry_run path of {}.",
+                root.display(),
+                dir.display(),
+                file.path.display()
+            );
+            self.dry_run = true;
         }
+        self.policy
+            .check_flags(self.invert, self.dry_run, self.rename_suffix.is_some())
+    }
 
-        Ok(self.extensions.clone())
+    // Files about to be deleted, or renamed, against the policy of the machine
+    pub(crate) fn check_policy(&self, candidates: &[Candidate]) -> Result<(), Box<dyn Error>> {
+        if self.dry_run {
+            return Ok(());
+        }
+        self.policy.check_deletion(candidates)
     }
 
-    pub fn get_extensions(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        let extensions = self.raw_get_extensions()?;
-        if !are_extensions_valid(&extensions) {
-            return Err("Invalid extensions.".into());
+    fn show_jobs(&self) {
+        let path = self.config_path();
+        println!("Parsing jobs in {}...", path.display());
+
+        for line in config::format_jobs(&self.settings.jobs) {
+            println!("{line}");
+        }
+
+        if self.settings.jobs.is_empty() {
+            println!("Could not find any job in {}", path.display());
+        }
+    }
+
+    // The given config file, or the one of the user
+    fn config_to_create(&self) -> Result<PathBuf, Box<dyn Error>> {
+        match &self.config {
+            Some(path) => Ok(path.clone()),
+            None => config::user_config_path().ok_or_else(|| {
+                "Could not find the user configuration directory, pass --config.".into()
+            }),
+        }
+    }
+
+    // Presets cannot work without a config file, which a new user does not have yet
+    fn offer_config_creation(&self) -> Result<(), Box<dyn Error>> {
+        if self.config.is_some() || self.config_path().exists() {
+            return Ok(());
+        }
+        let Some(path) = config::user_config_path() else {
+            return Ok(());
+        };
+
+        eprintln!(
+            "No configuration file found, presets are read from {} or {}.",
+            path.display(),
+            config::default_config_path().display()
+        );
+        if !io::stdin().is_terminal() {
+            eprintln!("Run `rmx --init-config` to create one with a few example presets.");
+            return Ok(());
+        }
+
+        print!(
+            "Create {} with a few example presets? [Y/n] ",
+            path.display()
+        );
+        io::stdout().flush()?;
+        let mut buf = String::new();
+        io::stdin().read_line(&mut buf)?;
+        if matches!(buf.trim(), "" | "y" | "Y") {
+            config::init_config(&path)?;
+            println!("Created {}", path.display());
+        }
+        Ok(())
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        config::resolve_config_path(self.config.as_deref())
+    }
+
+    // Directories to clean, in the order they were given, the current one when none was
+    pub fn get_paths(&self) -> Vec<PathBuf> {
+        self.path.clone()
+    }
+
+    // Deleting everything but a few extensions across a whole tree without any prompt is the
+    // most destructive combination, so it has to be acknowledged
+    pub fn needs_invert_recursive_ack(&self) -> bool {
+        self.invert
+            && self.recurse
+            && self.force
+            && !self.dry_run
+            && !self.allow_invert_recursive
+            && !self.settings.allow_invert_recursive
+    }
+
+    // Width messages meant to be read by a human are fitted to
+    pub fn human_width(&self) -> Option<usize> {
+        if self.no_truncate {
+            return None;
+        }
+        Some(display::output_width())
+    }
+
+    // Age of --older-than once lowered by --age-slack, checked to be shorter beforehand
+    fn cutoff_age(&self, older: Duration) -> Duration {
+        older - self.age_slack.unwrap_or_default()
+    }
+
+    // Thresholds of the colors of --long sizes, if colored (see --color)
+    fn size_heat(&self) -> Option<[u64; 2]> {
+        let colored = match self.color.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => io::stdout().is_terminal(),
+        };
+        colored.then(|| self.settings.size_heat.unwrap_or(DEFAULT_SIZE_HEAT))
+    }
+
+    pub fn oneline(&self) -> bool {
+        self.oneline
+    }
+
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    // Command to run after the deletion, if any. Dry runs only notify when configured to
+    pub fn notify_cmd(&self) -> Option<&str> {
+        if self.dry_run && !self.settings.notify_on_dry_run {
+            return None;
+        }
+        self.notify_cmd
+            .as_deref()
+            .or(self.settings.notify_cmd.as_deref())
+    }
+
+    fn show_history(&self, n: usize) -> Result<(), Box<dyn Error>> {
+        let path = history::history_path()
+            .ok_or("Could not find the user state directory, set XDG_STATE_HOME.")?;
+        let records = history::read_last(&path, n)?;
+
+        if self.history_json {
+            for record in &records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+        } else if records.is_empty() {
+            println!("No run recorded in {}", path.display());
+        } else {
+            for line in history::format_table(&records) {
+                println!("{line}");
+            }
+        }
+        Ok(())
+    }
+
+    // Runs kept in the history file, none being recorded at 0
+    pub fn suggestions(&self) -> bool {
+        self.settings.suggestions.unwrap_or(true)
+    }
+
+    // Extensions typed on the command line, sorted, none when a preset or a job gave them
+    pub fn ad_hoc_extensions(&self, extensions: &[String]) -> Vec<String> {
+        if !self.preset.is_empty() || self.job.is_some() || self.extensions.is_empty() {
+            return Vec::new();
+        }
+        let mut extensions = extensions.to_vec();
+        extensions.sort();
+        extensions.dedup();
+        extensions
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.settings
+            .history_limit
+            .unwrap_or(history::DEFAULT_LIMIT)
+    }
+
+    pub fn notify_timeout(&self) -> Duration {
+        self.settings
+            .notify_timeout
+            .unwrap_or(hook::DEFAULT_TIMEOUT)
+    }
+
+    pub fn plan_out(&self) -> Option<(&Path, bool)> {
+        self.plan_out.as_deref().map(|p| (p, self.plan_mtimes))
+    }
+
+    // Whether the run prints JSON, fatal errors then being printed as JSON too
+    pub fn json_output(&self) -> bool {
+        self.history_json || self.capabilities_json || self.plan_out.is_some()
+    }
+
+    // Same as json_output, from the command line read leniently, before or when parsing fails
+    pub fn json_requested(argv: &[OsString]) -> bool {
+        Args::command()
+            .ignore_errors(true)
+            .try_get_matches_from(argv)
+            .is_ok_and(|matches| {
+                matches.get_flag("history_json")
+                    || matches.get_flag("capabilities_json")
+                    || matches.contains_id("plan_out")
+            })
+    }
+
+    // Where to write the inventory, and how many files to hash at once
+    pub fn inventory(&self) -> Option<(&Path, usize)> {
+        let jobs = self
+            .inventory_jobs
+            .map(|n| n as usize)
+            .unwrap_or_else(|| self.threads());
+        self.inventory.as_deref().map(|p| (p, jobs))
+    }
+
+    fn threads(&self) -> usize {
+        self.threads.map(|n| n as usize).unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+    }
+
+    pub fn select(&self) -> bool {
+        self.select
+    }
+
+    pub fn checkpoint(&self) -> Option<&Path> {
+        self.checkpoint.as_deref()
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit.map(|n| n as usize)
+    }
+
+    // The file given as -p/--path, deleted itself rather than collected from
+    pub fn single_file(&self) -> Option<&Path> {
+        self.single_file.as_deref()
+    }
+
+    pub fn no_ext_check(&self) -> bool {
+        self.no_ext_check
+    }
+
+    pub fn files_from(&self) -> Option<&Path> {
+        self.files_from.as_deref()
+    }
+
+    pub fn simulate(&self) -> Option<&Path> {
+        self.simulate.as_deref()
+    }
+
+    pub fn pycache(&self) -> bool {
+        self.pycache
+    }
+
+    pub fn discover(&self) -> bool {
+        self.discover
+    }
+
+    pub fn nice(&self) -> bool {
+        self.nice
+    }
+
+    pub fn touch_parents(&self) -> bool {
+        self.touch_parents
+    }
+
+    // Requested extensions with where they come from, presets first in the given order. Values
+    // of presets and of the command line are normalized and validated alike
+    fn raw_get_extensions(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let path = self.config_path();
+        let mut extensions = Vec::new();
+        for p in &self.preset {
+            let source = format!("preset '{p}'");
+            let (preset, line) = preset::load_preset_with_line(p, &path)?;
+            for raw in &preset.extensions {
+                let origin = || ExtensionOrigin::Preset {
+                    name: p.clone(),
+                    path: path.clone(),
+                    line,
+                };
+                for e in normalize_extension(raw, origin)? {
+                    extensions.push((e, source.clone()));
+                }
+            }
+        }
+        for (e, age) in &self.rule {
+            let source = format!("rule '{e}:{}'", duration::format_duration(*age));
+            extensions.push((e.clone(), source));
+        }
+        for (i, raw) in self.extensions.iter().enumerate() {
+            let origin = || match (&self.job, self.job_extensions) {
+                (Some(name), true) => ExtensionOrigin::Job {
+                    name: name.clone(),
+                    path: path.clone(),
+                },
+                _ => ExtensionOrigin::CommandLine { position: i + 1 },
+            };
+            for e in normalize_extension(raw, origin)? {
+                extensions.push((e, "CLI".to_string()));
+            }
         }
         Ok(extensions)
     }
 
+    // Includes aliases of the requested extensions (see `alias` in the config file), along with
+    // the source of each extension, aliases being attributed to the extension they come from.
+    // An extension requested twice keeps its first source
+    pub fn get_extensions_with_sources(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut extensions: Vec<(String, String)> = Vec::new();
+        for (requested, source) in self.raw_get_extensions()? {
+            for e in
+                config::expand_aliases(std::slice::from_ref(&requested), &self.settings.aliases)
+            {
+                // Requested extensions are valid by now, unlike aliases of the config file
+                if !are_extensions_valid(std::slice::from_ref(&e)) {
+                    return Err(RmxError::InvalidExtension {
+                        value: e,
+                        origin: ExtensionOrigin::Alias {
+                            extension: requested,
+                            path: self.config_path(),
+                        },
+                    }
+                    .into());
+                }
+                if !extensions.iter().any(|(known, _)| *known == e) {
+                    extensions.push((e, source.clone()));
+                }
+            }
+        }
+        Ok(extensions)
+    }
+
+    // Aliases of an extension follow its rule (see `alias` in the config file)
+    fn age_rules(&self) -> Vec<(String, Duration)> {
+        let mut rules: Vec<(String, Duration)> = Vec::new();
+        for (requested, age) in &self.rule {
+            for e in config::expand_aliases(std::slice::from_ref(requested), &self.settings.aliases)
+            {
+                if !rules.iter().any(|(known, _)| *known == e) {
+                    rules.push((e, *age));
+                }
+            }
+        }
+        rules
+    }
+
+    pub fn get_extensions(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .get_extensions_with_sources()?
+            .into_iter()
+            .map(|(e, _)| e)
+            .collect())
+    }
+
     pub fn get_options(&self) -> (CollectOptions, DeleteOptions) {
         (
             CollectOptions {
                 all: self.all,
+                hidden_files: self.hidden_files,
+                hidden_dirs: self.hidden_dirs,
                 list: self.list,
                 recurse: self.recurse,
                 invert: self.invert,
+                sort: self.sort,
+                long: self.long,
+                apple_cruft: self.apple_cruft,
+                // A listing written to a file or a pipe is data, and is kept intact
+                truncate: self.human_width().filter(|_| io::stdout().is_terminal()),
+                never_older_than: self.never_older_than,
+                older_than: self.older_than.map(|older| self.cutoff_age(older)),
+                newer_than: self.newer_than,
+                accessed_before: self.accessed_before,
+                modified_before: self.older_than_file,
+                modified_after: self.newer_than_file,
+                protected_owners: self.protected_uids.clone(),
+                user: self.user,
+                group: self.group,
+                preserve_link_targets: self.preserve_link_targets,
+                skip_readonly: self.skip_readonly,
+                follow_dir_lists: self.follow_dir_lists.clone(),
+                empty_dirs: self.empty_dirs,
+                discover: self.discover,
+                orphans_only: self.orphans_only,
+                audit: self.audit.clone(),
+                peek_extensions: self.peek_ext.clone(),
+                renamed_suffix: self.rename_suffix.clone(),
+                max_depth: self.max_depth.map(|n| n as usize),
+                min_depth: self.min_depth.map(|n| n as usize),
+                exclude: self.exclude.clone(),
+                age_rules: self.age_rules(),
+                regex: self.compiled_regex.clone(),
+                regex_path: self.regex_path,
+                prefixes: self.prefix.clone(),
+                dirs: self.dirs,
+                expand_matched_dirs: self.expand_matched_dirs,
+                no_ext: self.no_ext,
+                names: self.name.clone(),
+                ignore_case: self.ignore_case,
+                case_insensitive_fs: self.case_insensitive_fs,
+                keep_from: self.keep_from.clone(),
+                base: self.base.clone(),
+                follow_symlinks: self.follow_symlinks,
+                one_file_system: self.one_file_system,
+                symlinks: self.symlinks,
+                size_heat: self.size_heat(),
+                min_size: self.min_size,
+                sort_limit: Some(self.settings.sort_limit.unwrap_or(DEFAULT_SORT_LIMIT)),
+                max_size: if self.empty { Some(0) } else { self.max_size },
+                threads: self.threads(),
+                limit: self.limit(),
+                traversal: match self.traversal.as_str() {
+                    "bfs" => Traversal::Bfs,
+                    _ => Traversal::Dfs,
+                },
             },
             DeleteOptions {
                 force: self.force,
                 dry_run: self.dry_run,
+                quiet: self.quiet || self.oneline,
+                clear_immutable: self.clear_immutable,
+                respect_locks: self.respect_locks,
+                confirm_chunks: self.confirm_chunks.map(|n| n as usize),
+                threads: self.threads(),
+                action: match &self.rename_suffix {
+                    Some(suffix) => Action::Rename(suffix.clone()),
+                    None => Action::Delete,
+                },
+                veto: None,
             },
         )
     }
 }
 
-fn are_extensions_valid(extensions: &[String]) -> bool {
-    let re = Regex::new(r"^[0-9A-Za-z_-]+(?:.[0-9A-Za-z_-]+)*$").unwrap();
+// Applied to every requested extension, whether from the command line or from a preset: `.log`
+// is `log`, and `log,tmp` is `log` and `tmp`. Case is kept, as matching is case sensitive
+fn normalize_extension(
+    raw: &str,
+    origin: impl Fn() -> ExtensionOrigin,
+) -> Result<Vec<String>, RmxError> {
+    raw.split(',')
+        .map(|part| {
+            let e = part.strip_prefix('.').unwrap_or(part).to_string();
+            if are_extensions_valid(std::slice::from_ref(&e)) {
+                Ok(e)
+            } else {
+                Err(RmxError::InvalidExtension {
+                    value: part.to_string(),
+                    origin: origin(),
+                })
+            }
+        })
+        .collect()
+}
+
+// Directories of --paths-from-stdin, one per line. Lines that are not a directory are skipped
+// with a warning, so that a stale entry of a generated list does not stop the others
+fn read_paths(input: impl BufRead) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        if path.is_dir() {
+            paths.push(path);
+        } else if path.exists() {
+            warning::warn(format!(
+                "{line} read from stdin is not a directory, skipping it"
+            ));
+        } else {
+            warning::warn(format!(
+                "{line} read from stdin does not exist, skipping it"
+            ));
+        }
+    }
+    Ok(paths)
+}
+
+// -p/--path values given on the command line, read again leniently as parsing them failed
+fn given_paths(argv: &[OsString]) -> Vec<PathBuf> {
+    Args::command()
+        .ignore_errors(true)
+        .try_get_matches_from(argv)
+        .ok()
+        .and_then(|matches| {
+            matches
+                .get_many::<PathBuf>("path")
+                .map(|paths| paths.cloned().collect())
+        })
+        .unwrap_or_default()
+}
+
+// Links are not files to delete themselves, being collected from like directories
+fn is_regular_file(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.is_file())
+}
+
+// A file given alone as -p/--path needs no extension, which clap cannot tell. None when parsing
+// failed for another reason
+fn parse_single_file(argv: &[OsString]) -> Option<Result<ArgMatches, clap::Error>> {
+    let [path] = given_paths(argv).try_into().ok()?;
+    if !is_regular_file(&path) {
+        return None;
+    }
+    let matches = Args::command()
+        .mut_arg("extensions", |arg| {
+            arg.required_unless_present(Resettable::Reset)
+        })
+        .try_get_matches_from(argv);
+    Some(matches)
+}
+
+// The file given as -p/--path, which only comes alone and without the modes walking a tree
+fn single_file(args: &Args) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let Some(file) = args.path.iter().find(|p| is_regular_file(p)) else {
+        return Ok(None);
+    };
+    if args.path.len() > 1 {
+        return Err(format!(
+            "{} is a file, which -p/--path only takes alone.",
+            file.display()
+        )
+        .into());
+    }
+    let modes = [
+        ("--empty-dirs", args.empty_dirs),
+        ("--pycache", args.pycache),
+        ("--discover", args.discover),
+        ("--simulate", args.simulate.is_some()),
+        ("--files-from", args.files_from.is_some()),
+        ("--plan-out", args.plan_out.is_some()),
+    ];
+    if let Some((flag, _)) = modes.iter().find(|(_, given)| *given) {
+        return Err(format!("{flag} does not work with a file as -p/--path.").into());
+    }
+    Ok(Some(file.clone()))
+}
+
+// Short and plain enough to be an extension rather than a directory name, like `txt` or `tar.gz`
+fn looks_like_extension(value: &str) -> bool {
+    (1..=10).contains(&value.len())
+        && value
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+// `rmx -p txt` takes `txt` as the start path, leaving no extension. Only a path that does not
+// exist is suspected
+fn swallowed_extension_hint(paths: &[PathBuf]) -> Option<String> {
+    paths.iter().find_map(|path| {
+        let value = path.to_str()?;
+        (looks_like_extension(value) && !path.exists()).then(|| {
+            format!("Hint: \"{value}\" was taken as the path, did you mean `rmx {value} -p <DIR>`?")
+        })
+    })
+}
+
+// `rmx build` takes the `build` directory as an extension, collecting `*.build` files from the
+// current directory
+fn directory_as_extension_hint(extensions: &[String]) -> Option<String> {
+    extensions
+        .iter()
+        .find(|entry| Path::new(entry).is_dir())
+        .map(|entry| {
+            format!("\"{entry}\" is a directory but was taken as an extension, did you mean `rmx <EXT> -p {entry}`?")
+        })
+}
+
+/// Extensions are made of ASCII alphanumerics, `_` and `-`, with dots only between parts
+///
+/// ```
+/// assert!(rmx::are_extensions_valid(&["tar.gz".to_string(), "R".to_string()]));
+/// assert!(!rmx::are_extensions_valid(&[".log".to_string()]));
+/// ```
+pub fn are_extensions_valid(extensions: &[String]) -> bool {
+    let re = Regex::new(r"^[0-9A-Za-z_-]+(?:\.[0-9A-Za-z_-]+)*$").unwrap();
     extensions.iter().all(|ext| re.is_match(ext))
 }
 
 #[cfg(test)]
+// The extension checks build their vectors one push at a time, as they always have
+#[allow(clippy::vec_init_then_push)]
 mod test {
     use super::*;
 
+    #[test]
+    fn paths_read_from_stdin() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        std::fs::create_dir(&a)?;
+        std::fs::create_dir(&b)?;
+        std::fs::File::create(dir.path().join("file.log"))?;
+
+        let input = format!(
+            "{}\n\n{}\r\n{}\n{}\n",
+            a.display(),
+            dir.path().join("missing").display(),
+            dir.path().join("file.log").display(),
+            b.display()
+        );
+        // Missing entries and files are skipped
+        assert_eq!(vec![a, b], read_paths(io::Cursor::new(input))?);
+        assert!(read_paths(io::Cursor::new(""))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn single_file_needs_no_extension() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("core.12345");
+        std::fs::File::create(&file)?;
+
+        let args =
+            Args::parse_from([OsString::from("rmx"), "-p".into(), file.clone().into()])?.unwrap();
+        assert_eq!(Some(file.as_path()), args.single_file());
+
+        // A directory still needs extensions
+        let dir_arg = dir.path().as_os_str().to_owned();
+        assert!(Args::parse_from([OsString::from("rmx"), "-p".into(), dir_arg]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn plausible_extensions() {
+        assert!(looks_like_extension("txt"));
+        assert!(looks_like_extension("tar.gz"));
+        assert!(looks_like_extension("R"));
+        assert!(!looks_like_extension(""));
+        assert!(!looks_like_extension("src/logs"));
+        assert!(!looks_like_extension("../logs"));
+        assert!(!looks_like_extension(".log"));
+        assert!(!looks_like_extension("my-project"));
+        assert!(!looks_like_extension("averyverylongname"));
+    }
+
+    #[test]
+    fn hint_for_swallowed_extension() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let existing = dir.path().join("txt");
+        std::fs::create_dir(&existing)?;
+
+        assert_eq!(
+            Some(
+                "Hint: \"txt\" was taken as the path, did you mean `rmx txt -p <DIR>`?".to_string()
+            ),
+            swallowed_extension_hint(&[PathBuf::from("txt")])
+        );
+        // Existing paths and paths with separators are what -p expects
+        assert_eq!(None, swallowed_extension_hint(&[existing]));
+        assert_eq!(
+            None,
+            swallowed_extension_hint(&[PathBuf::from("src/missing")])
+        );
+        assert_eq!(None, swallowed_extension_hint(&[]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn hint_for_directory_as_extension() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let build = dir.path().join("build");
+        std::fs::create_dir(&build)?;
+        let build = build.to_string_lossy().to_string();
+
+        assert_eq!(
+            Some(format!(
+                "\"{build}\" is a directory but was taken as an extension, did you mean `rmx <EXT> -p {build}`?"
+            )),
+            directory_as_extension_hint(&["log".to_string(), build.clone()])
+        );
+        assert_eq!(None, directory_as_extension_hint(&["log".to_string()]));
+
+        Ok(())
+    }
+
     #[test]
     fn check_one_valid_extension() {
         let mut extensions = Vec::new();
@@ -221,4 +1885,124 @@ mod test {
 
         assert!(!are_extensions_valid(&extensions));
     }
+
+    #[test]
+    fn check_dot_must_be_literal() {
+        // Any character used to be accepted in place of the dot
+        for ext in ["a/b", "a b", "a*b", "a\u{e9}b", "a\nb"] {
+            assert!(!are_extensions_valid(&[ext.to_string()]), "{ext:?}");
+        }
+    }
+
+    #[test]
+    fn check_empty_parts_extension() {
+        for ext in ["", ".", "a.", "a..b"] {
+            assert!(!are_extensions_valid(&[ext.to_string()]), "{ext:?}");
+        }
+    }
+
+    #[test]
+    fn normalize_dots_and_commas() {
+        let origin = || ExtensionOrigin::CommandLine { position: 1 };
+
+        assert_eq!(vec!["log"], normalize_extension(".log", origin).unwrap());
+        assert_eq!(
+            vec!["tar.gz"],
+            normalize_extension(".tar.gz", origin).unwrap()
+        );
+        assert_eq!(
+            vec!["log", "R", "tmp"],
+            normalize_extension("log,.R,tmp", origin).unwrap()
+        );
+        for raw in ["*", "..log", "log,", "log,*"] {
+            assert!(normalize_extension(raw, origin).is_err(), "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn preset_with_one_invalid_extension() -> Result<(), Box<dyn Error>> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        write!(file, "preset java=jar class\npreset c=.o,a so/out h")?;
+        let config = file.path().to_str().unwrap();
+
+        let args = Args::try_parse_from(["rmx", "--config", config, "--preset", "java"])?;
+        assert_eq!(vec!["jar", "class"], args.get_extensions()?);
+
+        let args = Args::try_parse_from(["rmx", "--config", config, "--preset", "c"])?;
+        let error = args.get_extensions().unwrap_err();
+        let Some(RmxError::InvalidExtension { value, origin }) = error.downcast_ref() else {
+            panic!("{error}");
+        };
+        assert_eq!("so/out", value);
+        assert_eq!(
+            &ExtensionOrigin::Preset {
+                name: "c".to_string(),
+                path: file.path().to_path_buf(),
+                line: 2,
+            },
+            origin
+        );
+
+        let args = Args::try_parse_from(["rmx", "--config", config, "txt", ".log", "*"])?;
+        assert_eq!(
+            "Invalid extension \"*\" given as extension 3 on the command line",
+            args.get_extensions().unwrap_err().to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn policy_beats_flags_and_config() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let (config, policy) = (dir.path().join("rmx.conf"), dir.path().join("policy.toml"));
+        std::fs::write(
+            &config,
+            format!(
+                "[job.prod]\npath = {}\nextensions = log\nforce = true\n",
+                dir.path().display()
+            ),
+        )?;
+        let parse = |extra: &[&str], rules: &str| -> Result<Args, Box<dyn Error>> {
+            std::fs::write(&policy, rules)?;
+            let mut argv: Vec<OsString> =
+                vec!["rmx".into(), "--config".into(), config.clone().into()];
+            argv.extend(["--job", "prod"].iter().chain(extra).map(OsString::from));
+            // Never the policy of the machine running the tests
+            let source = PolicySource {
+                path: policy.clone(),
+                owner: None,
+            };
+            Ok(Args::parse_with_policy(argv, &[source])?.unwrap())
+        };
+
+        // Flags beat the job, which the policy beats
+        let args = parse(&["txt"], "")?;
+        assert_eq!(vec!["txt".to_string()], args.extensions);
+        assert!(args.force && !args.dry_run);
+        let rules = format!(
+            "[[paths]]\npath = '{}'\ndry_run = true\n",
+            dir.path().display()
+        );
+        assert!(parse(&["txt"], &rules)?.dry_run);
+
+        assert!(parse(&["--invert"], "")?.invert);
+        let e = parse(&["--invert"], "forbid_invert = true\n").unwrap_err();
+        assert!(
+            e.to_string()
+                .starts_with("Blocked by policy: --invert is not allowed")
+        );
+
+        assert!(parse(&[], "require_rename_suffix = true\n").is_err());
+        assert!(
+            parse(
+                &["--rename-suffix", ".trashed"],
+                "require_rename_suffix = true\n"
+            )
+            .is_ok()
+        );
+        assert!(parse(&["-n"], "require_rename_suffix = true\n").is_ok());
+
+        Ok(())
+    }
 }
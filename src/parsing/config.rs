@@ -0,0 +1,797 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::env;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::parsing::duration::{format_duration, parse_duration};
+use crate::parsing::size::parse_size;
+use crate::warning;
+
+/// System wide config file (Linux only)
+///
+/// ```
+/// assert_eq!(std::path::Path::new("/etc/rmx/rmx.conf"), rmx::config::default_config_path());
+/// ```
+pub fn default_config_path() -> PathBuf {
+    PathBuf::from("/etc/rmx/rmx.conf")
+}
+
+/// Config file of the current user, following the XDG base directory specification
+///
+/// ```
+/// if let Some(path) = rmx::config::user_config_path() {
+///     assert!(path.ends_with("rmx/rmx.conf"));
+/// }
+/// ```
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        // Relative paths are to be ignored, as per the specification
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("rmx").join("rmx.conf"))
+}
+
+fn pick_config_path(explicit: Option<&Path>, user: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = explicit {
+        return path.to_path_buf();
+    }
+    user.filter(|p| p.exists())
+        .unwrap_or_else(default_config_path)
+}
+
+/// Config file holding presets and settings: the given one if any, then the one of the user if
+/// it exists, the default one otherwise
+///
+/// ```
+/// use std::path::Path;
+///
+/// let path = rmx::config::resolve_config_path(Some(Path::new("rmx.conf")));
+///
+/// assert_eq!(Path::new("rmx.conf"), path);
+/// ```
+pub fn resolve_config_path(explicit: Option<&Path>) -> PathBuf {
+    pick_config_path(explicit, user_config_path())
+}
+
+// Written by --init-config
+pub(crate) const TEMPLATE: &str = "\
+# This is the configuration file for rmx.
+# Presets group extensions, and are used with `rmx --preset NAME`:
+preset c=o a so out
+preset java=class jar
+preset latex=aux toc out log
+
+# Aliases make extensions interchangeable: requesting any of them matches all of them.
+#alias jpeg=jpg jpe
+
+# Settings are written as key = value, see /etc/rmx/rmx.conf or the README for all of them.
+#allow_invert_recursive = true
+";
+
+// Creates the config file with the template, along with its directories. An existing file is
+// never overwritten
+pub(crate) fn init_config(path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o644);
+
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => {
+            format!("{} already exists, leaving it untouched", path.display())
+        }
+        _ => format!("Could not create {}: {e}", path.display()),
+    })?;
+    file.write_all(TEMPLATE.as_bytes())?;
+    Ok(())
+}
+
+// Settings of the config file, written as `key = value` lines next to presets
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Config {
+    // Skips the acknowledgement required by -i -r -f
+    pub allow_invert_recursive: bool,
+    // Groups of synonym extensions, from `alias jpeg=jpg jpe` lines
+    pub aliases: Vec<Vec<String>>,
+    // Run once the deletion is over (see --notify-cmd)
+    pub notify_cmd: Option<String>,
+    // Also run notify_cmd after dry runs
+    pub notify_on_dry_run: bool,
+    pub notify_timeout: Option<Duration>,
+    // Named runs, from `[job.name]` sections
+    pub jobs: Vec<Job>,
+    // Users (names or uids) whose files are never deleted, see --ignore-protected-owners
+    pub protected_owners: Vec<String>,
+    // Sizes in bytes where --long sizes turn from green to yellow, then to red (see --color)
+    pub size_heat: Option<[u64; 2]>,
+    // Runs kept in the history file, none being recorded at 0 (see --history)
+    pub history_limit: Option<usize>,
+    // Matches a sorted listing buffers at most, before listing the others unsorted
+    pub sort_limit: Option<usize>,
+    // Tips shown after runs, like saving extensions typed again and again as a preset
+    pub suggestions: Option<bool>,
+}
+
+// A run described in a `[job.name]` section, each key standing for the command line option of
+// the same name. Options given on the command line alongside --job take precedence
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Job {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub extensions: Vec<String>,
+    pub preset: Vec<String>,
+    pub recurse: bool,
+    pub force: bool,
+    pub dry_run: bool,
+    pub invert: bool,
+    pub all: bool,
+    pub hidden_files: bool,
+    pub hidden_dirs: bool,
+    pub apple_cruft: bool,
+    pub nice: bool,
+    pub oneline: bool,
+    pub never_older_than: Option<Duration>,
+    pub older_than: Option<Duration>,
+    pub newer_than: Option<Duration>,
+    // Reference files, whose modification time is read when the job runs
+    pub older_than_file: Option<PathBuf>,
+    pub newer_than_file: Option<PathBuf>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub follow_dir_lists: Option<String>,
+    pub audit: Option<PathBuf>,
+}
+
+// Like `[job.nightly]`. Presets and settings of the config file are written before any section
+pub(crate) fn is_section_header(line: &str) -> bool {
+    line.trim().starts_with('[')
+}
+
+impl Job {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        let words = || value.split_whitespace().map(|s| s.to_string()).collect();
+        match key {
+            "path" => self.path = Some(PathBuf::from(value)),
+            "extensions" => self.extensions = words(),
+            "preset" => self.preset = words(),
+            "recurse" => self.recurse = parse_bool(key, value)?,
+            "force" => self.force = parse_bool(key, value)?,
+            "dry_run" => self.dry_run = parse_bool(key, value)?,
+            "invert" => self.invert = parse_bool(key, value)?,
+            "all" => self.all = parse_bool(key, value)?,
+            "hidden_files" => self.hidden_files = parse_bool(key, value)?,
+            "hidden_dirs" => self.hidden_dirs = parse_bool(key, value)?,
+            "apple_cruft" => self.apple_cruft = parse_bool(key, value)?,
+            "nice" => self.nice = parse_bool(key, value)?,
+            "oneline" => self.oneline = parse_bool(key, value)?,
+            "never_older_than" => self.never_older_than = Some(parse_duration(value)?),
+            "older_than" => self.older_than = Some(parse_duration(value)?),
+            "newer_than" => self.newer_than = Some(parse_duration(value)?),
+            "older_than_file" => self.older_than_file = Some(PathBuf::from(value)),
+            "newer_than_file" => self.newer_than_file = Some(PathBuf::from(value)),
+            "min_size" => self.min_size = Some(parse_size(value)?),
+            "max_size" => self.max_size = Some(parse_size(value)?),
+            "follow_dir_lists" => self.follow_dir_lists = Some(value.to_string()),
+            "audit" => self.audit = Some(PathBuf::from(value)),
+            // A typo in a job run unattended should not go unnoticed
+            _ => return Err(format!("Unknown key \"{key}\" in job \"{}\"", self.name).into()),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.preset.is_empty() && !self.extensions.is_empty() {
+            return Err(format!(
+                "Job \"{}\" cannot specify both preset and extensions",
+                self.name
+            )
+            .into());
+        }
+        if let (Some(older), Some(newer)) = (self.older_than, self.newer_than)
+            && newer <= older
+        {
+            return Err(format!(
+                "Job \"{}\" has newer_than {} not longer than older_than {}, nothing would be \
+                collected",
+                self.name,
+                format_duration(newer),
+                format_duration(older)
+            )
+            .into());
+        }
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size)
+            && min > max
+        {
+            return Err(format!(
+                "Job \"{}\" has min_size greater than max_size, nothing would be collected",
+                self.name
+            )
+            .into());
+        }
+        if self.preset.is_empty() && self.extensions.is_empty() && !self.apple_cruft {
+            return Err(format!("Job \"{}\" does not specify what to delete", self.name).into());
+        }
+        Ok(())
+    }
+
+    // What the job deletes and where, as shown by --jobs
+    fn describe(&self) -> String {
+        let what: Vec<String> = if self.preset.is_empty() {
+            self.extensions.iter().map(|e| format!("*.{e}")).collect()
+        } else {
+            self.preset.iter().map(|p| format!("preset {p}")).collect()
+        };
+        let path = match &self.path {
+            Some(p) => p.display().to_string(),
+            None => "the current directory".to_string(),
+        };
+        format!("Removes {} in {}", what.join(", "), path)
+    }
+}
+
+// One line per job, as shown by --jobs
+pub(crate) fn format_jobs(jobs: &[Job]) -> Vec<String> {
+    jobs.iter()
+        .map(|job| format!("rmx --job {}: \t{}", job.name, job.describe()))
+        .collect()
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, Box<dyn Error>> {
+    match value {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(format!("Setting \"{key}\" expects true or false, got \"{value}\"").into()),
+    }
+}
+
+fn parse_count(key: &str, value: &str) -> Result<usize, Box<dyn Error>> {
+    value
+        .parse()
+        .map_err(|_| format!("Setting \"{key}\" expects a number, got \"{value}\"").into())
+}
+
+// Accepts both `a b` and `["a", "b"]`
+fn parse_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|item| item.trim_matches(|c| c == '"' || c == '\''))
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+// Two increasing sizes in bytes, like `[1048576, 104857600]`
+fn parse_size_heat(value: &str) -> Result<[u64; 2], Box<dyn Error>> {
+    let invalid = || -> Box<dyn Error> {
+        format!("Setting \"size_heat\" expects two increasing sizes in bytes, got \"{value}\"")
+            .into()
+    };
+    let sizes: Vec<u64> = parse_list(value)
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    match sizes[..] {
+        [low, high] if low < high => Ok([low, high]),
+        _ => Err(invalid()),
+    }
+}
+
+// Uid of a user given by name or uid
+pub(crate) fn resolve_owner(owner: &str) -> Option<u32> {
+    if let Ok(uid) = owner.parse() {
+        return Some(uid);
+    }
+    #[cfg(unix)]
+    return crate::platform::users::uid_of(owner);
+    #[cfg(not(unix))]
+    None
+}
+
+// Gid of a group given by name or gid
+pub(crate) fn resolve_group(group: &str) -> Option<u32> {
+    if let Ok(gid) = group.parse() {
+        return Some(gid);
+    }
+    #[cfg(unix)]
+    return crate::platform::users::gid_of(group);
+    #[cfg(not(unix))]
+    None
+}
+
+fn parse_alias(line: &str) -> Option<Vec<String>> {
+    let (name, synonyms) = line.split_once('=')?;
+    let mut group = vec![name.trim().to_string()];
+    group.extend(synonyms.split_whitespace().map(|s| s.to_string()));
+
+    if group.len() < 2 || group[0].is_empty() {
+        return None;
+    }
+    Some(group)
+}
+
+// Adds every alias of the given extensions, in either direction and through chains of aliases
+// (a=b and b=c make a, b and c equivalent), keeping the requested extensions first
+pub(crate) fn expand_aliases(extensions: &[String], aliases: &[Vec<String>]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    let mut seen: BTreeSet<&str> = BTreeSet::new();
+    let mut pending: VecDeque<&str> = extensions.iter().map(|e| e.as_str()).collect();
+
+    while let Some(ext) = pending.pop_front() {
+        if !seen.insert(ext) {
+            continue;
+        }
+        expanded.push(ext.to_string());
+
+        for group in aliases.iter().filter(|g| g.iter().any(|e| e == ext)) {
+            pending.extend(group.iter().map(|e| e.as_str()));
+        }
+    }
+
+    expanded
+}
+
+impl Config {
+    // A missing config file is not an error: every setting keeps its default value
+    pub fn load(path: &Path) -> Result<Config, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(data) => {
+                Config::parse(&data).map_err(|e| format!("{} (in {})", e, path.display()).into())
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(_) => Err(format!("Could not read {}", path.display()).into()),
+        }
+    }
+
+    // Unknown users are only warned about, as a config file shared between machines may name
+    // users that do not exist on all of them
+    pub fn protected_uids(&self) -> Vec<u32> {
+        self.protected_owners
+            .iter()
+            .filter_map(|owner| {
+                let uid = resolve_owner(owner);
+                if uid.is_none() {
+                    warning::warn(format!("unknown user \"{owner}\" in protected_owners"));
+                }
+                uid
+            })
+            .collect()
+    }
+
+    pub fn find_job(&self, name: &str) -> Option<&Job> {
+        self.jobs.iter().find(|j| j.name == name)
+    }
+
+    fn parse(data: &str) -> Result<Config, Box<dyn Error>> {
+        let mut config = Config::default();
+
+        let mut lines = data.lines().map(str::trim);
+        for line in lines.by_ref() {
+            if line.is_empty() || line.starts_with('#') || line.starts_with("preset ") {
+                continue;
+            }
+            if is_section_header(line) {
+                config.jobs.push(Config::parse_job_header(line)?);
+                break;
+            }
+
+            if let Some(alias) = line.strip_prefix("alias ") {
+                match parse_alias(alias) {
+                    Some(group) => config.aliases.push(group),
+                    None => warning::warn(format!("alias line \"{line}\" not formatted correctly")),
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                warning::warn(format!("config line \"{line}\" not formatted correctly"));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "allow_invert_recursive" => config.allow_invert_recursive = parse_bool(key, value)?,
+                "notify_cmd" => config.notify_cmd = Some(value.to_string()),
+                "notify_on_dry_run" => config.notify_on_dry_run = parse_bool(key, value)?,
+                "notify_timeout" => config.notify_timeout = Some(parse_duration(value)?),
+                "protected_owners" => config.protected_owners = parse_list(value),
+                "size_heat" => config.size_heat = Some(parse_size_heat(value)?),
+                "history_limit" => config.history_limit = Some(parse_count(key, value)?),
+                "sort_limit" => config.sort_limit = Some(parse_count(key, value)?),
+                "suggestions" => config.suggestions = Some(parse_bool(key, value)?),
+                _ => warning::warn(format!("unknown setting \"{key}\" in config")),
+            }
+        }
+
+        // Every line left belongs to a section
+        for line in lines {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if is_section_header(line) {
+                config.jobs.push(Config::parse_job_header(line)?);
+                continue;
+            }
+
+            let job = config.jobs.last_mut().expect("a section was started");
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!(
+                    "Line \"{}\" of job \"{}\" not formatted correctly",
+                    line, job.name
+                )
+                .into());
+            };
+            job.set(key.trim(), value.trim())?;
+        }
+
+        for (i, job) in config.jobs.iter().enumerate() {
+            job.validate()?;
+            if config.jobs[..i].iter().any(|j| j.name == job.name) {
+                return Err(format!("Job \"{}\" is defined twice", job.name).into());
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn parse_job_header(line: &str) -> Result<Job, Box<dyn Error>> {
+        let name = line
+            .strip_prefix("[job.")
+            .and_then(|s| s.strip_suffix(']'))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("Unknown section \"{line}\", expected [job.name]"))?;
+        Ok(Job {
+            name: name.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty_config() -> Result<(), Box<dyn Error>> {
+        let config = Config::parse("")?;
+
+        assert!(!config.allow_invert_recursive);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ignores_presets_and_comments() -> Result<(), Box<dyn Error>> {
+        let data = "# allow_invert_recursive = true\npreset c=o a so out";
+
+        let config = Config::parse(data)?;
+
+        assert!(!config.allow_invert_recursive);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_allow_invert_recursive() -> Result<(), Box<dyn Error>> {
+        let data = "preset c=o a so out\nallow_invert_recursive = true";
+
+        let config = Config::parse(data)?;
+
+        assert!(config.allow_invert_recursive);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_bool_should_err() {
+        let data = "allow_invert_recursive = maybe";
+
+        assert!(Config::parse(data).is_err());
+    }
+
+    #[test]
+    fn load_missing_config_gives_defaults() -> Result<(), Box<dyn Error>> {
+        let config = Config::load(Path::new("-unknown file-"))?;
+
+        assert!(!config.allow_invert_recursive);
+
+        Ok(())
+    }
+
+    fn to_strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_aliases() -> Result<(), Box<dyn Error>> {
+        let data = "alias jpeg=jpg\nalias yml = yaml\nalias broken\nalias tif=tiff tf";
+
+        let config = Config::parse(data)?;
+
+        assert_eq!(
+            vec![
+                to_strings(&["jpeg", "jpg"]),
+                to_strings(&["yml", "yaml"]),
+                to_strings(&["tif", "tiff", "tf"]),
+            ],
+            config.aliases
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_aliases_both_directions() {
+        let aliases = vec![to_strings(&["jpeg", "jpg"])];
+
+        let from_name = expand_aliases(&to_strings(&["jpeg"]), &aliases);
+        let from_synonym = expand_aliases(&to_strings(&["jpg"]), &aliases);
+
+        assert_eq!(to_strings(&["jpeg", "jpg"]), from_name);
+        assert_eq!(to_strings(&["jpg", "jpeg"]), from_synonym);
+    }
+
+    #[test]
+    fn expand_aliases_chain() {
+        let aliases = vec![to_strings(&["a", "b"]), to_strings(&["b", "c"])];
+
+        let expanded = expand_aliases(&to_strings(&["c", "log"]), &aliases);
+
+        assert_eq!(to_strings(&["c", "log", "b", "a"]), expanded);
+    }
+
+    #[test]
+    fn expand_aliases_cycle() {
+        let aliases = vec![
+            to_strings(&["a", "b"]),
+            to_strings(&["b", "c"]),
+            to_strings(&["c", "a"]),
+        ];
+
+        let expanded = expand_aliases(&to_strings(&["a", "a"]), &aliases);
+
+        assert_eq!(to_strings(&["a", "b", "c"]), expanded);
+    }
+
+    #[test]
+    fn resolve_explicit_config_path() {
+        assert_eq!(
+            PathBuf::from("/tmp/rmx.conf"),
+            resolve_config_path(Some(Path::new("/tmp/rmx.conf")))
+        );
+        let missing_user = Some(PathBuf::from("-unknown dir-/rmx.conf"));
+        assert_eq!(default_config_path(), pick_config_path(None, missing_user));
+        assert_eq!(default_config_path(), pick_config_path(None, None));
+    }
+
+    #[test]
+    fn resolve_existing_user_config_path() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let user = dir.path().join("rmx.conf");
+        fs::write(&user, "")?;
+
+        assert_eq!(user, pick_config_path(None, Some(user.clone())));
+        assert_eq!(
+            PathBuf::from("/tmp/rmx.conf"),
+            pick_config_path(Some(Path::new("/tmp/rmx.conf")), Some(user.clone()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn init_config_never_overwrites() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("nested/rmx/rmx.conf");
+
+        init_config(&path)?;
+        fs::write(&path, "preset mine=log")?;
+        let again = init_config(&path);
+
+        assert!(again.unwrap_err().to_string().contains("already exists"));
+        assert_eq!("preset mine=log", fs::read_to_string(&path)?);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn init_config_permissions() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rmx.conf");
+
+        init_config(&path)?;
+
+        // The umask can only remove permissions
+        let mode = fs::metadata(&path)?.permissions().mode() & 0o777;
+        assert_eq!(0, mode & !0o644);
+        assert_eq!(TEMPLATE, fs::read_to_string(&path)?);
+        assert!(Config::parse(TEMPLATE).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_notify_settings() -> Result<(), Box<dyn Error>> {
+        let data = "notify_cmd = notify-send \"rmx: $RMX_STATUS\" --urgency=low\n\
+                    notify_on_dry_run = yes\n\
+                    notify_timeout = 2m";
+        let config = Config::parse(data)?;
+
+        assert_eq!(
+            Some("notify-send \"rmx: $RMX_STATUS\" --urgency=low".to_string()),
+            config.notify_cmd
+        );
+        assert!(config.notify_on_dry_run);
+        assert_eq!(Some(Duration::from_secs(120)), config.notify_timeout);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_limits() -> Result<(), Box<dyn Error>> {
+        assert_eq!(Some(50), Config::parse("history_limit = 50")?.history_limit);
+        assert_eq!(
+            Some(false),
+            Config::parse("suggestions = false")?.suggestions
+        );
+        assert_eq!(None, Config::parse("")?.suggestions);
+        assert!(Config::parse("history_limit = -1").is_err());
+        assert_eq!(Some(10), Config::parse("sort_limit = 10")?.sort_limit);
+        assert!(Config::parse("sort_limit = many").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_protected_owners() -> Result<(), Box<dyn Error>> {
+        let config = Config::parse("protected_owners = [\"postgres\", \"gitlab\", 998]")?;
+        assert_eq!(vec!["postgres", "gitlab", "998"], config.protected_owners);
+
+        let config = Config::parse("protected_owners = postgres 'gitlab'")?;
+        assert_eq!(vec!["postgres", "gitlab"], config.protected_owners);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_size_heat() -> Result<(), Box<dyn Error>> {
+        let config = Config::parse("size_heat = [1048576, 104857600]")?;
+        assert_eq!(Some([1048576, 104857600]), config.size_heat);
+
+        for value in ["[1048576]", "[2, 1]", "[1, 2, 3]", "[1, big]"] {
+            let e = Config::parse(&format!("size_heat = {value}")).unwrap_err();
+            assert!(e.to_string().contains("two increasing sizes"), "{value}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn protected_owners_uids() -> Result<(), Box<dyn Error>> {
+        let config = Config::parse("protected_owners = [\"root\", 998, \"rmx-no-such-user\"]")?;
+
+        // Unknown users are left aside
+        assert_eq!(vec![0, 998], config.protected_uids());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_jobs() -> Result<(), Box<dyn Error>> {
+        let data = "preset c=o a so out\n\
+                    allow_invert_recursive = true\n\
+                    [job.nightly]\n\
+                    # Build leftovers\n\
+                    path = /srv/build\n\
+                    preset = c latex\n\
+                    recurse = true\n\
+                    never_older_than = 30d\n\
+                    [job.logs]\n\
+                    extensions = log gz\n\
+                    older_than = 2w\n\
+                    newer_than = 1y\n\
+                    older_than_file = /srv/stamp\n\
+                    min_size = 1K\n\
+                    max_size = 10M";
+        let config = Config::parse(data)?;
+
+        assert!(config.allow_invert_recursive);
+        assert_eq!(
+            vec![
+                Job {
+                    name: "nightly".to_string(),
+                    path: Some(PathBuf::from("/srv/build")),
+                    preset: to_strings(&["c", "latex"]),
+                    recurse: true,
+                    never_older_than: Some(Duration::from_secs(30 * 24 * 3600)),
+                    ..Default::default()
+                },
+                Job {
+                    name: "logs".to_string(),
+                    extensions: to_strings(&["log", "gz"]),
+                    older_than: Some(Duration::from_secs(14 * 24 * 3600)),
+                    newer_than: Some(Duration::from_secs(365 * 24 * 3600)),
+                    older_than_file: Some(PathBuf::from("/srv/stamp")),
+                    min_size: Some(1024),
+                    max_size: Some(10 * 1024 * 1024),
+                    ..Default::default()
+                },
+            ],
+            config.jobs
+        );
+        assert!(config.find_job("logs").is_some());
+        assert!(config.find_job("log").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_job_with_preset_and_extensions_should_err() {
+        let data = "[job.both]\npreset = c\nextensions = log";
+
+        let e = Config::parse(data).unwrap_err();
+
+        assert!(e.to_string().contains("both preset and extensions"));
+    }
+
+    #[test]
+    fn parse_invalid_jobs_should_err() {
+        for data in [
+            "[job.empty]\nrecurse = true",
+            "[job.typo]\nextensions = log\nforse = true",
+            "[job.twice]\nextensions = log\n[job.twice]\nextensions = tmp",
+            "[jobs.other]\nextensions = log",
+            "[job.]\nextensions = log",
+            "[job.noequal]\nextensions log",
+            "[job.window]\nextensions = log\nolder_than = 30d\nnewer_than = 7d",
+            "[job.sizes]\nextensions = log\nmin_size = 10M\nmax_size = 1M",
+            "[job.size]\nextensions = log\nmin_size = lots",
+        ] {
+            assert!(Config::parse(data).is_err(), "{data:?}");
+        }
+    }
+
+    #[test]
+    fn jobs_description() {
+        let jobs = vec![
+            Job {
+                name: "nightly".to_string(),
+                path: Some(PathBuf::from("/srv/build")),
+                preset: to_strings(&["c"]),
+                ..Default::default()
+            },
+            Job {
+                name: "logs".to_string(),
+                extensions: to_strings(&["log", "gz"]),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            vec![
+                "rmx --job nightly: \tRemoves preset c in /srv/build",
+                "rmx --job logs: \tRemoves *.log, *.gz in the current directory",
+            ],
+            format_jobs(&jobs)
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::time::Duration;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+// Parses durations like `90m`, `12h`, `30d`, `2w` or `1y` (365 days)
+pub fn parse_duration(token: &str) -> Result<Duration, Box<dyn Error>> {
+    let invalid = || -> Box<dyn Error> {
+        format!("Invalid duration \"{token}\", expected a number followed by s, m, h, d, w or y")
+            .into()
+    };
+
+    let split = token
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (value, unit) = token.split_at(split);
+
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => MINUTE,
+        "h" => HOUR,
+        "d" => DAY,
+        "w" => 7 * DAY,
+        "y" => 365 * DAY,
+        _ => return Err(invalid()),
+    };
+
+    let secs = value.checked_mul(unit).ok_or_else(invalid)?;
+    Ok(Duration::from_secs(secs))
+}
+
+// Shortest spelling parse_duration reads back, like `30d` or `2w`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let units = [
+        (365 * DAY, "y"),
+        (7 * DAY, "w"),
+        (DAY, "d"),
+        (HOUR, "h"),
+        (MINUTE, "m"),
+    ];
+    match units
+        .iter()
+        .find(|(unit, _)| secs > 0 && secs.is_multiple_of(*unit))
+    {
+        Some((unit, suffix)) => format!("{}{suffix}", secs / unit),
+        None => format!("{secs}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_each_unit() -> Result<(), Box<dyn Error>> {
+        assert_eq!(Duration::from_secs(45), parse_duration("45s")?);
+        assert_eq!(Duration::from_secs(90 * 60), parse_duration("90m")?);
+        assert_eq!(Duration::from_secs(12 * 3600), parse_duration("12h")?);
+        assert_eq!(Duration::from_secs(30 * 86400), parse_duration("30d")?);
+        assert_eq!(Duration::from_secs(14 * 86400), parse_duration("2w")?);
+        assert_eq!(Duration::from_secs(365 * 86400), parse_duration("1y")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_round_trip() -> Result<(), Box<dyn Error>> {
+        for token in ["30d", "2w", "1y", "90m", "12h", "45s", "0s"] {
+            assert_eq!(token, format_duration(parse_duration(token)?));
+        }
+        assert_eq!("2w", format_duration(parse_duration("14d")?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_durations_should_err() {
+        for token in ["", "d", "30", "30x", "-1d", "1.5d", "30 d", "d30"] {
+            assert!(parse_duration(token).is_err(), "{token} should be invalid");
+        }
+    }
+
+    #[test]
+    fn parse_error_names_token() {
+        let e = parse_duration("30x").unwrap_err();
+
+        assert!(e.to_string().contains("\"30x\""));
+    }
+
+    #[test]
+    fn parse_overflow_should_err() {
+        assert!(parse_duration("99999999999999999y").is_err());
+    }
+}
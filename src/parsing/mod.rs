@@ -1,2 +1,6 @@
 pub mod arguments;
-pub mod preset;
\ No newline at end of file
+pub mod config;
+pub mod duration;
+pub mod policy;
+pub mod preset;
+pub mod size;
@@ -0,0 +1,529 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::paths;
+use crate::{Candidate, CandidateKind};
+
+// Policy file pushed to the machines of a fleet, owned by root
+pub(crate) const POLICY_PATH: &str = "/etc/rmx/policy.toml";
+// A further policy file, to try one out before pushing it. Being applied along with POLICY_PATH
+// rather than instead of it, it can only restrict runs further
+pub(crate) const POLICY_ENV: &str = "RMX_POLICY";
+
+// A policy file to read, along with the uid it must be owned by, if any
+#[derive(Debug, Clone)]
+pub(crate) struct PolicySource {
+    pub(crate) path: PathBuf,
+    pub(crate) owner: Option<u32>,
+}
+
+// The policy of the machine, which only root may write, then the one of RMX_POLICY. The latter
+// is trusted whoever owns it, as it can only restrict runs further
+pub(crate) fn sources() -> Vec<PolicySource> {
+    let mut sources = vec![PolicySource {
+        path: PathBuf::from(POLICY_PATH),
+        owner: Some(0),
+    }];
+    sources.extend(env::var_os(POLICY_ENV).map(|path| PolicySource {
+        path: PathBuf::from(path),
+        owner: None,
+    }));
+    sources
+}
+
+// What a policy file holds, every key being optional:
+//
+//     forbid_invert = true
+//     max_files = 500
+//     require_rename_suffix = true
+//
+//     [[paths]]
+//     path = "/srv/prod"
+//     dry_run = true
+//
+// Unknown keys are errors, as a rule left out would let through the runs it was meant to block
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Schema {
+    #[serde(default)]
+    forbid_invert: bool,
+    max_files: Option<usize>,
+    #[serde(default)]
+    require_rename_suffix: bool,
+    #[serde(default)]
+    paths: Vec<PathRules>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathRules {
+    path: PathBuf,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// Rules of a policy file, which no flag, job or setting can get around
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PolicyFile {
+    pub(crate) path: PathBuf,
+    // Runs can only be dry runs under these directories
+    pub(crate) dry_run_paths: Vec<PathBuf>,
+    pub(crate) forbid_invert: bool,
+    // Files a run deletes at most, a run matching more being blocked as a whole
+    pub(crate) max_files: Option<usize>,
+    // Files can only be renamed with --rename-suffix, never deleted for good
+    pub(crate) require_rename_suffix: bool,
+}
+
+impl PolicyFile {
+    fn parse(path: &Path, data: &str) -> Result<PolicyFile, Box<dyn Error>> {
+        let schema: Schema = toml::from_str(data).map_err(|e| match e.span() {
+            Some(span) => format!(
+                "{} at line {}",
+                e.message(),
+                data[..span.start].matches('\n').count() + 1
+            ),
+            None => e.message().to_string(),
+        })?;
+
+        if let Some(rules) = schema.paths.iter().find(|r| !r.path.is_absolute()) {
+            return Err(format!(
+                "paths expects absolute paths, got \"{}\"",
+                rules.path.display()
+            )
+            .into());
+        }
+
+        Ok(PolicyFile {
+            path: path.to_path_buf(),
+            dry_run_paths: schema
+                .paths
+                .into_iter()
+                .filter(|r| r.dry_run)
+                .map(|r| r.path)
+                .collect(),
+            forbid_invert: schema.forbid_invert,
+            max_files: schema.max_files,
+            require_rename_suffix: schema.require_rename_suffix,
+        })
+    }
+
+    // None when there is no policy file. Unlike a config file, one that cannot be read or
+    // understood, or that others than its expected owner could have written, blocks every run
+    fn load(source: &PolicySource) -> Result<Option<PolicyFile>, Box<dyn Error>> {
+        let path = &source.path;
+        let could_not_read =
+            |e| format!("Blocked by policy: could not read {}: {e}.", path.display());
+        let mut file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(could_not_read(e).into()),
+        };
+        // Checked on the file opened, so that it cannot be swapped in between
+        if let Some(owner) = source.owner {
+            check_owner(path, &file.metadata().map_err(could_not_read)?, owner)?;
+        }
+        let mut data = String::new();
+        file.read_to_string(&mut data).map_err(could_not_read)?;
+
+        PolicyFile::parse(path, &data)
+            .map(Some)
+            .map_err(|e| format!("Blocked by policy: {e} (in {}).", path.display()).into())
+    }
+
+    fn blocked(&self, what: &str, rule: &str) -> Box<dyn Error> {
+        format!(
+            "Blocked by policy: {what} ({rule} in {}).",
+            self.path.display()
+        )
+        .into()
+    }
+
+    // The directory of dry_run paths `path` is under, if any
+    fn dry_run_dir(&self, path: &Path) -> Option<&Path> {
+        let path = real_path(path);
+        self.dry_run_paths
+            .iter()
+            .find(|dir| path.starts_with(real_path(dir)))
+            .map(PathBuf::as_path)
+    }
+
+    // The directory of dry_run paths a deletion reaches, a directory deleted whole reaching
+    // those inside it too
+    fn dry_run_dir_reached(&self, candidate: &Candidate) -> Option<&Path> {
+        if let Some(dir) = self.dry_run_dir(&candidate.path) {
+            return Some(dir);
+        }
+        if candidate.kind != CandidateKind::Dir {
+            return None;
+        }
+        let path = real_path(&candidate.path);
+        self.dry_run_paths
+            .iter()
+            .find(|dir| real_path(dir).starts_with(&path))
+            .map(PathBuf::as_path)
+    }
+}
+
+#[cfg(unix)]
+fn check_owner(path: &Path, metadata: &fs::Metadata, owner: u32) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::MetadataExt;
+
+    if metadata.uid() != owner {
+        return Err(format!(
+            "Blocked by policy: {} is owned by uid {}, not by uid {owner}.",
+            path.display(),
+            metadata.uid()
+        )
+        .into());
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(format!(
+            "Blocked by policy: {} can be written by its group or others (mode {:o}).",
+            path.display(),
+            metadata.mode() & 0o7777
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// Files have no uid off Unix, where the permissions of the directory are relied on
+#[cfg(not(unix))]
+fn check_owner(_path: &Path, _metadata: &fs::Metadata, _owner: u32) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+// Symlinks resolved, so that a link to a directory of dry_run_paths does not get around it
+fn real_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| paths::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Policy files in force, every one of them applying
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Policy {
+    files: Vec<PolicyFile>,
+}
+
+impl Policy {
+    pub(crate) fn load_from(sources: &[PolicySource]) -> Result<Policy, Box<dyn Error>> {
+        let mut files = Vec::new();
+        for source in sources {
+            files.extend(PolicyFile::load(source)?);
+        }
+        Ok(Policy { files })
+    }
+
+    // The policy file making runs under `root` dry runs, and the directory it names, if any
+    pub(crate) fn dry_run_rule(&self, root: &Path) -> Option<(&PolicyFile, &Path)> {
+        self.files
+            .iter()
+            .find_map(|f| Some((f, f.dry_run_dir(root)?)))
+    }
+
+    // Flags of the run once parsed, a dry run deleting nothing
+    pub(crate) fn check_flags(
+        &self,
+        invert: bool,
+        dry_run: bool,
+        renames: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        for file in &self.files {
+            if invert && file.forbid_invert {
+                return Err(file.blocked("--invert is not allowed", "forbid_invert"));
+            }
+            if !dry_run && !renames && file.require_rename_suffix {
+                return Err(file.blocked(
+                    "files can only be renamed with --rename-suffix, or listed with -n/--dry-run",
+                    "require_rename_suffix",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Files a run that is not a dry run is about to delete, checked as a whole before the first
+    // one is. Directories deleted whole count the files they hold
+    pub(crate) fn check_deletion(&self, candidates: &[Candidate]) -> Result<(), Box<dyn Error>> {
+        let count: usize = candidates.iter().map(Candidate::file_count).sum();
+        for file in &self.files {
+            if let Some(max) = file.max_files
+                && count > max
+            {
+                return Err(file.blocked(
+                    &format!("{count} files would be deleted, at most {max} are allowed"),
+                    "max_files",
+                ));
+            }
+            if let Some((path, dir)) = candidates
+                .iter()
+                .find_map(|c| Some((&c.path, file.dry_run_dir_reached(c)?)))
+            {
+                return Err(file.blocked(
+                    &format!("{} can only be listed with -n/--dry-run", path.display()),
+                    &format!("dry_run of path \"{}\"", dir.display()),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    use crate::candidate::Contents;
+
+    fn untrusted(paths: &[PathBuf]) -> Vec<PolicySource> {
+        paths
+            .iter()
+            .map(|path| PolicySource {
+                path: path.clone(),
+                owner: None,
+            })
+            .collect()
+    }
+
+    fn policy(data: &str) -> Result<Policy, Box<dyn Error>> {
+        let path = Path::new("/etc/rmx/policy.toml");
+        Ok(Policy {
+            files: vec![PolicyFile::parse(path, data)?],
+        })
+    }
+
+    #[test]
+    fn parse_rules() -> Result<(), Box<dyn Error>> {
+        let data = r#"
+            # Pushed by the fleet
+            forbid_invert = true
+            max_files = 500
+            require_rename_suffix = false
+
+            [[paths]]
+            path = "/srv/prod"
+            dry_run = true
+
+            [[paths]]
+            path = "/srv/scratch"
+
+            [[paths]]
+            path = '/var/lib/db'
+            dry_run = true
+        "#;
+
+        assert_eq!(
+            PolicyFile {
+                path: PathBuf::from("/etc/rmx/policy.toml"),
+                dry_run_paths: vec![PathBuf::from("/srv/prod"), PathBuf::from("/var/lib/db")],
+                forbid_invert: true,
+                max_files: Some(500),
+                require_rename_suffix: false,
+            },
+            PolicyFile::parse(Path::new("/etc/rmx/policy.toml"), data)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_rejects_what_it_does_not_understand() {
+        let path = Path::new("policy.toml");
+        for data in [
+            "require_trash = true",
+            "forbid_invert",
+            "forbid_invert = maybe",
+            "max_files = -1",
+            "dry_run_paths = [\"/srv\"]",
+            "[[paths]]\npath = \"srv\"\ndry_run = true",
+            "[[paths]]\ndry_run = true",
+            "[[paths]]\npath = \"/srv\"\ndry_run = \"yes\"",
+        ] {
+            assert!(PolicyFile::parse(path, data).is_err(), "{data}");
+        }
+    }
+
+    #[test]
+    fn missing_and_unreadable_files() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let missing = dir.path().join("policy.toml");
+        assert_eq!(
+            Policy::default(),
+            Policy::load_from(&untrusted(std::slice::from_ref(&missing)))?
+        );
+
+        std::fs::write(&missing, "forbid_invert = true\nmax_file = 10\n")?;
+        let e = Policy::load_from(&untrusted(&[missing]))
+            .unwrap_err()
+            .to_string();
+        assert!(e.starts_with("Blocked by policy: unknown field `max_file`"));
+        assert!(e.contains("at line 2"), "{e}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn flags() -> Result<(), Box<dyn Error>> {
+        let policy = policy("forbid_invert = true\nrequire_rename_suffix = true\n")?;
+
+        let e = policy.check_flags(true, true, false).unwrap_err();
+        assert_eq!(
+            "Blocked by policy: --invert is not allowed (forbid_invert in /etc/rmx/policy.toml).",
+            e.to_string()
+        );
+        assert!(
+            policy
+                .check_flags(false, false, false)
+                .unwrap_err()
+                .to_string()
+                .contains("(require_rename_suffix in /etc/rmx/policy.toml)")
+        );
+        assert!(policy.check_flags(false, true, false).is_ok());
+        assert!(policy.check_flags(false, false, true).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn deletions() -> Result<(), Box<dyn Error>> {
+        let policy = policy("max_files = 2\n[[paths]]\npath = \"/srv/prod\"\ndry_run = true\n")?;
+        let files = |names: &[&str]| -> Vec<Candidate> {
+            names
+                .iter()
+                .map(|name| Candidate::new(PathBuf::from(name), CandidateKind::File))
+                .collect()
+        };
+
+        assert!(policy.check_deletion(&files(&["/srv/a", "/srv/b"])).is_ok());
+        assert_eq!(
+            "Blocked by policy: 3 files would be deleted, at most 2 are allowed (max_files in \
+            /etc/rmx/policy.toml).",
+            policy
+                .check_deletion(&files(&["/srv/a", "/srv/b", "/srv/c"]))
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(
+            policy
+                .check_deletion(&files(&["/srv/production/a", "/srv/prod/b"]))
+                .unwrap_err()
+                .to_string()
+                .contains(
+                    "/srv/prod/b can only be listed with -n/--dry-run (dry_run of path \"/srv/prod\""
+                )
+        );
+        assert!(policy.dry_run_rule(Path::new("/srv/prod/logs")).is_some());
+        assert!(policy.dry_run_rule(Path::new("/srv/production")).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dirs_deleted_whole() -> Result<(), Box<dyn Error>> {
+        let policy = policy("max_files = 2\n[[paths]]\npath = \"/srv/prod\"\ndry_run = true\n")?;
+        let mut build = Candidate::new(PathBuf::from("/srv/app/build"), CandidateKind::Dir);
+        build.contents = Some(Contents { files: 3, bytes: 0 });
+        let srv = Candidate::new(PathBuf::from("/srv"), CandidateKind::Dir);
+
+        assert!(
+            policy
+                .check_deletion(&[build])
+                .unwrap_err()
+                .to_string()
+                .contains("3 files would be deleted")
+        );
+        assert!(
+            policy
+                .check_deletion(&[srv])
+                .unwrap_err()
+                .to_string()
+                .contains("/srv can only be listed with -n/--dry-run")
+        );
+        assert!(
+            policy
+                .check_deletion(&[Candidate::new(PathBuf::from("/srv"), CandidateKind::File)])
+                .is_ok()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dry_run_paths_through_links() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let prod = dir.path().join("prod");
+        std::fs::create_dir(&prod)?;
+        std::os::unix::fs::symlink(&prod, dir.path().join("link"))?;
+        let policy = policy(&format!(
+            "[[paths]]\npath = '{}'\ndry_run = true\n",
+            prod.display()
+        ))?;
+
+        assert!(policy.dry_run_rule(&dir.path().join("link")).is_some());
+        assert!(policy.dry_run_rule(dir.path()).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn every_file_applies() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let (loose, strict) = (
+            dir.path().join("loose.toml"),
+            dir.path().join("strict.toml"),
+        );
+        std::fs::write(&loose, "max_files = 100\n")?;
+        std::fs::write(&strict, "max_files = 1\n")?;
+        let policy = Policy::load_from(&untrusted(&[loose, strict.clone()]))?;
+
+        let e = policy
+            .check_deletion(&[
+                Candidate::new(PathBuf::from("/a"), CandidateKind::File),
+                Candidate::new(PathBuf::from("/b"), CandidateKind::File),
+            ])
+            .unwrap_err();
+        assert!(e.to_string().contains(&strict.display().to_string()));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owner_and_mode_of_trusted_files() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempdir()?;
+        let path = dir.path().join("policy.toml");
+        std::fs::write(&path, "forbid_invert = true\n")?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        let uid = std::fs::metadata(&path)?.uid();
+        let load = |owner| {
+            Policy::load_from(&[PolicySource {
+                path: path.clone(),
+                owner,
+            }])
+        };
+
+        assert_eq!(1, load(Some(uid))?.files.len());
+        let e = load(Some(uid + 1)).unwrap_err().to_string();
+        assert!(e.contains(&format!("is owned by uid {uid}, not by uid {}", uid + 1)));
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o664))?;
+        let e = load(Some(uid)).unwrap_err().to_string();
+        assert!(e.ends_with("can be written by its group or others (mode 664)."));
+        // Further files can only restrict runs, whoever wrote them
+        assert_eq!(1, load(None)?.files.len());
+
+        Ok(())
+    }
+}
@@ -1,88 +1,217 @@
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::error::RmxError;
+use crate::parsing::config::is_section_header;
+
+/// Splits what follows `preset ` on a config line, like `c=o a so out`, into the preset name and
+/// its extensions
+///
+/// ```
+/// assert_eq!(Ok(("c", vec!["o", "a"])), rmx::preset::parse_line("c = o a"));
+/// assert!(rmx::preset::parse_line("c=").is_err());
+/// ```
+pub fn parse_line(line: &str) -> Result<(&str, Vec<&str>), String> {
+    let malformed = || format!("Preset line \"{}\" not formatted correctly", line);
+
+    let (name, extensions) = line.split_once('=').ok_or_else(malformed)?;
+    let name = name.trim();
+    if name.is_empty() || extensions.contains('=') {
+        return Err(malformed());
+    }
 
-pub fn show(path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    let Ok(data) = fs::read_to_string(path) else {
-        return Err(format!("Could not read {}", path.display()).into());
-    };
-    println!("Parsing presets in {}...", path.display());
+    let extensions: Vec<&str> = extensions.split_whitespace().collect();
+    if extensions.is_empty() {
+        return Err(format!(
+            "Preset \"{}\" does not contain any extensions",
+            name
+        ));
+    }
 
-    let lines = data.lines().filter_map(|s| s.strip_prefix("preset "));
+    Ok((name, extensions))
+}
 
-    let mut found = false;
+/// A preset of the config file, like `preset c=o a so out`
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "preset c=o a so out")?;
+///
+/// let preset = rmx::preset::load_preset("c", file.path())?;
+///
+/// assert_eq!(vec!["o", "a", "so", "out"], preset.extensions);
+/// assert_eq!(file.path(), preset.source);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    pub extensions: Vec<String>,
+    /// Config file the preset was read from
+    pub source: PathBuf,
+}
 
-    for line in lines {
-        let splitted: Vec<&str> = line.trim().split("=").collect();
-        if splitted.len() != 2 {
-            eprintln!("Preset line \"{}\" not formatted correctly", line);
-            continue;
+impl Preset {
+    fn new(name: &str, extensions: Vec<&str>, source: &Path) -> Preset {
+        Preset {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            source: source.to_path_buf(),
         }
+    }
+}
 
-        let preset = splitted.first().unwrap();
-        let extensions: Vec<&str> = splitted.last().unwrap().trim().split_whitespace().collect();
-        if extensions.is_empty() {
-            eprintln!("Preset \"{}\" does not contain any extensions", preset);
-            continue;
-        }
+fn read(path: &Path) -> Result<String, RmxError> {
+    fs::read_to_string(path).map_err(|source| RmxError::ConfigRead {
+        path: path.to_path_buf(),
+        source,
+    })
+}
 
-        print!("rmx --preset {preset}: \tRemoves ");
-        let n = extensions.len();
-        for i in 0..n - 1 {
-            print!("*.{}, ", extensions[i]);
-        }
-        println!("*.{}", extensions[n - 1]);
+// Presets are only read before the first section, where `preset` is a key of jobs. Lines are
+// numbered from 1, to point at them in errors
+fn preset_lines(data: &str) -> impl Iterator<Item = (usize, &str)> {
+    data.lines()
+        .take_while(|s| !is_section_header(s))
+        .enumerate()
+        .filter_map(|(i, s)| Some((i + 1, s.strip_prefix("preset ")?)))
+}
 
-        found = true;
-    }
+// Valid presets, along with the reason each invalid preset line was left aside
+fn read_presets(path: &Path) -> Result<(Vec<Preset>, Vec<String>), RmxError> {
+    let data = read(path)?;
 
-    if !found {
-        println!("Could not find any valid preset in {}", path.display());
+    let mut presets = Vec::new();
+    let mut invalid = Vec::new();
+    for (_, line) in preset_lines(&data) {
+        match parse_line(line) {
+            Ok((name, extensions)) => presets.push(Preset::new(name, extensions, path)),
+            Err(e) => invalid.push(e),
+        }
     }
 
-    Ok(())
+    Ok((presets, invalid))
 }
 
-// Only supported in linux filesystems
-pub fn parse(preset: &String, path: &PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
-    let Ok(data) = fs::read_to_string(path) else {
-        return Err(format!("Could not read {}", path.display()).into());
-    };
+/// Every valid preset of the config file, in file order. Invalid preset lines are skipped
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "preset java=jar class\npreset broken=\npreset c=o a")?;
+///
+/// let presets = rmx::preset::load_presets(file.path())?;
+///
+/// assert_eq!(2, presets.len());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn load_presets(path: &Path) -> Result<Vec<Preset>, RmxError> {
+    Ok(read_presets(path)?.0)
+}
 
-    let prefix = format!("preset {preset}");
-    let Some(line) = data.lines().find(|s| s.starts_with(&prefix)) else {
-        return Err(format!("Could not find preset \"{}\" in {}", preset, path.display()).into());
+/// Preset with this exact name, if any
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "preset cpp=o hpp\npreset c=o a")?;
+/// let presets = rmx::preset::load_presets(file.path())?;
+///
+/// assert_eq!(vec!["o", "a"], rmx::preset::find(&presets, "c").unwrap().extensions);
+/// assert!(rmx::preset::find(&presets, "java").is_none());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn find<'a>(presets: &'a [Preset], name: &str) -> Option<&'a Preset> {
+    presets.iter().find(|p| p.name == name)
+}
+
+/// Unlike find over load_presets, tells a missing preset apart from a malformed one
+///
+/// ```
+/// use std::io::Write;
+/// use rmx::RmxError;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "preset c=")?;
+///
+/// let missing = rmx::preset::load_preset("java", file.path());
+/// let invalid = rmx::preset::load_preset("c", file.path());
+///
+/// assert!(matches!(missing, Err(RmxError::PresetNotFound { .. })));
+/// assert!(matches!(invalid, Err(RmxError::InvalidPreset { .. })));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn load_preset(name: &str, path: &Path) -> Result<Preset, RmxError> {
+    Ok(load_preset_with_line(name, path)?.0)
+}
+
+/// Like load_preset, along with the number of the line defining the preset, from 1
+#[doc(hidden)]
+pub fn load_preset_with_line(name: &str, path: &Path) -> Result<(Preset, usize), RmxError> {
+    let data = read(path)?;
+
+    // Names are compared whole, so that `c` does not pick `preset cpp=...`
+    let Some((number, line)) =
+        preset_lines(&data).find(|(_, s)| s.split('=').next().is_some_and(|n| n.trim() == name))
+    else {
+        return Err(RmxError::PresetNotFound {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+        });
     };
 
-    let splitted: Vec<&str> = line.split("=").collect();
+    let (name, extensions) = parse_line(line).map_err(|reason| RmxError::InvalidPreset {
+        reason,
+        path: path.to_path_buf(),
+    })?;
 
-    if splitted.len() != 2 {
-        return Err(format!(
-            "Preset \"{}\" not formatted correctly in {}",
-            preset,
-            path.display()
-        )
-        .into());
-    }
+    Ok((Preset::new(name, extensions, path), number))
+}
 
-    let extensions: Vec<String> = splitted
-        .last()
-        .unwrap()
-        .trim()
-        .split_whitespace()
-        .map(|s| s.to_string())
-        .collect();
+// Why each invalid preset line of the config file is left aside, an unreadable file having none
+pub(crate) fn invalid_lines(path: &Path) -> Vec<String> {
+    read_presets(path)
+        .map(|(_, invalid)| invalid)
+        .unwrap_or_default()
+}
 
-    if extensions.is_empty() {
-        return Err(format!(
-            "Preset \"{}\" does not contain any extensions in {}",
-            preset,
-            path.display()
-        )
-        .into());
+// One line per preset, as shown by --presets
+pub(crate) fn format_presets(presets: &[Preset]) -> Vec<String> {
+    presets
+        .iter()
+        .map(|preset| {
+            let patterns: Vec<String> =
+                preset.extensions.iter().map(|e| format!("*.{e}")).collect();
+            format!(
+                "rmx --preset {}: \tRemoves {}",
+                preset.name,
+                patterns.join(", ")
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn show(path: &Path) -> Result<(), Box<dyn Error>> {
+    println!("Parsing presets in {}...", path.display());
+    let (presets, invalid) = read_presets(path)?;
+
+    for e in invalid {
+        eprintln!("{e}");
+    }
+    for line in format_presets(&presets) {
+        println!("{line}");
+    }
+
+    if presets.is_empty() {
+        println!("Could not find any valid preset in {}", path.display());
     }
 
-    Ok(extensions)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -106,7 +235,7 @@ mod tests {
         let preset = "out".to_string();
         let config_path = Path::new("-unknown file-").to_path_buf();
 
-        let res = parse(&preset, &config_path);
+        let res = load_preset(&preset, &config_path);
 
         assert!(res.is_err());
 
@@ -121,7 +250,7 @@ mod tests {
         let preset = "out".to_string();
         let config_path = file.path().to_path_buf();
 
-        let res = parse(&preset, &config_path);
+        let res = load_preset(&preset, &config_path);
 
         assert!(res.is_err());
 
@@ -136,7 +265,7 @@ mod tests {
         let preset = "c".to_string();
         let config_path = file.path().to_path_buf();
 
-        let res = parse(&preset, &config_path);
+        let res = load_preset(&preset, &config_path);
 
         assert!(res.is_err());
 
@@ -151,7 +280,7 @@ mod tests {
         let preset = "java".to_string();
         let config_path = file.path().to_path_buf();
 
-        let res = parse(&preset, &config_path)?;
+        let res = load_preset(&preset, &config_path)?.extensions;
 
         assert_eq!(1, res.len());
 
@@ -166,10 +295,103 @@ mod tests {
         let preset = "c".to_string();
         let config_path = file.path().to_path_buf();
 
-        let res = parse(&preset, &config_path)?;
+        let res = load_preset(&preset, &config_path)?.extensions;
 
         assert_eq!(4, res.len());
 
         Ok(())
     }
+
+    #[test]
+    fn parse_preset_does_not_match_name_prefix() -> Result<(), Box<dyn Error>> {
+        let presets = "preset cpp=o hpp\npreset c=o a so out";
+        let file = create_config_file(presets)?;
+
+        let preset = "c".to_string();
+        let config_path = file.path().to_path_buf();
+
+        let res = load_preset(&preset, &config_path)?.extensions;
+
+        assert_eq!(vec!["o", "a", "so", "out"], res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_line_edge_cases() {
+        assert_eq!(Ok(("c", vec!["o", "a"])), parse_line("c = o a"));
+        assert!(parse_line("").is_err());
+        assert!(parse_line("=").is_err());
+        assert!(parse_line("=o").is_err());
+        assert!(parse_line("c=").is_err());
+        assert!(parse_line("c=o=a").is_err());
+        assert!(parse_line("é=ü").is_ok());
+    }
+
+    #[test]
+    fn load_presets_skips_invalid_lines() -> Result<(), Box<dyn Error>> {
+        let presets = "preset java=jar class\npreset empty=\nalias jpeg=jpg\npreset c=o a";
+        let file = create_config_file(presets)?;
+
+        let res = load_presets(file.path())?;
+
+        assert_eq!(
+            vec![
+                Preset {
+                    name: "java".to_string(),
+                    extensions: vec!["jar".to_string(), "class".to_string()],
+                    source: file.path().to_path_buf(),
+                },
+                Preset {
+                    name: "c".to_string(),
+                    extensions: vec!["o".to_string(), "a".to_string()],
+                    source: file.path().to_path_buf(),
+                },
+            ],
+            res
+        );
+        assert_eq!(Some(&res[1]), find(&res, "c"));
+        assert_eq!(None, find(&res, "empty"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_preset_errors_are_typed() -> Result<(), Box<dyn Error>> {
+        let file = create_config_file("preset c=")?;
+
+        let missing = load_preset("java", file.path());
+        let invalid = load_preset("c", file.path());
+        let unreadable = load_presets(Path::new("-unknown file-"));
+
+        assert!(matches!(missing, Err(RmxError::PresetNotFound { .. })));
+        assert!(matches!(invalid, Err(RmxError::InvalidPreset { .. })));
+        assert!(matches!(unreadable, Err(RmxError::ConfigRead { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_presets_as_patterns() -> Result<(), Box<dyn Error>> {
+        let file = create_config_file("preset c=o a")?;
+
+        let lines = format_presets(&load_presets(file.path())?);
+
+        assert_eq!(vec!["rmx --preset c: \tRemoves *.o, *.a"], lines);
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_presets_stops_at_sections() -> Result<(), Box<dyn Error>> {
+        let file = create_config_file("preset c=o a\n[job.nightly]\npreset = c\npreset java=jar")?;
+
+        let res = read_presets(file.path())?;
+
+        assert_eq!(vec!["c"], res.0.iter().map(|p| &p.name).collect::<Vec<_>>());
+        assert!(res.1.is_empty());
+        assert!(load_preset("java", file.path()).is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,75 @@
+use std::error::Error;
+
+// Parses sizes like `500K`, `10M` or `1.5G`, units being powers of 1024 as in listings (`KiB`,
+// `MiB`...). A plain number is a number of bytes
+pub fn parse_size(token: &str) -> Result<u64, Box<dyn Error>> {
+    let invalid = || -> Box<dyn Error> {
+        format!("Invalid size \"{token}\", expected a number of bytes, or a number followed by K, M, G or T like 500K or 1.5G")
+            .into()
+    };
+
+    let split = token
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(token.len());
+    let (value, unit) = token.split_at(split);
+    // Parsed as a float for 1.5G, which also reads `inf` or `1e3`, hence the digits first
+    if value.is_empty() || value.starts_with('.') || value.ends_with('.') {
+        return Err(invalid());
+    }
+    let value: f64 = value.parse().map_err(|_| invalid())?;
+
+    let exponent = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        _ => return Err(invalid()),
+    };
+
+    let bytes = (value * 1024f64.powi(exponent)).round();
+    if bytes >= u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sizes() -> Result<(), Box<dyn Error>> {
+        assert_eq!(0, parse_size("0")?);
+        assert_eq!(512, parse_size("512")?);
+        assert_eq!(512, parse_size("512B")?);
+        assert_eq!(500 * 1024, parse_size("500K")?);
+        assert_eq!(10 * 1024 * 1024, parse_size("10M")?);
+        assert_eq!(10 * 1024 * 1024, parse_size("10MiB")?);
+        assert_eq!(1536 * 1024 * 1024, parse_size("1.5G")?);
+        assert_eq!(2 * 1024u64.pow(4), parse_size("2t")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_sizes_should_err() {
+        for token in [
+            "", "K", "-1K", "-5", "1.5.2M", ".5M", "5.M", "10X", "10 M", "M10", "inf", "1e3",
+        ] {
+            assert!(parse_size(token).is_err(), "{token} should be invalid");
+        }
+    }
+
+    #[test]
+    fn parse_error_names_token() {
+        let e = parse_size("-1K").unwrap_err();
+
+        assert!(e.to_string().contains("\"-1K\""));
+    }
+
+    #[test]
+    fn parse_overflow_should_err() {
+        assert!(parse_size("99999999999T").is_err());
+    }
+}
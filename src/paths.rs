@@ -0,0 +1,418 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// Paths rmx writes to files, like checkpoints, audit logs and inventories, are absolute, so that
+// reading them back does not depend on the directory rmx was run from. Relative entries of
+// lists rmx reads, like keep lists, are relative to the directory holding the list, unless
+// --base gives another one
+
+// They are also written with to_portable_string, which any path survives: UTF-8, with `%`,
+// control characters and what is not Unicode written `%HH`, so that line and tab separated
+// outputs stay whole. Names without these read the same as before it
+
+// Separators written by to_portable_string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Separators {
+    // As the platform writes them, for outputs read back on the machine that wrote them
+    Native,
+    // `/` whatever the platform, for outputs compared across machines, like plans
+    Forward,
+}
+
+// How a platform writes its paths: Windows takes `\` as a separator, and its names are UTF-16,
+// written as WTF-8 so that unpaired surrogates are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    Unix,
+    Windows,
+}
+
+const CURRENT: Flavor = if cfg!(windows) {
+    Flavor::Windows
+} else {
+    Flavor::Unix
+};
+
+// `path` as written to files (see Separators)
+pub(crate) fn to_portable_string(path: &Path, separators: Separators) -> String {
+    encode(&os_bytes(path), CURRENT, separators)
+}
+
+// The path written by to_portable_string with the same separators. Escapes of bytes this
+// platform cannot name, like those of a Unix path read on Windows, are read lossily
+pub(crate) fn from_portable_string(portable: &str, separators: Separators) -> PathBuf {
+    let bytes = decode(portable, CURRENT, separators);
+    PathBuf::from(
+        os_string(&bytes).unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned().into()),
+    )
+}
+
+fn encode(bytes: &[u8], flavor: Flavor, separators: Separators) -> String {
+    let mut portable = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c == '%' || c.is_ascii_control() {
+                let _ = write!(portable, "%{:02X}", c as u8);
+            } else if c == '\\' && flavor == Flavor::Windows && separators == Separators::Forward {
+                portable.push('/');
+            } else {
+                portable.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(portable, "%{byte:02X}");
+        }
+    }
+    portable
+}
+
+// A `%` not followed by two hexadecimal digits is taken as it is, as older outputs wrote it
+fn decode(portable: &str, flavor: Flavor, separators: Separators) -> Vec<u8> {
+    let bytes = portable.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                let forward = flavor == Flavor::Windows && separators == Separators::Forward;
+                decoded.push(if forward && bytes[i] == b'/' {
+                    b'\\'
+                } else {
+                    bytes[i]
+                });
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(unix)]
+fn os_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn os_string(bytes: &[u8]) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Some(OsString::from_vec(bytes.to_vec()))
+}
+
+#[cfg(windows)]
+fn os_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+
+    wtf8_from_wide(path.as_os_str().encode_wide())
+}
+
+#[cfg(windows)]
+fn os_string(bytes: &[u8]) -> Option<OsString> {
+    use std::os::windows::ffi::OsStringExt;
+
+    wide_from_wtf8(bytes).map(|wide| OsString::from_wide(&wide))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_string(bytes: &[u8]) -> Option<OsString> {
+    String::from_utf8(bytes.to_vec()).ok().map(OsString::from)
+}
+
+// UTF-8, unpaired surrogates being written as the three bytes UTF-8 would give them
+#[cfg(any(windows, test))]
+fn wtf8_from_wide(wide: impl IntoIterator<Item = u16>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for unit in char::decode_utf16(wide) {
+        match unit {
+            Ok(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Err(e) => {
+                let surrogate = e.unpaired_surrogate();
+                bytes.extend_from_slice(&[
+                    0xE0 | (surrogate >> 12) as u8,
+                    0x80 | ((surrogate >> 6) & 0x3F) as u8,
+                    0x80 | (surrogate & 0x3F) as u8,
+                ]);
+            }
+        }
+    }
+    bytes
+}
+
+// None when `bytes` is neither UTF-8 nor surrogates written by wtf8_from_wide
+#[cfg(any(windows, test))]
+fn wide_from_wtf8(mut bytes: &[u8]) -> Option<Vec<u16>> {
+    let mut wide = Vec::new();
+    while !bytes.is_empty() {
+        let valid = match std::str::from_utf8(bytes) {
+            Ok(valid) => valid,
+            Err(e) => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+        };
+        wide.extend(valid.encode_utf16());
+        bytes = &bytes[valid.len()..];
+        match bytes {
+            [] => (),
+            [0xED, high @ 0xA0..=0xBF, low @ 0x80..=0xBF, rest @ ..] => {
+                wide.push(0xD000 | (u16::from(high & 0x3F) << 6) | u16::from(low & 0x3F));
+                bytes = rest;
+            }
+            _ => return None,
+        }
+    }
+    Some(wide)
+}
+
+// `entry` as found from `base`: joined to it when relative, without `.` components, so that
+// `./a.log` and `a.log` are the same file, and with `..` removing the component before it, so
+// that a list can point next to its directory. Links are not followed, the filesystem not being
+// read, so that `link/..` is taken as the directory holding `link`
+pub(crate) fn resolve(base: &Path, entry: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(entry).components() {
+        match component {
+            Component::CurDir => (),
+            // The parent of the root is the root
+            Component::ParentDir if resolved.file_name().is_some() => {
+                resolved.pop();
+            }
+            Component::ParentDir if resolved.has_root() => (),
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+// `path` resolved against the current directory, as written to files
+pub(crate) fn absolute(path: &Path) -> io::Result<PathBuf> {
+    Ok(resolve(&env::current_dir()?, path))
+}
+
+// Directory the relative entries of the list `list` are resolved against: `base` when given,
+// else the directory holding the list
+pub(crate) fn list_base(list: &Path, base: Option<&Path>) -> io::Result<PathBuf> {
+    match base {
+        Some(base) => absolute(base),
+        None => {
+            let list = absolute(list)?;
+            Ok(list.parent().map_or(list.clone(), Path::to_path_buf))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_entries() {
+        let base = Path::new("/srv/app");
+
+        assert_eq!(
+            PathBuf::from("/srv/app/a.log"),
+            resolve(base, Path::new("a.log"))
+        );
+        assert_eq!(
+            PathBuf::from("/srv/app/logs/a.log"),
+            resolve(base, Path::new("./logs/./a.log"))
+        );
+        assert_eq!(
+            PathBuf::from("/srv/a.log"),
+            resolve(base, Path::new("../a.log"))
+        );
+        assert_eq!(
+            PathBuf::from("/a.log"),
+            resolve(base, Path::new("../../../a.log"))
+        );
+        // Nothing to remove before it
+        assert_eq!(
+            PathBuf::from("../a.log"),
+            resolve(Path::new(""), Path::new("../a.log"))
+        );
+        assert_eq!(
+            PathBuf::from("/etc/a.conf"),
+            resolve(base, Path::new("/etc/a.conf"))
+        );
+    }
+
+    #[test]
+    fn absolute_paths() -> io::Result<()> {
+        let cwd = env::current_dir()?;
+
+        assert_eq!(cwd.join("a.log"), absolute(Path::new("./a.log"))?);
+        assert_eq!(
+            PathBuf::from("/srv/a.log"),
+            absolute(Path::new("/srv/a.log"))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lists_are_relative_to_their_directory() -> io::Result<()> {
+        let cwd = env::current_dir()?;
+
+        assert_eq!(
+            PathBuf::from("/srv/app"),
+            list_base(Path::new("/srv/app/keep.txt"), None)?
+        );
+        assert_eq!(
+            cwd.join("conf"),
+            list_base(Path::new("conf/keep.txt"), None)?
+        );
+        assert_eq!(cwd, list_base(Path::new("keep.txt"), None)?);
+        assert_eq!(
+            PathBuf::from("/data"),
+            list_base(Path::new("/srv/app/keep.txt"), Some(Path::new("/data")))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn portable_unix_paths() {
+        let portable = |raw: &[u8], separators| encode(raw, Flavor::Unix, separators);
+        let round_trip = |raw: &[u8], separators| {
+            assert_eq!(
+                raw,
+                decode(&portable(raw, separators), Flavor::Unix, separators)
+            );
+        };
+
+        assert_eq!(
+            "/srv/app/a.log",
+            portable(b"/srv/app/a.log", Separators::Native)
+        );
+        // A name of its own on Unix, not a separator
+        assert_eq!(
+            "logs/a\\b.log",
+            portable(b"logs/a\\b.log", Separators::Forward)
+        );
+        assert_eq!(
+            "/srv/50%25/a.log",
+            portable(b"/srv/50%/a.log", Separators::Native)
+        );
+        assert_eq!(
+            "/srv/a%0Ab%09c",
+            portable(b"/srv/a\nb\tc", Separators::Native)
+        );
+        // Latin-1, not UTF-8
+        assert_eq!(
+            "/srv/caf%E9.log",
+            portable(b"/srv/caf\xe9.log", Separators::Native)
+        );
+        assert_eq!(
+            "/srv/été.log",
+            portable("/srv/été.log".as_bytes(), Separators::Native)
+        );
+
+        for raw in [
+            &b"/srv/app/a.log"[..],
+            b"logs/a\\b.log",
+            b"/srv/50%/a.log",
+            b"/srv/%41.log",
+            b"/srv/a\nb\r\tc",
+            b"/srv/caf\xe9.log",
+            b"\xff\xfe",
+        ] {
+            round_trip(raw, Separators::Native);
+            round_trip(raw, Separators::Forward);
+        }
+    }
+
+    #[test]
+    fn portable_windows_paths() {
+        let portable = |raw: &str, separators| encode(raw.as_bytes(), Flavor::Windows, separators);
+
+        assert_eq!(
+            "C:\\Users\\a\\b.log",
+            portable("C:\\Users\\a\\b.log", Separators::Native)
+        );
+        assert_eq!(
+            "C:/Users/a/b.log",
+            portable("C:\\Users\\a\\b.log", Separators::Forward)
+        );
+        assert_eq!(
+            "//server/share/logs/a.log",
+            portable("\\\\server\\share\\logs\\a.log", Separators::Forward)
+        );
+        assert_eq!(
+            b"\\\\server\\share\\logs\\a.log".to_vec(),
+            decode(
+                "//server/share/logs/a.log",
+                Flavor::Windows,
+                Separators::Forward
+            )
+        );
+        assert_eq!(
+            b"C:\\Users\\100%.log".to_vec(),
+            decode("C:/Users/100%25.log", Flavor::Windows, Separators::Forward)
+        );
+
+        // An unpaired surrogate, which Windows allows in names
+        let wide = [0x43, 0x3A, 0x5C, 0xD800, 0x61];
+        let bytes = wtf8_from_wide(wide);
+        let written = encode(&bytes, Flavor::Windows, Separators::Native);
+        assert_eq!("C:\\%ED%A0%80a", written);
+        let read = decode(&written, Flavor::Windows, Separators::Native);
+        assert_eq!(Some(wide.to_vec()), wide_from_wtf8(&read));
+    }
+
+    #[test]
+    fn wtf8_round_trip() {
+        let wide: Vec<u16> = "C:\\Users\\été\\🦀.log".encode_utf16().collect();
+        assert_eq!(
+            "C:\\Users\\été\\🦀.log".as_bytes(),
+            wtf8_from_wide(wide.clone())
+        );
+        assert_eq!(
+            Some(wide),
+            wide_from_wtf8("C:\\Users\\été\\🦀.log".as_bytes())
+        );
+        // Trailing surrogate, then a Unix byte Windows cannot name
+        assert_eq!(Some(vec![0x61, 0xDC00]), wide_from_wtf8(b"a\xed\xb0\x80"));
+        assert_eq!(None, wide_from_wtf8(b"caf\xe9"));
+    }
+
+    #[test]
+    fn portable_round_trip_of_real_paths() {
+        for path in ["/srv/app/a.log", "logs/50% off.log", "a\nb.log", "été.log"] {
+            let path = Path::new(path);
+            for separators in [Separators::Native, Separators::Forward] {
+                assert_eq!(
+                    path,
+                    from_portable_string(&to_portable_string(path, separators), separators)
+                );
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn portable_round_trip_of_non_unicode_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/srv/caf\xe9.log"));
+        let portable = to_portable_string(path, Separators::Native);
+
+        assert_eq!("/srv/caf%E9.log", portable);
+        assert_eq!(path, from_portable_string(&portable, Separators::Native));
+    }
+}
@@ -0,0 +1,186 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::candidate::{Candidate, CandidateKind};
+use crate::metadata::RealFs;
+use crate::paths::{self, Separators};
+
+// Bumped whenever the meaning of an existing field changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedFile {
+    // Relative to the start path, with forward slashes (see paths::to_portable_string)
+    pub path: String,
+    pub size: u64,
+    // Seconds since the Unix epoch, only stored on request (see --plan-mtimes) as it makes plans
+    // differ across machines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+}
+
+impl PlannedFile {
+    pub fn modified_time(&self) -> Option<SystemTime> {
+        self.modified
+            .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+    }
+}
+
+// What a run would delete, written by --plan-out to be compared across runs and machines: paths
+// are relative and sorted, and nothing depends on when the plan was made
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionPlan {
+    pub schema_version: u32,
+    pub files: Vec<PlannedFile>,
+}
+
+// Relative to `root` when inside it, with `/` as separator whatever the platform
+pub fn normalize_path(path: &Path, root: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    paths::to_portable_string(relative, Separators::Forward)
+}
+
+impl DeletionPlan {
+    pub fn new(root: &Path, files: &[PathBuf], with_mtimes: bool) -> DeletionPlan {
+        let candidates: Vec<Candidate> = files
+            .iter()
+            .map(|f| Candidate::new(f.clone(), CandidateKind::File))
+            .collect();
+        DeletionPlan::from_candidates(root, &candidates, with_mtimes)
+    }
+
+    // Metadata already read during the run is not read again
+    pub(crate) fn from_candidates(
+        root: &Path,
+        candidates: &[Candidate],
+        with_mtimes: bool,
+    ) -> DeletionPlan {
+        let mut files: Vec<PlannedFile> = candidates
+            .iter()
+            .map(|c| {
+                let stat = c.stat.read(&c.path, &RealFs).ok();
+                let modified = stat
+                    .and_then(|s| s.modified)
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .filter(|_| with_mtimes);
+                PlannedFile {
+                    path: normalize_path(&c.path, root),
+                    size: stat.map_or(0, |s| s.size),
+                    modified,
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        DeletionPlan {
+            schema_version: SCHEMA_VERSION,
+            files,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Serializing plain structs with string keys cannot fail
+        let mut json = serde_json::to_string_pretty(self).unwrap();
+        json.push('\n');
+        json
+    }
+
+    pub fn load(path: &Path) -> Result<DeletionPlan, Box<dyn Error>> {
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read plan {}: {}", path.display(), e))?;
+        let plan: DeletionPlan = serde_json::from_str(&data)
+            .map_err(|e| format!("Invalid plan {}: {}", path.display(), e))?;
+        if plan.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "Unsupported plan schema version {} in {}, expected {}",
+                plan.schema_version,
+                path.display(),
+                SCHEMA_VERSION
+            )
+            .into());
+        }
+        Ok(plan)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, self.to_json())
+            .map_err(|e| format!("Could not write plan to {}: {}", path.display(), e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_relative_paths() {
+        let root = Path::new("/data/run");
+
+        assert_eq!(
+            "sub/a.txt",
+            normalize_path(Path::new("/data/run/sub/a.txt"), root)
+        );
+        assert_eq!("a.txt", normalize_path(Path::new("/data/run/a.txt"), root));
+        // Outside of the start path, like directories from --follow-dir-lists
+        assert_eq!(
+            "/other/b.txt",
+            normalize_path(Path::new("/other/b.txt"), root)
+        );
+        assert_eq!("/", normalize_path(Path::new("/"), Path::new("/data")));
+    }
+
+    #[test]
+    fn plan_is_sorted_and_stable() {
+        let root = Path::new("/missing");
+        let files = [
+            PathBuf::from("/missing/b.txt"),
+            PathBuf::from("/missing/a/z.txt"),
+            PathBuf::from("/missing/a.txt"),
+        ];
+        let mut reversed = files.clone();
+        reversed.reverse();
+
+        let plan = DeletionPlan::new(root, &files, false);
+
+        assert_eq!(
+            vec!["a.txt", "a/z.txt", "b.txt"],
+            plan.files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            plan.to_json(),
+            DeletionPlan::new(root, &reversed, false).to_json()
+        );
+        assert!(
+            plan.to_json()
+                .starts_with("{\n  \"schema_version\": 1,\n  \"files\": [")
+        );
+    }
+
+    #[test]
+    fn plan_round_trip() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let file = temp_dir.path().join("a.txt");
+        fs::write(&file, "abc")?;
+        let plan_path = temp_dir.path().join("plan.json");
+
+        let plan = DeletionPlan::new(temp_dir.path(), &[file], true);
+        plan.write(&plan_path)?;
+
+        assert_eq!(plan, DeletionPlan::load(&plan_path)?);
+        assert_eq!(3, plan.files[0].size);
+        assert!(plan.files[0].modified_time().is_some());
+
+        fs::write(&plan_path, "{\"schema_version\": 2, \"files\": []}")?;
+        assert!(DeletionPlan::load(&plan_path).is_err());
+
+        Ok(())
+    }
+}
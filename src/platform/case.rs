@@ -0,0 +1,147 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::iter;
+use std::path::Path;
+
+// Whether the filesystem holding `dir` finds a file whatever the case of its name, like HFS+,
+// APFS or NTFS by default. It is assumed case sensitive when it cannot be told. Nothing is
+// written to `dir`, which is left as it is by dry runs and deleting runs alike
+pub(crate) fn is_case_insensitive(dir: &Path) -> bool {
+    detect(dir, probe)
+}
+
+// The probe is given so that tests can stand for filesystems the machine does not have
+pub(crate) fn detect(dir: &Path, probe: impl Fn(&Path) -> io::Result<bool>) -> bool {
+    probe(dir).unwrap_or(false)
+}
+
+// Looks `dir` up with its name in another case, or else the first of its entries whose name has
+// a letter, which only fails when none has
+fn probe(dir: &Path) -> io::Result<bool> {
+    let dir = fs::canonicalize(dir)?;
+    let entries = fs::read_dir(&dir)?.filter_map(|entry| Some(entry.ok()?.path()));
+    for path in iter::once(dir.clone()).chain(entries) {
+        if let Some(flipped) = path
+            .file_name()
+            .and_then(|name| flip_case(&name.to_string_lossy()))
+        {
+            return same_entry(&path, &path.with_file_name(flipped));
+        }
+    }
+    Err(io::Error::other("no letter to change the case of"))
+}
+
+// `Logs` is `lOGS`, None meaning there is no letter to change
+fn flip_case(name: &str) -> Option<String> {
+    let flipped: String = name
+        .chars()
+        .map(|c| {
+            if c.is_lowercase() {
+                c.to_uppercase().next().unwrap_or(c)
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect();
+    (flipped != name).then_some(flipped)
+}
+
+// Whether `other` exists and is `path` itself, rather than another file of a case sensitive
+// filesystem named alike
+fn same_entry(path: &Path, other: &Path) -> io::Result<bool> {
+    match fs::symlink_metadata(other) {
+        Ok(_) => Ok(entry_id(path)? == entry_id(other)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(unix)]
+fn entry_id(path: &Path) -> io::Result<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::symlink_metadata(path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn entry_id(path: &Path) -> io::Result<std::path::PathBuf> {
+    fs::canonicalize(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn detect_from_probe() {
+        let dir = Path::new("/Volumes/Data");
+
+        assert!(detect(dir, |_| Ok(true)));
+        assert!(!detect(dir, |_| Ok(false)));
+        // Cannot be told, like a missing directory
+        assert!(!detect(dir, |_| Err(io::Error::other("read-only"))));
+    }
+
+    #[test]
+    fn flips_case() {
+        assert_eq!(Some("lOGS".to_string()), flip_case("Logs"));
+        assert_eq!(Some("ÉTÉ".to_string()), flip_case("été"));
+        assert_eq!(None, flip_case("2024-06"));
+    }
+
+    #[test]
+    fn same_entry_by_identity() -> io::Result<()> {
+        let dir = tempdir()?;
+        let (a, b) = (dir.path().join("a.log"), dir.path().join("b.log"));
+        File::create(&a)?;
+        File::create(&b)?;
+
+        assert!(same_entry(&a, &a)?);
+        assert!(!same_entry(&a, &b)?);
+        assert!(!same_entry(&a, &dir.path().join("c.log"))?);
+
+        Ok(())
+    }
+
+    // The filesystems Linux runs its temporary directories on are case sensitive
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn probe_writes_nothing() -> io::Result<()> {
+        let parent = tempdir()?;
+        let dir = parent.path().join("Logs");
+        fs::create_dir(&dir)?;
+        let modified = fs::metadata(&dir)?.modified()?;
+
+        assert!(!probe(&dir)?);
+        assert!(!is_case_insensitive(&dir));
+        assert!(!is_case_insensitive(&dir.join("missing")));
+        assert_eq!(modified, fs::metadata(&dir)?.modified()?);
+        assert_eq!(0, fs::read_dir(&dir)?.count());
+        // A directory of another case is another directory
+        fs::create_dir(parent.path().join("lOGS"))?;
+        assert!(!probe(&dir)?);
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn probe_falls_back_to_entries() -> io::Result<()> {
+        let parent = tempdir()?;
+        let dir = parent.path().join("2024");
+        fs::create_dir(&dir)?;
+
+        // Neither the directory nor its entries have a letter
+        assert!(probe(&dir).is_err());
+        File::create(dir.join("06"))?;
+        assert!(probe(&dir).is_err());
+        File::create(dir.join("Report.log"))?;
+        assert!(!probe(&dir)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+// From linux/fs.h, which libc does not expose
+const FS_IMMUTABLE_FL: libc::c_int = 0x10;
+const FS_APPEND_FL: libc::c_int = 0x20;
+
+// Inode flags of a path, as shown by lsattr. A trait so that tests do not need the privileges
+// required by chattr
+pub(crate) trait InodeFlags {
+    fn get(&self, path: &Path) -> io::Result<libc::c_int>;
+    fn set(&self, path: &Path, flags: libc::c_int) -> io::Result<()>;
+}
+
+// The FS_IOC_GETFLAGS / FS_IOC_SETFLAGS ioctls, as used by lsattr and chattr
+pub(crate) struct Ioctl;
+
+fn open(path: &Path) -> io::Result<File> {
+    // Flags of a symlink target are not the ones preventing the deletion of the symlink
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+        .open(path)
+}
+
+impl InodeFlags for Ioctl {
+    fn get(&self, path: &Path) -> io::Result<libc::c_int> {
+        let file = open(path)?;
+        let mut flags: libc::c_int = 0;
+        let res = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(flags)
+    }
+
+    fn set(&self, path: &Path, flags: libc::c_int) -> io::Result<()> {
+        let file = open(path)?;
+        let res = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags) };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+// Why a path could not be deleted: the path itself, or the directory holding it, is immutable
+// or append-only
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Protection {
+    // Path holding the flags, the deleted path or its parent
+    path: PathBuf,
+    flags: libc::c_int,
+    parent: bool,
+}
+
+impl Protection {
+    fn clear(&self, inode_flags: &impl InodeFlags) -> io::Result<()> {
+        let flags = inode_flags.get(&self.path)?;
+        inode_flags.set(&self.path, flags & !(FS_IMMUTABLE_FL | FS_APPEND_FL))
+    }
+}
+
+impl fmt::Display for Protection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, option) = if self.flags & FS_IMMUTABLE_FL != 0 {
+            ("immutable", "-i")
+        } else {
+            ("append-only", "-a")
+        };
+        if self.parent {
+            write!(
+                f,
+                "parent directory {} is {what}, run chattr {option} on it to remove",
+                self.path.display()
+            )
+        } else {
+            write!(f, "{what}, run chattr {option} to remove")
+        }
+    }
+}
+
+// Whether a failed deletion comes from inode flags. Both flags make unlink fail with EPERM,
+// even for root, whether they are set on the file or on its directory
+pub(crate) fn protection(
+    err: &io::Error,
+    path: &Path,
+    inode_flags: &impl InodeFlags,
+) -> Option<Protection> {
+    if err.raw_os_error() != Some(libc::EPERM) {
+        return None;
+    }
+
+    let candidates = [(path, false)]
+        .into_iter()
+        .chain(path.parent().map(|dir| (dir, true)));
+    for (p, parent) in candidates {
+        // Files on filesystems without inode flags are not protected this way
+        let Ok(flags) = inode_flags.get(p) else {
+            continue;
+        };
+        let flags = flags & (FS_IMMUTABLE_FL | FS_APPEND_FL);
+        if flags != 0 {
+            return Some(Protection {
+                path: p.to_path_buf(),
+                flags,
+                parent,
+            });
+        }
+    }
+    None
+}
+
+// Deletes with `remove`, explaining failures caused by inode flags. With `clear`, such flags are
+// cleared and the deletion is retried once
+pub(crate) fn remove(
+    path: &Path,
+    clear: bool,
+    inode_flags: &impl InodeFlags,
+    remove: impl Fn() -> io::Result<()>,
+) -> Result<(), String> {
+    let Err(e) = remove() else {
+        return Ok(());
+    };
+    let Some(protection) = protection(&e, path, inode_flags) else {
+        return Err(e.to_string());
+    };
+    if !clear {
+        return Err(protection.to_string());
+    }
+
+    protection
+        .clear(inode_flags)
+        .map_err(|e| format!("{protection}, could not clear it: {e}"))?;
+    remove().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+
+    // Flags kept in memory, paths missing from the map having no flags support
+    #[derive(Default)]
+    struct MockFlags {
+        flags: RefCell<HashMap<PathBuf, libc::c_int>>,
+    }
+
+    impl MockFlags {
+        fn with(entries: &[(&str, libc::c_int)]) -> MockFlags {
+            let flags = entries
+                .iter()
+                .map(|(p, f)| (PathBuf::from(p), *f))
+                .collect();
+            MockFlags {
+                flags: RefCell::new(flags),
+            }
+        }
+
+        fn of(&self, path: &str) -> libc::c_int {
+            self.flags.borrow()[Path::new(path)]
+        }
+    }
+
+    impl InodeFlags for MockFlags {
+        fn get(&self, path: &Path) -> io::Result<libc::c_int> {
+            self.flags
+                .borrow()
+                .get(path)
+                .copied()
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOTTY))
+        }
+
+        fn set(&self, path: &Path, flags: libc::c_int) -> io::Result<()> {
+            self.flags.borrow_mut().insert(path.to_path_buf(), flags);
+            Ok(())
+        }
+    }
+
+    // Fails with EPERM while the file or its directory holds a protecting flag
+    fn mock_remove<'a>(
+        mock: &'a MockFlags,
+        path: &'a str,
+        attempts: &'a Cell<usize>,
+    ) -> impl Fn() -> io::Result<()> + 'a {
+        move || {
+            attempts.set(attempts.get() + 1);
+            let dir = Path::new(path).parent().unwrap();
+            let protected = [Path::new(path), dir]
+                .iter()
+                .any(|p| mock.get(p).unwrap_or(0) & (FS_IMMUTABLE_FL | FS_APPEND_FL) != 0);
+            if protected {
+                return Err(io::Error::from_raw_os_error(libc::EPERM));
+            }
+            Ok(())
+        }
+    }
+
+    const EXTENTS_FL: libc::c_int = 0x80000;
+
+    #[test]
+    fn classify_immutable_file() {
+        let mock = MockFlags::with(&[("/srv/a.log", FS_IMMUTABLE_FL | EXTENTS_FL), ("/srv", 0)]);
+        let eperm = io::Error::from_raw_os_error(libc::EPERM);
+
+        let protection = protection(&eperm, Path::new("/srv/a.log"), &mock).unwrap();
+
+        assert_eq!("immutable, run chattr -i to remove", protection.to_string());
+    }
+
+    #[test]
+    fn classify_append_only_parent() {
+        let mock = MockFlags::with(&[("/srv/a.log", 0), ("/srv", FS_APPEND_FL)]);
+        let eperm = io::Error::from_raw_os_error(libc::EPERM);
+
+        let protection = protection(&eperm, Path::new("/srv/a.log"), &mock).unwrap();
+
+        assert_eq!(
+            "parent directory /srv is append-only, run chattr -a on it to remove",
+            protection.to_string()
+        );
+    }
+
+    #[test]
+    fn classify_other_errors() {
+        let mock = MockFlags::with(&[("/srv/a.log", FS_IMMUTABLE_FL)]);
+        let eacces = io::Error::from_raw_os_error(libc::EACCES);
+        let eperm = io::Error::from_raw_os_error(libc::EPERM);
+
+        // Only EPERM comes from inode flags
+        assert_eq!(None, protection(&eacces, Path::new("/srv/a.log"), &mock));
+        // Neither the file nor its directory is flagged, or flags are not supported
+        assert_eq!(None, protection(&eperm, Path::new("/srv/b.log"), &mock));
+    }
+
+    #[test]
+    fn remove_without_clearing() {
+        let mock = MockFlags::with(&[("/srv/a.log", FS_IMMUTABLE_FL), ("/srv", 0)]);
+        let attempts = Cell::new(0);
+
+        let res = remove(
+            Path::new("/srv/a.log"),
+            false,
+            &mock,
+            mock_remove(&mock, "/srv/a.log", &attempts),
+        );
+
+        assert_eq!(Err("immutable, run chattr -i to remove".to_string()), res);
+        assert_eq!(1, attempts.get());
+        assert_eq!(FS_IMMUTABLE_FL, mock.of("/srv/a.log"));
+    }
+
+    #[test]
+    fn remove_clearing_flags_retries_once() {
+        let mock = MockFlags::with(&[
+            ("/srv/a.log", FS_IMMUTABLE_FL | FS_APPEND_FL | EXTENTS_FL),
+            ("/srv", 0),
+        ]);
+        let attempts = Cell::new(0);
+
+        let res = remove(
+            Path::new("/srv/a.log"),
+            true,
+            &mock,
+            mock_remove(&mock, "/srv/a.log", &attempts),
+        );
+
+        assert_eq!(Ok(()), res);
+        assert_eq!(2, attempts.get());
+        // Other flags are left as they were
+        assert_eq!(EXTENTS_FL, mock.of("/srv/a.log"));
+    }
+
+    #[test]
+    fn remove_other_failures_are_kept() {
+        let mock = MockFlags::default();
+        let attempts = Cell::new(0);
+
+        let res = remove(Path::new("/srv/a.log"), true, &mock, || {
+            attempts.set(attempts.get() + 1);
+            Err(io::Error::from_raw_os_error(libc::ENOENT))
+        });
+
+        assert!(res.unwrap_err().contains("No such file"));
+        assert_eq!(1, attempts.get());
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+// Whether another process holds an advisory lock on the file, be it a flock lock or an fcntl
+// (POSIX) lock, the two being independent on Linux. Only a lock that would prevent reading is
+// reported: writers hold exclusive locks, readers sharing the file are left alone
+pub(crate) fn is_locked(path: &Path) -> bool {
+    // Files that cannot be opened are left to the deletion, which reports why
+    let Ok(file) = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+        .open(path)
+    else {
+        return false;
+    };
+    flock_held(&file).unwrap_or(false) || fcntl_held(&file).unwrap_or(false)
+}
+
+// Takes a shared lock without waiting, releasing it right away
+fn flock_held(file: &File) -> io::Result<bool> {
+    let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) };
+    if res == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            return Ok(true);
+        }
+        return Err(err);
+    }
+    unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) };
+    Ok(false)
+}
+
+// F_GETLK only asks whether a read lock would conflict, without taking any lock. Locks held by
+// this process never conflict
+fn fcntl_held(file: &File) -> io::Result<bool> {
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_RDLCK as libc::c_short;
+    lock.l_whence = libc::SEEK_SET as libc::c_short;
+    // A length of 0 stands for the whole file
+    let res = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(lock.l_type != libc::F_UNLCK as libc::c_short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn flock_exclusive_is_locked() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("busy.log");
+        // flock locks belong to open files, so a second open of the same file conflicts even
+        // within this process
+        let holder = File::create(&path)?;
+
+        assert!(!is_locked(&path));
+
+        unsafe { libc::flock(holder.as_raw_fd(), libc::LOCK_EX) };
+        assert!(is_locked(&path));
+
+        unsafe { libc::flock(holder.as_raw_fd(), libc::LOCK_UN) };
+        assert!(!is_locked(&path));
+
+        Ok(())
+    }
+
+    #[test]
+    fn shared_locks_and_probes_do_not_lock() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("read.log");
+        let reader = File::create(&path)?;
+        unsafe { libc::flock(reader.as_raw_fd(), libc::LOCK_SH) };
+
+        assert!(!is_locked(&path));
+        // The probe released its own lock
+        let res = unsafe { libc::flock(reader.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        assert_eq!(0, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_files_are_not_locked() {
+        assert!(!is_locked(Path::new("/nonexistent/rmx.log")));
+    }
+}
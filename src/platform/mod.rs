@@ -0,0 +1,14 @@
+// Platform specific helpers, each module is gated on the platforms supporting it
+pub(crate) mod case;
+#[cfg(target_os = "linux")]
+pub(crate) mod immutable;
+#[cfg(unix)]
+pub(crate) mod locks;
+#[cfg(unix)]
+pub(crate) mod mounts;
+#[cfg(unix)]
+pub(crate) mod priority;
+#[cfg(unix)]
+pub(crate) mod space;
+#[cfg(unix)]
+pub(crate) mod users;
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Mount {
+    // Major and minor numbers of the device, as in st_dev
+    pub(crate) device: (u64, u64),
+    pub(crate) mount_point: PathBuf,
+}
+
+// Mount points escape spaces, tabs, newlines and backslashes as octal sequences (`\040`)
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && bytes[i + 1..i + 4]
+                .iter()
+                .all(|b| (b'0'..=b'7').contains(b))
+        {
+            let value =
+                (bytes[i + 1] - b'0') * 64 + (bytes[i + 2] - b'0') * 8 + (bytes[i + 3] - b'0');
+            out.push(value);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Parses the content of /proc/self/mountinfo, ignoring malformed lines, e.g.:
+// 36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+pub(crate) fn parse_mountinfo(data: &str) -> Vec<Mount> {
+    data.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (major, minor) = fields.nth(2)?.split_once(':')?;
+            let mount_point = fields.nth(1)?;
+            Some(Mount {
+                device: (major.parse().ok()?, minor.parse().ok()?),
+                mount_point: PathBuf::from(unescape_octal(mount_point)),
+            })
+        })
+        .collect()
+}
+
+// Decodes st_dev following the Linux (glibc) encoding
+pub(crate) fn major_minor(dev: u64) -> (u64, u64) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    (major, minor)
+}
+
+// Several mount points can share a device (bind mounts), so the deepest one containing the
+// file is preferred
+fn mount_point_of(mounts: &[Mount], device: (u64, u64), path: &Path) -> Option<PathBuf> {
+    let on_device = mounts.iter().filter(|m| m.device == device);
+    on_device
+        .clone()
+        .filter(|m| path.starts_with(&m.mount_point))
+        .max_by_key(|m| m.mount_point.components().count())
+        .or_else(|| on_device.min_by_key(|m| m.mount_point.components().count()))
+        .map(|m| m.mount_point.clone())
+}
+
+// Number of files per mount point, or None when mount points cannot be resolved
+pub(crate) fn count_per_mount_point(
+    mounts: &[Mount],
+    files: &[PathBuf],
+) -> Option<BTreeMap<PathBuf, usize>> {
+    let mut devices: BTreeMap<u64, (PathBuf, usize)> = BTreeMap::new();
+    for file in files {
+        let Ok(metadata) = fs::symlink_metadata(file) else {
+            continue;
+        };
+        let entry = devices
+            .entry(metadata.dev())
+            .or_insert_with(|| (file.clone(), 0));
+        entry.1 += 1;
+    }
+
+    let mut counts = BTreeMap::new();
+    for (dev, (sample, count)) in devices {
+        let mount_point = mount_point_of(mounts, major_minor(dev), &sample)?;
+        *counts.entry(mount_point).or_insert(0) += count;
+    }
+    Some(counts)
+}
+
+// Device holding `path`, as in st_dev, links being followed like the walk follows them
+pub(crate) fn device(path: &Path) -> io::Result<u64> {
+    Ok(fs::metadata(path)?.dev())
+}
+
+// Whether `dir` is on another filesystem than the start path (see --one-file-system). Directories
+// whose device cannot be read are walked, reading them failing just the same
+pub(crate) fn crosses_filesystem(
+    root_device: u64,
+    dir: &Path,
+    device_of: impl Fn(&Path) -> io::Result<u64>,
+) -> bool {
+    device_of(dir).is_ok_and(|device| device != root_device)
+}
+
+pub(crate) fn read_mounts() -> Option<Vec<Mount>> {
+    let data = fs::read_to_string("/proc/self/mountinfo").ok()?;
+    Some(parse_mountinfo(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTINFO: &str = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+25 22 0:21 / /proc rw,nosuid shared:12 - proc proc rw
+31 22 8:17 / /home rw,relatime shared:2 - ext4 /dev/sdb1 rw
+40 22 0:45 / /mnt/my\\040nas rw,relatime shared:3 - nfs4 nas:/export rw
+41 31 8:17 /user/bind /srv/bind rw,relatime shared:2 - ext4 /dev/sdb1 rw
+malformed line
+";
+
+    #[test]
+    fn crossing_filesystems() {
+        let device_of = |path: &Path| match path.to_str() {
+            Some("/srv/data") => Ok(2049),
+            Some("/srv/nfs") => Ok(45),
+            _ => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+        };
+
+        assert!(!crosses_filesystem(2049, Path::new("/srv/data"), device_of));
+        assert!(crosses_filesystem(2049, Path::new("/srv/nfs"), device_of));
+        assert!(!crosses_filesystem(
+            2049,
+            Path::new("/srv/locked"),
+            device_of
+        ));
+    }
+
+    #[test]
+    fn parse_mountinfo_fixture() {
+        let mounts = parse_mountinfo(MOUNTINFO);
+
+        assert_eq!(mounts.len(), 5);
+        assert_eq!(
+            mounts[0],
+            Mount {
+                device: (8, 1),
+                mount_point: PathBuf::from("/")
+            }
+        );
+        assert_eq!(mounts[2].device, (8, 17));
+        assert_eq!(mounts[2].mount_point, PathBuf::from("/home"));
+    }
+
+    #[test]
+    fn parse_mountinfo_unescapes_spaces() {
+        let mounts = parse_mountinfo(MOUNTINFO);
+
+        assert_eq!(mounts[3].mount_point, PathBuf::from("/mnt/my nas"));
+    }
+
+    #[test]
+    fn major_minor_decoding() {
+        assert_eq!((8, 1), major_minor(0x801));
+        assert_eq!((259, 3), major_minor(0x10303));
+        assert_eq!((8, 256), major_minor(0x100800));
+    }
+
+    #[test]
+    fn mount_point_prefers_deepest_containing_mount() {
+        let mounts = parse_mountinfo(MOUNTINFO);
+
+        let bind = mount_point_of(&mounts, (8, 17), Path::new("/srv/bind/file.txt"));
+        let home = mount_point_of(&mounts, (8, 17), Path::new("/home/user/file.txt"));
+        let unknown = mount_point_of(&mounts, (1, 1), Path::new("/file.txt"));
+
+        assert_eq!(Some(PathBuf::from("/srv/bind")), bind);
+        assert_eq!(Some(PathBuf::from("/home")), home);
+        assert_eq!(None, unknown);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn count_files_of_a_single_directory() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt")];
+        for file in files.iter() {
+            fs::File::create(file)?;
+        }
+
+        let mounts = read_mounts().unwrap();
+        let counts = count_per_mount_point(&mounts, &files).unwrap();
+
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.values().sum::<usize>(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::io;
+
+// Lowest CPU priority, as used by `nice -n 19`
+const NICENESS: libc::c_int = 19;
+
+// Both apply to the calling thread on Linux, so they have to be set before any thread is spawned
+pub(crate) fn lower_cpu_priority() -> io::Result<()> {
+    // The return value of setpriority is 0 or -1, unlike nice which returns the new niceness
+    let res = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, NICENESS) };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// IO is only served when no other process needs the disk, like `ionice -c 3`
+#[cfg(target_os = "linux")]
+pub(crate) fn lower_io_priority() -> io::Result<()> {
+    // From linux/ioprio.h, which libc does not expose
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        )
+    };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lower_io_priority() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "IO priority is only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lowering its own priority never requires privileges. Tests run in their own thread, so
+    // other tests are not slowed down
+    #[test]
+    fn lower_cpu_priority_succeeds() {
+        assert!(lower_cpu_priority().is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn lower_io_priority_succeeds() {
+        assert!(lower_io_priority().is_ok());
+    }
+}
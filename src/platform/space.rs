@@ -0,0 +1,51 @@
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+// Bytes available to unprivileged users on the filesystem holding `path`, as shown by `df`
+pub(crate) fn available_bytes(path: &Path) -> io::Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    let res = unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // Both fields are u32 on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+// st_dev of the filesystem holding `path`, symlinks not being followed
+pub(crate) fn device(path: &Path) -> io::Result<u64> {
+    Ok(fs::symlink_metadata(path)?.dev())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn available_bytes_of_existing_path() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+
+        available_bytes(dir.path())?;
+        assert!(available_bytes(&dir.path().join("missing")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn device_is_shared_within_a_directory() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        std::fs::File::create(dir.path().join("a.log"))?;
+
+        assert_eq!(device(dir.path())?, device(&dir.path().join("a.log"))?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::ffi::CString;
+
+// Uid of a user name, from the password database (like `id -u NAME`)
+pub(crate) fn uid_of(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    let res = unsafe {
+        libc::getpwnam_r(
+            c_name.as_ptr(),
+            &mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if res != 0 || result.is_null() {
+        return None;
+    }
+    Some(passwd.pw_uid)
+}
+
+// Gid of a group name, from the group database (like `getent group NAME`)
+pub(crate) fn gid_of(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+
+    let res = unsafe {
+        libc::getgrnam_r(
+            c_name.as_ptr(),
+            &mut group,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if res != 0 || result.is_null() {
+        return None;
+    }
+    Some(group.gr_gid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_user() {
+        assert_eq!(Some(0), uid_of("root"));
+    }
+
+    #[test]
+    fn unknown_users() {
+        assert_eq!(None, uid_of("rmx-no-such-user"));
+        assert_eq!(None, uid_of("nul\0byte"));
+    }
+
+    // Other systems, like macOS, name the group of gid 0 otherwise
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn root_group() {
+        assert_eq!(Some(0), gid_of("root"));
+    }
+
+    #[test]
+    fn unknown_groups() {
+        assert_eq!(None, gid_of("rmx-no-such-group"));
+        assert_eq!(None, gid_of("nul\0byte"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+pub(crate) const CACHE_DIR: &str = "__pycache__";
+
+pub(crate) fn is_bytecode(filename: &str) -> bool {
+    filename.ends_with(".pyc") || filename.ends_with(".pyo")
+}
+
+// Source a bytecode file was compiled from, following PEP 3147 and PEP 488:
+// `pkg/__pycache__/mod.cpython-311.pyc` and `pkg/__pycache__/mod.cpython-311.opt-2.pyc` come
+// from `pkg/mod.py`, while legacy `pkg/mod.pyc` files sit next to their source
+pub(crate) fn source_of(bytecode: &Path) -> Option<PathBuf> {
+    let name = bytecode.file_name()?.to_str()?;
+    let stem = name
+        .strip_suffix(".pyc")
+        .or_else(|| name.strip_suffix(".pyo"))?;
+    let dir = bytecode.parent()?;
+
+    if dir.file_name() != Some(OsStr::new(CACHE_DIR)) {
+        return (!stem.is_empty()).then(|| dir.join(format!("{stem}.py")));
+    }
+
+    // Optimization level first, then the interpreter tag, like cpython-311 or pypy39
+    let stem = match stem.rsplit_once('.') {
+        Some((rest, level)) if level.starts_with("opt-") => rest,
+        _ => stem,
+    };
+    let (module, _tag) = stem.rsplit_once('.')?;
+    if module.is_empty() {
+        return None;
+    }
+    Some(dir.parent()?.join(format!("{module}.py")))
+}
+
+// Bytecode whose source cannot be derived is never considered orphaned, to stay on the safe side
+pub(crate) fn is_orphan(bytecode: &Path) -> bool {
+    source_of(bytecode)
+        .is_some_and(|source| !source.exists() && !source.with_extension("pyw").exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(path: &str) -> Option<PathBuf> {
+        source_of(Path::new(path))
+    }
+
+    #[test]
+    fn source_of_cpython_tags() {
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.cpython-311.pyc")
+        );
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.cpython-38.pyc")
+        );
+        assert_eq!(
+            Some(PathBuf::from("pkg/__init__.py")),
+            source("pkg/__pycache__/__init__.cpython-312.pyc")
+        );
+    }
+
+    #[test]
+    fn source_of_optimized_bytecode() {
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.cpython-311.opt-1.pyc")
+        );
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.cpython-39.opt-2.pyc")
+        );
+        // Python 3.4 and older
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.cpython-34.pyo")
+        );
+    }
+
+    #[test]
+    fn source_of_other_interpreters() {
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.pypy39.pyc")
+        );
+        assert_eq!(
+            Some(PathBuf::from("pkg/mod.py")),
+            source("pkg/__pycache__/mod.pypy310.opt-1.pyc")
+        );
+    }
+
+    #[test]
+    fn source_of_legacy_bytecode() {
+        assert_eq!(Some(PathBuf::from("pkg/mod.py")), source("pkg/mod.pyc"));
+        assert_eq!(Some(PathBuf::from("pkg/mod.py")), source("pkg/mod.pyo"));
+    }
+
+    #[test]
+    fn source_of_unknown_names() {
+        // No interpreter tag, or no module name
+        assert_eq!(None, source("pkg/__pycache__/mod.pyc"));
+        assert_eq!(None, source("pkg/__pycache__/.cpython-311.pyc"));
+        assert_eq!(None, source("pkg/.pyc"));
+        assert_eq!(None, source("pkg/mod.py"));
+    }
+}
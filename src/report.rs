@@ -0,0 +1,887 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::display::line_with_path;
+
+/// Counters gathered during the collection, on top of the matches themselves
+///
+/// ```
+/// let stats = rmx::CollectStats::default();
+///
+/// assert_eq!(0, stats.hidden_skipped);
+/// ```
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CollectStats {
+    /// Hidden files that would have matched without the hidden filter (see --all)
+    pub hidden_skipped: usize,
+    /// Matching files kept by --never-older-than
+    pub age_protected: usize,
+    /// Matching files kept as their owner is protected (see CollectOptions::protected_owners)
+    pub owner_protected: usize,
+    /// Matching files kept by --preserve-link-targets
+    pub link_targets_preserved: usize,
+    /// Matching files kept as their owner cannot write to them (see --skip-readonly)
+    pub readonly_skipped: usize,
+    /// Matching files kept as an entry of the keep list matches them (see --keep-from)
+    pub keep_listed: usize,
+    /// Matching directories kept whole as something inside is excluded or kept by a safety
+    /// filter, like a file of a protected owner (see --dirs)
+    pub dirs_protected: usize,
+    /// Selected files per given extension, a file being counted for the first extension it
+    /// matches. Empty with --invert
+    pub matched_by_extension: Vec<usize>,
+    /// Whether more files matched than the limit, the walk having stopped there (see --limit)
+    pub limit_reached: bool,
+    /// Directories not walked as they are on another filesystem (see --one-file-system)
+    pub mount_points_skipped: Vec<PathBuf>,
+}
+
+/// Outcome of delete_files, used to summarize the run
+///
+/// ```
+/// let report = rmx::delete_files(&[], &rmx::DeleteOptions::default())?;
+///
+/// assert_eq!(0, report.matched);
+/// assert!(report.failures.is_empty());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct DeleteReport {
+    /// Number of files given to delete_files
+    pub matched: usize,
+    pub deleted: usize,
+    /// Deleted files and directories, in deletion order
+    pub deleted_paths: Vec<PathBuf>,
+    /// Size of deleted files, or of matched files during a dry run
+    pub bytes: u64,
+    pub failures: Vec<(PathBuf, String)>,
+    /// Files that no longer existed when deleting them, like files removed by another process
+    /// after the confirmation prompt was shown
+    pub vanished: Vec<PathBuf>,
+    /// Files skipped as another process held a lock on them (see --respect-locks)
+    pub locked: Vec<PathBuf>,
+    /// Files renamed instead of being deleted, with their new path (see Action::Rename)
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+    /// Files kept by `DeleteOptions::veto`, with the reason it gave
+    pub vetoed: Vec<(PathBuf, String)>,
+    /// Chunks the user declined, or left when quitting (see --confirm-chunks)
+    pub declined_chunks: usize,
+    /// Files of declined chunks, kept
+    pub declined: usize,
+    pub dry_run: bool,
+    pub cancelled: bool,
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("{n} {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+// Uses binary units, like `ls -h`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+// Groups digits by thousands, like 7,301
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Shown before the prompt when candidates live on several filesystems, as that changes both
+// how long the deletion takes and what is at stake
+pub fn format_filesystem_span(counts: &BTreeMap<PathBuf, usize>) -> Option<String> {
+    if counts.len() < 2 {
+        return None;
+    }
+
+    let details: Vec<String> = counts
+        .iter()
+        .map(|(mount_point, n)| {
+            let files = if *n == 1 { "file" } else { "files" };
+            format!("{} ({} {files})", mount_point.display(), format_count(*n))
+        })
+        .collect();
+    Some(format!(
+        "files span {} filesystems: {}",
+        counts.len(),
+        details.join(", ")
+    ))
+}
+
+// Space given back by the deletion, as measured on the filesystem, next to the summed size of
+// deleted files: hard links, sparse files and filesystem overhead make them differ. Other
+// processes writing meanwhile can make the measure negative, which is shown as nothing freed
+pub fn format_freed(available_before: u64, available_after: u64, files_bytes: u64) -> String {
+    format!(
+        "Freed {} (files totaled {}).",
+        format_size(available_after.saturating_sub(available_before)),
+        format_size(files_bytes)
+    )
+}
+
+pub const FREED_NOT_MEASURED: &str =
+    "Note: freed space not measured, as deleted files span several filesystems.";
+
+// Single line summary of the run, for --oneline
+// With a width, the path is shortened so that the line fits in it
+pub fn format_oneline(
+    report: &DeleteReport,
+    path: &Path,
+    elapsed: Duration,
+    width: Option<usize>,
+) -> String {
+    if report.matched == 0 {
+        return line_with_path("rmx: nothing to delete under ", path, "", width);
+    }
+
+    if report.dry_run {
+        let before = format!(
+            "rmx: would delete {} ({}) under ",
+            plural(report.matched, "file"),
+            format_size(report.bytes)
+        );
+        return line_with_path(&before, path, "", width);
+    }
+
+    if report.cancelled {
+        return line_with_path("rmx: cancelled, nothing deleted under ", path, "", width);
+    }
+
+    let before = if report.renamed.is_empty() {
+        format!(
+            "rmx: deleted {} ({}) under ",
+            plural(report.deleted, "file"),
+            format_size(report.bytes)
+        )
+    } else {
+        format!(
+            "rmx: renamed {} under ",
+            plural(report.renamed.len(), "file")
+        )
+    };
+    let mut after = format!(" in {:.1}s", elapsed.as_secs_f64());
+    if !report.failures.is_empty() {
+        after.push_str(&format!(", {} failed", report.failures.len()));
+    }
+    if !report.locked.is_empty() {
+        after.push_str(&format!(", {} locked", report.locked.len()));
+    }
+    line_with_path(&before, path, &after, width)
+}
+
+// Helps discovering --all when hidden files were left aside
+pub fn format_hidden_hint(stats: &CollectStats) -> Option<String> {
+    match stats.hidden_skipped {
+        0 => None,
+        1 => Some("Note: 1 hidden file matched but was skipped; pass --all to include it.".into()),
+        n => Some(format!(
+            "Note: {n} hidden files matched but were skipped; pass --all to include them."
+        )),
+    }
+}
+
+pub fn format_age_protection(stats: &CollectStats) -> Option<String> {
+    match stats.age_protected {
+        0 => None,
+        n => Some(format!(
+            "Note: {} protected by --never-older-than.",
+            plural(n, "file")
+        )),
+    }
+}
+
+pub fn format_owner_protection(stats: &CollectStats) -> Option<String> {
+    match stats.owner_protected {
+        0 => None,
+        n => Some(format!(
+            "Note: {} protected by protected_owners, pass --ignore-protected-owners to include them.",
+            plural(n, "file")
+        )),
+    }
+}
+
+pub fn format_readonly(stats: &CollectStats) -> Option<String> {
+    match stats.readonly_skipped {
+        0 => None,
+        n => Some(format!(
+            "Note: {} skipped (--skip-readonly).",
+            plural(n, "read-only file")
+        )),
+    }
+}
+
+pub fn format_link_targets(stats: &CollectStats) -> Option<String> {
+    match stats.link_targets_preserved {
+        0 => None,
+        n => Some(format!(
+            "Note: {} preserved: referenced by symlinks.",
+            plural(n, "file")
+        )),
+    }
+}
+
+// Like `Note: 2 mount points not walked (--one-file-system): /srv/nfs, /srv/backup.`
+pub fn format_mount_points(stats: &CollectStats) -> Option<String> {
+    if stats.mount_points_skipped.is_empty() {
+        return None;
+    }
+    let paths: Vec<String> = stats
+        .mount_points_skipped
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    Some(format!(
+        "Note: {} not walked (--one-file-system): {}.",
+        plural(paths.len(), "mount point"),
+        paths.join(", ")
+    ))
+}
+
+pub fn format_keep_list(stats: &CollectStats) -> Option<String> {
+    match stats.keep_listed {
+        0 => None,
+        n => Some(format!("Note: {} kept by --keep-from.", plural(n, "file"))),
+    }
+}
+
+pub fn format_dirs_protected(stats: &CollectStats) -> Option<String> {
+    match stats.dirs_protected {
+        0 => None,
+        1 => Some("Note: 1 matching directory kept: files inside are protected.".to_string()),
+        n => Some(format!(
+            "Note: {n} matching directories kept: files inside are protected."
+        )),
+    }
+}
+
+// Shown before the prompt, so that the files left are not mistaken for all the matches
+pub fn format_limit_reached(stats: &CollectStats, limit: usize) -> Option<String> {
+    stats.limit_reached.then(|| {
+        format!(
+            "Note: limit of {} reached, more files match and are left for another run.",
+            plural(limit, "file")
+        )
+    })
+}
+
+// Last line of a listing, like `-- 42 files, 1.3 GiB --`
+pub fn format_listing_footer(count: usize, bytes: u64) -> String {
+    format!("-- {}, {} --", plural(count, "file"), format_size(bytes))
+}
+
+// Shown for a start path given twice, or inside another one, both being canonical
+pub fn format_covered_root(dropped: &Path, kept: &Path) -> String {
+    if dropped == kept {
+        format!(
+            "Note: {} was given twice, collecting it once.",
+            kept.display()
+        )
+    } else {
+        format!(
+            "Note: {} is inside {}, collecting it once.",
+            dropped.display(),
+            kept.display()
+        )
+    }
+}
+
+// Shown when --checkpoint finds directories completed by an earlier run
+pub fn format_resume(completed: usize) -> String {
+    match completed {
+        1 => "Note: resuming from checkpoint, 1 directory already completed.".to_string(),
+        n => format!("Note: resuming from checkpoint, {n} directories already completed."),
+    }
+}
+
+// Tells how to get rid of renamed files for good
+pub fn format_renamed(report: &DeleteReport, suffix: &str) -> Option<String> {
+    match report.renamed.len() {
+        0 => None,
+        n => Some(format!(
+            "Note: {} renamed to *{suffix}, run `rmx {}` to delete them for good.",
+            plural(n, "file"),
+            suffix.trim_start_matches('.')
+        )),
+    }
+}
+
+// Summary of --simulate, the plan standing for the whole run
+pub fn format_simulation(kept: usize, planned: usize, bytes: u64) -> String {
+    format!(
+        "Simulation: {} of {} would be deleted ({}).",
+        kept,
+        plural(planned, "planned file"),
+        format_size(bytes)
+    )
+}
+
+// Which source of extensions (like `preset 'c'` or `CLI`) selected how many files, with one
+// source per extension as given to the collection. Only worth showing with several sources
+pub fn format_attribution(sources: &[String], stats: &CollectStats) -> Option<String> {
+    let mut totals: Vec<(&str, usize)> = Vec::new();
+    for (source, n) in sources.iter().zip(&stats.matched_by_extension) {
+        match totals.iter_mut().find(|(s, _)| s == source) {
+            Some((_, total)) => *total += n,
+            None => totals.push((source, *n)),
+        }
+    }
+
+    if totals.len() < 2 {
+        return None;
+    }
+    let details: Vec<String> = totals
+        .iter()
+        .map(|(source, n)| format!("via {source}: {}", plural(*n, "file")))
+        .collect();
+    Some(format!("Matches {}.", details.join("; ")))
+}
+
+pub fn format_locked_count(report: &DeleteReport) -> Option<String> {
+    match report.locked.len() {
+        0 => None,
+        n => Some(format!(
+            "Note: {} skipped: locked by another process.",
+            plural(n, "file")
+        )),
+    }
+}
+
+// Shown before asking for a chunk of --confirm-chunks: its size and the directories holding
+// most of its files
+pub fn format_chunk(
+    index: usize,
+    chunks: usize,
+    files: &[PathBuf],
+    bytes: u64,
+    what: &str,
+) -> String {
+    const SHOWN_DIRS: usize = 3;
+
+    let mut per_dir: BTreeMap<&Path, usize> = BTreeMap::new();
+    for file in files {
+        *per_dir
+            .entry(file.parent().unwrap_or(Path::new("")))
+            .or_insert(0) += 1;
+    }
+    let mut per_dir: Vec<(&Path, usize)> = per_dir.into_iter().collect();
+    // Stable, so that directories holding as many files keep the path order
+    per_dir.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+    let mut dirs: Vec<String> = per_dir
+        .iter()
+        .take(SHOWN_DIRS)
+        .map(|(dir, n)| format!("{} ({})", dir.display(), format_count(*n)))
+        .collect();
+    match per_dir.len().saturating_sub(SHOWN_DIRS) {
+        0 => (),
+        1 => dirs.push("1 other directory".to_string()),
+        n => dirs.push(format!("{n} other directories")),
+    }
+    format!(
+        "Chunk {index} of {chunks}: {} {what} ({}) in {}",
+        format_count(files.len()),
+        format_size(bytes),
+        dirs.join(", ")
+    )
+}
+
+pub fn format_declined(report: &DeleteReport) -> Option<String> {
+    match report.declined_chunks {
+        0 => None,
+        n => Some(format!(
+            "Note: {} declined, {} kept.",
+            plural(n, "chunk"),
+            plural(report.declined, "file")
+        )),
+    }
+}
+
+// Explains why fewer files than confirmed were deleted, empty when none vanished
+pub fn format_vanished(report: &DeleteReport, width: Option<usize>) -> Vec<String> {
+    if report.vanished.is_empty() {
+        return Vec::new();
+    }
+    let n = report.vanished.len();
+    let header = if n == 1 {
+        "Note: 1 file listed earlier no longer existed:".to_string()
+    } else {
+        format!("Note: {n} files listed earlier no longer existed:")
+    };
+    let mut lines = vec![header];
+    lines.extend(
+        report
+            .vanished
+            .iter()
+            .map(|path| line_with_path("  ", path, "", width)),
+    );
+    lines
+}
+
+// One line per file skipped by --respect-locks
+pub fn format_locked(report: &DeleteReport, width: Option<usize>) -> Vec<String> {
+    report
+        .locked
+        .iter()
+        .map(|path| line_with_path("rmx: skipped ", path, ": locked by another process", width))
+        .collect()
+}
+
+// One line per file that could not be deleted
+pub fn format_failures(report: &DeleteReport, width: Option<usize>) -> Vec<String> {
+    report
+        .failures
+        .iter()
+        .map(|(path, e)| line_with_path("rmx: could not delete ", path, &format!(": {e}"), width))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_size_bytes() {
+        assert_eq!("0 B", format_size(0));
+        assert_eq!("1023 B", format_size(1023));
+    }
+
+    #[test]
+    fn format_size_units() {
+        assert_eq!("1.0 KiB", format_size(1024));
+        assert_eq!("1.5 MiB", format_size(1024 * 1024 * 3 / 2));
+        assert_eq!("1.3 GiB", format_size(1_395_864_371));
+    }
+
+    #[test]
+    fn covered_roots() {
+        assert_eq!(
+            "Note: /data was given twice, collecting it once.",
+            format_covered_root(Path::new("/data"), Path::new("/data"))
+        );
+        assert_eq!(
+            "Note: /data/sub is inside /data, collecting it once.",
+            format_covered_root(Path::new("/data/sub"), Path::new("/data"))
+        );
+    }
+
+    #[test]
+    fn mount_points() {
+        let mut stats = CollectStats::default();
+        assert_eq!(None, format_mount_points(&stats));
+
+        stats.mount_points_skipped = vec![PathBuf::from("/srv/nfs"), PathBuf::from("/srv/backup")];
+        assert_eq!(
+            Some(
+                "Note: 2 mount points not walked (--one-file-system): /srv/nfs, /srv/backup."
+                    .to_string()
+            ),
+            format_mount_points(&stats)
+        );
+    }
+
+    #[test]
+    fn limit_reached() {
+        let mut stats = CollectStats::default();
+        assert_eq!(None, format_limit_reached(&stats, 2));
+
+        stats.limit_reached = true;
+        assert_eq!(
+            Some(
+                "Note: limit of 2 files reached, more files match and are left for another run."
+                    .to_string()
+            ),
+            format_limit_reached(&stats, 2)
+        );
+    }
+
+    #[test]
+    fn listing_footer() {
+        assert_eq!("-- 1 file, 0 B --", format_listing_footer(1, 0));
+        assert_eq!(
+            "-- 42 files, 1.3 GiB --",
+            format_listing_footer(42, 1_395_864_371)
+        );
+    }
+
+    #[test]
+    fn freed_space() {
+        let gib = 1024 * 1024 * 1024;
+
+        assert_eq!(
+            "Freed 4.1 GiB (files totaled 4.7 GiB).",
+            format_freed(10 * gib, 10 * gib + gib * 41 / 10, gib * 47 / 10)
+        );
+        // Hard links to deleted files keep their content around
+        assert_eq!(
+            "Freed 0 B (files totaled 2.0 KiB).",
+            format_freed(4096, 4096, 2048)
+        );
+        // Another process filled the filesystem meanwhile
+        assert_eq!(
+            "Freed 0 B (files totaled 1.0 KiB).",
+            format_freed(8192, 4096, 1024)
+        );
+    }
+
+    #[test]
+    fn oneline_success() {
+        let report = DeleteReport {
+            matched: 42,
+            deleted: 42,
+            bytes: 1_395_864_371,
+            ..Default::default()
+        };
+
+        let line = format_oneline(
+            &report,
+            Path::new("/srv/logs"),
+            Duration::from_millis(4200),
+            None,
+        );
+
+        assert_eq!(
+            "rmx: deleted 42 files (1.3 GiB) under /srv/logs in 4.2s",
+            line
+        );
+    }
+
+    #[test]
+    fn oneline_single_file() {
+        let report = DeleteReport {
+            matched: 1,
+            deleted: 1,
+            bytes: 10,
+            ..Default::default()
+        };
+
+        let line = format_oneline(&report, Path::new("/tmp"), Duration::from_secs(1), None);
+
+        assert_eq!("rmx: deleted 1 file (10 B) under /tmp in 1.0s", line);
+    }
+
+    #[test]
+    fn oneline_nothing_to_delete() {
+        let report = DeleteReport::default();
+
+        let line = format_oneline(&report, Path::new("/srv/logs"), Duration::ZERO, None);
+
+        assert_eq!("rmx: nothing to delete under /srv/logs", line);
+    }
+
+    #[test]
+    fn oneline_dry_run() {
+        let report = DeleteReport {
+            matched: 3,
+            bytes: 2048,
+            dry_run: true,
+            ..Default::default()
+        };
+
+        let line = format_oneline(&report, Path::new("/srv/logs"), Duration::ZERO, None);
+
+        assert_eq!("rmx: would delete 3 files (2.0 KiB) under /srv/logs", line);
+    }
+
+    #[test]
+    fn oneline_with_failures() {
+        let report = DeleteReport {
+            matched: 3,
+            deleted: 1,
+            bytes: 100,
+            failures: vec![
+                (PathBuf::from("/srv/a.log"), "Permission denied".to_string()),
+                (PathBuf::from("/srv/b.log"), "Permission denied".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let line = format_oneline(&report, Path::new("/srv"), Duration::from_secs(2), None);
+        let failures = format_failures(&report, None);
+
+        assert_eq!(
+            "rmx: deleted 1 file (100 B) under /srv in 2.0s, 2 failed",
+            line
+        );
+        assert_eq!(
+            vec![
+                "rmx: could not delete /srv/a.log: Permission denied",
+                "rmx: could not delete /srv/b.log: Permission denied"
+            ],
+            failures
+        );
+    }
+
+    #[test]
+    fn locked_files() {
+        let report = DeleteReport {
+            matched: 3,
+            deleted: 1,
+            bytes: 100,
+            locked: vec![PathBuf::from("/srv/a.log"), PathBuf::from("/srv/b.log")],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "rmx: deleted 1 file (100 B) under /srv in 2.0s, 2 locked",
+            format_oneline(&report, Path::new("/srv"), Duration::from_secs(2), None)
+        );
+        assert_eq!(
+            vec![
+                "rmx: skipped /srv/a.log: locked by another process",
+                "rmx: skipped /srv/b.log: locked by another process"
+            ],
+            format_locked(&report, None)
+        );
+        assert_eq!(
+            Some("Note: 2 files skipped: locked by another process.".to_string()),
+            format_locked_count(&report)
+        );
+        assert_eq!(None, format_locked_count(&DeleteReport::default()));
+    }
+
+    #[test]
+    fn chunk_summary() {
+        let files: Vec<PathBuf> = ["/a/1", "/a/2", "/b/1", "/c/1", "/c/2", "/c/3", "/d/1"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        assert_eq!(
+            "Chunk 2 of 5: 7 file(s) (2.0 KiB) in /c (3), /a (2), /b (1), 1 other directory",
+            format_chunk(2, 5, &files, 2048, "file(s)")
+        );
+        assert_eq!(
+            "Chunk 1 of 1: 2 file(s) (0 B) in /a (2)",
+            format_chunk(1, 1, &files[..2], 0, "file(s)")
+        );
+    }
+
+    #[test]
+    fn declined_chunks() {
+        let report = DeleteReport {
+            matched: 6,
+            deleted: 2,
+            declined_chunks: 2,
+            declined: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some("Note: 2 chunks declined, 4 files kept.".to_string()),
+            format_declined(&report)
+        );
+        assert_eq!(None, format_declined(&DeleteReport::default()));
+    }
+
+    #[test]
+    fn vanished_files() {
+        let report = DeleteReport {
+            matched: 3,
+            deleted: 1,
+            vanished: vec![PathBuf::from("/srv/a.log"), PathBuf::from("/srv/b.log")],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vec![
+                "Note: 2 files listed earlier no longer existed:",
+                "  /srv/a.log",
+                "  /srv/b.log"
+            ],
+            format_vanished(&report, None)
+        );
+        assert!(format_vanished(&DeleteReport::default(), None).is_empty());
+    }
+
+    #[test]
+    fn hidden_hint() {
+        let none = CollectStats::default();
+        let one = CollectStats {
+            hidden_skipped: 1,
+            ..Default::default()
+        };
+        let many = CollectStats {
+            hidden_skipped: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_hidden_hint(&none));
+        assert!(
+            format_hidden_hint(&one)
+                .unwrap()
+                .contains("1 hidden file matched")
+        );
+        assert_eq!(
+            "Note: 3 hidden files matched but were skipped; pass --all to include them.",
+            format_hidden_hint(&many).unwrap()
+        );
+    }
+
+    #[test]
+    fn count_grouping() {
+        assert_eq!("0", format_count(0));
+        assert_eq!("412", format_count(412));
+        assert_eq!("7,301", format_count(7301));
+        assert_eq!("1,234,567", format_count(1_234_567));
+    }
+
+    #[test]
+    fn filesystem_span_single_filesystem() {
+        let counts = BTreeMap::from([(PathBuf::from("/home"), 412)]);
+
+        assert_eq!(None, format_filesystem_span(&counts));
+    }
+
+    #[test]
+    fn filesystem_span_multiple_filesystems() {
+        let counts = BTreeMap::from([
+            (PathBuf::from("/mnt/nas"), 7301),
+            (PathBuf::from("/home"), 412),
+        ]);
+
+        assert_eq!(
+            Some("files span 2 filesystems: /home (412 files), /mnt/nas (7,301 files)".into()),
+            format_filesystem_span(&counts)
+        );
+    }
+
+    #[test]
+    fn oneline_shortens_long_path() {
+        let report = DeleteReport {
+            matched: 2,
+            deleted: 2,
+            bytes: 10,
+            ..Default::default()
+        };
+        let path = Path::new("/srv/nfs/projects/2024/build/output/logs");
+
+        let line = format_oneline(&report, path, Duration::from_secs(1), Some(60));
+
+        assert_eq!(
+            "rmx: deleted 2 files (10 B) under /srv/…/output/logs in 1.0s",
+            line
+        );
+    }
+
+    #[test]
+    fn age_protection() {
+        let none = CollectStats::default();
+        let many = CollectStats {
+            age_protected: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_age_protection(&none));
+        assert_eq!(
+            "Note: 3 files protected by --never-older-than.",
+            format_age_protection(&many).unwrap()
+        );
+    }
+
+    #[test]
+    fn owner_protection() {
+        let stats = CollectStats {
+            owner_protected: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_owner_protection(&CollectStats::default()));
+        assert_eq!(
+            "Note: 1 file protected by protected_owners, pass --ignore-protected-owners to include them.",
+            format_owner_protection(&stats).unwrap()
+        );
+    }
+
+    #[test]
+    fn readonly() {
+        let stats = CollectStats {
+            readonly_skipped: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_readonly(&CollectStats::default()));
+        assert_eq!(
+            "Note: 2 read-only files skipped (--skip-readonly).",
+            format_readonly(&stats).unwrap()
+        );
+    }
+
+    #[test]
+    fn link_targets() {
+        let stats = CollectStats {
+            link_targets_preserved: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_link_targets(&CollectStats::default()));
+        assert_eq!(
+            "Note: 3 files preserved: referenced by symlinks.",
+            format_link_targets(&stats).unwrap()
+        );
+    }
+
+    #[test]
+    fn simulation_summary() {
+        assert_eq!(
+            "Simulation: 2 of 5 planned files would be deleted (2.0 KiB).",
+            format_simulation(2, 5, 2048)
+        );
+    }
+
+    #[test]
+    fn attribution_per_source() {
+        let sources: Vec<String> = ["preset 'c'", "preset 'c'", "preset 'latex'", "CLI"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let stats = CollectStats {
+            matched_by_extension: vec![800, 12, 40, 1],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(
+                "Matches via preset 'c': 812 files; via preset 'latex': 40 files; via CLI: 1 file."
+                    .to_string()
+            ),
+            format_attribution(&sources, &stats)
+        );
+    }
+
+    #[test]
+    fn attribution_single_source() {
+        let sources = vec!["CLI".to_string(), "CLI".to_string()];
+        let stats = CollectStats {
+            matched_by_extension: vec![1, 2],
+            ..Default::default()
+        };
+
+        assert_eq!(None, format_attribution(&sources, &stats));
+    }
+}
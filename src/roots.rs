@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Whether `path` is `root` or lies under it. Works on components, so that /data/subx is not
+// considered inside /data/sub. Both paths are expected to be canonical.
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    path.starts_with(root)
+}
+
+// Where the start path leads when it is a symlink, so that a link pointing somewhere unexpected
+// is noticed before anything is deleted
+pub fn resolve_symlink(path: &Path) -> Option<PathBuf> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if !is_link {
+        return None;
+    }
+    fs::canonicalize(path).ok()
+}
+
+// Roots to collect from, once duplicates and roots nested in other roots are dropped
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Roots {
+    // Canonical paths, in the order they were given
+    pub kept: Vec<PathBuf>,
+    // Dropped root, with the kept root containing it
+    pub dropped: Vec<(PathBuf, PathBuf)>,
+}
+
+// Pure part of dedup_roots, on already canonical paths
+fn dedup_canonical(roots: Vec<PathBuf>) -> Roots {
+    let mut result = Roots::default();
+
+    for (i, root) in roots.iter().enumerate() {
+        // An equal root given earlier wins, otherwise the outermost root wins
+        let container = roots
+            .iter()
+            .enumerate()
+            .find(|(j, other)| *j != i && is_within(root, other) && (*other != root || *j < i));
+        match container {
+            Some((_, other)) => result.dropped.push((root.clone(), other.clone())),
+            None => result.kept.push(root.clone()),
+        }
+    }
+
+    result
+}
+
+// Canonicalizes roots (resolving symlinks), and drops those already covered by another root so
+// that no file is collected twice
+pub fn dedup_roots(roots: &[PathBuf]) -> Result<Roots, Box<dyn Error>> {
+    let canonical = roots
+        .iter()
+        .map(|r| {
+            fs::canonicalize(r).map_err(|e| format!("Could not resolve {}: {}", r.display(), e))
+        })
+        .collect::<Result<Vec<PathBuf>, String>>()?;
+
+    Ok(dedup_canonical(canonical))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    fn paths(values: &[&str]) -> Vec<PathBuf> {
+        values.iter().map(PathBuf::from).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_symlinked_start_path() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let release = dir.path().join("2024-06-01");
+        fs::create_dir(&release)?;
+        let current = dir.path().join("current");
+        std::os::unix::fs::symlink(&release, &current)?;
+
+        assert_eq!(Some(fs::canonicalize(&release)?), resolve_symlink(&current));
+        assert_eq!(None, resolve_symlink(&release));
+        assert_eq!(None, resolve_symlink(&dir.path().join("missing")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn within_equal_paths() {
+        assert!(is_within(Path::new("/data"), Path::new("/data")));
+    }
+
+    #[test]
+    fn within_nested_paths() {
+        assert!(is_within(Path::new("/data/sub/deeper"), Path::new("/data")));
+        assert!(!is_within(Path::new("/data"), Path::new("/data/sub")));
+    }
+
+    #[test]
+    fn within_sibling_paths() {
+        assert!(!is_within(Path::new("/data/a"), Path::new("/data/b")));
+    }
+
+    #[test]
+    fn within_textual_prefix_only() {
+        assert!(!is_within(Path::new("/data/subx"), Path::new("/data/sub")));
+    }
+
+    #[test]
+    fn dedup_nested_roots() {
+        let roots = dedup_canonical(paths(&["/data/sub", "/other", "/data"]));
+
+        assert_eq!(paths(&["/other", "/data"]), roots.kept);
+        assert_eq!(
+            vec![(PathBuf::from("/data/sub"), PathBuf::from("/data"))],
+            roots.dropped
+        );
+    }
+
+    #[test]
+    fn dedup_equal_roots_keeps_first() {
+        let roots = dedup_canonical(paths(&["/data", "/data"]));
+
+        assert_eq!(paths(&["/data"]), roots.kept);
+        assert_eq!(roots.dropped.len(), 1);
+    }
+
+    #[test]
+    fn dedup_keeps_textual_prefixes() {
+        let roots = dedup_canonical(paths(&["/data/sub", "/data/subx"]));
+
+        assert_eq!(paths(&["/data/sub", "/data/subx"]), roots.kept);
+        assert!(roots.dropped.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dedup_roots_through_symlink() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir()?;
+        let data = temp_dir.path().join("data");
+        let sub = data.join("sub");
+        fs::create_dir_all(&sub)?;
+        let link = temp_dir.path().join("link");
+        symlink(&sub, &link)?;
+
+        let roots = dedup_roots(&[data.clone(), link])?;
+
+        assert_eq!(vec![fs::canonicalize(&data)?], roots.kept);
+        assert_eq!(roots.dropped.len(), 1);
+
+        Ok(())
+    }
+}
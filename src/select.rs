@@ -0,0 +1,330 @@
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+// What the user can do in the selection screen (see --select)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Toggle,
+    ToggleAll,
+    Confirm,
+    Abort,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    // Indexes of the checked entries, in order
+    Confirmed(Vec<usize>),
+    Aborted,
+}
+
+// Checkboxes over an indexed list, along with the cursor and the first visible entry, kept apart
+// from the terminal so that it can be tested
+#[derive(Debug)]
+pub(crate) struct Selection {
+    checked: Vec<bool>,
+    cursor: usize,
+    offset: usize,
+}
+
+impl Selection {
+    // Every entry starts checked, as every one of them matched
+    pub(crate) fn new(len: usize) -> Selection {
+        Selection {
+            checked: vec![true; len],
+            cursor: 0,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn checked_count(&self) -> usize {
+        self.checked.iter().filter(|c| **c).count()
+    }
+
+    fn move_to(&mut self, index: usize) {
+        self.cursor = index.min(self.checked.len().saturating_sub(1));
+    }
+
+    // `page` is the number of visible entries, used by PageUp and PageDown
+    pub(crate) fn apply(&mut self, key: Key, page: usize) -> Option<Outcome> {
+        let page = page.max(1);
+        match key {
+            Key::Up => self.move_to(self.cursor.saturating_sub(1)),
+            Key::Down => self.move_to(self.cursor + 1),
+            Key::PageUp => self.move_to(self.cursor.saturating_sub(page)),
+            Key::PageDown => self.move_to(self.cursor + page),
+            Key::Home => self.move_to(0),
+            Key::End => self.move_to(usize::MAX),
+            Key::Toggle => {
+                if let Some(checked) = self.checked.get_mut(self.cursor) {
+                    *checked = !*checked;
+                }
+            }
+            // Checks everything, unless everything already is
+            Key::ToggleAll => {
+                let all = self.checked_count() == self.checked.len();
+                self.checked.iter_mut().for_each(|c| *c = !all);
+            }
+            Key::Confirm => {
+                let chosen = (0..self.checked.len()).filter(|i| self.checked[*i]);
+                return Some(Outcome::Confirmed(chosen.collect()));
+            }
+            Key::Abort => return Some(Outcome::Aborted),
+        }
+        None
+    }
+
+    // Scrolls just enough for the cursor to be among the `rows` visible entries, returning the
+    // first of them
+    pub(crate) fn scroll(&mut self, rows: usize) -> usize {
+        let rows = rows.max(1);
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + rows {
+            self.offset = self.cursor + 1 - rows;
+        }
+        self.offset
+    }
+}
+
+fn key_of(event: KeyEvent) -> Option<Key> {
+    if event.kind == KeyEventKind::Release {
+        return None;
+    }
+    // Raw mode turns Ctrl-C into a plain key
+    if event.modifiers.contains(KeyModifiers::CONTROL) && event.code == KeyCode::Char('c') {
+        return Some(Key::Abort);
+    }
+    match event.code {
+        KeyCode::Up | KeyCode::Char('k') => Some(Key::Up),
+        KeyCode::Down | KeyCode::Char('j') => Some(Key::Down),
+        KeyCode::PageUp => Some(Key::PageUp),
+        KeyCode::PageDown => Some(Key::PageDown),
+        KeyCode::Home | KeyCode::Char('g') => Some(Key::Home),
+        KeyCode::End | KeyCode::Char('G') => Some(Key::End),
+        KeyCode::Char(' ') => Some(Key::Toggle),
+        KeyCode::Char('a') => Some(Key::ToggleAll),
+        KeyCode::Enter => Some(Key::Confirm),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Key::Abort),
+        _ => None,
+    }
+}
+
+// Cuts lines longer than the terminal, which would otherwise wrap and shift the next rows
+fn fit(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
+
+const HELP: &str = "↑/↓ move  space toggle  a all  enter proceed  q abort";
+
+// Draws a header, the visible entries and the help line over `height` rows
+pub(crate) fn render(
+    out: &mut impl Write,
+    selection: &mut Selection,
+    lines: &[String],
+    (width, height): (usize, usize),
+) -> io::Result<()> {
+    let rows = height.saturating_sub(2);
+    let offset = selection.scroll(rows);
+
+    queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+    let header = format!(
+        "Select the files to delete: {} of {} checked",
+        selection.checked_count(),
+        lines.len()
+    );
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print(fit(&header, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    for (row, i) in (offset..lines.len()).take(rows).enumerate() {
+        let mark = if selection.checked[i] { "[x]" } else { "[ ]" };
+        let line = fit(&format!("{mark} {}", lines[i]), width);
+        queue!(out, MoveTo(0, row as u16 + 1))?;
+        if i == selection.cursor {
+            queue!(
+                out,
+                SetAttribute(Attribute::Reverse),
+                Print(line),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(out, Print(line))?;
+        }
+    }
+
+    queue!(
+        out,
+        MoveTo(0, height.saturating_sub(1) as u16),
+        Print(fit(HELP, width))
+    )?;
+    out.flush()
+}
+
+// Leaves the alternate screen and raw mode however the selection ends
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Lets the user check the entries to keep going with, one line each. Needs a terminal
+pub(crate) fn run(lines: &[String]) -> Result<Outcome, Box<dyn Error>> {
+    if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        return Err("--select needs a terminal, use --list or --dry-run instead.".into());
+    }
+
+    let _screen = Screen::enter()?;
+    let mut selection = Selection::new(lines.len());
+    let mut out = io::stdout();
+
+    loop {
+        // Some pseudo terminals report no size at all
+        let (width, height) = terminal::size()
+            .ok()
+            .filter(|(width, height)| *width > 0 && *height > 0)
+            .unwrap_or((80, 24));
+        let size = (width as usize, height as usize);
+        render(&mut out, &mut selection, lines, size)?;
+
+        // Anything else, like a resize, only redraws
+        if let Event::Key(event) = event::read()?
+            && let Some(key) = key_of(event)
+            && let Some(outcome) = selection.apply(key, size.1.saturating_sub(2))
+        {
+            return Ok(outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_toggles() {
+        let mut selection = Selection::new(3);
+        assert_eq!(3, selection.checked_count());
+
+        selection.apply(Key::Down, 10);
+        selection.apply(Key::Toggle, 10);
+        assert_eq!(
+            Some(Outcome::Confirmed(vec![0, 2])),
+            selection.apply(Key::Confirm, 10)
+        );
+
+        // Unchecks everything as long as everything is checked, checks everything otherwise
+        selection.apply(Key::ToggleAll, 10);
+        assert_eq!(3, selection.checked_count());
+        selection.apply(Key::ToggleAll, 10);
+        assert_eq!(
+            Some(Outcome::Confirmed(vec![])),
+            selection.apply(Key::Confirm, 10)
+        );
+
+        assert_eq!(Some(Outcome::Aborted), selection.apply(Key::Abort, 10));
+    }
+
+    #[test]
+    fn selection_cursor_stays_in_bounds() {
+        let mut selection = Selection::new(5);
+
+        selection.apply(Key::Up, 2);
+        assert_eq!(0, selection.cursor);
+        selection.apply(Key::PageDown, 2);
+        assert_eq!(2, selection.cursor);
+        selection.apply(Key::PageDown, 10);
+        assert_eq!(4, selection.cursor);
+        selection.apply(Key::Down, 2);
+        assert_eq!(4, selection.cursor);
+        selection.apply(Key::Home, 2);
+        assert_eq!(0, selection.cursor);
+        selection.apply(Key::End, 2);
+        assert_eq!(4, selection.cursor);
+
+        // Nothing to move to nor toggle
+        let mut empty = Selection::new(0);
+        empty.apply(Key::Down, 2);
+        empty.apply(Key::Toggle, 2);
+        assert_eq!(0, empty.cursor);
+        assert_eq!(0, empty.checked_count());
+    }
+
+    #[test]
+    fn selection_scrolls_to_cursor() {
+        let mut selection = Selection::new(10);
+        assert_eq!(0, selection.scroll(3));
+
+        selection.apply(Key::PageDown, 4);
+        assert_eq!(2, selection.scroll(3));
+        selection.apply(Key::Down, 3);
+        assert_eq!(3, selection.scroll(3));
+        // Moving back within the visible entries does not scroll
+        selection.apply(Key::Up, 3);
+        assert_eq!(3, selection.scroll(3));
+        selection.apply(Key::Home, 3);
+        assert_eq!(0, selection.scroll(3));
+    }
+
+    #[test]
+    fn keys() {
+        let key = |code| key_of(KeyEvent::new(code, KeyModifiers::NONE));
+
+        assert_eq!(Some(Key::Toggle), key(KeyCode::Char(' ')));
+        assert_eq!(Some(Key::ToggleAll), key(KeyCode::Char('a')));
+        assert_eq!(Some(Key::Confirm), key(KeyCode::Enter));
+        assert_eq!(Some(Key::Abort), key(KeyCode::Char('q')));
+        assert_eq!(None, key(KeyCode::Char('x')));
+        assert_eq!(
+            Some(Key::Abort),
+            key_of(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+    }
+
+    #[test]
+    fn render_visible_entries() -> io::Result<()> {
+        let lines: Vec<String> = (0..5).map(|i| format!("/data/{i}.log")).collect();
+        let mut selection = Selection::new(lines.len());
+        selection.apply(Key::Toggle, 3);
+        selection.apply(Key::End, 3);
+
+        let mut out = Vec::new();
+        render(&mut out, &mut selection, &lines, (80, 5))?;
+        let screen = String::from_utf8_lossy(&out);
+
+        assert!(screen.contains("Select the files to delete: 4 of 5 checked"));
+        // Three rows, scrolled down to the cursor
+        assert!(!screen.contains("/data/1.log"));
+        assert!(screen.contains("[x] /data/2.log"));
+        assert!(screen.contains("[x] /data/4.log"));
+        assert!(screen.contains(HELP));
+
+        Ok(())
+    }
+}
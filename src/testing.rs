@@ -0,0 +1,242 @@
+//! Temporary directory trees to test code built on `rmx` against, like the tests of `rmx`
+//! itself. Only available with the `test-util` feature
+//!
+//! ```
+//! use rmx::testing::FixtureTree;
+//!
+//! let dir = FixtureTree::new()
+//!     .file("a/b.log")
+//!     .hidden_file(".x.txt")
+//!     .build()?;
+//!
+//! assert!(dir.path().join("a").join("b.log").is_file());
+//! assert!(dir.path().join(".x.txt").is_file());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+enum Entry {
+    File(PathBuf),
+    Dir(PathBuf),
+    Symlink { link: PathBuf, target: PathBuf },
+}
+
+/// Files, directories and symlinks to create in a new temporary directory, paths being
+/// relative to it and separated by `/`. Parent directories are created as needed, and entries
+/// are created in the order they are given
+#[derive(Default)]
+pub struct FixtureTree {
+    entries: Vec<Entry>,
+}
+
+impl FixtureTree {
+    /// An empty tree
+    pub fn new() -> FixtureTree {
+        FixtureTree::default()
+    }
+
+    /// The tree the tests of `rmx` start from, as shown below, on top of which entries can be
+    /// added
+    ///
+    /// ```text
+    /// .hidden.txt
+    /// .hidden_folder/hidden.txt
+    /// data.dat
+    /// root.log
+    /// root.txt
+    /// subfolder1/sub1.log
+    /// subfolder1/sub1.txt
+    /// subfolder1/subfolder2/backup.bak
+    /// subfolder1/subfolder2/data.dat
+    /// subfolder1/subfolder2/sub2.txt
+    /// ```
+    pub fn standard() -> FixtureTree {
+        FixtureTree::new()
+            .file("root.txt")
+            .file("root.log")
+            .file("data.dat")
+            .hidden_file(".hidden.txt")
+            .file("subfolder1/sub1.txt")
+            .file("subfolder1/sub1.log")
+            .file("subfolder1/subfolder2/sub2.txt")
+            .file("subfolder1/subfolder2/backup.bak")
+            .file("subfolder1/subfolder2/data.dat")
+            .file(".hidden_folder/hidden.txt")
+    }
+
+    /// An empty file
+    pub fn file(mut self, path: impl AsRef<Path>) -> FixtureTree {
+        self.entries.push(Entry::File(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// An empty file whose name starts with a dot, which rmx skips unless told otherwise
+    ///
+    /// # Panics
+    ///
+    /// When the file name does not start with a dot
+    pub fn hidden_file(self, path: impl AsRef<Path>) -> FixtureTree {
+        let path = path.as_ref();
+        assert!(
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.')),
+            "{} is not a hidden file",
+            path.display()
+        );
+        self.file(path)
+    }
+
+    /// An empty directory
+    pub fn dir(mut self, path: impl AsRef<Path>) -> FixtureTree {
+        self.entries.push(Entry::Dir(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// A symlink at `link` pointing to `target`, which is relative to the directory of the link
+    /// unless absolute, as for `ln -s`. It may point to nothing
+    pub fn symlink(mut self, link: impl AsRef<Path>, target: impl AsRef<Path>) -> FixtureTree {
+        self.entries.push(Entry::Symlink {
+            link: link.as_ref().to_path_buf(),
+            target: target.as_ref().to_path_buf(),
+        });
+        self
+    }
+
+    /// Creates the tree in a new temporary directory, deleted when dropped
+    pub fn build(&self) -> io::Result<TempDir> {
+        let dir = tempfile::tempdir()?;
+        for entry in &self.entries {
+            match entry {
+                Entry::File(path) => {
+                    let path = dir.path().join(path);
+                    create_parent(&path)?;
+                    File::create(path)?;
+                }
+                Entry::Dir(path) => fs::create_dir_all(dir.path().join(path))?,
+                Entry::Symlink { link, target } => {
+                    let link = dir.path().join(link);
+                    create_parent(&link)?;
+                    symlink(target, &link)?;
+                }
+            }
+        }
+        Ok(dir)
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+// Windows tells links to directories from links to files, from what the target is when known
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let resolved = link
+        .parent()
+        .map_or(target.to_path_buf(), |dir| dir.join(target));
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paths under the directory, files, directories and symlinks alike, sorted
+    fn layout(dir: &Path) -> Vec<String> {
+        let mut paths = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current).unwrap() {
+                let path = entry.unwrap().path();
+                let relative = path.strip_prefix(dir).unwrap().to_string_lossy();
+                paths.push(relative.replace('\\', "/"));
+                if path.is_dir() && !path.is_symlink() {
+                    pending.push(path);
+                }
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn standard_tree() -> io::Result<()> {
+        let dir = FixtureTree::standard().build()?;
+
+        assert_eq!(
+            vec![
+                ".hidden.txt",
+                ".hidden_folder",
+                ".hidden_folder/hidden.txt",
+                "data.dat",
+                "root.log",
+                "root.txt",
+                "subfolder1",
+                "subfolder1/sub1.log",
+                "subfolder1/sub1.txt",
+                "subfolder1/subfolder2",
+                "subfolder1/subfolder2/backup.bak",
+                "subfolder1/subfolder2/data.dat",
+                "subfolder1/subfolder2/sub2.txt",
+            ],
+            layout(dir.path())
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn added_entries() -> io::Result<()> {
+        let dir = FixtureTree::new()
+            .file("a/b.log")
+            .hidden_file(".x.txt")
+            .dir("empty/nested")
+            .symlink("link", "a")
+            .symlink("a/dangling.log", "missing.log")
+            .build()?;
+
+        assert_eq!(
+            vec![
+                ".x.txt",
+                "a",
+                "a/b.log",
+                "a/dangling.log",
+                "empty",
+                "empty/nested",
+                "link",
+            ],
+            layout(dir.path())
+        );
+        assert!(dir.path().join("a/b.log").is_file());
+        assert!(dir.path().join("empty/nested").is_dir());
+        assert_eq!(Path::new("a"), fs::read_link(dir.path().join("link"))?);
+        assert!(dir.path().join("link/b.log").is_file());
+        assert!(!dir.path().join("a/dangling.log").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "not a hidden file")]
+    fn hidden_file_needs_a_dot() {
+        let _ = FixtureTree::new().hidden_file("x.txt");
+    }
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Warnings are printed as they happen, and kept so that --strict can refuse to go on once
+// anything unexpected happened. They come from everywhere, from parsing the config file to
+// deleting files, hence a single collector for the whole process
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+// Prints `Warning: {message}` to stderr, and records it
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    eprintln!("Warning: {message}");
+    WARNINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(message);
+}
+
+pub fn warnings() -> Vec<String> {
+    WARNINGS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// Lists the conditions that made --strict stop the run
+pub fn format_strict(stage: &str, conditions: &[String]) -> String {
+    let mut message = format!("--strict: {stage}, because of:");
+    for condition in conditions {
+        message.push_str("\n  ");
+        message.push_str(condition);
+    }
+    message
+}
+
+// Fails with every warning so far under --strict, as the run cannot go on
+pub fn check(stage: &str) -> Result<(), Box<dyn Error>> {
+    let warnings = warnings();
+    if !is_strict() || warnings.is_empty() {
+        return Ok(());
+    }
+    Err(format_strict(stage, &warnings).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_lists_conditions() {
+        let conditions = vec![
+            "could not read \"/srv/list\", ignoring it".to_string(),
+            "unknown user \"bob\" in protected_owners".to_string(),
+        ];
+
+        assert_eq!(
+            "--strict: aborting before deleting anything, because of:\n  \
+            could not read \"/srv/list\", ignoring it\n  \
+            unknown user \"bob\" in protected_owners",
+            format_strict("aborting before deleting anything", &conditions)
+        );
+    }
+}
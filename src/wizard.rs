@@ -0,0 +1,274 @@
+use std::error::Error;
+use std::ffi::OsString;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::arguments::{Args, are_extensions_valid};
+use crate::paths;
+use crate::preset;
+
+/// Questions of the wizard and their answers, read from the terminal, or from a script in tests
+pub struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    pub fn new(input: R, output: W) -> Prompter<R, W> {
+        Prompter { input, output }
+    }
+
+    /// What was written to the user
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    fn say(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.output, "{line}")
+    }
+
+    // The trimmed answer, None at the end of the input
+    fn ask(&mut self, question: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{question} ")?;
+        self.output.flush()?;
+        let mut buf = String::new();
+        if self.input.read_line(&mut buf)? == 0 {
+            writeln!(self.output)?;
+            return Ok(None);
+        }
+        Ok(Some(buf.trim().to_string()))
+    }
+
+    // Asks again until the answer is understood, an empty one being no
+    fn ask_yes_no(&mut self, question: &str) -> io::Result<Option<bool>> {
+        loop {
+            match self.ask(&format!("{question} [y/N]"))?.as_deref() {
+                None => return Ok(None),
+                Some("" | "n" | "N") => return Ok(Some(false)),
+                Some("y" | "Y") => return Ok(Some(true)),
+                Some(_) => self.say("Please answer y (yes) or n (no).")?,
+            }
+        }
+    }
+}
+
+// Directory to clean, `cwd` when the answer is empty
+fn ask_directory<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    cwd: &Path,
+) -> io::Result<Option<PathBuf>> {
+    loop {
+        let Some(answer) = prompter.ask(&format!("Directory to clean [{}]:", cwd.display()))?
+        else {
+            return Ok(None);
+        };
+        let dir = paths::resolve(cwd, Path::new(&answer));
+        if dir.is_dir() {
+            return Ok(Some(dir));
+        }
+        prompter.say(&format!("{} is not a directory.", dir.display()))?;
+    }
+}
+
+// Arguments for the extensions and presets given, words naming a preset of `presets` being taken
+// as it
+fn ask_extensions<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    presets: &[preset::Preset],
+) -> io::Result<Option<Vec<String>>> {
+    let question = match presets {
+        [] => "Extensions to delete, separated by spaces, like log tmp:".to_string(),
+        _ => {
+            let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+            format!(
+                "Extensions to delete, separated by spaces, or presets ({}):",
+                names.join(", ")
+            )
+        }
+    };
+    loop {
+        let Some(answer) = prompter.ask(&question)? else {
+            return Ok(None);
+        };
+        let mut args = Vec::new();
+        let mut invalid = None;
+        for word in answer.split_whitespace() {
+            if preset::find(presets, word).is_some() {
+                args.extend(["--preset".to_string(), word.to_string()]);
+            } else if are_extensions_valid(&[word.to_string()]) {
+                args.push(word.to_string());
+            } else {
+                invalid = Some(word);
+                break;
+            }
+        }
+        match invalid {
+            Some(word) => prompter.say(&format!(
+                "Invalid extension \"{word}\", extensions are written without their dot, like log."
+            ))?,
+            None if args.is_empty() => prompter.say("At least one extension is needed.")?,
+            None => return Ok(Some(args)),
+        }
+    }
+}
+
+/// Asks what to delete, for a bare `rmx` run from a terminal, then counts the matches and asks
+/// whether to delete them. Returns the arguments of the run to make, with the name of the binary
+/// first and `--force` as the user already agreed, None when nothing is to be deleted. Presets
+/// are read from `config`
+pub fn run<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    cwd: &Path,
+    config: &Path,
+) -> Result<Option<Vec<OsString>>, Box<dyn Error>> {
+    prompter.say("No arguments were given, answer a few questions to pick what to delete.")?;
+    let presets = preset::load_presets(config).unwrap_or_default();
+
+    let Some(dir) = ask_directory(prompter, cwd)? else {
+        return Ok(None);
+    };
+    let Some(extensions) = ask_extensions(prompter, &presets)? else {
+        return Ok(None);
+    };
+    let Some(recurse) = prompter.ask_yes_no("Look into subdirectories?")? else {
+        return Ok(None);
+    };
+    let Some(all) = prompter.ask_yes_no("Include hidden files and directories?")? else {
+        return Ok(None);
+    };
+
+    let mut argv: Vec<OsString> = vec!["rmx".into()];
+    argv.extend(extensions.iter().map(OsString::from));
+    argv.extend(["-p".into(), dir.clone().into_os_string()]);
+    if extensions.iter().any(|e| e == "--preset") {
+        argv.extend(["--config".into(), config.as_os_str().to_os_string()]);
+    }
+    if recurse {
+        argv.push("-r".into());
+    }
+    if all {
+        argv.push("-a".into());
+    }
+
+    // Counted from the options the run itself will use
+    let Some(args) = Args::parse_from(&argv)? else {
+        return Ok(None);
+    };
+    let (collect_options, _) = args.get_options();
+    let (candidates, _) = crate::collect_candidates_in_roots(
+        &args.get_extensions()?,
+        &args.get_paths(),
+        &collect_options,
+    )?;
+    if candidates.is_empty() {
+        prompter.say(&format!(
+            "No file matches under {}, nothing to delete.",
+            dir.display()
+        ))?;
+        return Ok(None);
+    }
+
+    prompter.say(&format!(
+        "{} file(s) would be deleted under {}.",
+        candidates.len(),
+        dir.display()
+    ))?;
+    if prompter.ask_yes_no("Delete them?")? != Some(true) {
+        prompter.say("Nothing was deleted.")?;
+        return Ok(None);
+    }
+    argv.push("--force".into());
+    Ok(Some(argv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::testing::FixtureTree;
+
+    // Arguments the wizard led to, and what it wrote
+    type Session = (Option<Vec<OsString>>, String);
+
+    fn session(script: &str, cwd: &Path, config: &Path) -> Result<Session, Box<dyn Error>> {
+        let mut prompter = Prompter::new(Cursor::new(script.to_string()), Vec::new());
+        let argv = run(&mut prompter, cwd, config)?;
+        Ok((argv, String::from_utf8(prompter.into_output())?))
+    }
+
+    #[test]
+    fn wizard_builds_the_arguments() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::standard().build()?;
+        let root = tree.path();
+
+        // Defaults to the current directory, and asks again until answers are understood
+        let (argv, output) = session(
+            "\nlog .bak\n\nlog bak\nmaybe\ny\n\ny\n",
+            root,
+            &root.join("missing.conf"),
+        )?;
+
+        let mut expected: Vec<OsString> = ["rmx", "log", "bak", "-p"].map(OsString::from).to_vec();
+        expected.extend([
+            root.as_os_str().to_os_string(),
+            "-r".into(),
+            "--force".into(),
+        ]);
+        assert_eq!(Some(expected), argv);
+        assert!(output.contains("Invalid extension \".bak\""));
+        assert!(output.contains("At least one extension is needed."));
+        assert!(output.contains("Please answer y (yes) or n (no)."));
+        assert!(output.contains("3 file(s) would be deleted under"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn wizard_offers_presets() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::standard().build()?;
+        let root = tree.path();
+        let config = root.join("rmx.conf");
+        std::fs::write(&config, "preset junk=bak dat\n")?;
+
+        let (argv, output) = session("subfolder1\njunk\ny\nn\ny\n", root, &config)?;
+
+        let argv = argv.unwrap();
+        assert!(output.contains("or presets (junk):"));
+        assert!(output.contains("2 file(s) would be deleted under"));
+        assert_eq!(
+            vec![
+                OsString::from("--preset"),
+                "junk".into(),
+                "-p".into(),
+                root.join("subfolder1").into_os_string(),
+                "--config".into(),
+                config.into_os_string(),
+            ],
+            argv[1..7]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn wizard_deletes_nothing_without_agreement() -> Result<(), Box<dyn Error>> {
+        let tree = FixtureTree::standard().build()?;
+        let root = tree.path();
+        let config = root.join("missing.conf");
+
+        let (argv, output) = session("\nlog\nn\nn\nn\n", root, &config)?;
+        assert_eq!(None, argv);
+        assert!(output.ends_with("Nothing was deleted.\n"));
+
+        let (argv, output) = session("\nzip\nn\nn\n", root, &config)?;
+        assert_eq!(None, argv);
+        assert!(output.contains("No file matches under"));
+
+        // Stopped midway, like with Ctrl-D
+        assert_eq!(None, session("missing\n", root, &config)?.0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use assert_cmd::Command;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::Path;
+use tempfile::TempDir;
+
+// Minimal cargo project, with build junk inside and outside of its target directory:
+// <temp_dir>
+// ├── Cargo.toml
+// ├── notes.o
+// ├── src
+// │   └── lib.rs
+// ├── target
+// │   └── debug
+// │       ├── build
+// │       │   └── shim.o
+// │       ├── deps
+// │       │   ├── libdemo.d
+// │       │   ├── libdemo.rlib
+// │       │   └── libdemo.rmeta
+// │       └── demo.txt
+// └── vendor
+//     └── libvendored.rlib
+fn create_cargo_project() -> Result<TempDir, Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("src").join("lib.rs"), "")?;
+    File::create(root.join("notes.o"))?;
+    fs::create_dir_all(root.join("vendor"))?;
+    File::create(root.join("vendor").join("libvendored.rlib"))?;
+
+    let debug = root.join("target").join("debug");
+    fs::create_dir_all(debug.join("deps"))?;
+    fs::create_dir_all(debug.join("build"))?;
+    for name in ["libdemo.d", "libdemo.rlib", "libdemo.rmeta"] {
+        File::create(debug.join("deps").join(name))?;
+    }
+    File::create(debug.join("build").join("shim.o"))?;
+    File::create(debug.join("demo.txt"))?;
+
+    Ok(temp_dir)
+}
+
+fn untouched_outside_target(root: &Path) {
+    assert!(root.join("Cargo.toml").exists());
+    assert!(root.join("src").join("lib.rs").exists());
+    assert!(root.join("notes.o").exists());
+    assert!(root.join("vendor").join("libvendored.rlib").exists());
+}
+
+#[test]
+fn it_cargo_rmx_cleans_target() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_cargo_project()?;
+    let root = temp_dir.path();
+    let debug = root.join("target").join("debug");
+
+    // From a subdirectory, the way cargo runs subcommands
+    Command::cargo_bin("cargo-rmx")?
+        .current_dir(root.join("src"))
+        .arg("rmx")
+        .arg("-f")
+        .assert()
+        .success();
+
+    for name in ["libdemo.d", "libdemo.rlib", "libdemo.rmeta"] {
+        assert!(!debug.join("deps").join(name).exists(), "{name}");
+    }
+    assert!(!debug.join("build").join("shim.o").exists());
+    assert!(debug.join("demo.txt").exists());
+    untouched_outside_target(root);
+
+    Ok(())
+}
+
+#[test]
+fn it_cargo_rmx_with_extensions() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_cargo_project()?;
+    let root = temp_dir.path();
+    let debug = root.join("target").join("debug");
+
+    Command::cargo_bin("cargo-rmx")?
+        .current_dir(root)
+        .arg("rmx")
+        .arg("-f")
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(!debug.join("demo.txt").exists());
+    assert!(debug.join("deps").join("libdemo.rlib").exists());
+    untouched_outside_target(root);
+
+    Ok(())
+}
+
+#[test]
+fn it_cargo_rmx_outside_workspace_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+
+    Command::cargo_bin("cargo-rmx")?
+        .current_dir(temp_dir.path())
+        .arg("rmx")
+        .arg("-n")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Could not find a cargo workspace",
+        ));
+
+    Ok(())
+}
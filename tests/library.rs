@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use rmx::config::resolve_config_path;
+use rmx::preset::{self, Preset};
+use rmx::{Candidate, CandidateKind, CollectOptions, RmxError};
+use tempfile::NamedTempFile;
+
+#[test]
+fn presets_through_library() -> Result<(), Box<dyn Error>> {
+    let file = NamedTempFile::new()?;
+    write!(&file, "preset java=jar class\npreset c=o a so out")?;
+    let path = resolve_config_path(Some(file.path()));
+
+    let presets: Vec<Preset> = preset::load_presets(&path)?;
+    let c = preset::find(&presets, "c").unwrap();
+
+    assert_eq!(2, presets.len());
+    assert_eq!(vec!["o", "a", "so", "out"], c.extensions);
+    assert_eq!(file.path(), c.source);
+    assert_eq!(*c, preset::load_preset("c", &path)?);
+
+    Ok(())
+}
+
+#[test]
+fn missing_preset_through_library() -> Result<(), Box<dyn Error>> {
+    let file = NamedTempFile::new()?;
+    write!(&file, "preset java=jar class")?;
+
+    let err = preset::load_preset("c", file.path()).unwrap_err();
+
+    assert!(matches!(err, RmxError::PresetNotFound { .. }));
+    assert_eq!(
+        format!("Could not find preset \"c\" in {}", file.path().display()),
+        err.to_string()
+    );
+    assert!(preset::load_presets(Path::new("-unknown file-")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn candidates_through_library() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    File::create(dir.path().join("a.tar.gz"))?;
+    File::create(dir.path().join("b.txt"))?;
+
+    let extensions = vec!["tar.gz".to_string()];
+    let candidates: Vec<Candidate> = rmx::collect_candidates(
+        &extensions,
+        &dir.path().to_path_buf(),
+        &CollectOptions::default(),
+    )?;
+
+    assert_eq!(1, candidates.len());
+    assert_eq!(dir.path().join("a.tar.gz"), candidates[0].path);
+    assert_eq!(Some("tar.gz".to_string()), candidates[0].matched_ext);
+    assert_eq!(CandidateKind::File, candidates[0].kind);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "mangen")]
+
+use assert_cmd::Command;
+use std::error::Error;
+use std::fs;
+
+#[test]
+fn it_man_to_stdout() -> Result<(), Box<dyn Error>> {
+    let output = Command::cargo_bin("man")?.output()?;
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains(".TH rmx"));
+
+    Ok(())
+}
+
+#[test]
+fn it_man_out_dir() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let out_dir = temp_dir.path().join("target").join("assets");
+
+    Command::cargo_bin("man")?
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .assert()
+        .success();
+
+    let expected = [
+        ("rmx.1", ".TH rmx"),
+        ("rmx.bash", "complete -F _rmx"),
+        ("_rmx", "#compdef rmx"),
+        ("rmx.fish", "complete -c rmx"),
+    ];
+
+    for (name, marker) in expected {
+        let data = fs::read_to_string(out_dir.join(name))?;
+        assert!(!data.is_empty(), "{name} is empty");
+        assert!(data.contains(marker), "{name} does not contain {marker}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+// Pins the signatures of the stable library API: any change to them fails to compile here, so
+// that it is made deliberately, along with the matching version bump. Hidden items (see the
+// crate documentation) are left out on purpose
+// Signatures are spelled out whole, rather than through type aliases
+#![allow(clippy::type_complexity)]
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use rmx::preset::{self, Preset};
+use rmx::{
+    Action, Candidate, CandidateKind, CollectOptions, CollectStats, DeleteOptions, DeleteReport,
+    ExtensionOrigin, RmxError, Sink, Traversal, Veto, config,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[test]
+fn stable_functions() {
+    let _: fn(&str) -> Option<&str> = rmx::get_fileext;
+    let _: fn(&[String]) -> bool = rmx::are_extensions_valid;
+
+    let _: fn(&[String], &PathBuf, &CollectOptions) -> Result<Vec<PathBuf>> =
+        rmx::collect_matching_files;
+    let _: fn(&[String], &PathBuf, &CollectOptions) -> Result<(Vec<PathBuf>, CollectStats)> =
+        rmx::collect_matching_files_with_stats;
+    let _: fn(&[String], &PathBuf, &CollectOptions, Option<Sink>) -> Result<Vec<PathBuf>> =
+        rmx::collect_matching_files_with_sink;
+    let _: fn(&[String], &PathBuf, &CollectOptions) -> Result<Vec<Candidate>> =
+        rmx::collect_candidates;
+    let _: fn(&[String], &PathBuf, &CollectOptions) -> Result<(Vec<Candidate>, CollectStats)> =
+        rmx::collect_candidates_with_stats;
+    let _: fn(&Path, &CollectOptions) -> Result<Vec<PathBuf>> = rmx::collect_empty_dirs;
+    let _: fn(&Path, &CollectOptions) -> Result<Vec<Candidate>> = rmx::collect_pycache;
+
+    let _: fn(&[PathBuf], &DeleteOptions) -> Result<DeleteReport> = rmx::delete_files;
+    let _: fn(&[PathBuf], &DeleteOptions) -> Result<DeleteReport> = rmx::delete_empty_dirs;
+    let _: fn(&[Candidate], &DeleteOptions) -> Result<DeleteReport> = rmx::delete_pycache;
+
+    let _: fn() -> PathBuf = config::default_config_path;
+    let _: fn() -> Option<PathBuf> = config::user_config_path;
+    let _: fn(Option<&Path>) -> PathBuf = config::resolve_config_path;
+
+    let _: fn(&str) -> std::result::Result<(&str, Vec<&str>), String> = preset::parse_line;
+    let _: fn(&Path) -> std::result::Result<Vec<Preset>, RmxError> = preset::load_presets;
+    let _: for<'a> fn(&'a [Preset], &str) -> Option<&'a Preset> = preset::find;
+    let _: fn(&str, &Path) -> std::result::Result<Preset, RmxError> = preset::load_preset;
+}
+
+#[test]
+fn stable_options() {
+    // Fields are only ever added, which #[non_exhaustive] keeps non breaking
+    let mut options = CollectOptions::default();
+    options.all = false;
+    options.hidden_files = false;
+    options.hidden_dirs = false;
+    options.list = false;
+    options.recurse = false;
+    options.invert = false;
+    options.sort = false;
+    options.long = false;
+    options.apple_cruft = false;
+    options.truncate = None::<usize>;
+    options.never_older_than = None::<Duration>;
+    options.protected_owners = Vec::<u32>::new();
+    options.user = None::<u32>;
+    options.skip_readonly = false;
+    options.group = None::<u32>;
+    options.preserve_link_targets = false;
+    options.follow_dir_lists = None::<String>;
+    options.empty_dirs = false;
+    options.discover = false;
+    options.orphans_only = false;
+    options.audit = None::<PathBuf>;
+    options.peek_extensions = Vec::<String>::new();
+    options.renamed_suffix = None::<String>;
+    options.traversal = Traversal::Bfs;
+    options.max_depth = None::<usize>;
+    options.min_depth = None::<usize>;
+    options.exclude = Vec::<String>::new();
+    options.age_rules = Vec::<(String, Duration)>::new();
+    options.regex = None::<regex::Regex>;
+    options.regex_path = false;
+    options.older_than = None::<Duration>;
+    options.newer_than = None::<Duration>;
+    options.accessed_before = None::<Duration>;
+    options.modified_before = None::<SystemTime>;
+    options.modified_after = None::<SystemTime>;
+    options.size_heat = None::<[u64; 2]>;
+    options.min_size = None::<u64>;
+    options.sort_limit = None::<usize>;
+    options.no_ext = false;
+    options.names = Vec::<String>::new();
+    options.ignore_case = false;
+    options.case_insensitive_fs = false;
+    options.keep_from = None::<PathBuf>;
+    options.base = None::<PathBuf>;
+    options.follow_symlinks = false;
+    options.prefixes = Vec::<String>::new();
+    options.dirs = false;
+    options.expand_matched_dirs = false;
+    options.one_file_system = false;
+    options.symlinks = false;
+    options.max_size = None::<u64>;
+    options.threads = 0usize;
+    options.limit = None::<usize>;
+    match options.traversal {
+        Traversal::Dfs | Traversal::Bfs => (),
+        _ => (),
+    }
+    let _: bool = options.include_hidden_files();
+    let _: bool = options.include_hidden_dirs();
+    let _: bool = options.buffered_listing();
+
+    let mut delete_options = DeleteOptions::default();
+    delete_options.force = false;
+    delete_options.dry_run = false;
+    delete_options.quiet = false;
+    delete_options.clear_immutable = false;
+    delete_options.respect_locks = false;
+    delete_options.confirm_chunks = None::<usize>;
+    delete_options.threads = 0usize;
+    delete_options.action = Action::Rename(String::new());
+    match &delete_options.action {
+        Action::Delete => (),
+        Action::Rename(suffix) => {
+            let _: &String = suffix;
+        }
+        _ => (),
+    }
+    delete_options.veto = Some(Box::new(|_: &Path| Veto::Skip(String::new())));
+    let veto: &Option<Box<dyn Fn(&Path) -> Veto>> = &delete_options.veto;
+    if let Some(veto) = veto {
+        match veto(Path::new("")) {
+            Veto::Allow => (),
+            Veto::Skip(reason) => {
+                let _: String = reason;
+            }
+            _ => (),
+        }
+    }
+    let _ = rmx::delete_files(&[], &delete_options);
+}
+
+#[test]
+fn stable_results() {
+    let stats = CollectStats::default();
+    let _: usize = stats.hidden_skipped;
+    let _: usize = stats.age_protected;
+    let _: usize = stats.owner_protected;
+    let _: usize = stats.readonly_skipped;
+    let _: usize = stats.link_targets_preserved;
+    let _: usize = stats.keep_listed;
+    let _: usize = stats.dirs_protected;
+    let _: &Vec<usize> = &stats.matched_by_extension;
+    let _: bool = stats.limit_reached;
+    let _: &Vec<PathBuf> = &stats.mount_points_skipped;
+
+    let report = DeleteReport::default();
+    let _: usize = report.matched;
+    let _: usize = report.deleted;
+    let _: &Vec<PathBuf> = &report.deleted_paths;
+    let _: u64 = report.bytes;
+    let _: &Vec<(PathBuf, String)> = &report.failures;
+    let _: &Vec<PathBuf> = &report.locked;
+    let _: &Vec<PathBuf> = &report.vanished;
+    let _: &Vec<(PathBuf, PathBuf)> = &report.renamed;
+    let _: &Vec<(PathBuf, String)> = &report.vetoed;
+    let _: usize = report.declined_chunks;
+    let _: usize = report.declined;
+    let _: bool = report.dry_run;
+    let _: bool = report.cancelled;
+}
+
+#[allow(dead_code)]
+fn stable_candidates(candidate: &Candidate) {
+    let _: &PathBuf = &candidate.path;
+    let _: &Option<String> = &candidate.matched_ext;
+    let _: Option<PathBuf> = candidate.link_target();
+    let _: u64 = candidate.size();
+
+    let kind: CandidateKind = candidate.kind;
+    let _: char = kind.as_char();
+    let _: bool = kind.is_dir();
+    match kind {
+        CandidateKind::File
+        | CandidateKind::Symlink
+        | CandidateKind::EmptyDir
+        | CandidateKind::Dir => (),
+        _ => (),
+    }
+}
+
+#[allow(dead_code)]
+fn stable_errors(error: &RmxError, preset: &Preset) {
+    let _: &dyn Error = error;
+    let _: (&str, Option<&Path>) = (error.kind(), error.path());
+    match error {
+        RmxError::ConfigRead { path, source } => {
+            let _: (&PathBuf, &std::io::Error) = (path, source);
+        }
+        RmxError::PresetNotFound { name, path } => {
+            let _: (&String, &PathBuf) = (name, path);
+        }
+        RmxError::InvalidPreset { reason, path } => {
+            let _: (&String, &PathBuf) = (reason, path);
+        }
+        RmxError::InvalidExtension { value, origin } => {
+            let _: &String = value;
+            match origin {
+                ExtensionOrigin::CommandLine { position } => {
+                    let _: &usize = position;
+                }
+                ExtensionOrigin::Job { name, path } => {
+                    let _: (&String, &PathBuf) = (name, path);
+                }
+                ExtensionOrigin::Preset { name, path, line } => {
+                    let _: (&String, &PathBuf, &usize) = (name, path, line);
+                }
+                ExtensionOrigin::Alias { extension, path } => {
+                    let _: (&String, &PathBuf) = (extension, path);
+                }
+                _ => (),
+            }
+        }
+        _ => (),
+    }
+
+    let _: (&String, &Vec<String>, &PathBuf) = (&preset.name, &preset.extensions, &preset.source);
+}
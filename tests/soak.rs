@@ -0,0 +1,346 @@
+// Random trees and options, the collection and the deletion being checked against a plain
+// model of what should match. The long run is ignored by default:
+//
+//     RMX_SOAK_RUNS=2000 cargo test --test soak -- --ignored
+//
+// A failing case prints its seed, and RMX_SOAK_SEED=<seed> RMX_SOAK_RUNS=1 runs it alone
+use std::collections::BTreeSet;
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use assert_cmd::Command;
+use rmx::Traversal;
+use rmx::arguments::Args;
+use rmx::testing::FixtureTree;
+use tempfile::TempDir;
+
+const FILE_STEMS: [&str; 6] = ["a", "b", "data", ".h", ".b", "ab"];
+const FILE_EXTENSIONS: [&str; 8] = [
+    "", ".log", ".txt", ".tar.gz", ".LOG", ".gz", ".Log.bak", ".",
+];
+const DIR_NAMES: [&str; 5] = ["d1", "d2", "sub", ".hd", "x.log"];
+const EXTENSIONS: [&str; 5] = ["log", "txt", "gz", "tar.gz", "bak"];
+const MAX_TREE_DEPTH: usize = 3;
+
+// splitmix64, so that a seed gives the same case on every platform
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+// Files of the tree, relative to its root, and its directories, even empty ones
+#[derive(Debug, Default)]
+struct Tree {
+    files: BTreeSet<PathBuf>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl Tree {
+    fn random(rng: &mut Rng) -> Tree {
+        let mut tree = Tree::default();
+        tree.fill(rng, Path::new(""), 0);
+        tree
+    }
+
+    fn fill(&mut self, rng: &mut Rng, dir: &Path, depth: usize) {
+        for _ in 0..rng.below(7) {
+            if depth < MAX_TREE_DEPTH && rng.chance(25) {
+                let sub = dir.join(rng.pick(&DIR_NAMES));
+                if self.dirs.insert(sub.clone()) {
+                    self.fill(rng, &sub, depth + 1);
+                }
+            } else {
+                let name = format!("{}{}", rng.pick(&FILE_STEMS), rng.pick(&FILE_EXTENSIONS));
+                let file = dir.join(name);
+                // A name taken by a directory stays one
+                if !self.dirs.contains(&file) {
+                    self.files.insert(file);
+                }
+            }
+        }
+    }
+
+    fn build(&self) -> std::io::Result<TempDir> {
+        let mut fixture = FixtureTree::new();
+        for dir in &self.dirs {
+            fixture = fixture.dir(dir);
+        }
+        for file in &self.files {
+            fixture = fixture.file(file);
+        }
+        fixture.build()
+    }
+}
+
+// Options of a case, as given on the command line
+#[derive(Debug, Default)]
+struct Case {
+    extensions: Vec<&'static str>,
+    recurse: bool,
+    all: bool,
+    hidden_files: bool,
+    hidden_dirs: bool,
+    invert: bool,
+    no_ext: bool,
+    ignore_case: bool,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    prefix: Option<&'static str>,
+    exclude: Option<&'static str>,
+    bfs: bool,
+}
+
+impl Case {
+    fn random(rng: &mut Rng) -> Case {
+        let mut extensions: Vec<&str> = EXTENSIONS
+            .iter()
+            .copied()
+            .filter(|_| rng.chance(30))
+            .collect();
+        if extensions.is_empty() {
+            extensions.push(rng.pick(&EXTENSIONS));
+        }
+        let min_depth = rng.chance(20).then(|| 1 + rng.below(3));
+        let max_depth = rng
+            .chance(25)
+            .then(|| min_depth.unwrap_or(1) + rng.below(3));
+        Case {
+            extensions,
+            recurse: rng.chance(60),
+            all: rng.chance(20),
+            hidden_files: rng.chance(20),
+            hidden_dirs: rng.chance(20),
+            invert: rng.chance(20),
+            no_ext: rng.chance(15),
+            ignore_case: rng.chance(30),
+            max_depth,
+            min_depth,
+            prefix: rng.chance(20).then(|| rng.pick(&["a", "b", ".h", "da"])),
+            exclude: rng.chance(20).then(|| rng.pick(&["b.*", "*.gz", "a*"])),
+            bfs: rng.chance(50),
+        }
+    }
+
+    fn argv(&self, root: &Path) -> Vec<OsString> {
+        let mut argv: Vec<OsString> = vec!["rmx".into(), "-p".into(), root.into()];
+        // Keeps the settings of the machine out of the case
+        argv.extend(["--config".into(), root.join("missing.conf").into()]);
+        argv.extend(self.extensions.iter().map(OsString::from));
+        let flags = [
+            ("-r", self.recurse),
+            ("-a", self.all),
+            ("--hidden-files", self.hidden_files),
+            ("--hidden-dirs", self.hidden_dirs),
+            ("--invert", self.invert),
+            ("--no-ext", self.no_ext),
+            ("--ignore-case", self.ignore_case),
+        ];
+        argv.extend(flags.iter().filter(|(_, on)| *on).map(|(f, _)| f.into()));
+        let values = [
+            ("--max-depth", self.max_depth.map(|n| n.to_string())),
+            ("--min-depth", self.min_depth.map(|n| n.to_string())),
+            ("--prefix", self.prefix.map(str::to_string)),
+            ("--exclude", self.exclude.map(str::to_string)),
+        ];
+        for (flag, value) in values {
+            if let Some(value) = value {
+                argv.extend([flag.into(), value.into()]);
+            }
+        }
+        argv.extend([
+            "--traversal".into(),
+            if self.bfs { "bfs" } else { "dfs" }.into(),
+        ]);
+        argv
+    }
+
+    // What the case should match, from the rules of the README rather than from the walk
+    fn expected(&self, tree: &Tree) -> BTreeSet<PathBuf> {
+        tree.files
+            .iter()
+            .filter(|file| self.reaches(file) && self.selects(file))
+            .cloned()
+            .collect()
+    }
+
+    // Whether the walk goes through the directories holding `file`
+    fn reaches(&self, file: &Path) -> bool {
+        let dirs: Vec<&str> = file
+            .parent()
+            .into_iter()
+            .flat_map(Path::iter)
+            .map(|d| d.to_str().unwrap())
+            .collect();
+        let recurse = self.recurse || self.max_depth.is_some() || self.min_depth.is_some();
+        (dirs.is_empty() || recurse)
+            && dirs
+                .iter()
+                .all(|d| !d.starts_with('.') || self.all || self.hidden_dirs)
+            && self.max_depth.is_none_or(|max| dirs.len() < max)
+            && self.min_depth.is_none_or(|min| dirs.len() + 1 >= min)
+    }
+
+    fn selects(&self, file: &Path) -> bool {
+        let name = file.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('.') && !(self.all || self.hidden_files) {
+            return false;
+        }
+        let excluded = match self.exclude {
+            Some("b.*") => name.starts_with("b."),
+            Some("*.gz") => name.ends_with(".gz"),
+            Some("a*") => name.starts_with('a'),
+            _ => false,
+        };
+        self.name_matches(name) && !excluded
+    }
+
+    fn name_matches(&self, name: &str) -> bool {
+        // Everything after the first dot, a leading one aside
+        let extension = name
+            .strip_prefix('.')
+            .unwrap_or(name)
+            .split_once('.')
+            .map(|(_, e)| e);
+        let given = |e: &str| {
+            self.extensions.iter().any(|wanted| {
+                let (e, wanted) = (format!(".{e}"), format!(".{wanted}"));
+                if self.ignore_case {
+                    e.to_ascii_lowercase()
+                        .ends_with(&wanted.to_ascii_lowercase())
+                } else {
+                    e.ends_with(&wanted)
+                }
+            })
+        };
+        match self.prefix {
+            None => match extension {
+                Some(e) => given(e) != self.invert,
+                None => self.no_ext && !self.invert,
+            },
+            Some(prefix) => {
+                let by_extension = extension.map_or(self.no_ext, given);
+                (by_extension && name.starts_with(prefix)) != self.invert
+            }
+        }
+    }
+}
+
+fn relative(root: &Path, files: impl IntoIterator<Item = PathBuf>) -> BTreeSet<PathBuf> {
+    files
+        .into_iter()
+        .map(|f| f.strip_prefix(root).unwrap().to_path_buf())
+        .collect()
+}
+
+// Files left in the tree, directories aside
+fn remaining(root: &Path, tree: &Tree) -> BTreeSet<PathBuf> {
+    tree.files
+        .iter()
+        .filter(|f| root.join(f).exists())
+        .cloned()
+        .collect()
+}
+
+fn run_case(seed: u64) -> Result<(), Box<dyn Error>> {
+    let mut rng = Rng(seed);
+    let tree = Tree::random(&mut rng);
+    let case = Case::random(&mut rng);
+    let temp_dir = tree.build()?;
+    let root = temp_dir.path();
+    let context = || format!("{case:#?}\n{tree:#?}");
+
+    let argv = case.argv(root);
+    let args = Args::parse_from(&argv)
+        .map_err(|e| format!("{}\n{e}", context()))?
+        .unwrap();
+    let mut options = args.get_options().0;
+    let extensions = args.get_extensions()?;
+    let expected = case.expected(&tree);
+
+    for traversal in [Traversal::Dfs, Traversal::Bfs] {
+        options.traversal = traversal;
+        let collected = rmx::collect_matching_files(&extensions, &root.to_path_buf(), &options)?;
+        assert_eq!(
+            expected,
+            relative(root, collected),
+            "{}\n{traversal:?}",
+            context()
+        );
+    }
+
+    let state_dir = tempfile::tempdir()?;
+    Command::cargo_bin("rmx")?
+        .env("XDG_STATE_HOME", state_dir.path())
+        .args(&argv[1..])
+        .args(["-f", "--allow-invert-recursive"])
+        .assert()
+        .success();
+    // Nothing but the matches is deleted, and every match is
+    let kept: BTreeSet<PathBuf> = tree.files.difference(&expected).cloned().collect();
+    assert_eq!(kept, remaining(root, &tree), "{}", context());
+
+    Ok(())
+}
+
+// Assertions of a case panic, so that their own message is printed before the seed
+fn soak(first_seed: u64, runs: u64) {
+    for seed in first_seed..first_seed.saturating_add(runs) {
+        let failure = match panic::catch_unwind(|| run_case(seed)) {
+            Ok(Ok(())) => continue,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "see the panic above".to_string(),
+        };
+        panic!(
+            "soak case {seed} failed: {failure}\nrerun it with RMX_SOAK_SEED={seed} RMX_SOAK_RUNS=1"
+        );
+    }
+}
+
+// A few fixed cases, so that the model keeps up with the walk
+#[test]
+fn soak_smoke() {
+    soak(0, 12);
+}
+
+#[test]
+#[ignore]
+fn soak_random() {
+    let seed = env::var("RMX_SOAK_SEED")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    let runs = env::var("RMX_SOAK_RUNS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(500);
+    println!("soak: seeds {seed} to {}", seed.saturating_add(runs));
+    soak(seed, runs);
+}
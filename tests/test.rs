@@ -1,66 +1,20 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
+use rmx::testing::FixtureTree;
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{self, Write};
+use std::path::Path;
 use tempfile::{self, NamedTempFile, TempDir};
 
-// Creates a directory as follow:
-//
-// <temp_dir>/
-// ├── .hidden_folder/
-// │   └── hidden.txt
-// ├── subfolder1/
-// │   ├── subfolder2/
-// │   │   ├── backup.bak
-// │   │   ├── data.dat
-// │   │   └── sub2.txt
-// │   ├── sub1.log
-// │   └── sub1.txt
-// ├── .hidden.txt
-// ├── data.dat
-// ├── file.aA-01.23
-// ├── .hidden.aA-01.23
-// ├── root.log
-// └── root.txt
-//
-
+// The standard tree, with files of an unusual extension, hidden or not
 fn create_temp_folder() -> TempDir {
-    let temp_dir = tempfile::tempdir().unwrap();
-
-    // Create files in the root directory
-    File::create(temp_dir.path().join("root.txt")).unwrap();
-    File::create(temp_dir.path().join("root.log")).unwrap();
-    File::create(temp_dir.path().join("data.dat")).unwrap();
-    File::create(temp_dir.path().join("file.aA-01.23")).unwrap();
-    File::create(temp_dir.path().join(".hidden.aA-01.23")).unwrap();
-    File::create(temp_dir.path().join(".hidden.txt")).unwrap();
-
-    // Create subfolder1
-    let subfolder1 = temp_dir.path().join("subfolder1");
-    fs::create_dir(&subfolder1).unwrap();
-
-    // Create files in subfolder1
-    File::create(subfolder1.join("sub1.txt")).unwrap();
-    File::create(subfolder1.join("sub1.log")).unwrap();
-
-    // Create subfolder2 inside subfolder1
-    let subfolder2 = subfolder1.join("subfolder2");
-    fs::create_dir(&subfolder2).unwrap();
-
-    // Create files in subfolder2
-    File::create(subfolder2.join("sub2.txt")).unwrap();
-    File::create(subfolder2.join("backup.bak")).unwrap();
-    File::create(subfolder2.join("data.dat")).unwrap();
-
-    // Create a hidden subfolder
-    let hidden_folder = temp_dir.path().join(".hidden_folder");
-    fs::create_dir(&hidden_folder).unwrap();
-
-    // Create a file in the hidden folder
-    File::create(hidden_folder.join("hidden.txt")).unwrap();
-
-    temp_dir
+    FixtureTree::standard()
+        .file("file.aA-01.23")
+        .hidden_file(".hidden.aA-01.23")
+        .build()
+        .unwrap()
 }
 
 fn create_config_file(data: &str) -> Result<NamedTempFile, Box<dyn Error>> {
@@ -120,7 +74,9 @@ fn it_malformed_extension_should_fail() -> Result<(), Box<dyn Error>> {
         .arg("*")
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid extensions"));
+        .stderr(predicate::str::contains(
+            "Invalid extension \"*\" given as extension 1 on the command line",
+        ));
 
     Ok(())
 }
@@ -648,36 +604,453 @@ fn it_malformed_extension_in_preset_should_fail() -> Result<(), Box<dyn Error>>
         .arg("--preset")
         .arg("some")
         .arg("--config")
-        .arg(config_path)
+        .arg(&config_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Invalid extension \"*\" in preset \"some\" of {}, line 1",
+            config_path.display()
+        )));
+
+    Ok(())
+}
+
+fn create_job_config(path: &Path) -> Result<NamedTempFile, Box<dyn Error>> {
+    let config = format!(
+        "preset data=dat\n\
+        [job.cleanup]\n\
+        path = {}\n\
+        extensions = txt log\n\
+        recurse = true\n\
+        force = true",
+        path.display()
+    );
+    create_config_file(&config)
+}
+
+#[test]
+fn it_job() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = create_job_config(temp_dir.path())?;
+
+    Command::cargo_bin("rmx")?
+        .arg("--job")
+        .arg("cleanup")
+        .arg("--config")
+        .arg(file.path())
+        .assert()
+        .success();
+
+    assert!(!temp_dir.path().join("root.txt").exists());
+    assert!(
+        !temp_dir
+            .path()
+            .join("subfolder1/subfolder2/sub2.txt")
+            .exists()
+    );
+    assert!(!temp_dir.path().join("subfolder1/sub1.log").exists());
+    assert!(temp_dir.path().join("data.dat").exists());
+    assert!(temp_dir.path().join(".hidden.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_job_with_cli_override() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = create_job_config(temp_dir.path())?;
+
+    // Given extensions replace those of the job, which keeps its other options
+    Command::cargo_bin("rmx")?
+        .arg("--job")
+        .arg("cleanup")
+        .arg("--config")
+        .arg(file.path())
+        .arg("--preset")
+        .arg("data")
+        .arg("-n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("subfolder2/data.dat"))
+        .stdout(predicate::str::contains("root.txt").not());
+
+    assert!(temp_dir.path().join("root.txt").exists());
+    assert!(temp_dir.path().join("data.dat").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_job_flag_turned_off_from_cli() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = create_job_config(temp_dir.path())?;
+
+    // Flags of the job are defaults: the prompt comes back, and is declined
+    Command::cargo_bin("rmx")?
+        .arg("--job")
+        .arg("cleanup")
+        .arg("--config")
+        .arg(file.path())
+        .arg("--force=false")
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Do you really want to delete"));
+
+    assert!(temp_dir.path().join("root.txt").exists());
+    assert!(temp_dir.path().join("subfolder1/sub1.log").exists());
+
+    // Same for the other ones, the job no longer walking subfolders
+    Command::cargo_bin("rmx")?
+        .arg("--job")
+        .arg("cleanup")
+        .arg("--config")
+        .arg(file.path())
+        .arg("--recurse=false")
+        .assert()
+        .success();
+
+    assert!(!temp_dir.path().join("root.txt").exists());
+    assert!(temp_dir.path().join("subfolder1/sub1.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_job_age_overridden_from_cli() -> Result<(), Box<dyn Error>> {
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    File::options()
+        .write(true)
+        .open(path_buf.join("root.txt"))?
+        .set_times(
+            FileTimes::new().set_modified(SystemTime::now() - Duration::from_secs(2 * 86400)),
+        )?;
+    let file = create_config_file(&format!(
+        "[job.old]\n\
+        path = {}\n\
+        extensions = txt\n\
+        older_than = 30d",
+        path_buf.display()
+    ))?;
+    let rmx = |extra: &[&str]| -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("rmx")?;
+        cmd.args(["--job", "old", "-n", "--config"])
+            .arg(file.path())
+            .args(extra);
+        Ok(cmd)
+    };
+
+    // Too recent for the job
+    rmx(&[])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("root.txt").not());
+
+    // Old enough for the command line
+    rmx(&["--older-than", "1d"])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("root.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn it_accessed_before() -> Result<(), Box<dyn Error>> {
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let read_long_ago = path_buf.join("root.txt");
+    File::options()
+        .write(true)
+        .open(&read_long_ago)?
+        .set_times(
+            FileTimes::new().set_accessed(SystemTime::now() - Duration::from_secs(40 * 86400)),
+        )?;
+
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "--accessed-before", "30d", "-p"])
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!read_long_ago.exists());
+    // Read just now, as far as the filesystem tells
+    assert!(path_buf.join("subfolder1/sub1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_older_than_file() -> Result<(), Box<dyn Error>> {
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+    let set_modified = |name: &str, modified| -> std::io::Result<()> {
+        File::create(path.join(name))?.set_times(FileTimes::new().set_modified(modified))
+    };
+    // A first batch of artifacts, the stamp of the build, then a second batch
+    let now = SystemTime::now();
+    set_modified("a.o", now - Duration::from_secs(7200))?;
+    set_modified("b.o", now - Duration::from_secs(7000))?;
+    set_modified("build.stamp", now - Duration::from_secs(3600))?;
+    set_modified("c.o", now - Duration::from_secs(60))?;
+    File::create(path.join("d.o"))?;
+
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "o", "--older-than-file"])
+        .arg(path.join("build.stamp"))
+        .arg("-p")
+        .arg(path)
+        .assert()
+        .success();
+    assert!(!path.join("a.o").exists());
+    assert!(!path.join("b.o").exists());
+    assert!(path.join("c.o").exists());
+    assert!(path.join("d.o").exists());
+
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "o", "--newer-than-file"])
+        .arg(path.join("build.stamp"))
+        .arg("-p")
+        .arg(path)
+        .assert()
+        .success();
+    assert!(!path.join("c.o").exists());
+    assert!(!path.join("d.o").exists());
+
+    // Nothing is walked without the reference
+    File::create(path.join("e.o"))?;
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "o", "--older-than-file"])
+        .arg(path.join("missing.stamp"))
+        .arg("-p")
+        .arg(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "could not read the modification time of",
+        ));
+    assert!(path.join("e.o").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_skip_readonly() -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let readonly = path_buf.join("root.txt");
+    fs::set_permissions(&readonly, fs::Permissions::from_mode(0o444))?;
+
+    Command::cargo_bin("rmx")?
+        .args(["-f", "--skip-readonly", "-p"])
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 1 read-only file skipped (--skip-readonly).",
+        ));
+    assert!(readonly.exists());
+
+    Command::cargo_bin("rmx")?
+        .args(["-f", "-p"])
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!readonly.exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_user_and_group() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+    // Another user's files are left alone
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "--user"])
+        .arg((uid + 1).to_string())
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(path_buf.join("root.txt").exists());
+
+    Command::cargo_bin("rmx")?
+        .args(["-rf", "--user"])
+        .arg(uid.to_string())
+        .arg("--group")
+        .arg(gid.to_string())
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!path_buf.join("root.txt").exists());
+
+    Command::cargo_bin("rmx")?
+        .args(["--user", "rmx-no-such-user", "txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown user \"rmx-no-such-user\"",
+        ));
+
+    Ok(())
+}
+
+// The current user stands for a protected service account
+#[cfg(unix)]
+#[test]
+fn it_protected_owners() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let uid = unsafe { libc::getuid() };
+    let file = create_config_file(&format!("protected_owners = [\"postgres\", {uid}]"))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--config")
+        .arg(file.path())
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 3 files protected by protected_owners",
+        ));
+    assert!(path_buf.join("root.txt").exists());
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--ignore-protected-owners")
+        .arg("--config")
+        .arg(file.path())
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_jobs() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = create_job_config(temp_dir.path())?;
+
+    Command::cargo_bin("rmx")?
+        .arg("--jobs")
+        .arg("--config")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "rmx --job cleanup: \tRemoves *.txt, *.log in {}",
+            temp_dir.path().display()
+        )));
+
+    Command::cargo_bin("rmx")?
+        .arg("--job")
+        .arg("nightly")
+        .arg("--config")
+        .arg(file.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not find job \"nightly\""));
+
+    Ok(())
+}
+
+#[test]
+fn it_init_config() -> Result<(), Box<dyn Error>> {
+    let config_home = tempfile::tempdir()?;
+    let config_path = config_home.path().join("rmx/rmx.conf");
+
+    // Without any config file, presets point to --init-config
+    Command::cargo_bin("rmx")?
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .arg("--presets")
+        .assert()
+        .stderr(predicate::str::contains("rmx --init-config"));
+    assert!(!config_path.exists());
+
+    Command::cargo_bin("rmx")?
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .arg("--init-config")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created"));
+    assert!(config_path.exists());
+
+    Command::cargo_bin("rmx")?
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .arg("--presets")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rmx --preset latex"));
+
+    // Never overwritten
+    fs::write(&config_path, "preset mine=log")?;
+    Command::cargo_bin("rmx")?
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .arg("--init-config")
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid extensions"));
+        .stderr(predicate::str::contains("already exists"));
+    assert_eq!("preset mine=log", fs::read_to_string(&config_path)?);
 
     Ok(())
 }
 
 #[test]
-fn it_using_preset_should_not_allow_other_extensions() -> Result<(), Box<dyn Error>> {
+fn it_combining_presets_and_extensions() -> Result<(), Box<dyn Error>> {
     let temp_dir = create_temp_folder();
     let path_buf = temp_dir.path().to_path_buf();
 
-    let presets = "preset some=a\npreset other=txt log";
+    // `log` is requested by both presets, and attributed to the first one
+    let presets = "preset first=txt log\npreset second=log dat";
     let file = create_config_file(presets)?;
 
     let config_path = file.path().to_path_buf();
 
     Command::cargo_bin("rmx")?
-        .arg("-n")
+        .arg("-nr")
         .arg("-p")
         .arg(path_buf.to_str().unwrap())
         .arg("--preset")
-        .arg("some")
+        .arg("first")
+        .arg("--preset")
+        .arg("second")
         .arg("--config")
         .arg(config_path)
-        .arg("class")
+        .arg("bak")
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("cannot be used with"));
+        .success()
+        .stdout(predicate::str::contains(
+            "Matches via preset 'first': 5 files; via preset 'second': 2 files; via CLI: 1 file.",
+        ));
 
     Ok(())
 }
@@ -711,3 +1084,4069 @@ fn it_using_presets() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn it_list_streams_before_prompt() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let file = path_buf.clone().join("root.txt");
+    let file_str = file.to_str().unwrap();
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("-l")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .write_stdin("n\n")
+        .output()?;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    let listed_at = stdout.find(file_str).unwrap();
+    let prompt_at = stdout.find("Do you really want to delete").unwrap();
+    assert!(listed_at < prompt_at);
+    assert!(file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_sorted_list_matches_streamed_list() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let list = |sort: bool| -> Result<Vec<String>, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("rmx")?;
+        cmd.arg("-n")
+            .arg("-r")
+            .arg("-a")
+            .arg("-p")
+            .arg(path_buf.to_str().unwrap())
+            .arg("txt")
+            .arg("log");
+        if sort {
+            cmd.arg("--sort");
+        }
+        let output = cmd.output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(|l| l.to_string())
+            .collect())
+    };
+
+    let mut streamed = list(false)?;
+    let sorted = list(true)?;
+
+    assert_eq!(sorted.len(), 7);
+    assert!(sorted.is_sorted());
+    streamed.sort();
+    assert_eq!(streamed, sorted);
+
+    Ok(())
+}
+
+// Matches of a large directory are written in batches, which must not lose nor repeat any
+#[test]
+fn it_list_flat_directory() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    let mut expected = Vec::new();
+    for i in 0..5000 {
+        let extension = if i % 2 == 0 { "log" } else { "txt" };
+        let file = path_buf.join(format!("file-{i}.{extension}"));
+        File::create(&file)?;
+        if extension == "log" {
+            expected.push(file.to_str().unwrap().to_string());
+        }
+    }
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .output()?;
+    assert!(output.status.success());
+
+    let mut listed: Vec<String> = String::from_utf8(output.stdout)?
+        .lines()
+        .filter(|l| l.starts_with(path_buf.to_str().unwrap()))
+        .map(|l| l.to_string())
+        .collect();
+    listed.sort();
+    expected.sort();
+    assert_eq!(expected, listed);
+
+    Ok(())
+}
+
+#[test]
+fn it_sorted_list_past_sort_limit() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let file = create_config_file("sort_limit = 2")?;
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-r")
+        .arg("-a")
+        .arg("--sort")
+        .arg("--config")
+        .arg(file.path())
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains(
+        "Warning: more than 2 matches to sort, listing them unsorted as they are found instead"
+    ));
+
+    // Every match is still listed once
+    let mut listed: Vec<String> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    listed.sort();
+    let mut expected: Vec<String> = [
+        "root.txt",
+        "root.log",
+        "subfolder1/sub1.txt",
+        "subfolder1/sub1.log",
+        "subfolder1/subfolder2/sub2.txt",
+        ".hidden.txt",
+        ".hidden_folder/hidden.txt",
+    ]
+    .iter()
+    .map(|f| path_buf.join(f).to_str().unwrap().to_string())
+    .collect();
+    expected.sort();
+    assert_eq!(expected, listed);
+
+    Ok(())
+}
+
+#[test]
+fn it_long_list() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let file = path_buf.clone().join("root.txt");
+    let expected_line = format!("-        0 B {}", file.to_str().unwrap());
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--long")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(expected_line));
+
+    assert!(file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_long_list_color() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let big = path_buf.join("big.log");
+    File::create(&big)?.set_len(200 * 1024 * 1024)?;
+
+    let command = |color: &str| -> Result<Command, Box<dyn Error>> {
+        let mut command = Command::cargo_bin("rmx")?;
+        command
+            .arg("-n")
+            .arg("--long")
+            .arg("--color")
+            .arg(color)
+            .arg("-p")
+            .arg(path_buf.to_str().unwrap())
+            .arg("log");
+        Ok(command)
+    };
+
+    command("always")?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "- \x1b[31m 200.0 MiB\x1b[0m {}",
+            big.display()
+        )));
+    command("never")?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "-  200.0 MiB {}",
+            big.display()
+        )))
+        .stdout(predicate::str::contains("\x1b").not());
+
+    assert!(big.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_apple_cruft_with_extensions() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let files = [
+        path_buf.clone().join("._root.txt"),
+        path_buf.clone().join("._orphan.jpg"),
+        path_buf.clone().join("subfolder1").join(".DS_Store"),
+        path_buf.clone().join("root.log"),
+    ];
+    for file in files.iter().take(3) {
+        File::create(file)?;
+    }
+
+    let control_files = [
+        path_buf.clone().join("root.txt"),
+        path_buf.clone().join(".hidden.txt"),
+    ];
+
+    Command::cargo_bin("rmx")?
+        .arg("-r")
+        .arg("-f")
+        .arg("--apple-cruft")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("log")
+        .assert()
+        .success();
+
+    assert!(files.iter().all(|f| !f.exists()));
+    assert!(control_files.iter().all(|f| f.exists()));
+
+    Ok(())
+}
+
+#[test]
+fn it_apple_cruft_without_extensions() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let file = path_buf.clone().join(".DS_Store");
+    File::create(&file)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--apple-cruft")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleting files"));
+
+    assert!(!file.exists());
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_invert_without_extensions_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-i")
+        .arg("--apple-cruft")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--invert requires extensions"));
+
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_oneline_success() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    fs::write(path_buf.join("root.txt"), "0123456789")?;
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("--oneline")
+        .arg("-f")
+        .arg("-r")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .output()?;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 1);
+    let expected = format!(
+        "rmx: deleted 3 files (10 B) under {} in ",
+        path_buf.to_str().unwrap()
+    );
+    assert!(lines[0].starts_with(&expected));
+    assert!(!path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_oneline_nothing_to_delete() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("--oneline")
+        .arg("-f")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("json")
+        .output()?;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert_eq!(
+        stdout,
+        format!(
+            "rmx: nothing to delete under {}\n",
+            path_buf.to_str().unwrap()
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_invert_recursive_force_should_be_refused() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let control_file = path_buf.clone().join("root.log");
+
+    Command::cargo_bin("rmx")?
+        .arg("-i")
+        .arg("-r")
+        .arg("-f")
+        .arg("--config")
+        .arg(path_buf.join("missing.conf"))
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--allow-invert-recursive"));
+
+    assert!(control_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_invert_recursive_force_with_override() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let deleted_file = path_buf.clone().join("root.log");
+    let control_file = path_buf.clone().join("root.txt");
+
+    Command::cargo_bin("rmx")?
+        .arg("-i")
+        .arg("-r")
+        .arg("-f")
+        .arg("--allow-invert-recursive")
+        .arg("--config")
+        .arg(path_buf.join("missing.conf"))
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(!deleted_file.exists());
+    assert!(control_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_invert_recursive_force_with_config_opt_out() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let file = create_config_file("allow_invert_recursive = true\npreset other=txt log")?;
+    let config_path = file.path().to_path_buf();
+
+    let deleted_file = path_buf.clone().join("root.log");
+    let control_file = path_buf.clone().join("root.txt");
+
+    Command::cargo_bin("rmx")?
+        .arg("-i")
+        .arg("-r")
+        .arg("-f")
+        .arg("--config")
+        .arg(config_path)
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(!deleted_file.exists());
+    assert!(control_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_skipped_hidden_files_hint() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    File::create(path_buf.join(".other.txt"))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 2 hidden files matched but were skipped; pass --all to include them.",
+        ));
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-a")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note:").not());
+
+    Ok(())
+}
+
+#[test]
+fn it_using_alias() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let file = create_config_file("alias jpeg=jpg\npreset other=txt log")?;
+    let config_path = file.path().to_path_buf();
+
+    let files = [path_buf.join("a.jpg"), path_buf.join("a.jpeg")];
+    for file in files.iter() {
+        File::create(file)?;
+    }
+    let control_file = path_buf.join("root.txt");
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--config")
+        .arg(config_path)
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("jpeg")
+        .assert()
+        .success();
+
+    assert!(files.iter().all(|f| !f.exists()));
+    assert!(control_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_truncates_paths_only_in_human_output() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let deep = temp_dir
+        .path()
+        .join("a_rather_long_directory_name")
+        .join("another_long_directory_name")
+        .join("yet_another_directory");
+    fs::create_dir_all(&deep)?;
+    let file = deep.join("deep.txt");
+    File::create(&file)?;
+
+    // Listing to a pipe is data, and is never shortened
+    Command::cargo_bin("rmx")?
+        .env("COLUMNS", "100")
+        .arg("-n")
+        .arg("-p")
+        .arg(deep.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(file.to_str().unwrap()));
+
+    Command::cargo_bin("rmx")?
+        .env("COLUMNS", "100")
+        .arg("-n")
+        .arg("--oneline")
+        .arg("-p")
+        .arg(deep.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("/…/another_long_directory_name/yet_another_directory")
+                .and(predicate::str::contains(deep.to_str().unwrap()).not()),
+        );
+
+    Command::cargo_bin("rmx")?
+        .env("COLUMNS", "100")
+        .arg("-n")
+        .arg("--oneline")
+        .arg("--no-truncate")
+        .arg("-p")
+        .arg(deep.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(deep.to_str().unwrap()));
+
+    Ok(())
+}
+
+fn backdate(path: &std::path::Path, days: u64) -> Result<(), Box<dyn Error>> {
+    let time = std::time::SystemTime::now() - std::time::Duration::from_secs(days * 86400);
+    filetime::set_file_mtime(path, filetime::FileTime::from_system_time(time))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_freed_space() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"Freed .+ \(files totaled .+\)\.",
+        )?);
+
+    // Nothing to measure without any deletion
+    Command::cargo_bin("rmx")?
+        .arg("-rn")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Freed").not());
+
+    Ok(())
+}
+
+#[test]
+fn it_inventory() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let data_dir = temp_dir.path().join("data");
+    fs::create_dir(&data_dir)?;
+    fs::write(data_dir.join("hello.log"), "hello\n")?;
+    fs::write(data_dir.join("empty.log"), "")?;
+    fs::write(data_dir.join("kept.txt"), "kept")?;
+    let inventory = temp_dir.path().join("inv.csv");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--inventory")
+        .arg(&inventory)
+        .arg("--inventory-jobs")
+        .arg("2")
+        .arg("-p")
+        .arg(&data_dir)
+        .arg("log")
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&inventory)?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    assert_eq!(Some("hash,size,mtime,path"), lines.first().copied());
+    lines.remove(0);
+    lines.sort_by_key(|line| line.rsplit(',').next());
+    assert_eq!(2, lines.len());
+    assert!(
+        lines[0].starts_with("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855,0,")
+    );
+    assert!(lines[0].ends_with(&format!(",{}", data_dir.join("empty.log").display())));
+    assert!(
+        lines[1].starts_with("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03,6,")
+    );
+    assert!(lines[1].ends_with(&format!(",{}", data_dir.join("hello.log").display())));
+
+    assert!(!data_dir.join("hello.log").exists());
+    assert!(data_dir.join("kept.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_inventory_write_error_deletes_nothing() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--inventory")
+        .arg(path_buf.join("missing").join("inv.csv"))
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not write inventory"));
+
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_rename_suffix() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+    File::create(path.join("a.log"))?;
+    File::create(path.join("b.log"))?;
+    File::create(path.join("keep.txt"))?;
+    // Collision, left as is
+    fs::write(path.join("b.log.trashed"), "older")?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--rename-suffix")
+        .arg(".trashed")
+        .arg("-p")
+        .arg(path)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renaming files..."))
+        .stdout(predicate::str::contains(
+            "Note: 2 files renamed to *.trashed, run `rmx trashed` to delete them for good.",
+        ));
+
+    assert!(!path.join("a.log").exists());
+    assert!(path.join("a.log.trashed").exists());
+    assert!(!path.join("b.log").exists());
+    assert!(path.join("b.log.1.trashed").exists());
+    assert_eq!("older", fs::read_to_string(path.join("b.log.trashed"))?);
+
+    // Renamed files are not matched again by --invert
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-i")
+        .arg("--rename-suffix")
+        .arg(".trashed")
+        .arg("-p")
+        .arg(path)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file renamed"));
+
+    assert!(path.join("keep.txt.trashed").exists());
+    assert!(path.join("a.log.trashed").exists());
+    assert!(!path.join("a.log.trashed.trashed").exists());
+
+    // Purge
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(path)
+        .arg("trashed")
+        .assert()
+        .success();
+
+    assert_eq!(0, fs::read_dir(path)?.count());
+
+    Ok(())
+}
+
+#[test]
+fn it_rename_suffix_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    for suffix in ["trashed", ".", "./x", ".a/b"] {
+        Command::cargo_bin("rmx")?
+            .arg("-f")
+            .arg(format!("--rename-suffix={suffix}"))
+            .arg("-p")
+            .arg(temp_dir.path())
+            .arg("log")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Invalid suffix for --rename-suffix",
+            ));
+    }
+    assert!(temp_dir.path().join("root.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_confirm_chunks() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    for name in names {
+        File::create(temp_dir.path().join(format!("{name}.log")))?;
+    }
+
+    // Chunks of sorted files: [a, b] deleted, [c, d] declined, then every remaining chunk
+    Command::cargo_bin("rmx")?
+        .arg("--confirm-chunks")
+        .arg("2")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("log")
+        .write_stdin("y\nn\na\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Chunk 1 of 4: 2 file(s)"))
+        .stdout(predicate::str::contains("Chunk 3 of 4"))
+        .stdout(predicate::str::contains("Chunk 4 of 4").not())
+        .stdout(predicate::str::contains(
+            "Note: 1 chunk declined, 2 files kept.",
+        ));
+
+    let mut survivors: Vec<String> = fs::read_dir(temp_dir.path())?
+        .map(|e| e.map(|e| e.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<_, _>>()?;
+    survivors.sort();
+    assert_eq!(vec!["c.log", "d.log"], survivors);
+
+    Ok(())
+}
+
+#[test]
+fn it_confirm_chunks_quit() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-r")
+        .arg("--confirm-chunks")
+        .arg("1")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .write_stdin("q\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 3 chunks declined, 3 files kept.",
+        ));
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+// A listed file is removed by someone else while the prompt waits for an answer
+#[test]
+fn it_vanished_before_confirmation() -> Result<(), Box<dyn Error>> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("rmx"))
+        .arg("-lr")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let mut seen = Vec::new();
+    let mut byte = [0u8; 1];
+    while !String::from_utf8_lossy(&seen).ends_with("[Y/n] ") {
+        if stdout.read(&mut byte)? == 0 {
+            return Err("rmx exited before prompting".into());
+        }
+        seen.push(byte[0]);
+    }
+
+    fs::remove_file(path_buf.join("root.txt"))?;
+    child.stdin.take().unwrap().write_all(b"y\n")?;
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest)?;
+
+    assert!(child.wait()?.success());
+    assert!(rest.contains("Note: 1 file listed earlier no longer existed:"));
+    assert!(rest.contains(&format!("  {}", path_buf.join("root.txt").display())));
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+
+    Ok(())
+}
+
+// Creates a Python package as follow, `gone.py` having been deleted since it was compiled:
+//
+// <temp_dir>/
+// └── pkg/
+//     ├── __init__.py
+//     ├── live.py
+//     ├── __pycache__/
+//     │   ├── __init__.cpython-311.pyc
+//     │   ├── gone.cpython-311.pyc
+//     │   └── live.cpython-311.opt-1.pyc
+//     └── legacy/
+//         ├── kept.py
+//         ├── kept.pyc
+//         └── stale.pyc
+fn create_python_package() -> Result<TempDir, Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let pkg = temp_dir.path().join("pkg");
+    fs::create_dir_all(pkg.join("__pycache__"))?;
+    fs::create_dir_all(pkg.join("legacy"))?;
+    for file in [
+        "__init__.py",
+        "live.py",
+        "__pycache__/__init__.cpython-311.pyc",
+        "__pycache__/gone.cpython-311.pyc",
+        "__pycache__/live.cpython-311.opt-1.pyc",
+        "legacy/kept.py",
+        "legacy/kept.pyc",
+        "legacy/stale.pyc",
+    ] {
+        File::create(pkg.join(file))?;
+    }
+    Ok(temp_dir)
+}
+
+#[test]
+fn it_pycache() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_python_package()?;
+    let pkg = temp_dir.path().join("pkg");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--pycache")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .assert()
+        .success();
+
+    assert!(!pkg.join("__pycache__").exists());
+    assert!(!pkg.join("legacy/kept.pyc").exists());
+    assert!(!pkg.join("legacy/stale.pyc").exists());
+    assert!(pkg.join("live.py").exists());
+    assert!(pkg.join("legacy/kept.py").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_pycache_orphans_only() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_python_package()?;
+    let pkg = temp_dir.path().join("pkg");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--pycache")
+        .arg("--orphans-only")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .assert()
+        .success();
+
+    assert!(!pkg.join("__pycache__/gone.cpython-311.pyc").exists());
+    assert!(!pkg.join("legacy/stale.pyc").exists());
+    assert!(pkg.join("__pycache__/__init__.cpython-311.pyc").exists());
+    assert!(pkg.join("__pycache__/live.cpython-311.opt-1.pyc").exists());
+    assert!(pkg.join("legacy/kept.pyc").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_pycache_keep_from() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_python_package()?;
+    let pkg = temp_dir.path().join("pkg");
+    let keep = temp_dir.path().join("keep.txt");
+    fs::write(&keep, "gone.*.pyc\nkept.py?\n")?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--pycache")
+        .arg("--keep-from")
+        .arg(keep.to_str().unwrap())
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 1 matching directory kept: files inside are protected.",
+        ));
+
+    // The directory is kept whole for the file it holds
+    assert!(pkg.join("__pycache__/live.cpython-311.opt-1.pyc").exists());
+    assert!(pkg.join("legacy/kept.pyc").exists());
+    assert!(!pkg.join("legacy/stale.pyc").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_pycache_with_extensions_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("--pycache")
+        .arg("pyc")
+        .assert()
+        .failure();
+
+    Command::cargo_bin("rmx")?
+        .arg("--orphans-only")
+        .arg("pyc")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--orphans-only requires --pycache",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_touch_parents() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let affected = path_buf.join("subfolder1/subfolder2");
+    let unaffected = path_buf.join("subfolder1");
+    backdate(&affected, 10)?;
+    backdate(&unaffected, 10)?;
+    let before = fs::metadata(&unaffected)?.modified()?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--touch-parents")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("bak")
+        .assert()
+        .success();
+
+    let day_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(86400);
+    assert!(fs::metadata(&affected)?.modified()? > day_ago);
+    assert_eq!(before, fs::metadata(&unaffected)?.modified()?);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_keep_from() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let keep = create_config_file(&format!(
+        "# Must survive
+{}
+
+sub2.*
+{}
+",
+        path_buf.join("root.txt").display(),
+        path_buf.join("missing.txt").display()
+    ))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--keep-from")
+        .arg(keep.path())
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 2 files kept by --keep-from.",
+        ))
+        .stderr(predicate::str::contains(format!(
+            "Warning: {:?} of the keep list matched no file",
+            path_buf.join("missing.txt").display().to_string()
+        )));
+
+    assert!(path_buf.join("root.txt").exists());
+    assert!(path_buf.join("subfolder1/subfolder2/sub2.txt").exists());
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_multiple_paths() -> Result<(), Box<dyn Error>> {
+    let first = create_temp_folder();
+    let second = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-r")
+        .arg("-p")
+        .arg(first.path())
+        .arg("-p")
+        .arg(second.path())
+        .arg("txt")
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        // A single prompt, for the matches of both
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 6 file(s)?",
+        ));
+
+    for dir in [first.path(), second.path()] {
+        assert!(!dir.join("root.txt").exists());
+        assert!(!dir.join("subfolder1/sub1.txt").exists());
+        assert!(!dir.join("subfolder1/subfolder2/sub2.txt").exists());
+        assert!(dir.join("root.log").exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_paths_from_stdin() -> Result<(), Box<dyn Error>> {
+    let first = create_temp_folder();
+    let second = create_temp_folder();
+    let missing = first.path().join("missing");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--paths-from-stdin")
+        .arg("txt")
+        .write_stdin(format!(
+            "{}\n{}\n{}\n",
+            first.path().join("subfolder1").display(),
+            missing.display(),
+            second.path().display()
+        ))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Warning: {} read from stdin does not exist, skipping it",
+            missing.display()
+        )));
+
+    assert!(first.path().join("root.txt").exists());
+    assert!(!first.path().join("subfolder1/sub1.txt").exists());
+    assert!(!first.path().join("subfolder1/subfolder2/sub2.txt").exists());
+    assert!(!second.path().join("root.txt").exists());
+    assert!(!second.path().join("subfolder1/sub1.txt").exists());
+    assert!(second.path().join("root.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_paths_from_stdin_with_prompt_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("--paths-from-stdin")
+        .arg("txt")
+        .write_stdin(format!("{}\n", temp_dir.path().display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--paths-from-stdin requires -f/--force or -n/--dry-run",
+        ));
+    assert!(temp_dir.path().join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_files_from() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let list = temp_dir.path().join("list.txt");
+    fs::write(
+        &list,
+        "root.txt  \n\nsubfolder1/sub1.log\nsubfolder1\nmissing.txt\n",
+    )?;
+
+    Command::cargo_bin("rmx")?
+        .arg("--files-from")
+        .arg(&list)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        // Only the files left once the directory and the missing file are skipped
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 2 file(s)?",
+        ))
+        .stderr(
+            predicate::str::contains("missing.txt")
+                .and(predicate::str::contains("is a directory, skipping it")),
+        );
+
+    assert!(!temp_dir.path().join("root.txt").exists());
+    assert!(!temp_dir.path().join("subfolder1/sub1.log").exists());
+    assert!(temp_dir.path().join("subfolder1/sub1.txt").exists());
+    assert!(temp_dir.path().join("root.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_single_file() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = temp_dir.path().join("subfolder1/sub1.txt");
+
+    // Listed in a dry run, like collected files
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-p")
+        .arg(&file)
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(file.display().to_string()));
+    assert!(file.exists());
+
+    Command::cargo_bin("rmx")?
+        .arg("-p")
+        .arg(&file)
+        .arg("txt")
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 1 file(s)?",
+        ));
+
+    // Only the file is deleted, nothing is walked
+    assert!(!file.exists());
+    assert!(
+        temp_dir
+            .path()
+            .join("subfolder1/subfolder2/sub2.txt")
+            .exists()
+    );
+    assert!(temp_dir.path().join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_single_file_not_matching_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let file = temp_dir.path().join("root.log");
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(&file)
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "does not match the given extensions, --no-ext-check deletes it anyway.",
+        ));
+    assert!(file.exists());
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--no-ext-check")
+        .arg("-p")
+        .arg(&file)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!file.exists());
+
+    // Directories are collected from, whatever the extensions
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--no-ext-check")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--no-ext-check requires -p/--path to be a file.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_single_file_without_extensions() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let file = temp_dir.path().join("core.12345");
+    File::create(&file)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(&file)
+        .assert()
+        .success();
+    assert!(!file.exists());
+
+    // Only alone
+    File::create(&file)?;
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(&file)
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "is a file, which -p/--path only takes alone.",
+        ));
+    assert!(file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_files_from_stdin() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .current_dir(temp_dir.path())
+        .args(["-f", "--files-from", "-", "txt"])
+        .write_stdin("root.txt\nroot.log\nsubfolder1/sub1.txt\n")
+        .assert()
+        .success();
+
+    // Narrowed by the given extension
+    assert!(!temp_dir.path().join("root.txt").exists());
+    assert!(!temp_dir.path().join("subfolder1/sub1.txt").exists());
+    assert!(temp_dir.path().join("root.log").exists());
+
+    Command::cargo_bin("rmx")?
+        .args(["--files-from", "-"])
+        .write_stdin("root.log\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--files-from - requires -f/--force or -n/--dry-run",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_multiple_paths_collected_once() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = fs::canonicalize(temp_dir.path())?;
+    let sub = path_buf.join("subfolder1");
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("-nr")
+        .arg("-p")
+        .arg(&sub)
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("-p")
+        .arg(path_buf.join("."))
+        .arg("txt")
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout)?;
+
+    assert!(stdout.contains(&format!(
+        "Note: {} is inside {}, collecting it once.",
+        sub.display(),
+        path_buf.display()
+    )));
+    assert!(stdout.contains(&format!(
+        "Note: {} was given twice, collecting it once.",
+        path_buf.display()
+    )));
+    let mut listed: Vec<&str> = stdout.lines().filter(|l| l.ends_with(".txt")).collect();
+    listed.sort();
+    let expected = [
+        path_buf.join("root.txt"),
+        sub.join("sub1.txt"),
+        sub.join("subfolder2/sub2.txt"),
+    ];
+    assert_eq!(
+        expected
+            .iter()
+            .map(|p| p.to_str().unwrap())
+            .collect::<Vec<_>>(),
+        listed
+    );
+    // Counted once too
+    assert!(stdout.contains("Note: 1 hidden file matched but was skipped"));
+
+    Ok(())
+}
+
+#[test]
+fn it_multiple_paths_with_plan_out_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("-p")
+        .arg(temp_dir.path().join("subfolder1"))
+        .arg("--plan-out")
+        .arg(temp_dir.path().join("plan.json"))
+        .arg("txt")
+        .assert()
+        .failure()
+        // Given as JSON, like the plan
+        .stdout(predicate::str::contains(
+            "--plan-out takes a single -p/--path",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_paths_from_another_directory() -> Result<(), Box<dyn Error>> {
+    let temp_dir = FixtureTree::new()
+        .file("work/tree/a.log")
+        .file("work/tree/sub/b.log")
+        .file("work/tree/keep.log")
+        .file("work/tree/sub/kept.log")
+        .build()?;
+    let root = fs::canonicalize(temp_dir.path())?;
+    let work = root.join("work");
+    let tree = work.join("tree");
+    // Relative to the directory of the list, not to the current one
+    fs::create_dir(work.join("lists"))?;
+    fs::write(work.join("lists/keep.txt"), "../tree/keep.log\n")?;
+
+    Command::cargo_bin("rmx")?
+        .current_dir(&work)
+        .args(["-rf", "-p", "tree", "--keep-from", "lists/keep.txt"])
+        .args(["--audit", "audit.tsv", "--inventory", "inventory.csv"])
+        .args(["--checkpoint", "checkpoint.txt", "log"])
+        .arg("--exclude")
+        .arg("kept.log")
+        .assert()
+        .success();
+
+    assert!(!tree.join("a.log").exists());
+    assert!(!tree.join("sub/b.log").exists());
+    assert!(tree.join("keep.log").exists());
+
+    // Every stored path is absolute, so that it can be read back from anywhere
+    let audit = fs::read_to_string(work.join("audit.tsv"))?;
+    assert_eq!(4, audit.lines().count());
+    assert!(audit.lines().all(|l| l.starts_with(tree.to_str().unwrap())));
+    let inventory = fs::read_to_string(work.join("inventory.csv"))?;
+    let mut rows: Vec<&str> = inventory
+        .lines()
+        .skip(1)
+        .map(|l| l.rsplit(',').next().unwrap())
+        .collect();
+    rows.sort();
+    assert_eq!(
+        vec![
+            tree.join("a.log").display().to_string(),
+            tree.join("sub/b.log").display().to_string(),
+        ],
+        rows
+    );
+    let checkpoint = fs::read_to_string(work.join("checkpoint.txt"))?;
+    let mut dirs: Vec<&str> = checkpoint
+        .lines()
+        .filter_map(|l| l.split('\t').nth(1))
+        .collect();
+    dirs.sort();
+    assert_eq!(
+        vec![
+            tree.display().to_string(),
+            tree.join("sub").display().to_string()
+        ],
+        dirs
+    );
+
+    // Relative to --base instead
+    fs::write(work.join("lists/keep.txt"), "tree/sub/kept.log\n")?;
+    Command::cargo_bin("rmx")?
+        .current_dir(&root)
+        .args([
+            "-rf",
+            "-p",
+            "work/tree",
+            "--keep-from",
+            "work/lists/keep.txt",
+        ])
+        .args(["--base", "work", "log"])
+        .assert()
+        .success();
+
+    assert!(!tree.join("keep.log").exists());
+    assert!(tree.join("sub/kept.log").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_symlinks() -> Result<(), Box<dyn Error>> {
+    let temp_dir = FixtureTree::new()
+        .file("var/build.log")
+        .file("var/logs/old.log")
+        .symlink("tree/build.log", "../var/build.log")
+        .symlink("tree/logs", "../var/logs")
+        .build()?;
+    let path_buf = temp_dir.path().join("tree");
+    let link = path_buf.join("build.log");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rn")
+        .arg("--symlinks")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}@\n", link.display())))
+        .stdout(predicate::str::contains("old.log").not());
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--symlinks")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .assert()
+        .success();
+
+    assert!(fs::symlink_metadata(&link).is_err());
+    assert!(temp_dir.path().join("var/build.log").exists());
+    assert!(temp_dir.path().join("var/logs/old.log").exists());
+    assert!(path_buf.join("logs").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_follow_symlinks() -> Result<(), Box<dyn Error>> {
+    let temp_dir = FixtureTree::new()
+        .file("real/data.log")
+        .symlink("tree/linked", "../real")
+        .symlink("real/loop", "..")
+        .build()?;
+    let path_buf = temp_dir.path().join("tree");
+
+    Command::cargo_bin("rmx")?
+        .arg("-rn")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.log").not());
+
+    Command::cargo_bin("rmx")?
+        .arg("-rn")
+        .arg("--follow-symlinks")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            path_buf.join("linked/data.log").to_str().unwrap(),
+        ))
+        .stderr(predicate::str::contains(
+            "already walked through another path, like a symlink loop",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_preserve_link_targets() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let logs = temp_dir.path().join("logs");
+    fs::create_dir(&logs)?;
+    File::create(logs.join("2024-05-01.log"))?;
+    File::create(logs.join("2024-06-01.log"))?;
+    std::os::unix::fs::symlink("logs/2024-06-01.log", temp_dir.path().join("current.log"))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--preserve-link-targets")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 1 file preserved: referenced by symlinks.",
+        ));
+
+    assert!(!logs.join("2024-05-01.log").exists());
+    assert!(logs.join("2024-06-01.log").exists());
+    assert!(fs::metadata(temp_dir.path().join("current.log")).is_ok());
+
+    Ok(())
+}
+
+// The test process stands for the batch job holding the locks while rmx runs
+#[cfg(unix)]
+#[test]
+fn it_respect_locks() -> Result<(), Box<dyn Error>> {
+    use std::os::fd::AsRawFd;
+
+    let temp_dir = tempfile::tempdir()?;
+    let flocked = File::create(temp_dir.path().join("flocked.log"))?;
+    let fcntl_locked = fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(temp_dir.path().join("fcntl.log"))?;
+    File::create(temp_dir.path().join("done.log"))?;
+
+    assert_eq!(0, unsafe {
+        libc::flock(flocked.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB)
+    });
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as libc::c_short;
+    lock.l_whence = libc::SEEK_SET as libc::c_short;
+    assert_eq!(0, unsafe {
+        libc::fcntl(fcntl_locked.as_raw_fd(), libc::F_SETLK, &lock)
+    });
+
+    Command::cargo_bin("rmx")?
+        .arg("--force")
+        .arg("--respect-locks")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "flocked.log: locked by another process",
+        ))
+        .stdout(predicate::str::contains(
+            "fcntl.log: locked by another process",
+        ))
+        .stdout(predicate::str::contains(
+            "Note: 2 files skipped: locked by another process.",
+        ));
+
+    assert!(temp_dir.path().join("flocked.log").exists());
+    assert!(temp_dir.path().join("fcntl.log").exists());
+    assert!(!temp_dir.path().join("done.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_peek_archives() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let mut zip = zip::ZipWriter::new(File::create(temp_dir.path().join("bundle.zip"))?);
+    for name in ["run.log", "notes.md", "data.csv"] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())?;
+        zip.write_all(b"content")?;
+    }
+    zip.finish()?;
+    fs::write(temp_dir.path().join("broken.zip"), b"not a zip")?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--peek-archives")
+        .arg("--peek-ext")
+        .arg("log")
+        .arg("-p")
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("zip")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "bundle.zip  [archive: 1 of 3 entries match]",
+        ))
+        .stdout(predicate::str::contains(
+            "broken.zip  [archive: unreadable]",
+        ));
+
+    // Archives are never extracted
+    assert_eq!(2, fs::read_dir(temp_dir.path())?.count());
+
+    Ok(())
+}
+
+#[test]
+fn it_peek_ext_requires_peek_archives() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("zip")
+        .arg("--peek-ext")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--peek-ext requires --peek-archives",
+        ));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn it_immutable_file() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let file = path_buf.join("root.log");
+
+    // Setting the flag requires privileges, and a filesystem supporting it
+    let chattr = std::process::Command::new("chattr")
+        .arg("+i")
+        .arg(&file)
+        .output();
+    if !chattr.is_ok_and(|o| o.status.success()) {
+        eprintln!("Skipping it_immutable_file: could not run chattr +i");
+        return Ok(());
+    }
+
+    let failed = Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "root.log: immutable, run chattr -i to remove",
+        ))
+        .try_success();
+    let still_there = file.exists();
+
+    let cleared = Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--clear-immutable")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("log")
+        .assert()
+        .try_success();
+
+    // Lets the temporary directory be removed whatever happened
+    if file.exists() {
+        std::process::Command::new("chattr")
+            .arg("-i")
+            .arg(&file)
+            .status()?;
+    }
+
+    assert!(failed.is_err());
+    assert!(still_there);
+    assert!(cleared.is_ok());
+    assert!(!file.exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_symlinked_start_path() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let links = tempfile::tempdir()?;
+    let current = links.path().join("current");
+    std::os::unix::fs::symlink(temp_dir.path(), &current)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-p")
+        .arg(&current)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Note: {} resolves to {}",
+            current.display(),
+            fs::canonicalize(temp_dir.path())?.display()
+        )))
+        // Listed paths keep going through the link
+        .stdout(predicate::str::contains(
+            current.join("root.log").display().to_string(),
+        ));
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-p")
+        .arg(&current)
+        .arg("log")
+        .assert()
+        .success();
+
+    assert!(!temp_dir.path().join("root.log").exists());
+    assert!(current.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_never_older_than() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let old_file = path_buf.join("root.log");
+    let recent_file = path_buf.join("root.txt");
+    backdate(&old_file, 400)?;
+    backdate(&recent_file, 30)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--never-older-than")
+        .arg("1y")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: 1 file protected by --never-older-than.",
+        ));
+
+    assert!(old_file.exists());
+    assert!(!recent_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_older_than() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let old_file = path_buf.join("root.log");
+    let recent_file = path_buf.join("root.txt");
+    let deep_file = path_buf.join("subfolder1").join("sub1.txt");
+    backdate(&old_file, 40)?;
+    backdate(&recent_file, 10)?;
+    backdate(&deep_file, 31)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--older-than")
+        .arg("30d")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .assert()
+        .success();
+
+    assert!(!old_file.exists());
+    assert!(!deep_file.exists());
+    assert!(recent_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_age_window() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let fresh_file = path_buf.join("root.txt");
+    let old_file = path_buf.join("root.log");
+    let older_file = path_buf.join("subfolder1").join("sub1.log");
+    backdate(&old_file, 3)?;
+    backdate(&older_file, 10)?;
+
+    // Only the fresh file is newer than an hour
+    let listed = listed_names(&path_buf, &["--newer-than", "1h", "txt", "log"])?;
+    assert!(listed.contains(&"root.txt".to_string()));
+    assert!(!listed.contains(&"root.log".to_string()));
+
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--older-than")
+        .arg("1d")
+        .arg("--newer-than")
+        .arg("1w")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .assert()
+        .success();
+
+    assert!(fresh_file.exists());
+    assert!(!old_file.exists());
+    assert!(older_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_age_window_empty_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--older-than")
+        .arg("2w")
+        .arg("--newer-than")
+        .arg("7d")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--newer-than 1w is not longer than --older-than 2w, nothing would be collected.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_age_slack() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    // 30 days old by the next weekly run
+    backdate(&path_buf.join("root.log"), 25)?;
+    backdate(&path_buf.join("subfolder1").join("sub1.log"), 20)?;
+
+    let listed = listed_names(&path_buf, &["--older-than", "30d", "log"])?;
+    assert!(listed.is_empty());
+
+    let listed = listed_names(
+        &path_buf,
+        &["--older-than", "30d", "--age-slack", "7d", "log"],
+    )?;
+    assert_eq!(vec!["root.log".to_string()], listed);
+
+    // The window starts at 23 days as well
+    let listed = listed_names(
+        &path_buf,
+        &[
+            "--older-than",
+            "30d",
+            "--age-slack",
+            "1w",
+            "--newer-than",
+            "24d",
+            "log",
+        ],
+    )?;
+    assert!(listed.is_empty());
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--older-than")
+        .arg("30d")
+        .arg("--age-slack")
+        .arg("7d")
+        .arg("--newer-than")
+        .arg("3w")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--newer-than 3w is not longer than --older-than 30d minus --age-slack 1w, nothing \
+            would be collected.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_age_slack_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--age-slack")
+        .arg("7d")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--older-than <DURATION>"));
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--older-than")
+        .arg("7d")
+        .arg("--age-slack")
+        .arg("1w")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--age-slack 1w is not shorter than --older-than 1w, every file would be collected.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_older_than_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--older-than")
+        .arg("30days")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration \"30days\""));
+
+    Ok(())
+}
+
+#[test]
+fn it_invalid_duration_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--never-older-than")
+        .arg("1x")
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration \"1x\""));
+
+    Ok(())
+}
+
+#[test]
+fn it_follow_dir_lists() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let other_dir = tempfile::tempdir()?;
+    let other = other_dir.path().to_path_buf();
+    let third_dir = tempfile::tempdir()?;
+    let third = third_dir.path().to_path_buf();
+    File::create(other.join("other.txt"))?;
+    File::create(third.join("third.txt"))?;
+
+    // <temp_dir> lists <other>, which lists <third>
+    fs::write(path_buf.join("rmx.dirs"), format!("{}\n", other.display()))?;
+    fs::write(
+        other.join("rmx.dirs"),
+        format!("{}\nmissing\n", third.display()),
+    )?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--follow-dir-lists")
+        .arg("rmx.dirs")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("dirs")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("is not a directory, skipping it"));
+
+    assert!(!path_buf.join("root.txt").exists());
+    assert!(!other.join("other.txt").exists());
+    assert!(!third.join("third.txt").exists());
+    assert!(path_buf.join("rmx.dirs").exists());
+    assert!(other.join("rmx.dirs").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_strict() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    fs::write(path_buf.join("rmx.dirs"), "missing\n")?;
+
+    let command = |strict: bool| -> Result<Command, Box<dyn Error>> {
+        let mut command = Command::cargo_bin("rmx")?;
+        command
+            .arg("-f")
+            .arg("--follow-dir-lists")
+            .arg("rmx.dirs")
+            .arg("-p")
+            .arg(path_buf.to_str().unwrap())
+            .arg("txt");
+        if strict {
+            command.arg("--strict");
+        }
+        Ok(command)
+    };
+
+    // The listed directory is missing: nothing is deleted under --strict
+    command(true)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--strict: aborting before deleting anything, because of:\n  ",
+        ))
+        .stderr(predicate::str::contains("is not a directory, skipping it"));
+    assert!(path_buf.join("root.txt").exists());
+
+    command(false)?
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: "));
+    assert!(!path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_empty_dirs() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let chain = path_buf.join("chain");
+    fs::create_dir_all(chain.join("a").join("b"))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("-r")
+        .arg("--empty-dirs")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .assert()
+        .success();
+
+    assert!(!chain.exists());
+    assert!(path_buf.exists());
+    assert!(path_buf.join("subfolder1").join("subfolder2").exists());
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_empty_dirs_with_extensions_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--empty-dirs")
+        .arg("txt")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn it_nice() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--nice")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning").not());
+
+    assert!(!path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_discover() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("--discover")
+        .arg("-r")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^txt +3 +0 B$")?)
+        .stdout(predicate::str::is_match(r"(?m)^log +2 +0 B$")?)
+        .stdout(predicate::str::is_match(r"(?m)^dat +2 +0 B$")?)
+        .stdout(predicate::str::contains("Do you really").not());
+
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+// Writes the RMX_* variables given to the notify command to a file, one per line
+fn notify_env_command(output: &std::path::Path) -> String {
+    format!("env | grep '^RMX_' | sort > '{}'", output.to_str().unwrap())
+}
+
+#[test]
+fn it_notify_cmd_after_success() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let env_dir = tempfile::tempdir()?;
+    let env_file = env_dir.path().join("env");
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--notify-cmd")
+        .arg(notify_env_command(&env_file))
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .assert()
+        .success();
+
+    let env = fs::read_to_string(&env_file)?;
+    assert!(env.contains("RMX_DELETED=2\n"));
+    assert!(env.contains("RMX_FAILED=0\n"));
+    assert!(env.contains("RMX_BYTES=0\n"));
+    assert!(env.contains(&format!("RMX_PATH={}\n", path_buf.display())));
+    assert!(env.contains("RMX_STATUS=success\n"));
+
+    Ok(())
+}
+
+#[test]
+fn it_notify_cmd_after_partial_failure() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let env_dir = tempfile::tempdir()?;
+    let env_file = env_dir.path().join("env");
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("rmx"))
+        .arg("--notify-cmd")
+        .arg(notify_env_command(&env_file))
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+
+    // Replacing a match by a directory while rmx waits for confirmation makes its deletion fail
+    let mut stdout = child.stdout.take().unwrap();
+    let mut prompt = Vec::new();
+    let mut byte = [0; 1];
+    while !String::from_utf8_lossy(&prompt).contains("[Y/n]") {
+        std::io::Read::read_exact(&mut stdout, &mut byte)?;
+        prompt.push(byte[0]);
+    }
+    fs::remove_file(path_buf.join("root.log"))?;
+    fs::create_dir_all(path_buf.join("root.log/kept"))?;
+    child.stdin.take().unwrap().write_all(b"y\n")?;
+
+    assert!(!child.wait()?.success());
+
+    let env = fs::read_to_string(&env_file)?;
+    assert!(env.contains("RMX_DELETED=1\n"));
+    assert!(env.contains("RMX_FAILED=1\n"));
+    assert!(env.contains("RMX_STATUS=partial\n"));
+
+    Ok(())
+}
+
+#[test]
+fn it_notify_cmd_skipped_on_dry_run() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let env_dir = tempfile::tempdir()?;
+    let env_file = env_dir.path().join("env");
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--notify-cmd")
+        .arg(notify_env_command(&env_file))
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(!env_file.exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_notify_cmd_failure_is_a_warning() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--notify-cmd")
+        .arg("exit 1")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: notify command failed"));
+
+    Ok(())
+}
+
+#[test]
+fn it_hidden_dirs_without_hidden_files() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--hidden-dirs")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("-r")
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(!path_buf.join(".hidden_folder").join("hidden.txt").exists());
+    assert!(path_buf.join(".hidden.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_audit_gzip() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let audit_dir = tempfile::tempdir()?;
+    let audit_path = audit_dir.path().join("audit.tsv.gz");
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-r")
+        .arg("--audit")
+        .arg(audit_path.to_str().unwrap())
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .assert()
+        .success();
+
+    let mut audit = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(File::open(&audit_path)?),
+        &mut audit,
+    )?;
+    assert!(audit.contains(&format!(
+        "{}\tyes\tselected\n",
+        path_buf.join("subfolder1").join("sub1.txt").display()
+    )));
+    assert!(audit.contains(&format!(
+        "{}\tno\textension mismatch\n",
+        path_buf.join("root.log").display()
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn it_plan_out_is_reproducible() -> Result<(), Box<dyn Error>> {
+    let plan_dir = tempfile::tempdir()?;
+    let mut plans = Vec::new();
+
+    // Two distinct trees with the same content give the same plan
+    for run in 0..2 {
+        let temp_dir = create_temp_folder();
+        let path_buf = temp_dir.path().to_path_buf();
+        let plan_path = plan_dir.path().join(format!("plan{run}.json"));
+
+        Command::cargo_bin("rmx")?
+            .arg("-n")
+            .arg("-r")
+            .arg("--plan-out")
+            .arg(plan_path.to_str().unwrap())
+            .arg("-p")
+            .arg(path_buf.to_str().unwrap())
+            .arg("txt")
+            .assert()
+            .success();
+
+        plans.push(fs::read(&plan_path)?);
+    }
+
+    assert_eq!(plans[0], plans[1]);
+    assert_eq!(
+        "{\n  \"schema_version\": 1,\n  \"files\": [\n    {\n      \"path\": \"root.txt\",\n      \"size\": 0\n    },\n    {\n      \"path\": \"subfolder1/sub1.txt\",\n      \"size\": 0\n    },\n    {\n      \"path\": \"subfolder1/subfolder2/sub2.txt\",\n      \"size\": 0\n    }\n  ]\n}\n",
+        String::from_utf8(plans.remove(0))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_simulate_plan() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let plan_path = path_buf.join("plan.json");
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("-r")
+        .arg("--plan-out")
+        .arg(plan_path.to_str().unwrap())
+        .arg("--plan-mtimes")
+        .arg("-p")
+        .arg(path_buf.to_str().unwrap())
+        .arg("txt")
+        .arg("log")
+        .assert()
+        .success();
+
+    // Only the plan is read, the tree itself could be gone
+    backdate(&path_buf.join("root.txt"), 400)?;
+    fs::remove_file(path_buf.join("root.log"))?;
+
+    Command::cargo_bin("rmx")?
+        .arg("--simulate")
+        .arg(plan_path.to_str().unwrap())
+        .arg("--never-older-than")
+        .arg("1d")
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("root.log\n"))
+        .stdout(predicate::str::contains("subfolder1/sub1.log\n"))
+        .stdout(predicate::str::contains("root.txt").not())
+        .stdout(predicate::str::contains(
+            "Simulation: 2 of 5 planned files would be deleted (0 B).",
+        ));
+
+    assert!(path_buf.join("subfolder1").join("sub1.log").exists());
+
+    Ok(())
+}
+
+// Runs rmx on a directory holding a single a.log file, with the flags of the cell and "y" as the
+// answer to any prompt
+fn run_truth_table_cell(
+    extension: &str,
+    flags: &[&str],
+) -> Result<(TempDir, std::process::Output), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    File::create(temp_dir.path().join("a.log"))?;
+
+    let output = Command::cargo_bin("rmx")?
+        .args(flags)
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg(extension)
+        .write_stdin("y\n")
+        .output()?;
+    Ok((temp_dir, output))
+}
+
+// Every combination of -l, -n, -f and -q, as (list, dry_run, force, quiet, flags)
+fn truth_table_cells() -> Vec<(bool, bool, bool, bool, Vec<&'static str>)> {
+    let mut cells = Vec::new();
+    for cell in 0..16 {
+        let (list, dry_run, force, quiet) =
+            (cell & 1 != 0, cell & 2 != 0, cell & 4 != 0, cell & 8 != 0);
+        let flags = [(list, "-l"), (dry_run, "-n"), (force, "-f"), (quiet, "-q")]
+            .into_iter()
+            .filter_map(|(set, flag)| set.then_some(flag))
+            .collect();
+        cells.push((list, dry_run, force, quiet, flags));
+    }
+    cells
+}
+
+// Everything goes to stdout, in this order:
+// - the listing, with -l or -n, whatever -q, its footer going to stderr as stdout is not a terminal
+// - the prompt, unless -n or -f, whatever -q
+// - the progress messages and notes, unless -n or -q
+#[test]
+fn it_output_truth_table() -> Result<(), Box<dyn Error>> {
+    for (list, dry_run, force, quiet, flags) in truth_table_cells() {
+        let (temp_dir, output) = run_truth_table_cell("log", &flags)?;
+        let file = temp_dir.path().join("a.log");
+
+        let mut expected: Vec<String> = Vec::new();
+        if list || dry_run {
+            expected.push(file.display().to_string());
+        }
+        if !dry_run && !force {
+            expected.push("Do you really want to delete 1 file(s)? [Y/n] ".to_string());
+        }
+        if !dry_run && !quiet {
+            expected.push("Deleting files...".to_string());
+            expected.push("Done!".to_string());
+            expected.push("Freed ".to_string());
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(expected.len(), lines.len(), "{flags:?}:\n{stdout}");
+        for (expected, line) in expected.iter().zip(&lines) {
+            assert!(line.starts_with(expected.as_str()), "{flags:?}:\n{stdout}");
+        }
+        let footer = if list || dry_run {
+            "-- 1 file, 0 B --\n"
+        } else {
+            ""
+        };
+        assert_eq!(footer, String::from_utf8(output.stderr)?, "{flags:?}");
+        assert!(output.status.success(), "{flags:?}");
+        assert_eq!(dry_run, file.exists(), "{flags:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_output_truth_table_without_match() -> Result<(), Box<dyn Error>> {
+    for (_, _, _, quiet, flags) in truth_table_cells() {
+        let (temp_dir, output) = run_truth_table_cell("txt", &flags)?;
+
+        let expected = if quiet { "" } else { "No matching file.\n" };
+        assert_eq!(expected, String::from_utf8(output.stdout)?, "{flags:?}");
+        assert!(output.stderr.is_empty(), "{flags:?}");
+        assert!(output.status.success(), "{flags:?}");
+        assert!(temp_dir.path().join("a.log").exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_quiet_with_oneline_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-q")
+        .arg("--oneline")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("log")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+fn checkpoint_run(path: &Path, checkpoint: &Path, extension: &str) -> Command {
+    let mut cmd = Command::cargo_bin("rmx").unwrap();
+    cmd.arg("-rf")
+        .arg("--checkpoint")
+        .arg(checkpoint)
+        .arg("-p")
+        .arg(path)
+        .arg(extension);
+    cmd
+}
+
+#[test]
+fn it_checkpoint() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let checkpoint_dir = tempfile::tempdir()?;
+    let checkpoint = checkpoint_dir.path().join("checkpoint");
+
+    checkpoint_run(&path_buf, &checkpoint, "txt")
+        .assert()
+        .success();
+
+    // One line per directory, in path order, all from the same invocation
+    let content = fs::read_to_string(&checkpoint)?;
+    let lines: Vec<(&str, &str)> = content
+        .lines()
+        .map(|l| l.split_once('\t').unwrap())
+        .collect();
+    let dirs: Vec<&str> = lines.iter().map(|(_, dir)| *dir).collect();
+    assert_eq!(
+        vec![
+            path_buf.display().to_string(),
+            path_buf.join("subfolder1").display().to_string(),
+            path_buf.join("subfolder1/subfolder2").display().to_string(),
+        ],
+        dirs
+    );
+    assert!(lines.iter().all(|(hash, _)| *hash == lines[0].0));
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_checkpoint_resume_after_interruption() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let checkpoint_dir = tempfile::tempdir()?;
+    let checkpoint = checkpoint_dir.path().join("checkpoint");
+
+    checkpoint_run(&path_buf, &checkpoint, "txt")
+        .assert()
+        .success();
+
+    // Killed while writing the second line, before the last directories were done
+    let content = fs::read_to_string(&checkpoint)?;
+    let first_line = content.lines().next().unwrap().to_string();
+    let partial = &content[..first_line.len() + 10];
+    fs::write(&checkpoint, partial)?;
+    File::create(path_buf.join("subfolder1/sub1.txt"))?;
+    File::create(path_buf.join("subfolder1/subfolder2/sub2.txt"))?;
+    // Left in a directory the checkpoint says is done, so it is deleted again
+    File::create(path_buf.join("root.txt"))?;
+
+    checkpoint_run(&path_buf, &checkpoint, "txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: resuming from checkpoint, 1 directory already completed.",
+        ))
+        .stderr(predicate::str::contains(format!(
+            "Warning: {} was completed by an earlier run, but still holds 1 matching file(s)",
+            path_buf.display()
+        )));
+
+    assert!(!path_buf.join("root.txt").exists());
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+    assert!(!path_buf.join("subfolder1/subfolder2/sub2.txt").exists());
+
+    // The first directory is not recorded twice, and the partial line is kept apart
+    let content = fs::read_to_string(&checkpoint)?;
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(4, lines.len());
+    assert_eq!(first_line, lines[0]);
+    assert_eq!(&partial[first_line.len() + 1..], lines[1]);
+    assert!(lines[3].ends_with("subfolder1/subfolder2"));
+
+    Ok(())
+}
+
+#[test]
+fn it_checkpoint_other_invocation_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let checkpoint_dir = tempfile::tempdir()?;
+    let checkpoint = checkpoint_dir.path().join("checkpoint");
+
+    checkpoint_run(&path_buf, &checkpoint, "txt")
+        .assert()
+        .success();
+
+    checkpoint_run(&path_buf, &checkpoint, "log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("another invocation"));
+
+    assert!(path_buf.join("root.log").exists());
+
+    Ok(())
+}
+
+// Position of each path in the listing printed by rmx
+fn listing_positions(
+    path: &Path,
+    traversal: &str,
+    files: &[&Path],
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    let output = Command::cargo_bin("rmx")?
+        .arg("-nra")
+        .arg("--traversal")
+        .arg(traversal)
+        .arg("-p")
+        .arg(path)
+        .arg("txt")
+        .output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    Ok(files
+        .iter()
+        .map(|f| {
+            let f = f.display().to_string();
+            lines.iter().position(|l| *l == f).unwrap()
+        })
+        .collect())
+}
+
+#[test]
+fn it_traversal_order() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let root = path_buf.join("root.txt");
+    let sub2 = path_buf.join("subfolder1/subfolder2/sub2.txt");
+    let hidden = path_buf.join(".hidden_folder/hidden.txt");
+
+    // The start path comes first in both orders
+    for traversal in ["dfs", "bfs"] {
+        let positions = listing_positions(&path_buf, traversal, &[&root, &sub2])?;
+        assert!(positions[0] < positions[1], "{traversal}");
+    }
+
+    // Breadth-first lists every shallower file before the deeper ones
+    let positions = listing_positions(&path_buf, "bfs", &[&hidden, &sub2])?;
+    assert!(positions[0] < positions[1]);
+
+    // Depth-first finishes a subdirectory before the next one, whatever the order they are read
+    let positions = listing_positions(
+        &path_buf,
+        "dfs",
+        &[&hidden, &path_buf.join("subfolder1/sub1.txt"), &sub2],
+    )?;
+    assert!(positions[0] < positions[1] || positions[0] > positions[2]);
+    assert_eq!(positions[1] + 1, positions[2]);
+
+    Ok(())
+}
+
+#[test]
+fn it_traversal_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("--traversal")
+        .arg("random")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn it_select_without_terminal_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--select")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--select needs a terminal"));
+
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+// Names listed by `rmx -nra` with the given arguments, relative to the start path
+fn listed_names(path: &Path, args: &[&str]) -> Result<Vec<String>, Box<dyn Error>> {
+    let output = Command::cargo_bin("rmx")?
+        .arg("-nra")
+        .args(args)
+        .arg("-p")
+        .arg(path)
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut names: Vec<String> = stdout
+        .lines()
+        .filter_map(|l| Path::new(l).strip_prefix(path).ok())
+        .map(|p| p.display().to_string())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[test]
+fn it_regex() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    File::create(path_buf.join("file.tar.gz"))?;
+    File::create(path_buf.join("file.tar"))?;
+
+    // The whole name has to match, whatever the dots in it
+    assert_eq!(
+        vec!["file.tar.gz"],
+        listed_names(&path_buf, &["--regex", r".*\.tar\.gz"])?
+    );
+    assert_eq!(
+        vec!["file.tar"],
+        listed_names(&path_buf, &["--regex", r"file\.tar"])?
+    );
+    assert_eq!(
+        vec!["file.aA-01.23", "file.tar", "file.tar.gz"],
+        listed_names(&path_buf, &["--regex", r"file\..*"])?
+    );
+
+    // Along with extensions, files must match both
+    assert_eq!(
+        vec!["subfolder1/sub1.txt", "subfolder1/subfolder2/sub2.txt"],
+        listed_names(&path_buf, &["--regex", "sub.*", "txt"])?
+    );
+
+    // Against the whole path
+    assert_eq!(
+        vec!["subfolder1/sub1.log", "subfolder1/sub1.txt"],
+        listed_names(
+            &path_buf,
+            &["--regex-path", "--regex", ".*/subfolder1/[^/]*"]
+        )?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_json_errors() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let missing = path_buf.join("missing");
+    let plan = path_buf.join("plan.json");
+    let error = |args: &[&OsStr]| -> Result<serde_json::Value, Box<dyn Error>> {
+        let output = Command::cargo_bin("rmx")?.args(args).output()?;
+        assert_eq!(Some(1), output.status.code());
+        Ok(serde_json::from_slice::<serde_json::Value>(&output.stdout)?["error"].take())
+    };
+
+    let e = error(&[
+        "txt".as_ref(),
+        "-p".as_ref(),
+        missing.as_ref(),
+        "--plan-out".as_ref(),
+        plan.as_ref(),
+    ])?;
+    assert_eq!("path_not_found", e["kind"]);
+    assert_eq!(missing.to_str().unwrap(), e["path"]);
+    assert!(e["message"].as_str().unwrap().starts_with("Could not find"));
+
+    let e = error(&[
+        "a*".as_ref(),
+        "-p".as_ref(),
+        path_buf.as_ref(),
+        "--plan-out".as_ref(),
+        plan.as_ref(),
+    ])?;
+    assert_eq!("invalid_extension", e["kind"]);
+    assert_eq!(serde_json::Value::Null, e["path"]);
+    assert!(!plan.exists());
+
+    // Other runs keep the message on stderr
+    Command::cargo_bin("rmx")?
+        .arg("txt")
+        .arg("-p")
+        .arg(&missing)
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(format!(
+            "Could not find {}",
+            missing.display()
+        )));
+
+    Ok(())
+}
+
+#[test]
+fn it_dirs() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+    for file in [
+        "foo.egg-info/PKG-INFO",
+        "foo.egg-info/sub/top_level.txt",
+        "mac/App.dSYM/Contents/Info.plist",
+        "mac/App.dSYM.txt",
+        "lib.egg-info",
+        "setup.py",
+    ] {
+        fs::create_dir_all(path.join(file).parent().unwrap())?;
+        File::create(path.join(file))?;
+    }
+    let rmx = |extra: &[&str]| -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("rmx")?;
+        cmd.arg("-r").arg("--dirs").args(extra).arg("-p").arg(path);
+        cmd.arg("egg-info").arg("dSYM");
+        Ok(cmd)
+    };
+
+    // A dry run lists matched directories, and touches nothing
+    rmx(&["-n"])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{}/",
+            path.join("foo.egg-info").display()
+        )));
+    assert!(path.join("foo.egg-info/sub/top_level.txt").exists());
+    assert!(path.join("mac/App.dSYM/Contents/Info.plist").exists());
+    assert!(path.join("lib.egg-info").exists());
+
+    rmx(&[])?
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 1 file(s) and 2 directory(ies)?",
+        ));
+
+    // Matched directories are gone with their content, siblings survive
+    assert!(!path.join("foo.egg-info").exists());
+    assert!(!path.join("mac/App.dSYM").exists());
+    assert!(!path.join("lib.egg-info").exists());
+    assert!(path.join("mac/App.dSYM.txt").exists());
+    assert!(path.join("setup.py").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_expand_matched_dirs() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+    for file in [
+        "foo.egg-info/PKG-INFO",
+        "foo.egg-info/top_level.egg-info",
+        "foo.egg-info/sub/deps.egg-info",
+    ] {
+        fs::create_dir_all(path.join(file).parent().unwrap())?;
+        File::create(path.join(file))?;
+    }
+    let rmx = |extra: &[&str]| -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("rmx")?;
+        cmd.arg("-r").arg("--dirs").args(extra).arg("-p").arg(path);
+        cmd.arg("egg-info");
+        Ok(cmd)
+    };
+    // Listed on its own line, its content starting with the same path
+    let dir = format!("{}/\n", path.join("foo.egg-info").display());
+    let inner = path
+        .join("foo.egg-info/top_level.egg-info")
+        .display()
+        .to_string();
+
+    // By default, the directory is listed once, and not what it holds
+    rmx(&["-n"])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&dir))
+        .stdout(predicate::str::contains(&inner).not());
+
+    // Expanded, the matching files it holds are listed instead of it
+    rmx(&["-n", "--expand-matched-dirs"])?
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&inner))
+        .stdout(predicate::str::contains("deps.egg-info"))
+        .stdout(predicate::str::contains("PKG-INFO").not())
+        .stdout(predicate::str::contains(&dir).not());
+
+    rmx(&["--expand-matched-dirs"])?
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 2 file(s)?",
+        ));
+
+    // The directory stays, with its non-matching files
+    assert!(path.join("foo.egg-info/PKG-INFO").exists());
+    assert!(!path.join("foo.egg-info/top_level.egg-info").exists());
+    assert!(!path.join("foo.egg-info/sub/deps.egg-info").exists());
+
+    rmx(&[])?
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 1 directory(ies)?",
+        ));
+    assert!(!path.join("foo.egg-info").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_expand_matched_dirs_without_dirs_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("--expand-matched-dirs")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--expand-matched-dirs requires --dirs.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_dirs_without_extensions_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("--dirs")
+        .arg("--no-ext")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--dirs requires extensions to match directories with.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_prefix() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    for name in [
+        "tmp_a.txt",
+        "tmp_b",
+        "mytmp_c.txt",
+        "~$Document.docx",
+        ".tmp_d",
+    ] {
+        File::create(path_buf.join(name))?;
+    }
+
+    // Hidden names only match with --all, which listed_names gives
+    assert_eq!(
+        vec!["tmp_a.txt", "tmp_b"],
+        listed_names(&path_buf, &["--prefix", "tmp_"])?
+    );
+    assert_eq!(
+        vec![".tmp_d"],
+        listed_names(&path_buf, &["--prefix", ".tmp"])?
+    );
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--prefix")
+        .arg(".tmp")
+        .arg("-p")
+        .arg(&path_buf)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".tmp_d").not());
+
+    // Along with extensions, files must match both
+    assert_eq!(
+        vec!["tmp_a.txt"],
+        listed_names(&path_buf, &["--prefix", "tmp_", "txt"])?
+    );
+    // Repeated, files match any of them
+    assert_eq!(
+        vec!["tmp_a.txt", "tmp_b", "~$Document.docx"],
+        listed_names(&path_buf, &["--prefix", "tmp_", "--prefix", "~$"])?
+    );
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--prefix=")
+        .arg("-p")
+        .arg(&path_buf)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the prefix cannot be empty"));
+
+    Ok(())
+}
+
+#[test]
+fn it_regex_with_invert() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    File::create(path_buf.join("file.tar.gz"))?;
+
+    // Files matching the regex are kept, whatever their extension
+    assert_eq!(
+        vec![
+            ".hidden_folder/hidden.txt",
+            "subfolder1/sub1.log",
+            "subfolder1/sub1.txt",
+            "subfolder1/subfolder2/backup.bak",
+            "subfolder1/subfolder2/sub2.txt",
+        ],
+        listed_names(
+            &path_buf,
+            &["--invert", "--regex", r"(root|file)\..*|data\.dat"]
+        )?
+        .into_iter()
+        .filter(|n| n.contains('/'))
+        .collect::<Vec<_>>()
+    );
+    let names = listed_names(&path_buf, &["--invert", "--regex", r".*\.gz"])?;
+    assert!(!names.contains(&"file.tar.gz".to_string()));
+    assert!(names.contains(&"root.txt".to_string()));
+
+    // Along with extensions, files matching both are kept
+    let names = listed_names(&path_buf, &["--invert", "--regex", "sub.*", "txt"])?;
+    assert!(!names.contains(&"subfolder1/sub1.txt".to_string()));
+    assert!(names.contains(&"subfolder1/sub1.log".to_string()));
+    assert!(names.contains(&"root.txt".to_string()));
+
+    Ok(())
+}
+
+#[test]
+fn it_regex_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--regex")
+        .arg("file(.tar")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --regex \"file(.tar\""))
+        .stderr(predicate::str::contains("file(.tar\n        ^"));
+
+    assert!(temp_dir.path().join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_max_depth() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    // Implies --recurse
+    assert_eq!(
+        vec!["subfolder1/sub1.txt"],
+        listed_names(&path_buf, &["--max-depth", "2", "txt"])?
+            .into_iter()
+            .filter(|n| n.starts_with("subfolder1"))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["subfolder1/sub1.txt", "subfolder1/subfolder2/sub2.txt"],
+        listed_names(&path_buf, &["--max-depth", "3", "txt"])?
+            .into_iter()
+            .filter(|n| n.starts_with("subfolder1"))
+            .collect::<Vec<_>>()
+    );
+
+    // Nothing is deleted below the limit
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--max-depth")
+        .arg("2")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+    assert!(path_buf.join("subfolder1/subfolder2/sub2.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_max_depth_zero_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--max-depth")
+        .arg("0")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure();
+
+    assert!(temp_dir.path().join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_extensions_normalized() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let file = create_config_file("preset logs=.log,dat")?;
+
+    // Presets and the command line go through the same normalization
+    assert_eq!(
+        vec!["data.dat", "root.log"],
+        listed_names(
+            &path_buf,
+            &[
+                "--config",
+                file.path().to_str().unwrap(),
+                "--preset",
+                "logs"
+            ]
+        )?
+        .into_iter()
+        .filter(|n| !n.contains('/'))
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["data.dat", "root.log"],
+        listed_names(&path_buf, &[".log,dat"])?
+            .into_iter()
+            .filter(|n| !n.contains('/'))
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_min_depth() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--min-depth")
+        .arg("2")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("txt")
+        .assert()
+        .success();
+
+    assert!(path_buf.join("root.txt").exists());
+    assert!(!path_buf.join("subfolder1/sub1.txt").exists());
+    assert!(!path_buf.join("subfolder1/subfolder2/sub2.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_min_depth_above_max_depth_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--min-depth")
+        .arg("3")
+        .arg("--max-depth")
+        .arg("2")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--min-depth 3 is greater than --max-depth 2",
+        ));
+
+    assert!(temp_dir.path().join("subfolder1/sub1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_hidden_start_path() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let start = temp_dir.path().join(".hidden_folder");
+    fs::create_dir(start.join(".cache"))?;
+    File::create(start.join(".cache/foo.txt"))?;
+    fs::create_dir(start.join("sub"))?;
+    File::create(start.join("sub/foo.txt"))?;
+
+    // Walked whatever --all, its hidden entries following it
+    for (flags, expected) in [
+        ("-nr", vec!["hidden.txt", "sub/foo.txt"]),
+        ("-nra", vec![".cache/foo.txt", "hidden.txt", "sub/foo.txt"]),
+    ] {
+        let output = Command::cargo_bin("rmx")?
+            .arg(flags)
+            .arg("--sort")
+            .arg("-p")
+            .arg(&start)
+            .arg("txt")
+            .output()?;
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let listed: Vec<String> = stdout
+            .lines()
+            .filter_map(|l| Path::new(l).strip_prefix(&start).ok())
+            .map(|p| p.display().to_string())
+            .collect();
+        assert_eq!(expected, listed, "{flags}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_list_footer() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    fs::write(path_buf.join("root.txt"), vec![0; 1000])?;
+    fs::write(path_buf.join("subfolder1/sub1.txt"), vec![0; 2000])?;
+
+    // Streamed, or listed once collected
+    for flags in ["-lnr", "-lnr --sort", "-lnr --long"] {
+        let output = Command::cargo_bin("rmx")?
+            .args(flags.split(' '))
+            .arg("-p")
+            .arg(&path_buf)
+            .arg("txt")
+            .output()?;
+        assert!(output.status.success());
+
+        // stdout is piped, so the footer goes to stderr
+        let stdout = String::from_utf8(output.stdout)?;
+        assert!(!stdout.contains("-- 3 files"), "{flags}:\n{stdout}");
+        assert_eq!(
+            "-- 3 files, 2.9 KiB --\n",
+            String::from_utf8(output.stderr)?,
+            "{flags}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_exclude() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .arg("-irl")
+        .arg("txt")
+        .arg("--exclude")
+        .arg("data.*")
+        .arg("--exclude")
+        .arg("*.bak")
+        .arg("-p")
+        .arg(&path_buf)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("data.dat").not())
+        .stdout(predicate::str::contains("backup.bak").not())
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 3 file(s)?",
+        ));
+
+    assert!(path_buf.join("data.dat").exists());
+    assert!(path_buf.join("subfolder1/subfolder2/data.dat").exists());
+    assert!(path_buf.join("subfolder1/subfolder2/backup.bak").exists());
+    assert!(path_buf.join("root.txt").exists());
+    assert!(!path_buf.join("root.log").exists());
+    assert!(!path_buf.join("subfolder1/sub1.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_min_size() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    File::create(path_buf.join("small.log"))?.set_len(400 * 1024)?;
+    File::create(path_buf.join("big.log"))?.set_len(500 * 1024)?;
+    File::create(path_buf.join("bigger.log"))?.set_len(2 * 1024 * 1024)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-l")
+        .arg("--min-size")
+        .arg("500K")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("log")
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("small.log").not())
+        .stdout(predicate::str::contains("big.log"))
+        .stdout(predicate::str::contains(
+            "Do you really want to delete 2 file(s)?",
+        ));
+
+    assert!(path_buf.join("small.log").exists());
+    assert!(!path_buf.join("big.log").exists());
+    assert!(!path_buf.join("bigger.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_min_size_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--min-size=-10M")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid size \"-10M\""));
+
+    Ok(())
+}
+
+// Sizes are checked once the extensions are inverted
+#[test]
+fn it_max_size_with_invert() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    File::create(path_buf.join("tiny.tmp"))?.set_len(10)?;
+    File::create(path_buf.join("small.log"))?.set_len(2 * 1024)?;
+    File::create(path_buf.join("big.bin"))?.set_len(5 * 1024 * 1024)?;
+    File::create(path_buf.join("small.tar"))?.set_len(10)?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-if")
+        .arg("--max-size")
+        .arg("1M")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("tar")
+        .assert()
+        .success();
+
+    assert!(!path_buf.join("tiny.tmp").exists());
+    assert!(!path_buf.join("small.log").exists());
+    assert!(path_buf.join("big.bin").exists());
+    assert!(path_buf.join("small.tar").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_size_range() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    for (name, len) in [("a.log", 100), ("b.log", 1024), ("c.log", 4096)] {
+        File::create(path_buf.join(name))?.set_len(len)?;
+    }
+
+    let listed = listed_names(&path_buf, &["--min-size", "1K", "--max-size", "2K", "log"])?;
+    assert_eq!(vec!["b.log"], listed);
+
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--min-size")
+        .arg("2M")
+        .arg("--max-size")
+        .arg("1M")
+        .arg("log")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--min-size 2.0 MiB is greater than --max-size 1.0 MiB, nothing would be collected.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_history() -> Result<(), Box<dyn Error>> {
+    let state_dir = tempfile::tempdir()?;
+    let first = create_temp_folder();
+    let second = create_temp_folder();
+
+    let rmx = || -> Result<Command, Box<dyn Error>> {
+        let mut command = Command::cargo_bin("rmx")?;
+        command.env("XDG_STATE_HOME", state_dir.path());
+        Ok(command)
+    };
+
+    rmx()?
+        .arg("--history")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No run recorded in"));
+
+    rmx()?
+        .arg("-f")
+        .arg("-p")
+        .arg(first.path())
+        .arg("txt")
+        .assert()
+        .success();
+    // Dry runs delete nothing, and are not recorded
+    rmx()?
+        .arg("-n")
+        .arg("-p")
+        .arg(first.path())
+        .arg("log")
+        .assert()
+        .success();
+    rmx()?
+        .arg("-rf")
+        .arg("-p")
+        .arg(second.path())
+        .arg("log")
+        .assert()
+        .success();
+
+    let history = state_dir.path().join("rmx").join("history.jsonl");
+    assert_eq!(2, fs::read_to_string(&history)?.lines().count());
+
+    rmx()?
+        .arg("--history")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Date (UTC)"))
+        .stdout(predicate::str::contains("exit 0  1/1"))
+        .stdout(predicate::str::contains("exit 0  2/2"));
+
+    let output = rmx()?
+        .arg("--history")
+        .arg("1")
+        .arg("--history-json")
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(1, stdout.lines().count());
+    let record: serde_json::Value = serde_json::from_str(stdout.trim())?;
+    assert_eq!(2, record["deleted"]);
+    assert_eq!(0, record["status"]);
+    assert_eq!(
+        fs::canonicalize(second.path())?.to_str().unwrap(),
+        record["path"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_capabilities() -> Result<(), Box<dyn Error>> {
+    let output = Command::cargo_bin("rmx")?.arg("--capabilities").output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(format!("rmx {}", env!("CARGO_PKG_VERSION")), lines[0]);
+    assert!(lines.contains(&"older-than"));
+    assert!(lines.contains(&"files-from"));
+
+    let output = Command::cargo_bin("rmx")?
+        .arg("--capabilities")
+        .arg("--capabilities-json")
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(env!("CARGO_PKG_VERSION"), json["version"]);
+    assert!(
+        json["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&"one-file-system".into())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_history_unwritable() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    // A file stands where the state directory should be
+    let state_file = tempfile::NamedTempFile::new()?;
+
+    Command::cargo_bin("rmx")?
+        .env("XDG_STATE_HOME", state_file.path())
+        .arg("-f")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: could not record the run",
+        ));
+
+    assert!(!temp_dir.path().join("root.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_empty() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    File::create(path_buf.join("build.lock"))?;
+    fs::write(path_buf.join("held.lock"), "4242")?;
+    File::create(path_buf.join("marker.stamp"))?;
+    fs::write(path_buf.join("notes.stamp"), "done")?;
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--empty")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("lock")
+        .assert()
+        .success();
+
+    assert!(!path_buf.join("build.lock").exists());
+    assert!(path_buf.join("held.lock").exists());
+    assert!(path_buf.join("marker.stamp").exists());
+
+    // Every empty file not matching the extensions
+    Command::cargo_bin("rmx")?
+        .arg("-if")
+        .arg("--empty")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("lock")
+        .assert()
+        .success();
+
+    assert!(!path_buf.join("marker.stamp").exists());
+    assert!(path_buf.join("notes.stamp").exists());
+    assert!(path_buf.join("held.lock").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_no_ext() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    for name in ["Makefile", "core", ".bashrc", "file.tar.gz", "main.o"] {
+        File::create(path_buf.join(name))?;
+    }
+
+    let listed = listed_names(&path_buf, &["--no-ext"])?;
+    assert_eq!(vec![".bashrc", "Makefile", "core"], listed);
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .arg("--no-ext")
+        .arg("-p")
+        .arg(&path_buf)
+        .arg("o")
+        .assert()
+        .success();
+
+    assert!(!path_buf.join("Makefile").exists());
+    assert!(!path_buf.join("core").exists());
+    assert!(!path_buf.join("main.o").exists());
+    assert!(path_buf.join(".bashrc").exists());
+    assert!(path_buf.join("file.tar.gz").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_name() -> Result<(), Box<dyn Error>> {
+    let temp_dir = FixtureTree::standard()
+        .hidden_file(".DS_Store")
+        .hidden_file("subfolder1/.DS_Store")
+        .hidden_file("subfolder1/subfolder2/.DS_Store")
+        .file("Thumbs.db")
+        .build()?;
+    let path_buf = temp_dir.path().to_path_buf();
+
+    let listed = listed_names(
+        &path_buf,
+        &["--name", "Thumbs.db", "--name", ".DS_Store", "log"],
+    )?;
+    assert_eq!(
+        vec![
+            ".DS_Store",
+            "Thumbs.db",
+            "root.log",
+            "subfolder1/.DS_Store",
+            "subfolder1/sub1.log",
+            "subfolder1/subfolder2/.DS_Store",
+        ],
+        listed
+    );
+
+    // Without --all, the name being given explicitly
+    Command::cargo_bin("rmx")?
+        .arg("-rf")
+        .arg("--name")
+        .arg(".DS_Store")
+        .arg("-p")
+        .arg(&path_buf)
+        .assert()
+        .success();
+
+    assert!(!path_buf.join(".DS_Store").exists());
+    assert!(!path_buf.join("subfolder1/.DS_Store").exists());
+    assert!(!path_buf.join("subfolder1/subfolder2/.DS_Store").exists());
+    assert!(path_buf.join("Thumbs.db").exists());
+    assert!(path_buf.join(".hidden.txt").exists());
+    assert!(path_buf.join("root.log").exists());
+    assert!(path_buf.join("subfolder1/subfolder2/backup.bak").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_case_insensitive_fs() -> Result<(), Box<dyn Error>> {
+    let temp_dir = FixtureTree::new()
+        .file("Important.log")
+        .file("debug.log")
+        .file("Current.LOG")
+        .build()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    let keep = temp_dir.path().join("keep.txt");
+    fs::write(
+        &keep,
+        path_buf.join("current.log").to_string_lossy().as_bytes(),
+    )?;
+
+    let keep = keep.to_str().unwrap();
+    let args = [
+        "--exclude",
+        "important.log",
+        "--keep-from",
+        keep,
+        "--ignore-case",
+    ];
+    let listed = listed_names(&path_buf, &[&args[..], &["log"]].concat())?;
+    assert_eq!(vec!["Current.LOG", "Important.log", "debug.log"], listed);
+
+    // Forced, the filesystems of Linux being case sensitive
+    let listed = listed_names(
+        &path_buf,
+        &[&args[..], &["--case-insensitive-fs", "log"]].concat(),
+    )?;
+    assert_eq!(vec!["debug.log"], listed);
+
+    Ok(())
+}
+
+#[test]
+fn it_empty_with_min_size_should_fail() -> Result<(), Box<dyn Error>> {
+    Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--empty")
+        .arg("--min-size")
+        .arg("1K")
+        .arg("lock")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn it_rules() -> Result<(), Box<dyn Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let path_buf = temp_dir.path().to_path_buf();
+    // On both sides of each cutoff
+    for (name, days) in [
+        ("old.log", 40),
+        ("new.log", 10),
+        ("old.tmp", 2),
+        ("new.tmp", 0),
+    ] {
+        File::create(path_buf.join(name))?;
+        backdate(&path_buf.join(name), days)?;
+    }
+    File::create(path_buf.join("old.txt"))?;
+    backdate(&path_buf.join("old.txt"), 400)?;
+
+    let rules = ["--rule", "log:30d", "--rule", ".tmp:1d"];
+    let output = Command::cargo_bin("rmx")?
+        .arg("-n")
+        .arg("--sort")
+        .args(rules)
+        .arg("-p")
+        .arg(&path_buf)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        format!(
+            "{}  [rule log:30d]\n{}  [rule tmp:1d]\n\
+            Matches via rule 'log:30d': 1 file; via rule 'tmp:1d': 1 file.\n",
+            path_buf.join("old.log").display(),
+            path_buf.join("old.tmp").display()
+        ),
+        String::from_utf8(output.stdout)?
+    );
+
+    Command::cargo_bin("rmx")?
+        .arg("-f")
+        .args(rules)
+        .arg("-p")
+        .arg(&path_buf)
+        .assert()
+        .success();
+    assert!(!path_buf.join("old.log").exists());
+    assert!(!path_buf.join("old.tmp").exists());
+    assert!(path_buf.join("new.log").exists());
+    assert!(path_buf.join("new.tmp").exists());
+    assert!(path_buf.join("old.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_rules_invalid_should_fail() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    for args in [
+        vec!["--rule", "log:30d", "-i"],
+        vec!["--rule", "log:30d", "txt"],
+        vec!["--rule", "log"],
+        vec!["--rule", "*:30d"],
+        vec!["--rule", "log:30x"],
+    ] {
+        Command::cargo_bin("rmx")?
+            .arg("-f")
+            .args(&args)
+            .arg("-p")
+            .arg(temp_dir.path())
+            .assert()
+            .failure();
+    }
+
+    assert!(temp_dir.path().join("root.log").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_path_swallowing_extension_hint() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .current_dir(temp_dir.path())
+        .arg("-n")
+        .arg("-p")
+        .arg("txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Hint: \"txt\" was taken as the path, did you mean `rmx txt -p <DIR>`?",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_directory_as_extension_hint() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .current_dir(temp_dir.path())
+        .arg("-n")
+        .arg("subfolder1")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: \"subfolder1\" is a directory but was taken as an extension, did you mean `rmx <EXT> -p subfolder1`?",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_limit() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-raf")
+        .arg("--limit")
+        .arg("2")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Note: limit of 2 files reached, more files match and are left for another run.",
+        ));
+
+    // 5 files match, the 3 beyond the limit are left
+    assert_eq!(3, listed_names(temp_dir.path(), &["txt"])?.len());
+
+    Ok(())
+}
+
+// The temporary tree is on a single filesystem, nothing is skipped
+#[cfg(unix)]
+#[test]
+fn it_one_file_system() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+
+    Command::cargo_bin("rmx")?
+        .arg("-raf")
+        .arg("--one-file-system")
+        .arg("-p")
+        .arg(temp_dir.path())
+        .arg("txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--one-file-system").not());
+
+    assert!(listed_names(temp_dir.path(), &["txt"])?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn it_preset_tip() -> Result<(), Box<dyn Error>> {
+    let state_dir = tempfile::tempdir()?;
+    let temp_dir = create_temp_folder();
+    let mut config = NamedTempFile::new()?;
+    writeln!(config, "preset junk = bak")?;
+
+    let run = |extensions: &[&str]| -> Result<String, Box<dyn Error>> {
+        let output = Command::cargo_bin("rmx")?
+            .env("XDG_STATE_HOME", state_dir.path())
+            .arg("--config")
+            .arg(config.path())
+            .arg("-f")
+            .arg("-p")
+            .arg(temp_dir.path())
+            .args(extensions)
+            .output()?;
+        assert!(output.status.success(), "{:?}", output);
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    // The same set in another order, while other sets do not count
+    assert!(!run(&["tmp", "log"])?.contains("Tip:"));
+    assert!(!run(&["bak"])?.contains("Tip:"));
+    assert!(!run(&["log", "tmp"])?.contains("Tip:"));
+    assert!(
+        run(&["log", "tmp"])?
+            .contains("Tip: save this as a preset with a `preset NAME=log tmp` line")
+    );
+    // Only once
+    assert!(!run(&["log", "tmp"])?.contains("Tip:"));
+
+    // Sets given by presets are not counted
+    for _ in 0..3 {
+        let output = Command::cargo_bin("rmx")?
+            .env("XDG_STATE_HOME", state_dir.path())
+            .arg("--config")
+            .arg(config.path())
+            .args(["-f", "--preset", "junk", "-p"])
+            .arg(temp_dir.path())
+            .output()?;
+        assert!(!String::from_utf8(output.stdout)?.contains("Tip:"));
+    }
+
+    // Turned off in the config file
+    writeln!(config, "suggestions = false")?;
+    let other = tempfile::tempdir()?;
+    for _ in 0..3 {
+        let output = Command::cargo_bin("rmx")?
+            .env("XDG_STATE_HOME", other.path())
+            .arg("--config")
+            .arg(config.path())
+            .args(["-f", "-p"])
+            .arg(temp_dir.path())
+            .arg("dat")
+            .output()?;
+        assert!(!String::from_utf8(output.stdout)?.contains("Tip:"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_wizard() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let state_dir = tempfile::tempdir()?;
+    let config = temp_dir.path().join("rmx.conf");
+    fs::write(&config, "preset junk=bak dat\n")?;
+
+    // Directory, extensions and a preset, recurse, hidden, then the deletion itself
+    let script = format!("{}\nlog junk\ny\nn\ny\n", temp_dir.path().display());
+    let mut prompter = rmx::wizard::Prompter::new(script.as_bytes(), Vec::new());
+    let argv = rmx::wizard::run(&mut prompter, Path::new("/"), &config)?.unwrap();
+    let output = String::from_utf8(prompter.into_output())?;
+    assert!(output.contains("or presets (junk):"));
+    assert!(output.contains("5 file(s) would be deleted under"));
+
+    Command::cargo_bin("rmx")?
+        .env("XDG_STATE_HOME", state_dir.path())
+        .args(&argv[1..])
+        .assert()
+        .success();
+    for deleted in [
+        "root.log",
+        "data.dat",
+        "subfolder1/sub1.log",
+        "subfolder1/subfolder2/backup.bak",
+        "subfolder1/subfolder2/data.dat",
+    ] {
+        assert!(!temp_dir.path().join(deleted).exists(), "{deleted}");
+    }
+    assert!(temp_dir.path().join("root.txt").exists());
+    assert!(temp_dir.path().join(".hidden_folder/hidden.txt").exists());
+
+    // Without a terminal, a bare run keeps failing for scripts
+    Command::cargo_bin("rmx")?
+        .env("XDG_STATE_HOME", state_dir.path())
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "required arguments were not provided",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn it_policy() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let policy = path_buf.join("policy.toml");
+    fs::write(
+        &policy,
+        format!(
+            "# Pushed to the fleet\nforbid_invert = true\nmax_files = 2\n\n\
+            [[paths]]\npath = '{}'\ndry_run = true\n",
+            path_buf.join("subfolder1").display()
+        ),
+    )?;
+    let rmx = || -> Result<Command, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("rmx")?;
+        cmd.env("RMX_POLICY", &policy).arg("-p").arg(&path_buf);
+        Ok(cmd)
+    };
+
+    // Flags cannot get around the policy
+    rmx()?
+        .args(["-f", "--invert", "--allow-invert-recursive", "txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Blocked by policy: --invert is not allowed (forbid_invert in {}).",
+            policy.display()
+        )));
+    rmx()?
+        .args(["-f", "-r", "txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Blocked by policy: 3 files would be deleted, at most 2 are allowed (max_files in",
+        ));
+    rmx()?
+        .args(["-f", "-r", "log"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "sub1.log can only be listed with -n/--dry-run (dry_run of path",
+        ));
+    assert!(path_buf.join("root.txt").exists());
+    assert!(path_buf.join("root.log").exists());
+
+    // Under a dry_run path, runs are dry runs
+    Command::cargo_bin("rmx")?
+        .env("RMX_POLICY", &policy)
+        .args(["-f", "-r", "txt", "-p"])
+        .arg(path_buf.join("subfolder1"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("sub1.txt"))
+        .stderr(predicate::str::contains("Note: dry run only, as"));
+    assert!(path_buf.join("subfolder1/sub1.txt").exists());
+
+    rmx()?.args(["-f", "log"]).assert().success();
+    assert!(!path_buf.join("root.log").exists());
+
+    // A policy that cannot be understood blocks every run
+    fs::write(&policy, "require_trash = true\n")?;
+    rmx()?
+        .args(["-n", "txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Blocked by policy: unknown field `require_trash`",
+        ));
+
+    fs::write(&policy, "require_rename_suffix = true\n")?;
+    rmx()?
+        .args(["-f", "txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("(require_rename_suffix in"));
+    rmx()?
+        .args(["-f", "--rename-suffix", ".trashed", "txt"])
+        .assert()
+        .success();
+    assert!(path_buf.join("root.txt.trashed").exists());
+
+    Ok(())
+}
+
+// Telling whether the filesystem ignores case must not write to the tree
+#[cfg(unix)]
+#[test]
+fn it_dry_run_writes_nothing() -> Result<(), Box<dyn Error>> {
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+    let state_dir = tempfile::tempdir()?;
+    let long_ago = SystemTime::now() - Duration::from_secs(86400);
+    File::open(&path_buf)?.set_times(FileTimes::new().set_modified(long_ago))?;
+
+    for flag in ["-n", "-l"] {
+        Command::cargo_bin("rmx")?
+            .env("XDG_STATE_HOME", state_dir.path())
+            .args([flag, "--exclude", "data.*", "txt", "-p"])
+            .arg(&path_buf)
+            .write_stdin("n\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("root.txt"));
+    }
+
+    assert_eq!(long_ago, fs::metadata(&path_buf)?.modified()?);
+    assert!(path_buf.join("root.txt").exists());
+
+    Ok(())
+}
+
+// A listing run deletes too, and is told the case of the filesystem the same way
+#[test]
+fn it_list_and_delete_with_exclude() -> Result<(), Box<dyn Error>> {
+    let temp_dir = create_temp_folder();
+    let path_buf = temp_dir.path().to_path_buf();
+
+    Command::cargo_bin("rmx")?
+        .args(["-l", "--exclude", "root.log", "txt", "log", "-p"])
+        .arg(&path_buf)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("root.txt"))
+        .stdout(predicate::str::contains("root.log").not());
+
+    assert!(!path_buf.join("root.txt").exists());
+    assert!(path_buf.join("root.log").exists());
+    // Nothing but what was there, less the deleted file
+    let mut names: Vec<String> = fs::read_dir(&path_buf)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<io::Result<_>>()?;
+    names.sort();
+    assert_eq!(
+        vec![
+            ".hidden.aA-01.23",
+            ".hidden.txt",
+            ".hidden_folder",
+            "data.dat",
+            "file.aA-01.23",
+            "root.log",
+            "subfolder1",
+        ],
+        names
+    );
+
+    Ok(())
+}